@@ -11,6 +11,8 @@ fn criterion_benchmark(c: &mut Criterion) {
         "5_eval_available_filter",
         "6_parse_pgn",
         "7_perfect_checkmate_avg",
+        "8_init_boards",
+        "9_export_pgn",
     ];
 
     for config in configs {
@@ -1,8 +1,10 @@
 #[macro_use]
 mod cell;
 mod annotation;
+mod castling_rights;
 mod chess_move;
 mod file;
+mod game_ending;
 mod game_result;
 mod optional_piece;
 #[macro_use]
@@ -17,9 +19,11 @@ mod termination;
 mod time_control;
 
 pub use annotation::Annotation;
+pub use castling_rights::CastlingRights;
 pub use cell::Cell;
 pub use chess_move::Move;
 pub use file::File;
+pub use game_ending::GameEnding;
 pub use game_result::GameResult;
 pub use optional_piece::OptionalPiece;
 pub use partial_cell::PartialCell;
@@ -0,0 +1,112 @@
+use serde::{Deserialize, Serialize};
+
+use crate::basic_types::{File, Player};
+
+// Castling moves each player may still make, as in the third field of a FEN
+#[derive(PartialEq, Clone, Debug, Copy, Eq, Default, Serialize, Deserialize)]
+pub struct CastlingRights {
+    pub white_kingside: bool,
+    pub white_queenside: bool,
+    pub black_kingside: bool,
+    pub black_queenside: bool,
+}
+
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+impl CastlingRights {
+    pub fn all() -> Self {
+        CastlingRights {
+            white_kingside: true,
+            white_queenside: true,
+            black_kingside: true,
+            black_queenside: true,
+        }
+    }
+
+    pub fn from_fen(fen: &str) -> Result<Self, String> {
+        let mut rights = CastlingRights::default();
+        if fen == "-" {
+            return Ok(rights);
+        }
+
+        for c in fen.chars() {
+            match c {
+                'K' => rights.white_kingside = true,
+                'Q' => rights.white_queenside = true,
+                'k' => rights.black_kingside = true,
+                'q' => rights.black_queenside = true,
+                u => return Err(format!("Unrecognized castling right: {u}")),
+            }
+        }
+
+        Ok(rights)
+    }
+
+    pub fn to_fen(self) -> String {
+        let fen: String = [
+            (self.white_kingside, 'K'),
+            (self.white_queenside, 'Q'),
+            (self.black_kingside, 'k'),
+            (self.black_queenside, 'q'),
+        ]
+        .iter()
+        .filter(|(right, _)| *right)
+        .map(|(_, c)| c)
+        .collect();
+
+        if fen.is_empty() {
+            "-".to_string()
+        } else {
+            fen
+        }
+    }
+
+    // Kingside when `rook_file` is the h-file, queenside when it is the a-file
+    pub fn can_castle(self, player: Player, rook_file: File) -> bool {
+        match (player, rook_file) {
+            (Player::White, File::_H) => self.white_kingside,
+            (Player::White, File::_A) => self.white_queenside,
+            (Player::Black, File::_H) => self.black_kingside,
+            (Player::Black, File::_A) => self.black_queenside,
+            _ => false,
+        }
+    }
+
+    pub fn remove(&mut self, player: Player, rook_file: File) {
+        match (player, rook_file) {
+            (Player::White, File::_H) => self.white_kingside = false,
+            (Player::White, File::_A) => self.white_queenside = false,
+            (Player::Black, File::_H) => self.black_kingside = false,
+            (Player::Black, File::_A) => self.black_queenside = false,
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_fen {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        for fen in ["KQkq", "Kq", "k", "-"] {
+            assert_eq!(CastlingRights::from_fen(fen).unwrap().to_fen(), fen);
+        }
+    }
+
+    #[test]
+    fn test_unrecognized_right() {
+        assert_eq!(
+            CastlingRights::from_fen("KX"),
+            Err("Unrecognized castling right: X".to_string())
+        );
+    }
+
+    #[test]
+    fn test_remove() {
+        let mut rights = CastlingRights::all();
+        rights.remove(Player::White, File::_A);
+        assert_eq!(rights.to_fen(), "Kkq");
+        assert!(!rights.can_castle(Player::White, File::_A));
+        assert!(rights.can_castle(Player::Black, File::_A));
+    }
+}
@@ -1,10 +1,7 @@
-use crate::basic_types::{Annotation, Cell, OptionalPiece, PartialCell, Piece};
+use crate::basic_types::{Annotation, Cell, File, OptionalPiece, PartialCell, Piece, Rank};
 use packed_struct::prelude::*;
 use serde::{Deserialize, Serialize};
 
-#[cfg(test)]
-use crate::basic_types::{File, Rank};
-
 #[derive(PartialEq, Eq, Clone, Debug, Copy, PackedStruct)]
 #[packed_struct(bit_numbering = "msb0", size_bytes = "4")]
 pub struct Move {
@@ -70,7 +67,6 @@ impl<'de> Deserialize<'de> for Move {
 
 #[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
 impl Move {
-    #[cfg(test)]
     pub fn new_to_from(
         from_file: Option<File>,
         from_rank: Option<Rank>,
@@ -49,6 +49,19 @@ impl File {
         }
     }
 
+    pub fn to_pgn(self) -> &'static str {
+        match self {
+            File::_A => "a",
+            File::_B => "b",
+            File::_C => "c",
+            File::_D => "d",
+            File::_E => "e",
+            File::_F => "f",
+            File::_G => "g",
+            File::_H => "h",
+        }
+    }
+
     #[cfg(test)]
     pub fn from_usize(val: usize) -> Self {
         match val {
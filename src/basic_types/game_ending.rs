@@ -0,0 +1,46 @@
+use crate::step_param_utils::ParamType;
+use serde::{Deserialize, Serialize};
+
+// Why a game ended, as far as the final position and the positions before it tell
+#[derive(PartialEq, Eq, Clone, Debug, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GameEnding {
+    Checkmate,
+    Stalemate,
+    InsufficientMaterial,
+    FivefoldRepetition,
+    SeventyFiveMoveRule,
+    // Draws that had to be claimed, only reported when the game was drawn
+    ThreefoldRepetition,
+    FiftyMoveRule,
+    // Resignation, time forfeit, agreed draw or an unfinished game
+    Other,
+}
+
+impl ParamType for GameEnding {
+    fn type_name() -> String {
+        "checkmate | stalemate | insufficient_material | fivefold_repetition | \
+         seventy_five_move_rule | threefold_repetition | fifty_move_rule | other"
+            .to_string()
+    }
+}
+
+#[cfg(test)]
+mod test_default_impls {
+    use super::*;
+
+    #[test]
+    fn test_debug() {
+        assert_eq!(format!("{:?}", GameEnding::Stalemate), "Stalemate");
+    }
+
+    #[test]
+    fn test_deserialize() {
+        let endings: Vec<GameEnding> =
+            serde_yaml::from_str("[checkmate, seventy_five_move_rule]").unwrap();
+        assert_eq!(
+            endings,
+            vec![GameEnding::Checkmate, GameEnding::SeventyFiveMoveRule]
+        );
+    }
+}
@@ -76,16 +76,6 @@ impl PartialCell {
     pub fn is_fully_defined(self) -> bool {
         self.file.is_some() && self.rank.is_some()
     }
-
-    pub fn possible_ranks(self) -> Vec<Rank> {
-        self.rank
-            .map_or(Rank::all_ranks(), |some_rank| vec![some_rank])
-    }
-
-    pub fn possible_files(self) -> Vec<File> {
-        self.file
-            .map_or(File::all_files(), |some_file| vec![some_file])
-    }
 }
 
 #[cfg(test)]
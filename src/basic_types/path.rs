@@ -5,6 +5,7 @@ use crate::general_utils::get_unit_value;
 pub struct Path(Vec<Cell>);
 
 #[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+#[allow(dead_code)]
 impl Path {
     pub fn empty() -> Self {
         Path(vec![])
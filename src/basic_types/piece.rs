@@ -25,6 +25,17 @@ impl Piece {
         }
     }
 
+    pub fn to_pgn(self) -> &'static str {
+        match self {
+            Piece::Pawn => "",
+            Piece::Knight => "N",
+            Piece::Bishop => "B",
+            Piece::Rook => "R",
+            Piece::Queen => "Q",
+            Piece::King => "K",
+        }
+    }
+
     pub fn from_fen(piece_str: &str) -> Self {
         match piece_str.to_ascii_uppercase().as_ref() {
             "P" => Piece::Pawn,
@@ -37,6 +48,17 @@ impl Piece {
         }
     }
 
+    pub fn all_pieces() -> [Piece; 6] {
+        [
+            Piece::Pawn,
+            Piece::Knight,
+            Piece::Bishop,
+            Piece::Rook,
+            Piece::Queen,
+            Piece::King,
+        ]
+    }
+
     #[allow(dead_code)]
     pub fn to_fen(self) -> &'static str {
         match self {
@@ -15,6 +15,25 @@ pub enum Rank {
     _8 = 8,
 }
 
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+impl TryFrom<u32> for Rank {
+    type Error = String;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        match value {
+            1 => Ok(Rank::_1),
+            2 => Ok(Rank::_2),
+            3 => Ok(Rank::_3),
+            4 => Ok(Rank::_4),
+            5 => Ok(Rank::_5),
+            6 => Ok(Rank::_6),
+            7 => Ok(Rank::_7),
+            8 => Ok(Rank::_8),
+            u => Err(format!("Unrecognized rank: {u}")),
+        }
+    }
+}
+
 #[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
 impl Rank {
     pub fn from_pgn(rank_str: &str) -> Option<Self> {
@@ -32,6 +51,19 @@ impl Rank {
         }
     }
 
+    pub fn to_pgn(self) -> &'static str {
+        match self {
+            Rank::_1 => "1",
+            Rank::_2 => "2",
+            Rank::_3 => "3",
+            Rank::_4 => "4",
+            Rank::_5 => "5",
+            Rank::_6 => "6",
+            Rank::_7 => "7",
+            Rank::_8 => "8",
+        }
+    }
+
     pub fn from_int(val: i32) -> Self {
         match val {
             1 => Rank::_1,
@@ -249,6 +281,37 @@ mod test_rank_from_uint {
     }
 }
 
+#[cfg(test)]
+mod test_rank_try_from_uint {
+    use super::*;
+
+    macro_rules! tests {
+        ($($name:ident: $value:expr,)*) => {
+        $(
+            #[test]
+            fn $name() {
+                let (input, expected) = $value;
+                assert_eq!(expected, Rank::try_from(input));
+            }
+        )*
+        }
+    }
+
+    tests! {
+        test_try_from_uint_1: (1, Ok(Rank::_1)),
+        test_try_from_uint_2: (2, Ok(Rank::_2)),
+        test_try_from_uint_3: (3, Ok(Rank::_3)),
+        test_try_from_uint_4: (4, Ok(Rank::_4)),
+        test_try_from_uint_5: (5, Ok(Rank::_5)),
+        test_try_from_uint_6: (6, Ok(Rank::_6)),
+        test_try_from_uint_7: (7, Ok(Rank::_7)),
+        test_try_from_uint_8: (8, Ok(Rank::_8)),
+        test_try_from_uint_invalid_1: (0, Err("Unrecognized rank: 0".to_string())),
+        test_try_from_uint_invalid_2: (9, Err("Unrecognized rank: 9".to_string())),
+        test_try_from_uint_invalid_3: (u32::MAX, Err("Unrecognized rank: 4294967295".to_string())),
+    }
+}
+
 #[cfg(test)]
 mod test_rank_from_usize {
     use super::*;
@@ -0,0 +1,386 @@
+use crate::basic_types::{Cell, File, PartialCell, Piece, Player, Rank};
+use serde::{Deserialize, Serialize};
+use std::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, Not};
+
+const FILE_A: u64 = 0x0101_0101_0101_0101;
+const FILE_H: u64 = FILE_A << 7;
+const RANK_1: u64 = 0xFF;
+
+const KNIGHT_DELTAS: [(i32, i32); 8] = [
+    (1, 2),
+    (2, 1),
+    (2, -1),
+    (1, -2),
+    (-1, -2),
+    (-2, -1),
+    (-2, 1),
+    (-1, 2),
+];
+const KING_DELTAS: [(i32, i32); 8] = [
+    (0, 1),
+    (1, 1),
+    (1, 0),
+    (1, -1),
+    (0, -1),
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+];
+const BISHOP_DIRECTIONS: [(i32, i32); 4] = [(1, 1), (1, -1), (-1, -1), (-1, 1)];
+const ROOK_DIRECTIONS: [(i32, i32); 4] = [(0, 1), (1, 0), (0, -1), (-1, 0)];
+
+static KNIGHT_ATTACKS: [u64; 64] = leaper_attacks(KNIGHT_DELTAS);
+static KING_ATTACKS: [u64; 64] = leaper_attacks(KING_DELTAS);
+
+// Cells a piece jumping by one of `deltas` (file, rank) attacks from each cell
+const fn leaper_attacks(deltas: [(i32, i32); 8]) -> [u64; 64] {
+    let mut table = [0; 64];
+    let mut index = 0;
+    while index < 64 {
+        let mut delta = 0;
+        while delta < 8 {
+            let file = (index % 8) as i32 + deltas[delta].0;
+            let rank = (index / 8) as i32 + deltas[delta].1;
+            if file >= 0 && file < 8 && rank >= 0 && rank < 8 {
+                table[index] |= 1 << (rank * 8 + file);
+            }
+            delta += 1;
+        }
+        index += 1;
+    }
+    table
+}
+
+// Set of cells, with a1 as the lowest bit and h8 as the highest
+#[derive(PartialEq, Clone, Debug, Copy, Eq, Default, Hash, Serialize, Deserialize)]
+pub struct Bitboard(u64);
+
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+impl Bitboard {
+    pub const EMPTY: Bitboard = Bitboard(0);
+    pub const LIGHT_CELLS: Bitboard = Bitboard(0x55AA_55AA_55AA_55AA);
+
+    pub fn from_cell(cell: Cell) -> Self {
+        Bitboard(1 << index(cell))
+    }
+
+    // Cells matching a partially known origin, like the disambiguation of a SAN move
+    pub fn matching(partial_cell: PartialCell) -> Self {
+        let files = match partial_cell.file {
+            Some(file) => FILE_A << (file as u32 - 1),
+            None => !0,
+        };
+        let ranks = match partial_cell.rank {
+            Some(rank) => RANK_1 << ((rank as u32 - 1) * 8),
+            None => !0,
+        };
+        Bitboard(files & ranks)
+    }
+
+    pub fn contains(self, cell: Cell) -> bool {
+        self.0 & (1 << index(cell)) != 0
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn count(self) -> u32 {
+        self.0.count_ones()
+    }
+
+    pub fn first(self) -> Option<Cell> {
+        if self.is_empty() {
+            None
+        } else {
+            Some(cell_at(self.0.trailing_zeros()))
+        }
+    }
+
+    // Cells in the set, from a1 to h8 rank by rank
+    pub fn cells(self) -> Cells {
+        Cells(self.0)
+    }
+}
+
+impl BitAnd for Bitboard {
+    type Output = Bitboard;
+
+    fn bitand(self, rhs: Bitboard) -> Bitboard {
+        Bitboard(self.0 & rhs.0)
+    }
+}
+
+impl BitOr for Bitboard {
+    type Output = Bitboard;
+
+    fn bitor(self, rhs: Bitboard) -> Bitboard {
+        Bitboard(self.0 | rhs.0)
+    }
+}
+
+impl Not for Bitboard {
+    type Output = Bitboard;
+
+    fn not(self) -> Bitboard {
+        Bitboard(!self.0)
+    }
+}
+
+impl BitAndAssign for Bitboard {
+    fn bitand_assign(&mut self, rhs: Bitboard) {
+        self.0 &= rhs.0;
+    }
+}
+
+impl BitOrAssign for Bitboard {
+    fn bitor_assign(&mut self, rhs: Bitboard) {
+        self.0 |= rhs.0;
+    }
+}
+
+pub struct Cells(u64);
+
+impl Iterator for Cells {
+    type Item = Cell;
+
+    fn next(&mut self) -> Option<Cell> {
+        if self.0 == 0 {
+            None
+        } else {
+            let index = self.0.trailing_zeros();
+            self.0 &= self.0 - 1;
+            Some(cell_at(index))
+        }
+    }
+}
+
+fn index(cell: Cell) -> u32 {
+    (cell.rank as u32 - 1) * 8 + (cell.file as u32 - 1)
+}
+
+fn cell_at(index: u32) -> Cell {
+    cell!(
+        File::from_int((index % 8) as i32 + 1),
+        Rank::from_int((index / 8) as i32 + 1)
+    )
+}
+
+// Cells a slider attacks along `directions`, stopping at the first occupied cell of each
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+fn slider_attacks(cell: Cell, occupied: Bitboard, directions: &[(i32, i32); 4]) -> Bitboard {
+    let mut attacks = 0;
+    for (file_step, rank_step) in directions {
+        let mut file = cell.file as i32 - 1 + file_step;
+        let mut rank = cell.rank as i32 - 1 + rank_step;
+        while (0..8).contains(&file) && (0..8).contains(&rank) {
+            let bit = 1 << (rank * 8 + file);
+            attacks |= bit;
+            if occupied.0 & bit != 0 {
+                break;
+            }
+            file += file_step;
+            rank += rank_step;
+        }
+    }
+    Bitboard(attacks)
+}
+
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+pub fn pawn_attacks(player: Player, cell: Cell) -> Bitboard {
+    let bit = 1u64 << index(cell);
+    match player {
+        Player::White => Bitboard(((bit << 7) & !FILE_H) | ((bit << 9) & !FILE_A)),
+        Player::Black => Bitboard(((bit >> 9) & !FILE_H) | ((bit >> 7) & !FILE_A)),
+        Player::NA => Bitboard::EMPTY,
+    }
+}
+
+// Cells from which a pawn of `player` could advance to `cell` without capturing: the cell
+// behind it, and the one behind that too for a double step if nothing stands in between
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+pub fn pawn_push_origins(player: Player, cell: Cell, occupied: Bitboard) -> Bitboard {
+    let bit = 1u64 << index(cell);
+    let (single, double_rank) = match player {
+        Player::White => (bit >> 8, Rank::_4),
+        Player::Black => (bit << 8, Rank::_5),
+        Player::NA => return Bitboard::EMPTY,
+    };
+
+    if cell.rank != double_rank || occupied.0 & single != 0 {
+        Bitboard(single)
+    } else if player == Player::White {
+        Bitboard(single | single >> 8)
+    } else {
+        Bitboard(single | single << 8)
+    }
+}
+
+// Cells a pawn of `player` on `cell` can advance to without capturing
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+pub fn pawn_pushes(player: Player, cell: Cell, occupied: Bitboard) -> Bitboard {
+    let bit = 1u64 << index(cell);
+    let (single, double, start_rank) = match player {
+        Player::White => (bit << 8, bit << 16, Rank::_2),
+        Player::Black => (bit >> 8, bit >> 16, Rank::_7),
+        Player::NA => return Bitboard::EMPTY,
+    };
+
+    if occupied.0 & single != 0 {
+        Bitboard::EMPTY
+    } else if cell.rank == start_rank && occupied.0 & double == 0 {
+        Bitboard(single | double)
+    } else {
+        Bitboard(single)
+    }
+}
+
+// Cells attacked by `piece` of `player` standing on `cell`
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+pub fn attacks(piece: Piece, player: Player, cell: Cell, occupied: Bitboard) -> Bitboard {
+    match piece {
+        Piece::Pawn => pawn_attacks(player, cell),
+        Piece::Knight => Bitboard(KNIGHT_ATTACKS[index(cell) as usize]),
+        Piece::Bishop => slider_attacks(cell, occupied, &BISHOP_DIRECTIONS),
+        Piece::Rook => slider_attacks(cell, occupied, &ROOK_DIRECTIONS),
+        Piece::Queen => {
+            slider_attacks(cell, occupied, &BISHOP_DIRECTIONS)
+                | slider_attacks(cell, occupied, &ROOK_DIRECTIONS)
+        }
+        Piece::King => Bitboard(KING_ATTACKS[index(cell) as usize]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cells(bitboard: Bitboard) -> Vec<Cell> {
+        bitboard.cells().collect()
+    }
+
+    #[test]
+    fn iterates_cells_rank_by_rank() {
+        let bitboard = Bitboard::from_cell(cell!(File::_H, Rank::_8))
+            | Bitboard::from_cell(cell!(File::_B, Rank::_1))
+            | Bitboard::from_cell(cell!(File::_A, Rank::_2));
+
+        assert_eq!(
+            cells(bitboard),
+            vec![
+                cell!(File::_B, Rank::_1),
+                cell!(File::_A, Rank::_2),
+                cell!(File::_H, Rank::_8)
+            ]
+        );
+        assert_eq!(bitboard.count(), 3);
+        assert_eq!(bitboard.first(), Some(cell!(File::_B, Rank::_1)));
+    }
+
+    #[test]
+    fn matches_partial_cells() {
+        let file = Bitboard::matching(partial_cell!(Some(File::_C), None));
+        assert_eq!(file.count(), 8);
+        assert!(file.contains(cell!(File::_C, Rank::_7)));
+
+        let rank = Bitboard::matching(partial_cell!(None, Some(Rank::_5)));
+        assert_eq!(rank.count(), 8);
+        assert!(rank.contains(cell!(File::_H, Rank::_5)));
+
+        let both = Bitboard::matching(partial_cell!(Some(File::_C), Some(Rank::_5)));
+        assert_eq!(cells(both), vec![cell!(File::_C, Rank::_5)]);
+    }
+
+    #[test]
+    fn leapers_stay_on_the_board() {
+        let corner = cell!(File::_A, Rank::_1);
+        assert_eq!(
+            cells(attacks(
+                Piece::Knight,
+                Player::White,
+                corner,
+                Bitboard::EMPTY
+            )),
+            vec![cell!(File::_C, Rank::_2), cell!(File::_B, Rank::_3)]
+        );
+        assert_eq!(
+            attacks(Piece::King, Player::White, corner, Bitboard::EMPTY).count(),
+            3
+        );
+        assert_eq!(
+            cells(pawn_attacks(Player::White, cell!(File::_H, Rank::_2))),
+            vec![cell!(File::_G, Rank::_3)]
+        );
+        assert_eq!(
+            cells(pawn_attacks(Player::Black, cell!(File::_A, Rank::_7))),
+            vec![cell!(File::_B, Rank::_6)]
+        );
+    }
+
+    #[test]
+    fn sliders_stop_at_blockers() {
+        let blocker = Bitboard::from_cell(cell!(File::_D, Rank::_4));
+        let rook = attacks(
+            Piece::Rook,
+            Player::White,
+            cell!(File::_D, Rank::_1),
+            blocker,
+        );
+        assert_eq!(rook.count(), 10);
+        assert!(rook.contains(cell!(File::_D, Rank::_4)));
+        assert!(!rook.contains(cell!(File::_D, Rank::_5)));
+
+        let bishop = attacks(
+            Piece::Bishop,
+            Player::White,
+            cell!(File::_A, Rank::_1),
+            blocker,
+        );
+        assert_eq!(
+            cells(bishop),
+            vec![
+                cell!(File::_B, Rank::_2),
+                cell!(File::_C, Rank::_3),
+                cell!(File::_D, Rank::_4)
+            ]
+        );
+    }
+
+    #[test]
+    fn pawn_advances() {
+        let e2 = cell!(File::_E, Rank::_2);
+        assert_eq!(
+            cells(pawn_pushes(Player::White, e2, Bitboard::EMPTY)),
+            vec![cell!(File::_E, Rank::_3), cell!(File::_E, Rank::_4)]
+        );
+        let blocked = Bitboard::from_cell(cell!(File::_E, Rank::_4));
+        assert_eq!(
+            cells(pawn_pushes(Player::White, e2, blocked)),
+            vec![cell!(File::_E, Rank::_3)]
+        );
+        let blocked = Bitboard::from_cell(cell!(File::_E, Rank::_6));
+        assert!(pawn_pushes(Player::Black, cell!(File::_E, Rank::_7), blocked).is_empty());
+    }
+
+    #[test]
+    fn pawn_push_origins_behind() {
+        let e4 = cell!(File::_E, Rank::_4);
+        assert_eq!(
+            cells(pawn_push_origins(Player::White, e4, Bitboard::EMPTY)),
+            vec![cell!(File::_E, Rank::_2), cell!(File::_E, Rank::_3)]
+        );
+        let blocked = Bitboard::from_cell(cell!(File::_E, Rank::_3));
+        assert_eq!(
+            cells(pawn_push_origins(Player::White, e4, blocked)),
+            vec![cell!(File::_E, Rank::_3)]
+        );
+        assert_eq!(
+            cells(pawn_push_origins(
+                Player::Black,
+                cell!(File::_E, Rank::_5),
+                Bitboard::EMPTY
+            )),
+            vec![cell!(File::_E, Rank::_6), cell!(File::_E, Rank::_7)]
+        );
+    }
+}
@@ -1,21 +1,32 @@
 use crate::{
-    basic_types::{Cell, File, Move, PartialCell, Path, Piece, Player, PlayerPiece, Rank},
+    basic_types::{
+        CastlingRights, Cell, File, Move, OptionalPiece, PartialCell, Path, Piece, Player,
+        PlayerPiece, Rank,
+    },
+    bitboard::{self, Bitboard},
+    error::Error,
     general_utils::parse_ascii_digit,
+    polyglot,
+    position::Position,
+    zobrist,
 };
-use itertools::Itertools;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
 
-#[derive(PartialEq, Eq, Clone, Debug, Serialize, Deserialize)]
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Serialize, Deserialize)]
 pub struct Board {
-    board: HashMap<Cell, PlayerPiece>,
+    position: Position,
     to_move: Player,
+    castling: CastlingRights,
+    // Cell a pawn skipped over with its last move, which may be captured en passant
+    en_passant: Option<Cell>,
+    // Plies since the last capture or pawn move
+    halfmove_clock: u16,
+    fullmove_number: u16,
 }
 
 // TODO investigate no-panic
 #[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
 impl Board {
-    #[cfg(test)]
     pub fn to_fen(&self) -> String {
         let mut fen = String::default();
 
@@ -29,9 +40,7 @@ impl Board {
             for file in File::all_files() {
                 let cell = cell!(file, *rank);
 
-                if self.board.contains_key(&cell) {
-                    let piece = self.board.get(&cell).unwrap();
-
+                if let Some(piece) = self.position.piece_at(cell) {
                     if blanks > 0 {
                         fen = format!("{}{}", fen, blanks);
                         blanks = 0;
@@ -61,115 +70,109 @@ impl Board {
             }
         }
 
-        if self.to_move == Player::White {
-            fen + " w"
+        let to_move = if self.to_move == Player::White {
+            "w"
+        } else {
+            "b"
+        };
+        let en_passant = match self.en_passant {
+            Some(cell) => format!("{}{}", cell.file.to_pgn(), cell.rank.to_pgn()),
+            None => "-".to_string(),
+        };
+
+        format!(
+            "{fen} {to_move} {} {en_passant} {} {}",
+            self.castling.to_fen(),
+            self.halfmove_clock,
+            self.fullmove_number
+        )
+    }
+
+    // Number of plies played since the start of the game
+    pub fn ply(&self) -> usize {
+        let ply = usize::from(self.fullmove_number.max(1) - 1) * 2;
+        if self.to_move == Player::Black {
+            ply + 1
         } else {
-            fen + " b"
+            ply
         }
     }
 
+    pub fn to_move(&self) -> Player {
+        self.to_move
+    }
+
     pub fn toggle_to_move(&mut self) {
         self.to_move = self.to_move.get_opposing_player();
     }
 
     pub fn is_cell_empty(&self, cell: Cell) -> bool {
-        !self.board.contains_key(&cell)
+        !self.position.occupied().contains(cell)
     }
 
+    #[allow(dead_code)]
     pub fn is_path_clear(&self, path: &Path) -> bool {
         path.iter().all(|cell| self.is_cell_empty(*cell))
     }
 
-    pub fn is_in_check(&self, player: Player) -> bool {
-        let king_loc = self.find_king_loc(player);
-        let opposing_player = player.get_opposing_player();
-        let opposing_pieces = self.find_player_piece_locs(opposing_player);
-
-        opposing_pieces
-            .iter()
-            .any(|opposing_piece_loc| self.does_piece_check_loc(*opposing_piece_loc, king_loc))
+    pub fn is_in_check(&self, player: Player) -> Result<bool, Error> {
+        let king_loc = self.find_king_loc(player)?;
+        Ok(self
+            .position
+            .is_attacked(king_loc, player.get_opposing_player()))
     }
 
-    // TODO convert to Ok/Err
-    pub fn does_piece_check_loc(&self, attacker_cell: Cell, target_cell: Cell) -> bool {
-        // let  = attacker_location;
-        // TODO use fns
-        let rank_diff = (target_cell.rank as i32) - (attacker_cell.rank as i32);
-        let file_diff = (target_cell.file as i32) - (attacker_cell.file as i32);
-
-        let is_vertical = rank_diff != 0 && file_diff == 0;
-        let is_horizontal = rank_diff == 0 && file_diff != 0;
-        let is_diagonal = rank_diff.abs() == file_diff.abs();
-        let is_orthogonal = is_vertical || is_horizontal;
-
-        let is_linear = is_vertical || is_horizontal || is_diagonal;
-
-        let path = if is_linear {
-            Path::generate_path(attacker_cell, target_cell)
-        } else {
-            Path::empty()
+    // Note: assume target is occupied, we're just checking if the attacker is applying check to
+    // the target
+    #[allow(dead_code)]
+    pub fn does_piece_check_loc(
+        &self,
+        attacker_cell: Cell,
+        target_cell: Cell,
+    ) -> Result<bool, Error> {
+        let attacker = match self.position.piece_at(attacker_cell) {
+            Some(attacker) => attacker,
+            None => {
+                return Err(Error::illegal_move(
+                    "does_piece_check_loc: no piece in attacker location",
+                ))
+            }
         };
 
-        // Note: assume target is occupied, we're just checking if the attacker is applying check to the target
-        // TODO: properly unwrap
-        if self.board.contains_key(&attacker_cell) {
-            // TODO move logic to piece
-            match self.board.get(&attacker_cell).unwrap().piece {
-                Piece::Pawn => {
-                    if self.board.get(&attacker_cell).unwrap().player == Player::White {
-                        file_diff.abs() == 1 && rank_diff == 1
-                    } else {
-                        file_diff.abs() == 1 && rank_diff == -1
-                    }
-                }
-                Piece::Bishop => is_diagonal && !is_orthogonal && self.is_path_clear(&path),
-                Piece::Knight => rank_diff.abs() + file_diff.abs() == 3 && !is_orthogonal,
-                Piece::Rook => !is_diagonal && is_orthogonal && self.is_path_clear(&path),
-                Piece::Queen => (is_diagonal || is_orthogonal) && self.is_path_clear(&path),
-                Piece::King => false,
-            }
-        } else {
-            // TODO convert to Ok/Err
-            panic!("does_piece_check_loc: no piece in attacker location");
-        }
+        Ok(attacker.piece != Piece::King
+            && bitboard::attacks(
+                attacker.piece,
+                attacker.player,
+                attacker_cell,
+                self.position.occupied(),
+            )
+            .contains(target_cell))
     }
 
+    #[allow(dead_code)]
     pub fn find_player_piece_locs(&self, player: Player) -> Vec<Cell> {
-        self.board
-            .keys()
-            .filter_map(|cell| {
-                let piece = self.board.get(cell).unwrap();
-
-                if piece.player == player {
-                    Some(*cell)
-                } else {
-                    None
-                }
-            })
-            .collect()
+        self.position.player_pieces(player).cells().collect()
     }
 
-    // TODO convert to Ok/Err
-    pub fn find_king_loc(&self, player: Player) -> Cell {
-        for cell in self.board.keys() {
-            let piece = self.board.get(cell).unwrap();
-            if piece.piece == Piece::King && piece.player == player {
-                return *cell;
-            }
-        }
-
-        // TODO convert to Ok/Err
-        panic!("find_king_loc: king not found on board");
+    pub fn find_king_loc(&self, player: Player) -> Result<Cell, Error> {
+        self.position
+            .pieces(player, Piece::King)
+            .first()
+            .ok_or_else(|| Error::illegal_move("find_king_loc: king not found on board"))
     }
 
-    // TODO convert to Ok/Err
-    pub fn execute_move(&mut self, piece: Piece, from_cell: Cell, to_cell: Cell) {
+    pub fn execute_move(
+        &mut self,
+        piece: Piece,
+        from_cell: Cell,
+        to_cell: Cell,
+    ) -> Result<(), Error> {
         // TODO use fns
         let diff_file = to_cell.file as i32 - from_cell.file as i32;
 
         // Special cases
         if piece == Piece::Pawn {
-            if diff_file != 0 && self.is_cell_empty(to_cell) {
+            if diff_file != 0 && self.en_passant == Some(to_cell) && self.is_cell_empty(to_cell) {
                 // En passant
                 self.clear(Cell {
                     file: to_cell.file,
@@ -183,189 +186,134 @@ impl Board {
                     Piece::Rook,
                     cell!(File::_H, from_cell.rank),
                     cell!(File::_F, to_cell.rank),
-                );
+                )?;
             } else if diff_file == -2 {
                 self.execute_move(
                     Piece::Rook,
                     cell!(File::_A, from_cell.rank),
                     cell!(File::_D, to_cell.rank),
-                );
+                )?;
             }
         }
 
-        self.set_piece(to_cell, *self.board.get(&from_cell).unwrap());
+        let moved_piece = match self.position.piece_at(from_cell) {
+            Some(moved_piece) => moved_piece,
+            None => {
+                return Err(Error::illegal_move(format!(
+                    "No piece to move from {from_cell:?}"
+                )))
+            }
+        };
+        self.set_piece(to_cell, moved_piece);
         self.clear(from_cell);
+
+        Ok(())
     }
 
-    // TODO convert to Ok/Err
-    pub fn find_origin(&self, piece: Piece, dest: Cell, from: PartialCell) -> Cell {
-        let possible_origins = self.find_possible_origins(piece, dest, from);
+    pub fn find_origin(&self, piece: Piece, dest: Cell, from: PartialCell) -> Result<Cell, Error> {
+        let origins = self.legal_origins(piece, dest, from)?;
 
-        let filtered_origins = possible_origins
-            .iter()
-            .filter(|possible_origin| {
-                if piece == Piece::Knight {
-                    true
-                } else {
-                    let path = Path::generate_path(**possible_origin, dest);
-                    self.is_path_clear(&path)
-                }
-            })
-            .filter(|possible_origin| {
-                let mut test_board = self.clone();
-
-                test_board.execute_move(piece, **possible_origin, dest);
-                !test_board.is_in_check(self.to_move)
-            })
-            .filter(|possible_origin| {
-                if piece == Piece::Pawn {
-                    let diff_file = dest.file as i32 - possible_origin.file as i32;
-
-                    // TODO: yeah, get rid of this!
-                    if (diff_file != 0
-                        && self.to_move == Player::White
-                        && dest.rank == Rank::_6
-                        && self.is_cell_empty(dest)
-                        && !self.is_cell_empty(cell!(dest.file, Rank::_5))
-                        && *self.board.get(&cell!(dest.file, Rank::_5)).unwrap()
-                            == (PlayerPiece {
-                                player: Player::Black,
-                                piece: Piece::Pawn,
-                            }))
-                        || (diff_file != 0
-                            && self.to_move == Player::Black
-                            && dest.rank == Rank::_3
-                            && self.is_cell_empty(dest)
-                            && !self.is_cell_empty(cell!(dest.file, Rank::_4))
-                            && *self.board.get(&cell!(dest.file, Rank::_4)).unwrap()
-                                == (PlayerPiece {
-                                    player: Player::White,
-                                    piece: Piece::Pawn,
-                                }))
-                    {
-                        true
-                    } else if self.is_cell_empty(dest) {
-                        diff_file == 0 // If not capturing, must not be diagonal
-                    } else {
-                        diff_file != 0 // If capturing, must be diagonal
-                    }
-                } else {
-                    true
-                }
-            })
-            .collect::<Vec<&Cell>>();
-
-        if filtered_origins.is_empty() {
-            // TODO convert to Ok/Err
-            panic!("No possible origins found");
-        } else if filtered_origins.len() > 1 {
-            // TODO convert to Ok/Err
-            panic!("Too many possible origins found: {filtered_origins:?}");
+        match origins.count() {
+            0 => Err(Error::illegal_move("No possible origins found")),
+            1 => Ok(origins.first().unwrap()),
+            _ => Err(Error::illegal_move(format!(
+                "Too many possible origins found: {:?}",
+                origins.cells().collect::<Vec<_>>()
+            ))),
+        }
+    }
+
+    // Cells from which a piece of the player to move can legally go to the destination
+    pub fn legal_origins(
+        &self,
+        piece: Piece,
+        dest: Cell,
+        from: PartialCell,
+    ) -> Result<Bitboard, Error> {
+        if self.position.player_pieces(self.to_move).contains(dest) {
+            return Ok(Bitboard::EMPTY);
+        }
+
+        let occupied = self.position.occupied();
+        let reachable = if piece != Piece::Pawn {
+            // Every other piece attacks the cells it moves from when standing on the destination
+            bitboard::attacks(piece, self.to_move, dest, occupied)
         } else {
-            *filtered_origins[0]
+            // Pawns move straight to an empty cell, or diagonally to capture, which is where an
+            // opposing pawn on the destination would attack
+            let mut reachable = Bitboard::EMPTY;
+            if self.is_cell_empty(dest) {
+                reachable |= bitboard::pawn_push_origins(self.to_move, dest, occupied);
+            }
+            if !self.is_cell_empty(dest) || self.en_passant == Some(dest) {
+                let opponent = self.to_move.get_opposing_player();
+                reachable |= bitboard::pawn_attacks(opponent, dest);
+            }
+            reachable
+        };
+        let candidates =
+            reachable & self.position.pieces(self.to_move, piece) & Bitboard::matching(from);
+
+        // Moves that would leave the mover's own king in check are not legal
+        let mut legal_origins = Bitboard::EMPTY;
+        for candidate in candidates.cells() {
+            let mut test_board = *self;
+            test_board.execute_move(piece, candidate, dest)?;
+            if !test_board.is_in_check(self.to_move)? {
+                legal_origins |= Bitboard::from_cell(candidate);
+            }
         }
+
+        Ok(legal_origins)
     }
 
-    // Return a list locations that contain the matching piece and that piece could move to the destination __if__ it was an otherwise empty board
+    // Return a list locations that contain the matching piece and that piece could move to the
+    // destination __if__ it was an otherwise empty board
+    #[allow(dead_code)]
     pub fn find_possible_origins(
         &self,
         piece: Piece,
         dest_cell: Cell,
         from_cell: PartialCell,
     ) -> Vec<Cell> {
-        let search_ranks = from_cell.possible_ranks();
-        let search_files = from_cell.possible_files();
-
-        let search_cells = (search_ranks.iter()).cartesian_product(search_files.iter());
+        let reachable = match piece {
+            Piece::Pawn => {
+                bitboard::pawn_attacks(self.to_move.get_opposing_player(), dest_cell)
+                    | bitboard::pawn_push_origins(self.to_move, dest_cell, Bitboard::EMPTY)
+            }
+            // King: will never have to disambiguate, so just use it once we find it
+            Piece::King => !Bitboard::EMPTY,
+            _ => bitboard::attacks(piece, self.to_move, dest_cell, Bitboard::EMPTY),
+        };
 
-        search_cells
-            .filter(|(rank, file)| {
-                let cell = Cell {
-                    rank: **rank,
-                    file: **file,
-                };
-                self.board.contains_key(&cell)
-            })
-            .filter_map(|(rank, file)| {
-                let cell = Cell {
-                    rank: *rank,
-                    file: *file,
-                };
-                let found_piece = self.board.get(&cell).unwrap();
-
-                let rank_diff = dest_cell.rank as i32 - cell.rank as i32;
-                let file_diff = dest_cell.file as i32 - cell.file as i32;
-
-                // TODO migrate piece logic to piece type
-                if found_piece.piece == piece && found_piece.player == self.to_move {
-                    if piece == Piece::Pawn {
-                        if self.to_move == Player::White {
-                            if (cell.rank == Rank::_2 && rank_diff == 2 && file_diff == 0)
-                                || (rank_diff == 1 && file_diff.abs() <= 1)
-                            {
-                                Some(cell)
-                            } else {
-                                None
-                            }
-                        } else if (cell.rank == Rank::_7 && rank_diff == -2 && file_diff == 0)
-                            || (rank_diff == -1 && file_diff.abs() <= 1)
-                        {
-                            Some(cell)
-                        } else {
-                            None
-                        }
-                    } else if piece == Piece::Bishop {
-                        if rank_diff.abs() == file_diff.abs() {
-                            Some(cell)
-                        } else {
-                            None
-                        }
-                    } else if piece == Piece::Knight {
-                        if (rank_diff.abs() == 2 && file_diff.abs() == 1)
-                            || (rank_diff.abs() == 1 && file_diff.abs() == 2)
-                        {
-                            Some(cell)
-                        } else {
-                            None
-                        }
-                    } else if piece == Piece::Rook {
-                        if (rank_diff != 0 && file_diff == 0) || (rank_diff == 0 && file_diff != 0)
-                        {
-                            Some(cell)
-                        } else {
-                            None
-                        }
-                    } else if piece == Piece::Queen {
-                        if (rank_diff != 0 && file_diff == 0)
-                            || (rank_diff == 0 && file_diff != 0)
-                            || (rank_diff.abs() == file_diff.abs())
-                        {
-                            Some(cell)
-                        } else {
-                            None
-                        }
-                    } else {
-                        // King: will never have to disambiguate, so just use it once we find it
-                        Some(cell)
-                    }
-                } else {
-                    None
-                }
-            })
+        (reachable & self.position.pieces(self.to_move, piece) & Bitboard::matching(from_cell))
+            .cells()
             .collect()
     }
 
-    // TODO convert to Ok/Err
-    pub fn move_piece(&mut self, move_description: Move) {
+    pub fn move_piece(&mut self, move_description: Move) -> Result<(), Error> {
         let piece_moved = move_description.piece_moved;
+        let to_cell = move_description.to;
 
         let from_cell = if move_description.from.is_fully_defined() {
             move_description.from.to_cell()
         } else {
-            self.find_origin(piece_moved, move_description.to, move_description.from)
+            self.find_origin(piece_moved, to_cell, move_description.from)?
         };
 
-        self.execute_move(piece_moved, from_cell, move_description.to);
+        let diff_file = to_cell.file as i32 - from_cell.file as i32;
+        if piece_moved == Piece::King && diff_file.abs() == 2 {
+            let rook_file = if diff_file > 0 { File::_H } else { File::_A };
+            if !self.castling.can_castle(self.to_move, rook_file) {
+                return Err(Error::illegal_move(format!(
+                    "Castling from {from_cell:?} without the right to"
+                )));
+            }
+        }
+
+        let resets_clock = piece_moved == Piece::Pawn || !self.is_cell_empty(to_cell);
+        self.execute_move(piece_moved, from_cell, to_cell)?;
+        self.update_state(piece_moved, from_cell, to_cell, resets_clock);
 
         if move_description.promoted_to.is_some() {
             self.set_piece(
@@ -378,29 +326,357 @@ impl Board {
         }
 
         self.toggle_to_move();
+
+        Ok(())
+    }
+
+    // Every move the player to move can legally make, with its origin fully defined. Whether
+    // a move mates is left unset, as finding out needs the replies to it.
+    pub fn legal_moves(&self) -> Vec<Move> {
+        let player = self.to_move;
+        let own = self.position.player_pieces(player);
+        let opponents = self.position.player_pieces(player.get_opposing_player());
+        let occupied = self.position.occupied();
+        let en_passant = self.en_passant.map_or(Bitboard::EMPTY, Bitboard::from_cell);
+
+        let mut moves = vec![];
+        for piece in Piece::all_pieces() {
+            for from_cell in self.position.pieces(player, piece).cells() {
+                let targets = if piece == Piece::Pawn {
+                    bitboard::pawn_pushes(player, from_cell, occupied)
+                        | (bitboard::pawn_attacks(player, from_cell) & (opponents | en_passant))
+                } else {
+                    bitboard::attacks(piece, player, from_cell, occupied) & !own
+                };
+
+                for to_cell in targets.cells() {
+                    self.push_if_legal(&mut moves, piece, from_cell, to_cell);
+                }
+            }
+        }
+
+        for rook_file in [File::_H, File::_A] {
+            if let Some((from_cell, to_cell)) = self.castling_cells(rook_file) {
+                self.push_if_legal(&mut moves, Piece::King, from_cell, to_cell);
+            }
+        }
+
+        moves
+    }
+
+    // Number of pieces of the given kind on the board, of either player
+    pub fn piece_count(&self, piece: Piece) -> u32 {
+        self.position.pieces(Player::White, piece).count()
+            + self.position.pieces(Player::Black, piece).count()
+    }
+
+    pub fn halfmove_clock(&self) -> u16 {
+        self.halfmove_clock
+    }
+
+    pub fn is_checkmate(&self) -> bool {
+        self.legal_moves().is_empty() && self.is_in_check(self.to_move).unwrap_or(false)
+    }
+
+    pub fn is_stalemate(&self) -> bool {
+        self.legal_moves().is_empty() && !self.is_in_check(self.to_move).unwrap_or(false)
+    }
+
+    // Neither side has the pieces left to deliver mate: bare kings, a single minor piece, or
+    // bishops that all stand on cells of one colour
+    pub fn has_insufficient_material(&self) -> bool {
+        let of_both = |piece| {
+            self.position.pieces(Player::White, piece) | self.position.pieces(Player::Black, piece)
+        };
+
+        if [Piece::Pawn, Piece::Rook, Piece::Queen]
+            .into_iter()
+            .any(|piece| !of_both(piece).is_empty())
+        {
+            return false;
+        }
+
+        let knights = of_both(Piece::Knight);
+        let bishops = of_both(Piece::Bishop);
+        if (knights | bishops).count() <= 1 {
+            return true;
+        }
+
+        knights.is_empty()
+            && ((bishops & Bitboard::LIGHT_CELLS).is_empty()
+                || (bishops & !Bitboard::LIGHT_CELLS).is_empty())
+    }
+
+    // Whether both boards count as the same position for the repetition rules. An en passant
+    // cell only matters when a pawn could actually take on it.
+    pub fn is_repetition_of(&self, other: &Board) -> bool {
+        self.position == other.position
+            && self.to_move == other.to_move
+            && self.castling == other.castling
+            && self.capturable_en_passant() == other.capturable_en_passant()
+    }
+
+    // En passant cell a pawn of the side to move attacks, whether or not taking is legal
+    fn attacked_en_passant(&self) -> Option<Cell> {
+        self.en_passant.filter(|cell| {
+            let capturers = bitboard::pawn_attacks(self.to_move.get_opposing_player(), *cell)
+                & self.position.pieces(self.to_move, Piece::Pawn);
+            !capturers.is_empty()
+        })
+    }
+
+    fn capturable_en_passant(&self) -> Option<Cell> {
+        self.attacked_en_passant().filter(|cell| {
+            self.legal_moves()
+                .iter()
+                .any(|curr_move| curr_move.piece_moved == Piece::Pawn && curr_move.to == *cell)
+        })
+    }
+
+    // Zobrist hash of the placement, side to move, castling rights and any en passant capture
+    // that is possible, so boards that repeat each other hash the same
+    pub fn zobrist_hash(&self) -> u64 {
+        let mut hash = 0;
+        for player in [Player::White, Player::Black] {
+            for piece in Piece::all_pieces() {
+                for cell in self.position.pieces(player, piece).cells() {
+                    hash ^= zobrist::piece_key(player, piece, cell);
+                }
+            }
+
+            for rook_file in [File::_H, File::_A] {
+                if self.castling.can_castle(player, rook_file) {
+                    hash ^= zobrist::castling_key(player, rook_file);
+                }
+            }
+        }
+
+        if self.to_move == Player::Black {
+            hash ^= zobrist::black_to_move_key();
+        }
+
+        if let Some(cell) = self.capturable_en_passant() {
+            hash ^= zobrist::en_passant_key(cell.file);
+        }
+
+        hash
+    }
+
+    // Key of the position in Polyglot opening books, which hash the en passant file whenever a
+    // pawn could take on it, even if the capture would be illegal
+    pub fn polyglot_key(&self) -> u64 {
+        let mut key = 0;
+        for player in [Player::White, Player::Black] {
+            for piece in Piece::all_pieces() {
+                for cell in self.position.pieces(player, piece).cells() {
+                    key ^= polyglot::piece_key(player, piece, cell);
+                }
+            }
+
+            for rook_file in [File::_H, File::_A] {
+                if self.castling.can_castle(player, rook_file) {
+                    key ^= polyglot::castling_key(player, rook_file);
+                }
+            }
+        }
+
+        if let Some(cell) = self.attacked_en_passant() {
+            key ^= polyglot::en_passant_key(cell.file);
+        }
+
+        if self.to_move == Player::White {
+            key ^= polyglot::white_to_move_key();
+        }
+
+        key
+    }
+
+    // Number of move sequences of the given length from this position
+    #[allow(dead_code)]
+    pub fn perft(&self, depth: u32) -> Result<u64, Error> {
+        if depth == 0 {
+            return Ok(1);
+        }
+
+        let moves = self.legal_moves();
+        if depth == 1 {
+            return Ok(moves.len() as u64);
+        }
+
+        let mut count = 0;
+        for curr_move in moves {
+            let mut board = *self;
+            board.move_piece(curr_move)?;
+            count += board.perft(depth - 1)?;
+        }
+
+        Ok(count)
+    }
+
+    // Cells the king goes from and to when castling with the rook on `rook_file`, if the player
+    // to move still has the right to, nothing stands in between and the king doesn't start,
+    // pass or land on an attacked cell
+    fn castling_cells(&self, rook_file: File) -> Option<(Cell, Cell)> {
+        let player = self.to_move;
+        if !self.castling.can_castle(player, rook_file) {
+            return None;
+        }
+
+        let rank = if player == Player::White {
+            Rank::_1
+        } else {
+            Rank::_8
+        };
+        let king = PlayerPiece {
+            piece: Piece::King,
+            player,
+        };
+        let rook = PlayerPiece {
+            piece: Piece::Rook,
+            player,
+        };
+        let from_cell = cell!(File::_E, rank);
+        if self.position.piece_at(from_cell) != Some(king)
+            || self.position.piece_at(cell!(rook_file, rank)) != Some(rook)
+        {
+            return None;
+        }
+
+        let (between, king_path): (&[File], [File; 3]) = if rook_file == File::_H {
+            (&[File::_F, File::_G], [File::_E, File::_F, File::_G])
+        } else {
+            (
+                &[File::_B, File::_C, File::_D],
+                [File::_E, File::_D, File::_C],
+            )
+        };
+        let opponent = player.get_opposing_player();
+        if between
+            .iter()
+            .any(|file| !self.is_cell_empty(cell!(*file, rank)))
+            || king_path
+                .iter()
+                .any(|file| self.position.is_attacked(cell!(*file, rank), opponent))
+        {
+            return None;
+        }
+
+        Some((from_cell, cell!(king_path[2], rank)))
+    }
+
+    fn push_if_legal(&self, moves: &mut Vec<Move>, piece: Piece, from_cell: Cell, to_cell: Cell) {
+        let player = self.to_move;
+        let captures = !self.is_cell_empty(to_cell)
+            || (piece == Piece::Pawn && from_cell.file != to_cell.file);
+
+        let mut board = *self;
+        if board.execute_move(piece, from_cell, to_cell).is_err()
+            || board.is_in_check(player).unwrap_or(true)
+        {
+            return;
+        }
+
+        let mut curr_move = Move::new_to_from(
+            Some(from_cell.file),
+            Some(from_cell.rank),
+            to_cell.file,
+            to_cell.rank,
+            piece,
+        );
+        curr_move.captures = captures;
+
+        let last_rank = if player == Player::White {
+            Rank::_8
+        } else {
+            Rank::_1
+        };
+        if piece == Piece::Pawn && to_cell.rank == last_rank {
+            for promoted_to in [Piece::Queen, Piece::Rook, Piece::Bishop, Piece::Knight] {
+                board.set_piece(
+                    to_cell,
+                    PlayerPiece {
+                        piece: promoted_to,
+                        player,
+                    },
+                );
+                curr_move.promoted_to = OptionalPiece::new_some(promoted_to);
+                curr_move.checks = board.gives_check();
+                moves.push(curr_move);
+            }
+        } else {
+            curr_move.checks = board.gives_check();
+            moves.push(curr_move);
+        }
+    }
+
+    // Whether the player to move has put the opponent in check, on a board the move was executed
+    // on without handing the turn over
+    fn gives_check(&self) -> bool {
+        self.is_in_check(self.to_move.get_opposing_player())
+            .unwrap_or(false)
+    }
+
+    // Castling rights, en passant cell and clocks after the player to move made a move
+    fn update_state(&mut self, piece: Piece, from_cell: Cell, to_cell: Cell, resets_clock: bool) {
+        let player = self.to_move;
+        if piece == Piece::King {
+            self.castling.remove(player, File::_A);
+            self.castling.remove(player, File::_H);
+        }
+
+        // A rook leaving or captured on its starting cell can no longer castle
+        for (cell, owner) in [
+            (cell!(File::_A, Rank::_1), Player::White),
+            (cell!(File::_H, Rank::_1), Player::White),
+            (cell!(File::_A, Rank::_8), Player::Black),
+            (cell!(File::_H, Rank::_8), Player::Black),
+        ] {
+            if cell == from_cell || cell == to_cell {
+                self.castling.remove(owner, cell.file);
+            }
+        }
+
+        let diff_rank = to_cell.rank as i32 - from_cell.rank as i32;
+        self.en_passant = if piece == Piece::Pawn && diff_rank.abs() == 2 {
+            Some(cell!(from_cell.file, from_cell.rank.shift(diff_rank / 2)))
+        } else {
+            None
+        };
+
+        self.halfmove_clock = if resets_clock {
+            0
+        } else {
+            self.halfmove_clock.saturating_add(1)
+        };
+        if player == Player::Black {
+            self.fullmove_number = self.fullmove_number.saturating_add(1);
+        }
     }
 
     pub fn set_piece(&mut self, cell: Cell, piece: PlayerPiece) {
-        self.board.insert(cell, piece);
+        self.position.set(cell, piece);
     }
 
     pub fn clear(&mut self, cell: Cell) {
-        self.board.remove(&cell);
+        self.position.clear(cell);
     }
 
     pub fn empty() -> Self {
         Board {
-            board: HashMap::default(),
+            position: Position::default(),
             to_move: Player::NA,
+            castling: CastlingRights::default(),
+            en_passant: None,
+            halfmove_clock: 0,
+            fullmove_number: 1,
         }
     }
 
-    #[allow(dead_code)]
-    pub fn from_fen(fen: &str) -> Result<Self, String> {
+    pub fn from_fen(fen: &str) -> Result<Self, Error> {
         if fen.is_empty() {
-            Err("Cannot parse empty FEN".to_string())
+            Err(Error::pgn("Cannot parse empty FEN"))
         } else {
-            let fields: Vec<&str> = fen.split(' ').collect();
+            let fields: Vec<&str> = fen.split_whitespace().collect();
 
             if fields.len() == 6 {
                 let ranks: Vec<&str> = fields.first().unwrap().split('/').collect();
@@ -408,11 +684,11 @@ impl Board {
                 if ranks.len() == 8 {
                     let mut board = Board::empty();
 
-                    if fields.get(1).unwrap() == &"b" {
-                        board.to_move = Player::Black;
-                    } else {
-                        board.to_move = Player::White;
-                    }
+                    board.to_move = match fields[1] {
+                        "w" => Player::White,
+                        "b" => Player::Black,
+                        u => return Err(Error::pgn(format!("Unrecognized side to move: {u}"))),
+                    };
 
                     for (rank, fen_rank) in ranks.iter().enumerate() {
                         let mut file = 1;
@@ -420,6 +696,9 @@ impl Board {
                             if c.is_ascii_digit() {
                                 file += parse_ascii_digit(c).unwrap();
                             } else {
+                                if !"PNBRQKpnbrqk".contains(c) {
+                                    return Err(Error::pgn(format!("Unrecognized piece: {c}")));
+                                }
                                 let piece = PlayerPiece {
                                     piece: Piece::from_fen(c.to_string().as_ref()),
                                     player: if c.is_ascii_uppercase() {
@@ -429,7 +708,7 @@ impl Board {
                                     },
                                 };
 
-                                let piece_file = File::try_from(file)?;
+                                let piece_file = File::try_from(file).map_err(Error::pgn)?;
                                 let piece_rank = Rank::from_usize(8 - rank);
 
                                 board.set_piece(cell!(piece_file, piece_rank), piece);
@@ -438,22 +717,47 @@ impl Board {
                         }
                     }
 
+                    board.castling = CastlingRights::from_fen(fields[2]).map_err(Error::pgn)?;
+                    board.en_passant = parse_en_passant(fields[3]).map_err(Error::pgn)?;
+                    board.halfmove_clock = fields[4].parse().map_err(|_| {
+                        Error::pgn(format!("Invalid halfmove clock: {}", fields[4]))
+                    })?;
+                    board.fullmove_number = fields[5].parse().map_err(|_| {
+                        Error::pgn(format!("Invalid fullmove number: {}", fields[5]))
+                    })?;
+
                     Ok(board)
                 } else {
-                    Err("Starting position has wrong number of rows".to_string())
+                    Err(Error::pgn("Starting position has wrong number of rows"))
                 }
             } else {
-                Err("Incorrect number of fields".to_string())
+                Err(Error::pgn("Incorrect number of fields"))
             }
         }
     }
 }
 
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+fn parse_en_passant(field: &str) -> Result<Option<Cell>, String> {
+    if field == "-" {
+        return Ok(None);
+    }
+
+    let mut chars = field.chars();
+    match (chars.next(), chars.next(), chars.next()) {
+        (Some(file @ 'a'..='h'), Some(rank @ '1'..='8'), None) => Ok(Some(cell!(
+            File::from_pgn(&file.to_string()).unwrap(),
+            Rank::from_pgn(&rank.to_string()).unwrap()
+        ))),
+        _ => Err(format!("Invalid en passant cell: {field}")),
+    }
+}
+
 #[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
 impl Default for Board {
     fn default() -> Board {
         Board {
-            board: [
+            position: [
                 (cell!(File::_A, Rank::_1), white!(Piece::Rook)),
                 (cell!(File::_B, Rank::_1), white!(Piece::Knight)),
                 (cell!(File::_C, Rank::_1), white!(Piece::Bishop)),
@@ -492,6 +796,10 @@ impl Default for Board {
             .collect(),
 
             to_move: Player::White,
+            castling: CastlingRights::all(),
+            en_passant: None,
+            halfmove_clock: 0,
+            fullmove_number: 1,
         }
     }
 }
@@ -513,13 +821,18 @@ mod test_from_fen {
     }
 
     tests! {
-        test_empty_fen: ("", Err("Cannot parse empty FEN".to_string())),
+        test_empty_fen: ("", Err(Error::pgn("Cannot parse empty FEN"))),
         test_default_fen: ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", Ok(Board::default())),
-        test_only_board_portion: ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR", Err("Incorrect number of fields".to_string())),
-        test_not_enough_rows: ("rnbqkbnr/pppppppp/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", Err("Starting position has wrong number of rows".to_string())),
+        test_bad_piece: ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNX w KQkq - 0 1", Err(Error::pgn("Unrecognized piece: X"))),
+        test_bad_side: ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR x KQkq - 0 1", Err(Error::pgn("Unrecognized side to move: x"))),
+        test_bad_castling: ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkx - 0 1", Err(Error::pgn("Unrecognized castling right: x"))),
+        test_bad_en_passant: ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq e9 0 1", Err(Error::pgn("Invalid en passant cell: e9"))),
+        test_bad_halfmove: ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - x 1", Err(Error::pgn("Invalid halfmove clock: x"))),
+        test_only_board_portion: ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR", Err(Error::pgn("Incorrect number of fields"))),
+        test_not_enough_rows: ("rnbqkbnr/pppppppp/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", Err(Error::pgn("Starting position has wrong number of rows"))),
         test_black_to_move: ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR b KQkq - 0 1", Ok(
             Board {
-                board: [
+                position: [
                     (cell!(File::_A, Rank::_1), white!(Piece::Rook)),
                     (cell!(File::_B, Rank::_1), white!(Piece::Knight)),
                     (cell!(File::_C, Rank::_1), white!(Piece::Bishop)),
@@ -558,10 +871,11 @@ mod test_from_fen {
                 .collect(),
 
                 to_move: Player::Black,
+                ..Board::default()
             })),
         test_valid_fen_1: ("r1bqkb1r/pp1npppp/2p2N2/8/2PP4/8/PP3PPP/R1BQKBNR b KQkq - 0 6", Ok(
             Board {
-                board: [
+                position: [
                     (cell!(File::_A, Rank::_1), white!(Piece::Rook)),
                     (cell!(File::_C, Rank::_1), white!(Piece::Bishop)),
                     (cell!(File::_D, Rank::_1), white!(Piece::Queen)),
@@ -600,12 +914,14 @@ mod test_from_fen {
                 .iter()
                 .cloned()
                 .collect(),
-                to_move: Player::Black
+                to_move: Player::Black,
+                fullmove_number: 6,
+                ..Board::default()
             }
         )),
         test_valid_fen_2: ("r2rb1k1/pp2qpbp/2n2np1/6N1/4P3/2N1B1PP/PPP1QPB1/3RR1K1 w - - 5 17", Ok(
             Board {
-                board: [
+                position: [
                     (cell!(File::_D, Rank::_1), white!(Piece::Rook)),
                     (cell!(File::_E, Rank::_1), white!(Piece::Rook)),
                     (cell!(File::_G, Rank::_1), white!(Piece::King)),
@@ -642,7 +958,11 @@ mod test_from_fen {
                     (cell!(File::_E, Rank::_8), black!(Piece::Bishop)),
                     (cell!(File::_G, Rank::_8), black!(Piece::King)),
                 ].iter().cloned().collect(),
-                to_move: Player::White
+                to_move: Player::White,
+                castling: CastlingRights::default(),
+                en_passant: None,
+                halfmove_clock: 5,
+                fullmove_number: 17,
             }
         )),
     }
@@ -665,10 +985,11 @@ mod test_to_fen {
     }
 
     tests! {
-        test_initial_white: ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w"),
-        test_initial_black: ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR b KQkq - 0 1", "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR b"),
-        test_other_1: ("r1bqkb1r/pp1npppp/2p2N2/8/2PP4/8/PP3PPP/R1BQKBNR b KQkq - 0 6", "r1bqkb1r/pp1npppp/2p2N2/8/2PP4/8/PP3PPP/R1BQKBNR b"),
-        test_other_2: ("r2rb1k1/pp2qpbp/2n2np1/6N1/4P3/2N1B1PP/PPP1QPB1/3RR1K1 w - - 5 17", "r2rb1k1/pp2qpbp/2n2np1/6N1/4P3/2N1B1PP/PPP1QPB1/3RR1K1 w"),
+        test_initial_white: ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"),
+        test_initial_black: ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR b KQkq - 0 1", "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR b KQkq - 0 1"),
+        test_other_1: ("r1bqkb1r/pp1npppp/2p2N2/8/2PP4/8/PP3PPP/R1BQKBNR b KQkq - 0 6", "r1bqkb1r/pp1npppp/2p2N2/8/2PP4/8/PP3PPP/R1BQKBNR b KQkq - 0 6"),
+        test_other_2: ("r2rb1k1/pp2qpbp/2n2np1/6N1/4P3/2N1B1PP/PPP1QPB1/3RR1K1 w - - 5 17", "r2rb1k1/pp2qpbp/2n2np1/6N1/4P3/2N1B1PP/PPP1QPB1/3RR1K1 w - - 5 17"),
+        test_en_passant_cell: ("rnbqkbnr/ppppp1p1/8/4Pp1p/8/8/PPPP1PPP/RNBQKBNR w Kq f6 12 3", "rnbqkbnr/ppppp1p1/8/4Pp1p/8/8/PPPP1PPP/RNBQKBNR w Kq f6 12 3"),
     }
 }
 
@@ -715,10 +1036,10 @@ mod test_toggle_to_move {
     }
 
     tests! {
-        test_initial_white: ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR b"),
-        test_initial_black: ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR b KQkq - 0 1", "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w"),
-        test_other_1: ("r1bqkb1r/pp1npppp/2p2N2/8/2PP4/8/PP3PPP/R1BQKBNR b KQkq - 0 6", "r1bqkb1r/pp1npppp/2p2N2/8/2PP4/8/PP3PPP/R1BQKBNR w"),
-        test_other_2: ("r2rb1k1/pp2qpbp/2n2np1/6N1/4P3/2N1B1PP/PPP1QPB1/3RR1K1 w - - 5 17", "r2rb1k1/pp2qpbp/2n2np1/6N1/4P3/2N1B1PP/PPP1QPB1/3RR1K1 b"),
+        test_initial_white: ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR b KQkq - 0 1"),
+        test_initial_black: ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR b KQkq - 0 1", "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"),
+        test_other_1: ("r1bqkb1r/pp1npppp/2p2N2/8/2PP4/8/PP3PPP/R1BQKBNR b KQkq - 0 6", "r1bqkb1r/pp1npppp/2p2N2/8/2PP4/8/PP3PPP/R1BQKBNR w KQkq - 0 6"),
+        test_other_2: ("r2rb1k1/pp2qpbp/2n2np1/6N1/4P3/2N1B1PP/PPP1QPB1/3RR1K1 w - - 5 17", "r2rb1k1/pp2qpbp/2n2np1/6N1/4P3/2N1B1PP/PPP1QPB1/3RR1K1 b - - 5 17"),
     }
 }
 
@@ -762,7 +1083,7 @@ mod test_find_king_loc {
             #[test]
             fn $name() {
                 let (board, input, expected) = $value;
-                assert_eq!(Cell::from_indices(expected), Board::from_fen(board).unwrap().find_king_loc(input));
+                assert_eq!(Cell::from_indices(expected), Board::from_fen(board).unwrap().find_king_loc(input).unwrap());
             }
         )*
         }
@@ -780,20 +1101,19 @@ mod test_find_king_loc {
         test_missing_black_white: ("r2rb11K/pp2qpbp/2n2np1/6N1/4P3/2N1B1PP/PPP1QPB1/3RR3 w - - 5 17", Player::White, (7, 7)),
     }
 
-    macro_rules! tests_panic {
+    macro_rules! tests_err {
         ($($name:ident: $value:expr,)*) => {
         $(
             #[test]
-            #[should_panic(expected="find_king_loc: king not found on board")]
             fn $name() {
                 let (board, input) = $value;
-                Board::from_fen(board).unwrap().find_king_loc(input);
+                assert_eq!(Err(Error::illegal_move("find_king_loc: king not found on board")), Board::from_fen(board).unwrap().find_king_loc(input));
             }
         )*
         }
     }
 
-    tests_panic! {
+    tests_err! {
         test_missing_white_white: ("r2rb1k1/pp2qpbp/2n2np1/6N1/4P3/2N1B1PP/PPP1QPB1/3RR3 w - - 5 17", Player::White),
         test_missing_black_black: ("r2rb11K/pp2qpbp/2n2np1/6N1/4P3/2N1B1PP/PPP1QPB1/3RR3 w - - 5 17", Player::Black),
     }
@@ -809,7 +1129,7 @@ mod test_does_piece_check_loc {
             #[test]
             fn $name() {
                 let (board, attacker, target, expected) = $value;
-                assert_eq!(expected, Board::from_fen(board).unwrap().does_piece_check_loc(Cell::from_indices(attacker), Cell::from_indices(target)));
+                assert_eq!(expected, Board::from_fen(board).unwrap().does_piece_check_loc(Cell::from_indices(attacker), Cell::from_indices(target)).unwrap());
             }
         )*
         }
@@ -853,21 +1173,20 @@ mod test_does_piece_check_loc {
         test_rook_6: ("8/8/2r5/8/8/4P3/3R4/8 w - - 0 1", (1, 3), (1, 4), true),
     }
 
-    macro_rules! tests_panic {
+    macro_rules! tests_err {
         ($($name:ident: $value:expr,)*) => {
         $(
             #[test]
-            #[should_panic(expected="does_piece_check_loc: no piece in attacker location")]
             fn $name() {
                 let (board, attacker, target) = $value;
-                Board::from_fen(board).unwrap().does_piece_check_loc(Cell::from_indices(attacker), Cell::from_indices(target));
+                assert_eq!(Err(Error::illegal_move("does_piece_check_loc: no piece in attacker location")), Board::from_fen(board).unwrap().does_piece_check_loc(Cell::from_indices(attacker), Cell::from_indices(target)));
             }
         )*
         }
     }
 
     // TODO: convert to actual cells
-    tests_panic! {
+    tests_err! {
         test_empty_board: ("8/8/8/8/8/8/8/8 w - - 0 1", (0, 0), (1, 1)),
         test_initial_board_1: ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", (2, 2), (1, 1)),
     }
@@ -910,7 +1229,7 @@ mod test_execute_move {
             fn $name() {
                 let (board, piece, from, to, expected) = $value;
                 let mut board = Board::from_fen(board).unwrap();
-                board.execute_move(piece, Cell::from_indices(from), Cell::from_indices(to));
+                board.execute_move(piece, Cell::from_indices(from), Cell::from_indices(to)).unwrap();
                 assert_eq!(expected, board.to_fen());
             }
         )*
@@ -919,14 +1238,14 @@ mod test_execute_move {
 
     // TODO: convert to actual cells
     tests! {
-        test_en_passant: ("r3kb1r/pp1ppppp/5n2/1Pp5/6B1/2P5/P2PPPPP/RNBQK2R w KQkq - 0 1", Piece::Pawn, (4, 1), (5, 2), "r3kb1r/pp1ppppp/2P2n2/8/6B1/2P5/P2PPPPP/RNBQK2R w"),
-        test_queenside: ("r3kb1r/pp1ppppp/5n2/1Pp5/6B1/2P5/P2PPPPP/RNBQK2R w KQkq - 0 1", Piece::King, (7, 4), (7, 2), "2kr1b1r/pp1ppppp/5n2/1Pp5/6B1/2P5/P2PPPPP/RNBQK2R w"),
-        test_kingside: ("r3kb1r/pp1ppppp/5n2/1Pp5/6B1/2P5/P2PPPPP/RNBQK2R w KQkq - 0 1", Piece::King, (0, 4), (0, 6), "r3kb1r/pp1ppppp/5n2/1Pp5/6B1/2P5/P2PPPPP/RNBQ1RK1 w"),
-        test_bishop_cap: ("r3kb1r/pp1ppppp/5n2/1Pp5/6B1/2P5/P2PPPPP/RNBQK2R w KQkq - 0 1", Piece::Bishop, (3, 6), (6, 3), "r3kb1r/pp1Bpppp/5n2/1Pp5/8/2P5/P2PPPPP/RNBQK2R w"),
-        test_king_normal: ("r3kb1r/pp1ppppp/5n2/1Pp5/6B1/2P5/P2PPPPP/RNBQK2R w KQkq - 0 1", Piece::King, (0, 4), (0, 5), "r3kb1r/pp1ppppp/5n2/1Pp5/6B1/2P5/P2PPPPP/RNBQ1K1R w"),
-        test_knight_cap: ("r3kb1r/pp1ppppp/5n2/1Pp5/6B1/2P5/P2PPPPP/RNBQK2R w KQkq - 0 1", Piece::Knight, (5, 5), (3, 6), "r3kb1r/pp1ppppp/8/1Pp5/6n1/2P5/P2PPPPP/RNBQK2R w"),
-        test_pawn_jump:  ("r3kb1r/pp1ppppp/5n2/1Pp5/6B1/2P5/P2PPPPP/RNBQK2R w KQkq - 0 1", Piece::Pawn, (1, 0), (3, 0), "r3kb1r/pp1ppppp/5n2/1Pp5/P5B1/2P5/3PPPPP/RNBQK2R w"),
-        test_pawn_normal: ("r3kb1r/pp1ppppp/5n2/1Pp5/6B1/2P5/P2PPPPP/RNBQK2R w KQkq - 0 1", Piece::Pawn, (1, 0), (2, 0), "r3kb1r/pp1ppppp/5n2/1Pp5/6B1/P1P5/3PPPPP/RNBQK2R w"),
+        test_en_passant: ("r3kb1r/pp1ppppp/5n2/1Pp5/6B1/2P5/P2PPPPP/RNBQK2R w KQkq c6 0 1", Piece::Pawn, (4, 1), (5, 2), "r3kb1r/pp1ppppp/2P2n2/8/6B1/2P5/P2PPPPP/RNBQK2R w KQkq c6 0 1"),
+        test_queenside: ("r3kb1r/pp1ppppp/5n2/1Pp5/6B1/2P5/P2PPPPP/RNBQK2R w KQkq - 0 1", Piece::King, (7, 4), (7, 2), "2kr1b1r/pp1ppppp/5n2/1Pp5/6B1/2P5/P2PPPPP/RNBQK2R w KQkq - 0 1"),
+        test_kingside: ("r3kb1r/pp1ppppp/5n2/1Pp5/6B1/2P5/P2PPPPP/RNBQK2R w KQkq - 0 1", Piece::King, (0, 4), (0, 6), "r3kb1r/pp1ppppp/5n2/1Pp5/6B1/2P5/P2PPPPP/RNBQ1RK1 w KQkq - 0 1"),
+        test_bishop_cap: ("r3kb1r/pp1ppppp/5n2/1Pp5/6B1/2P5/P2PPPPP/RNBQK2R w KQkq - 0 1", Piece::Bishop, (3, 6), (6, 3), "r3kb1r/pp1Bpppp/5n2/1Pp5/8/2P5/P2PPPPP/RNBQK2R w KQkq - 0 1"),
+        test_king_normal: ("r3kb1r/pp1ppppp/5n2/1Pp5/6B1/2P5/P2PPPPP/RNBQK2R w KQkq - 0 1", Piece::King, (0, 4), (0, 5), "r3kb1r/pp1ppppp/5n2/1Pp5/6B1/2P5/P2PPPPP/RNBQ1K1R w KQkq - 0 1"),
+        test_knight_cap: ("r3kb1r/pp1ppppp/5n2/1Pp5/6B1/2P5/P2PPPPP/RNBQK2R w KQkq - 0 1", Piece::Knight, (5, 5), (3, 6), "r3kb1r/pp1ppppp/8/1Pp5/6n1/2P5/P2PPPPP/RNBQK2R w KQkq - 0 1"),
+        test_pawn_jump:  ("r3kb1r/pp1ppppp/5n2/1Pp5/6B1/2P5/P2PPPPP/RNBQK2R w KQkq - 0 1", Piece::Pawn, (1, 0), (3, 0), "r3kb1r/pp1ppppp/5n2/1Pp5/P5B1/2P5/3PPPPP/RNBQK2R w KQkq - 0 1"),
+        test_pawn_normal: ("r3kb1r/pp1ppppp/5n2/1Pp5/6B1/2P5/P2PPPPP/RNBQK2R w KQkq - 0 1", Piece::Pawn, (1, 0), (2, 0), "r3kb1r/pp1ppppp/5n2/1Pp5/6B1/P1P5/3PPPPP/RNBQK2R w KQkq - 0 1"),
     }
 }
 
@@ -941,8 +1260,8 @@ mod test_is_in_check {
             fn $name() {
                 let (board, expected_white, expected_black) = $value;
                 let board = Board::from_fen(board).unwrap();
-                assert_eq!(expected_white, board.is_in_check(Player::White));
-                assert_eq!(expected_black, board.is_in_check(Player::Black));
+                assert_eq!(Ok(expected_white), board.is_in_check(Player::White));
+                assert_eq!(Ok(expected_black), board.is_in_check(Player::Black));
             }
         )*
         }
@@ -1034,6 +1353,7 @@ mod test_find_possible_origins {
 #[cfg(test)]
 mod test_find_origin {
     use super::*;
+    use crate::error::ErrorKind;
 
     macro_rules! tests {
         ($($name:ident: $value:expr,)*) => {
@@ -1042,14 +1362,14 @@ mod test_find_origin {
             fn $name() {
                 let (board, piece, dest, from, expected): (&str, Piece, Cell, PartialCell, Cell) = $value;
                 let board = Board::from_fen(board).unwrap();
-                assert_eq!(expected, board.find_origin(piece, dest, from));
+                assert_eq!(Ok(expected), board.find_origin(piece, dest, from));
             }
         )*
         }
     }
 
     tests! {
-        test_pawn_1: ("3bR3/2pP2KN/qprn1kpB/2b1pR1N/P2n1B1P/1PP2pQ1/1r1QP2B/6q1 w - - 0 1", Piece::Pawn, cell!(File::_A, Rank::_5), partial_cell!(None, None), cell!(File::_A, Rank::_4)),
+        test_pawn_1: ("k2bR3/2pP2KN/qprn2pB/2b1pR1N/P2n1B1P/1PP2pQ1/1r1QP2B/6q1 w - - 0 1", Piece::Pawn, cell!(File::_A, Rank::_5), partial_cell!(None, None), cell!(File::_A, Rank::_4)),
         test_pawn_2: ("k2bR3/2pP2KN/qprn2pB/P1b1pR1N/P2n1B1P/1PP2pQ1/1r1QP2B/6q1 b - - 0 1", Piece::Pawn, cell!(File::_A, Rank::_5), partial_cell!(None, None), cell!(File::_B, Rank::_6)),
         test_pawn_3: ("3bR2K/2pP3N/qprn1kpB/2b1pR1N/P2n1B1P/1PP2pQ1/1r1QP2B/6q1 w - - 0 1", Piece::Pawn, cell!(File::_B, Rank::_4), partial_cell!(Some(File::_B), None), cell!(File::_B, Rank::_3)),
         test_pawn_4: ("3bR2K/2pP3N/qprn1kpB/2b1pR1N/Pq1n1B1P/1PP2pQ1/1r1QP2B/6q1 w - - 0 1", Piece::Pawn, cell!(File::_B, Rank::_4), partial_cell!(None, None), cell!(File::_C, Rank::_3)),
@@ -1057,25 +1377,25 @@ mod test_find_origin {
         test_pawn_6: ("rnbqkbnr/pp1ppppp/8/8/PPpP4/8/2P1PPPP/RNBQKBNR b KQkq d3 0 3", Piece::Pawn, cell!(File::_D, Rank::_3), partial_cell!(Some(File::_C), None), cell!(File::_C, Rank::_4)),
         test_pawn_7: ("rnbqkbnr/pppp1p2/1P6/8/2P1P1Pp/3p4/P5PP/RNBQKBNR w KQkq - 0 9", Piece::Pawn, cell!(File::_G, Rank::_3), partial_cell!(None, None), cell!(File::_G, Rank::_2)),
         test_pawn_8: ("rnbqkbnr/1pppp1pp/8/p5p1/3P4/4P3/PPP2P1P/RNBQKBNR b KQkq - 0 4", Piece::Pawn, cell!(File::_G, Rank::_6), partial_cell!(None, None), cell!(File::_G, Rank::_7)),
-        test_bishop_1: ("3bR3/2pP2KN/qprn1kpB/2b1pR1N/P2n1B1P/1PP2pQ1/1r1QP2B/6q1 w - - 0 1", Piece::Bishop, cell!(File::_G, Rank::_5), partial_cell!(None, Some(Rank::_4)), cell!(File::_F, Rank::_4)),
-        test_bishop_2: ("3bR3/2pP2KN/qprn1kpB/2b1pR1N/P2n1B1P/1PP2pQ1/1r1QP2B/6q1 w - - 0 1", Piece::Bishop, cell!(File::_G, Rank::_5), partial_cell!(Some(File::_H), None), cell!(File::_H, Rank::_6)),
+        test_bishop_1: ("k2bR3/2pP2KN/qprn2pB/2b1pR1N/P2n1B1P/1PP2pQ1/1r1QP2B/6q1 w - - 0 1", Piece::Bishop, cell!(File::_G, Rank::_5), partial_cell!(None, Some(Rank::_4)), cell!(File::_F, Rank::_4)),
+        test_bishop_2: ("k2bR3/2pP2KN/qprn2pB/2b1pR1N/P2n1B1P/1PP2pQ1/1r1QP2B/6q1 w - - 0 1", Piece::Bishop, cell!(File::_G, Rank::_5), partial_cell!(Some(File::_H), None), cell!(File::_H, Rank::_6)),
         test_bishop_3: ("3bR3/k1pP2KN/qprn2pB/2b1pR1N/P2n1B1P/1PP2pQ1/1r1QP2B/6q1 b - - 0 1", Piece::Bishop, cell!(File::_E, Rank::_7), partial_cell!(None, None), cell!(File::_D, Rank::_8)),
         test_bishop_4: ("3bR3/k1pP2KN/qprn2pB/2b1pR1N/P2n1B1P/1PP2pQ1/1r1QP2B/6q1 b - - 0 1", Piece::Bishop, cell!(File::_E, Rank::_7), partial_cell!(None, Some(Rank::_8)), cell!(File::_D, Rank::_8)),
         test_bishop_5: ("3bR3/k1pP2KN/qpr3pB/2b1pR1N/P1n2B1P/1PP1npQ1/1r1QP2B/6q1 b - - 0 1", Piece::Bishop, cell!(File::_E, Rank::_7), partial_cell!(Some(File::_C), None), cell!(File::_C, Rank::_5)),
 
         test_knight_1: ("3bR3/2pP2KN/qprn1kpB/2b1pR1N/P2n1B1P/1PP2pQ1/1r1QP2B/6q1 w - - 0 1", Piece::Knight, cell!(File::_F, Rank::_6), partial_cell!(None, Some(Rank::_7)), cell!(File::_H, Rank::_7)),
-        test_knight_2: ("3bR3/2pP2KN/qprn1kpB/2b1pR1N/P2n1B1P/1PP2pQ1/1r1QP2B/6q1 w - - 0 1", Piece::Knight, cell!(File::_G, Rank::_3), partial_cell!(None, None), cell!(File::_H, Rank::_5)),
+        test_knight_2: ("k2bR3/2pP2KN/qprn2pB/2b1pR1N/P2n1B1P/1PP2p2/1r1QP2B/6q1 w - - 0 1", Piece::Knight, cell!(File::_G, Rank::_3), partial_cell!(None, None), cell!(File::_H, Rank::_5)),
         test_knight_3: ("3bR3/2pP2KN/qprk2pB/2b1pR1N/Pn1n1B1P/1PP2pQ1/1r1QP2B/n3N1q1 b - - 0 1", Piece::Knight, cell!(File::_C, Rank::_2), partial_cell!(None, Some(Rank::_1)), cell!(File::_A, Rank::_1)),
         test_knight_4: ("1k1bR3/2pP2KN/qpr3pB/2b1pR1N/Pn1n1B1P/1PP2pQ1/1r1QP2B/n3N1q1 b - - 0 1", Piece::Knight, cell!(File::_C, Rank::_2), partial_cell!(Some(File::_D), Some(Rank::_4)), cell!(File::_D, Rank::_4)),
         test_knight_5: ("3bR3/2pP2KN/qprb2pB/k3pR1N/Pn3B1P/1P2PpQ1/1r1QP2B/n3N1q1 b - - 0 1", Piece::Knight, cell!(File::_C, Rank::_2), partial_cell!(None, None), cell!(File::_A, Rank::_1)),
 
-        test_rook_1: ("3bR3/2pP2KN/qprn1kpB/2b1pR1N/P2n1B1P/1PP2pQ1/1r1QP2B/6q1 w - - 0 1", Piece::Rook, cell!(File::_H, Rank::_8), partial_cell!(None, None), cell!(File::_E, Rank::_8)),
-        test_rook_2: ("3bR3/2pP2KN/qprn1kpB/2b1pR1N/P2n1B1P/1PP2pQ1/1r1QP2B/6q1 w - - 0 1", Piece::Rook, cell!(File::_E, Rank::_5), partial_cell!(Some(File::_F), None), cell!(File::_F, Rank::_5)),
+        test_rook_1: ("k2bR3/2pP2KN/qprn2pB/2b1pR1N/P2n1B1P/1PP2pQ1/1r1QP2B/6q1 w - - 0 1", Piece::Rook, cell!(File::_H, Rank::_8), partial_cell!(None, None), cell!(File::_E, Rank::_8)),
+        test_rook_2: ("k2bR3/2pP2KN/qprn2pB/2b1pR1N/P2n1B1P/1PP2pQ1/1r1QP2B/6q1 w - - 0 1", Piece::Rook, cell!(File::_E, Rank::_5), partial_cell!(Some(File::_F), None), cell!(File::_F, Rank::_5)),
         test_rook_3: ("2nbR1p1/2pP2K1/qpr3NB/2b1pR1N/P2n1B1P/1PP2pQ1/1r1QP2B/k5q1 b - - 0 1", Piece::Rook, cell!(File::_G, Rank::_6), partial_cell!(None, None), cell!(File::_C, Rank::_6)),
         test_rook_4: ("k2bR3/2pP2KN/qprn2pB/b3pR1N/P2n1B1P/1P3pQ1/1r1QPP1B/6q1 b - - 0 1", Piece::Rook, cell!(File::_C, Rank::_2), partial_cell!(Some(File::_B), None), cell!(File::_B, Rank::_2)),
 
-        test_queen_1: ("3bR3/2pP2KN/qprn1kpB/2b1pR1N/P2n1B1P/1PP2pQ1/1r1QP2B/6q1 w - - 0 1", Piece::Queen, cell!(File::_G, Rank::_2), partial_cell!(None, None), cell!(File::_G, Rank::_3)),
-        test_queen_2: ("3bR3/2pP2KN/qprn1kpB/2b1pR1N/P2n1B1P/1PP2pQ1/1r1QP2B/6q1 w - - 0 1", Piece::Queen, cell!(File::_E, Rank::_3), partial_cell!(None, None), cell!(File::_D, Rank::_2)),
+        test_queen_1: ("k2bR3/2pP2KN/qprn2pB/2b1pR1N/P2n1B1P/1PP2pQ1/1r1QP2B/6q1 w - - 0 1", Piece::Queen, cell!(File::_G, Rank::_2), partial_cell!(None, None), cell!(File::_G, Rank::_3)),
+        test_queen_2: ("k2bR3/2pP2KN/qprn2pB/2b1pR1N/P2n1B1P/1PP2pQ1/1r1QP2B/6q1 w - - 0 1", Piece::Queen, cell!(File::_E, Rank::_3), partial_cell!(None, None), cell!(File::_D, Rank::_2)),
         test_queen_3: ("3bR2K/2pP3N/qprn1kpB/2b1pR1N/P2n1B1P/1PP3Q1/1r1QPp1B/6q1 w - - 0 1", Piece::Queen, cell!(File::_E, Rank::_3), partial_cell!(None, Some(Rank::_3)), cell!(File::_G, Rank::_3)),
         test_queen_4: ("3bR3/2pP2KN/qprk2pB/1Pb1pR1N/Pn1n1B1P/2P2pQ1/1rnQPN1B/6q1 b - - 0 1", Piece::Queen, cell!(File::_A, Rank::_1), partial_cell!(None, None), cell!(File::_G, Rank::_1)),
         test_queen_5: ("3bR3/2pP2KN/qprk2pB/2b1pR1N/Pn1n1B1P/1PP2pQ1/1r1QP2B/n3N1q1 b - - 0 1", Piece::Queen, cell!(File::_C, Rank::_4), partial_cell!(None, None), cell!(File::_A, Rank::_6)),
@@ -1089,34 +1409,35 @@ mod test_find_origin {
         test_king_5: ("3bR3/2pP2KN/qprk2pB/2b1pR1N/Pn1n1B1P/1PP2pQ1/1r1QP2B/n3N1q1 b - - 0 1", Piece::King, cell!(File::_D, Rank::_5), partial_cell!(None, None), cell!(File::_D, Rank::_6)),
     }
 
-    macro_rules! panic_tests {
-        ($($name:ident: $value:expr; $panic:literal,)*) => {
+    macro_rules! err_tests {
+        ($($name:ident: $value:expr; $err:literal,)*) => {
         $(
             #[test]
-            #[should_panic(expected=$panic)]
             fn $name() {
                 let (board, piece, dest, from): (&str, Piece, Cell, PartialCell) = $value;
                 let board = Board::from_fen(board).unwrap();
-                board.find_origin(piece, dest, from);
+                let err = board.find_origin(piece, dest, from).unwrap_err();
+                assert_eq!(ErrorKind::IllegalMove, err.kind());
+                assert!(err.message().starts_with($err));
             }
         )*
         }
     }
 
-    panic_tests! {
-        test_panic_pawn_1: ("3bR3/2pP2KN/qprn1kpB/2b1pR1N/P2n1B1P/1PP2pQ1/1r1QP2B/6q1 w - - 0 1", Piece::Pawn, cell!(File::_B, Rank::_5), partial_cell!(Some(File::_B), None)); "No possible origins found",
+    err_tests! {
+        test_err_pawn_1: ("3bR3/2pP2KN/qprn1kpB/2b1pR1N/P2n1B1P/1PP2pQ1/1r1QP2B/6q1 w - - 0 1", Piece::Pawn, cell!(File::_B, Rank::_5), partial_cell!(Some(File::_B), None)); "No possible origins found",
 
-        test_panic_bishop_1: ("3bR3/2pP2KN/qprn1kpB/2b1pR1N/P2n1B1P/1PP2pQ1/1r1QP2B/6q1 w - - 0 1", Piece::Bishop, cell!(File::_G, Rank::_5), partial_cell!(None, None)); "Too many possible origins",
+        test_err_bishop_1: ("k2bR3/2pP2KN/qprn2pB/2b1pR1N/P2n1B1P/1PP2pQ1/1r1QP2B/6q1 w - - 0 1", Piece::Bishop, cell!(File::_G, Rank::_5), partial_cell!(None, None)); "Too many possible origins",
 
-        test_panic_knight_1: ("3bR3/2pP2KN/qprn1kpB/2b1pR1N/P2n1B1P/1PP2pQ1/1r1QP2B/6q1 w - - 0 1", Piece::Knight, cell!(File::_A, Rank::_1), partial_cell!(None, None)); "No possible origins found",
-        test_panic_knight_2: ("3bR3/2pP2KN/qprn1kpB/2b1pR1N/P2n1B1P/1PP2pQ1/1r1QP2B/6q1 w - - 0 1", Piece::Knight, cell!(File::_F, Rank::_6), partial_cell!(None, None)); "Too many possible origins",
-        test_panic_knight_3: ("3bR3/2pP2KN/qprn1kpB/2b1pR1N/P2n1B1P/1PP2pQ1/1r1QP2B/6q1 w - - 0 1", Piece::Knight, cell!(File::_F, Rank::_6), partial_cell!(Some(File::_H), None)); "Too many possible origins",
+        test_err_knight_1: ("3bR3/2pP2KN/qprn1kpB/2b1pR1N/P2n1B1P/1PP2pQ1/1r1QP2B/6q1 w - - 0 1", Piece::Knight, cell!(File::_A, Rank::_1), partial_cell!(None, None)); "No possible origins found",
+        test_err_knight_2: ("3bR3/2pP2KN/qprn1kpB/2b1pR1N/P2n1B1P/1PP2pQ1/1r1QP2B/6q1 w - - 0 1", Piece::Knight, cell!(File::_F, Rank::_6), partial_cell!(None, None)); "Too many possible origins",
+        test_err_knight_3: ("3bR3/2pP2KN/qprn1kpB/2b1pR1N/P2n1B1P/1PP2pQ1/1r1QP2B/6q1 w - - 0 1", Piece::Knight, cell!(File::_F, Rank::_6), partial_cell!(Some(File::_H), None)); "Too many possible origins",
 
-        test_panic_rook_1: ("3bR3/2pP2KN/qprn1kpB/2b1pR1N/P2n1B1P/1PP2pQ1/1r1QP2B/6q1 w - - 0 1", Piece::Rook, cell!(File::_E, Rank::_5), partial_cell!(None, None)); "Too many possible origins",
+        test_err_rook_1: ("k2bR3/2pP2KN/qprn2pB/2b1pR1N/P2n1B1P/1PP2pQ1/1r1QP2B/6q1 w - - 0 1", Piece::Rook, cell!(File::_E, Rank::_5), partial_cell!(None, None)); "Too many possible origins",
 
-        test_panic_queen_1: ("3bR3/2pP2KN/qprn1kpB/2b1pR1N/P2n1B1P/1PP2pQ1/1r1QP2B/6q1 w - - 0 1", Piece::Queen, cell!(File::_H, Rank::_8), partial_cell!(None, Some(Rank::_8))); "No possible origins found",
-        test_panic_queen_2: ("3bR3/2pP2KN/qprn1kpB/2b1pR1N/P2n1B1P/1PP2pQ1/1r1QP2B/6q1 w - - 0 1", Piece::Queen, cell!(File::_F, Rank::_4), partial_cell!(None, Some(Rank::_1))); "No possible origins found",
-        test_panic_queen_3: ("3b1R2/2pP2KN/q1rnk1pB/4pR1N/P4B1P/1PP2pQ1/1r1QP2B/6q1 b - - 0 1", Piece::Queen, cell!(File::_B, Rank::_6), partial_cell!(None, None)); "Too many possible origins",
+        test_err_queen_1: ("3bR3/2pP2KN/qprn1kpB/2b1pR1N/P2n1B1P/1PP2pQ1/1r1QP2B/6q1 w - - 0 1", Piece::Queen, cell!(File::_H, Rank::_8), partial_cell!(None, Some(Rank::_8))); "No possible origins found",
+        test_err_queen_2: ("3bR3/2pP2KN/qprn1kpB/2b1pR1N/P2n1B1P/1PP2pQ1/1r1QP2B/6q1 w - - 0 1", Piece::Queen, cell!(File::_F, Rank::_4), partial_cell!(None, Some(Rank::_1))); "No possible origins found",
+        test_err_queen_3: ("3b1R2/2pP2KN/q1rnk1pB/4pR1N/P4B1P/1PP2pQ1/1r1QP2B/6q1 b - - 0 1", Piece::Queen, cell!(File::_B, Rank::_6), partial_cell!(None, None)); "Too many possible origins",
 
     }
 }
@@ -1134,7 +1455,7 @@ mod test_move_piece {
             fn $name() {
                 let (initial_board_fen, the_move, expected_board_fen): (&str, Move, &str) = $value;
                 let mut initial_board = Board::from_fen(initial_board_fen).unwrap();
-                initial_board.move_piece(the_move);
+                initial_board.move_piece(the_move).unwrap();
                 assert_eq!(expected_board_fen, initial_board.to_fen());
             }
         )*
@@ -1151,7 +1472,7 @@ mod test_move_piece {
             mates: false,
             nag: Annotation::None,
             promoted_to: OptionalPiece::new_none(),
-        }, "rnbqr1k1/pp3pbp/5np1/3p4/3NP3/2N2P2/PP2B1PP/R1BQ1R1K w"),
+        }, "rnbqr1k1/pp3pbp/5np1/3p4/3NP3/2N2P2/PP2B1PP/R1BQ1R1K w - - 0 12"),
 
         test_2: ("8/2KP1p2/6p1/5pk1/3r4/2R5/6P1/8 w - - 1 53", Move {
             piece_moved: Piece::Pawn,
@@ -1162,7 +1483,7 @@ mod test_move_piece {
             mates: false,
             nag: Annotation::None,
             promoted_to: OptionalPiece::new_some(Piece::Queen),
-        }, "3Q4/2K2p2/6p1/5pk1/3r4/2R5/6P1/8 b"),
+        }, "3Q4/2K2p2/6p1/5pk1/3r4/2R5/6P1/8 b - - 0 53"),
 
         test_3: ("r2Q1bkr/p5pp/5p2/1p1n4/8/2pQ1Q2/P1P1PPPP/RNB1KBNR w KQ - 0 16", Move {
             piece_moved: Piece::Queen,
@@ -1173,7 +1494,302 @@ mod test_move_piece {
             mates: true,
             nag: Annotation::None,
             promoted_to: OptionalPiece::new_some(Piece::Queen),
-        }, "r2Q1bkr/p5pp/5p2/1p1Q4/8/2p2Q2/P1P1PPPP/RNB1KBNR b"),
+        }, "r2Q1bkr/p5pp/5p2/1p1Q4/8/2p2Q2/P1P1PPPP/RNB1KBNR b KQ - 0 16"),
+    }
+}
+
+#[cfg(test)]
+mod test_legal_moves {
+    use super::*;
+
+    fn sans(fen: &str) -> Vec<String> {
+        let board = Board::from_fen(fen).unwrap();
+        let mut sans: Vec<String> = board
+            .legal_moves()
+            .iter()
+            .map(|curr_move| crate::notation::move_to_san(&board, curr_move).unwrap())
+            .collect();
+        sans.sort();
+        sans
+    }
+
+    #[test]
+    fn test_stalemate() {
+        assert!(sans("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1").is_empty());
+    }
+
+    #[test]
+    fn test_no_castling_through_attacked_cell() {
+        assert_eq!(
+            sans("4k3/8/8/8/8/8/5r2/R3K2R w KQ - 0 1")
+                .into_iter()
+                .filter(|san| san.starts_with('O'))
+                .collect::<Vec<_>>(),
+            vec!["O-O-O"]
+        );
+        assert_eq!(
+            sans("4k3/8/8/8/8/8/1r6/R3K2R w KQ - 0 1")
+                .into_iter()
+                .filter(|san| san.starts_with('O'))
+                .collect::<Vec<_>>(),
+            vec!["O-O", "O-O-O"]
+        );
+    }
+
+    #[test]
+    fn test_en_passant_and_promotions() {
+        assert_eq!(
+            sans("2n1k3/1P6/8/3pP3/8/8/8/4K3 w - d6 0 1"),
+            vec![
+                "Kd1", "Kd2", "Ke2", "Kf1", "Kf2", "b8=B", "b8=N", "b8=Q", "b8=R", "bxc8=B",
+                "bxc8=N", "bxc8=Q+", "bxc8=R+", "e6", "exd6"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_checks() {
+        let board = Board::from_fen("4k3/8/8/8/8/8/8/R3K3 w - - 0 1").unwrap();
+        let checks: Vec<Cell> = board
+            .legal_moves()
+            .iter()
+            .filter(|curr_move| curr_move.checks)
+            .map(|curr_move| curr_move.to)
+            .collect();
+        assert_eq!(checks, vec![cell!(File::_A, Rank::_8)]);
+    }
+}
+
+#[cfg(test)]
+mod test_perft {
+    use super::*;
+
+    macro_rules! tests {
+        ($($name:ident: $value:expr,)*) => {
+        $(
+            #[test]
+            fn $name() {
+                let (fen, expected): (&str, Vec<u64>) = $value;
+                let board = Board::from_fen(fen).unwrap();
+                for (depth, nodes) in expected.into_iter().enumerate() {
+                    assert_eq!(Ok(nodes), board.perft(depth as u32 + 1), "depth {}", depth + 1);
+                }
+            }
+        )*
+        }
+    }
+
+    // Reference positions and node counts from the Chess Programming Wiki
+    tests! {
+        test_initial: ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", vec![20, 400, 8902, 197281]),
+        test_kiwipete: ("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1", vec![48, 2039, 97862]),
+        test_position_3: ("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1", vec![14, 191, 2812, 43238]),
+        test_position_4: ("r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1", vec![6, 264, 9467]),
+        test_position_4_mirrored: ("r2q1rk1/pP1p2pp/Q4n2/bbp1p3/Np6/1B3NBn/pPPP1PPP/R3K2R b KQ - 0 1", vec![6, 264, 9467]),
+        test_position_5: ("rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8", vec![44, 1486, 62379]),
+        test_position_6: ("r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10", vec![46, 2079, 89890]),
+    }
+}
+
+#[cfg(test)]
+mod test_game_ending_checks {
+    use super::*;
+
+    #[test]
+    fn test_checkmate() {
+        let board =
+            Board::from_fen("rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3")
+                .unwrap();
+        assert!(board.is_checkmate());
+        assert!(!board.is_stalemate());
+        assert!(!Board::default().is_checkmate());
+    }
+
+    #[test]
+    fn test_stalemate() {
+        let board = Board::from_fen("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1").unwrap();
+        assert!(board.is_stalemate());
+        assert!(!board.is_checkmate());
+        assert!(!Board::default().is_stalemate());
+    }
+
+    macro_rules! insufficient_material_tests {
+        ($($name:ident: $value:expr,)*) => {
+        $(
+            #[test]
+            fn $name() {
+                let (fen, expected) = $value;
+                assert_eq!(expected, Board::from_fen(fen).unwrap().has_insufficient_material());
+            }
+        )*
+        }
+    }
+
+    insufficient_material_tests! {
+        test_bare_kings: ("8/8/4k3/8/8/4K3/8/8 w - - 0 1", true),
+        test_single_knight: ("8/8/4k3/8/8/4K3/8/6N1 w - - 0 1", true),
+        test_bishops_on_one_colour: ("5b2/8/4k3/8/8/4K3/8/2B5 w - - 0 1", true),
+        test_bishops_on_both_colours: ("2b5/8/4k3/8/8/4K3/8/2B5 w - - 0 1", false),
+        test_two_knights: ("8/8/4k3/8/8/4K3/8/1N4N1 w - - 0 1", false),
+        test_pawn: ("8/8/4k3/8/8/4K3/4P3/8 w - - 0 1", false),
+        test_initial: ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", false),
+    }
+
+    #[test]
+    fn test_repetition_ignores_clocks() {
+        let board = Board::default();
+        let later =
+            Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 4 3").unwrap();
+        assert!(board.is_repetition_of(&later));
+    }
+
+    #[test]
+    fn test_repetition_needs_same_side_and_castling() {
+        let board = Board::default();
+        let black =
+            Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR b KQkq - 0 1").unwrap();
+        let no_castling =
+            Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w Kkq - 0 1").unwrap();
+        assert!(!board.is_repetition_of(&black));
+        assert!(!board.is_repetition_of(&no_castling));
+    }
+
+    #[test]
+    fn test_repetition_uncapturable_en_passant() {
+        let board =
+            Board::from_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1").unwrap();
+        let without =
+            Board::from_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1").unwrap();
+        assert!(board.is_repetition_of(&without));
+    }
+
+    #[test]
+    fn test_repetition_capturable_en_passant() {
+        let board = Board::from_fen("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 2").unwrap();
+        let without = Board::from_fen("4k3/8/8/3pP3/8/8/8/4K3 w - - 0 2").unwrap();
+        assert!(!board.is_repetition_of(&without));
+    }
+}
+
+#[cfg(test)]
+mod test_zobrist_hash {
+    use super::*;
+
+    fn play(moves: &[(File, Rank, Piece)]) -> Board {
+        let mut board = Board::default();
+        for (file, rank, piece) in moves {
+            board
+                .move_piece(Move::new_to(*file, *rank, *piece))
+                .unwrap();
+        }
+        board
+    }
+
+    #[test]
+    fn test_transposition() {
+        let first = play(&[
+            (File::_F, Rank::_3, Piece::Knight),
+            (File::_F, Rank::_6, Piece::Knight),
+            (File::_C, Rank::_3, Piece::Knight),
+        ]);
+        let second = play(&[
+            (File::_C, Rank::_3, Piece::Knight),
+            (File::_F, Rank::_6, Piece::Knight),
+            (File::_F, Rank::_3, Piece::Knight),
+        ]);
+        assert_eq!(first.zobrist_hash(), second.zobrist_hash());
+        assert_ne!(first.zobrist_hash(), Board::default().zobrist_hash());
+    }
+
+    #[test]
+    fn test_ignores_clocks() {
+        let board =
+            Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 4 3").unwrap();
+        assert_eq!(board.zobrist_hash(), Board::default().zobrist_hash());
+    }
+
+    #[test]
+    fn test_side_to_move_and_castling() {
+        let black =
+            Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR b KQkq - 0 1").unwrap();
+        let no_castling =
+            Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w Kkq - 0 1").unwrap();
+        let default_hash = Board::default().zobrist_hash();
+        assert_ne!(black.zobrist_hash(), default_hash);
+        assert_ne!(no_castling.zobrist_hash(), default_hash);
+        assert_ne!(black.zobrist_hash(), no_castling.zobrist_hash());
+    }
+
+    #[test]
+    fn test_en_passant() {
+        let uncapturable = play(&[(File::_E, Rank::_4, Piece::Pawn)]);
+        let without =
+            Board::from_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1").unwrap();
+        assert_eq!(uncapturable.zobrist_hash(), without.zobrist_hash());
+
+        let capturable = Board::from_fen("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 2").unwrap();
+        let without = Board::from_fen("4k3/8/8/3pP3/8/8/8/4K3 w - - 0 2").unwrap();
+        assert_ne!(capturable.zobrist_hash(), without.zobrist_hash());
+    }
+}
+
+#[cfg(test)]
+mod test_polyglot_key {
+    use super::*;
+
+    // Reference keys from the Polyglot book format specification
+    #[test]
+    fn test_reference_keys() {
+        let positions = [
+            (
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+                0x463b_9618_1691_fc9c,
+            ),
+            (
+                "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1",
+                0x823c_9b50_fd11_4196,
+            ),
+            (
+                "rnbqkbnr/ppp1pppp/8/3p4/4P3/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 2",
+                0x0756_b944_61c5_0fb0,
+            ),
+            (
+                "rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR b KQkq - 0 2",
+                0x662f_afb9_65db_29d4,
+            ),
+            (
+                "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+                0x22a4_8b5a_8e47_ff78,
+            ),
+            (
+                "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPPKPPP/RNBQ1BNR b kq - 0 3",
+                0x652a_607c_a3f2_42c1,
+            ),
+            (
+                "rnbq1bnr/ppp1pkpp/8/3pPp2/8/8/PPPPKPPP/RNBQ1BNR w - - 0 4",
+                0x00fd_d303_c946_bdd9,
+            ),
+            (
+                "rnbqkbnr/p1pppppp/8/8/PpP4P/8/1P1PPPP1/RNBQKBNR b KQkq c3 0 3",
+                0x3c81_23ea_7b06_7637,
+            ),
+            (
+                "rnbqkbnr/p1pppppp/8/8/P6P/R1p5/1P1PPPP1/1NBQKBNR b Kkq - 0 4",
+                0x5c3f_9b82_9b27_9560,
+            ),
+        ];
+
+        for (fen, key) in positions {
+            assert_eq!(Board::from_fen(fen).unwrap().polyglot_key(), key, "{fen}");
+        }
+    }
+
+    #[test]
+    fn test_en_passant_ignores_pins() {
+        let pinned = Board::from_fen("8/8/8/K2pP2r/8/8/8/4k3 w - d6 0 2").unwrap();
+        let without = Board::from_fen("8/8/8/K2pP2r/8/8/8/4k3 w - - 0 2").unwrap();
+        assert_ne!(pinned.polyglot_key(), without.polyglot_key());
+        assert_eq!(pinned.zobrist_hash(), without.zobrist_hash());
     }
 }
 
@@ -1185,7 +1801,7 @@ mod test_debug_fmt {
     fn test_empty() {
         assert_eq!(
             format!("{:?}", Board::empty()),
-            "Board { board: {}, to_move: NA }".to_string()
+            "Board { position: Position { pieces: [Bitboard(0), Bitboard(0), Bitboard(0), Bitboard(0), Bitboard(0), Bitboard(0)], players: [Bitboard(0), Bitboard(0)] }, to_move: NA, castling: CastlingRights { white_kingside: false, white_queenside: false, black_kingside: false, black_queenside: false }, en_passant: None, halfmove_clock: 0, fullmove_number: 1 }".to_string()
         );
     }
 }
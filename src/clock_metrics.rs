@@ -0,0 +1,222 @@
+use std::time::Duration;
+
+use serde::Deserialize;
+
+use crate::{
+    basic_types::{GameResult, Piece, Player, Termination},
+    eval_metrics::{centipawns, Side},
+    game::Game,
+    step_param_utils::ParamType,
+};
+
+// Plies after which the opening is over even if no pieces were traded
+const OPENING_PLIES: usize = 20;
+// Knights, bishops, rooks and queens left on the board once the opening and the middlegame end
+const MIDDLEGAME_PIECES: u32 = 10;
+const ENDGAME_PIECES: u32 = 6;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GamePhase {
+    Opening,
+    Middlegame,
+    Endgame,
+}
+
+impl ParamType for GamePhase {
+    fn type_name() -> String {
+        "opening | middlegame | endgame".to_string()
+    }
+}
+
+// A move of a game with clock data, from the mover's clock
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TimedMove {
+    pub player: Player,
+    // Time left before the move was played
+    pub remaining: Duration,
+    // Time the move took, the increment it earned given back
+    pub spent: Duration,
+    pub phase: GamePhase,
+}
+
+// Phase of the game a move was played in, from the pieces left before it. The opening ends
+// after `OPENING_PLIES` plies or once pieces are traded off
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+fn phase(ply: usize, pieces: u32) -> GamePhase {
+    if pieces <= ENDGAME_PIECES {
+        GamePhase::Endgame
+    } else if ply < OPENING_PLIES && pieces > MIDDLEGAME_PIECES {
+        GamePhase::Opening
+    } else {
+        GamePhase::Middlegame
+    }
+}
+
+// Moves of the game up to the last one with a clock time and the first illegal one. Both
+// players start on the main time of the time control
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+pub fn timed_moves(game: &Game) -> Vec<TimedMove> {
+    let mut board = match game.starting_board() {
+        Ok(board) => board,
+        Err(_) => return vec![],
+    };
+    let increment = Duration::from_secs(u64::from(game.time_control_increment));
+    let main = Duration::from_secs(u64::from(game.time_control_main));
+    // Clock of the player to move, then of their opponent
+    let mut clocks = (main, main);
+
+    let mut timed = vec![];
+    for (ply, (curr_move, after)) in game.moves.iter().zip(&game.clock).enumerate() {
+        let pieces = [Piece::Knight, Piece::Bishop, Piece::Rook, Piece::Queen]
+            .into_iter()
+            .map(|piece| board.piece_count(piece))
+            .sum();
+        timed.push(TimedMove {
+            player: board.to_move(),
+            remaining: clocks.0,
+            spent: (clocks.0 + increment).saturating_sub(*after),
+            phase: phase(ply, pieces),
+        });
+
+        if board.move_piece(*curr_move).is_err() {
+            break;
+        }
+        clocks = (clocks.1, *after);
+    }
+
+    timed
+}
+
+// Average time, in milliseconds, the selected players spent on their moves of the given
+// phase, or of the whole game
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+pub fn average_think_time(game: &Game, side: Side, phase: Option<GamePhase>) -> u64 {
+    let spent: Vec<Duration> = timed_moves(game)
+        .into_iter()
+        .filter(|timed| side.includes(timed.player))
+        .filter(|timed| phase.is_none_or(|phase| timed.phase == phase))
+        .map(|timed| timed.spent)
+        .collect();
+
+    if spent.is_empty() {
+        return 0;
+    }
+    (spent.iter().sum::<Duration>().as_millis() / spent.len() as u128) as u64
+}
+
+// Number of moves the selected players started with less than `threshold` on their clock
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+pub fn time_trouble_moves(game: &Game, side: Side, threshold: Duration) -> u64 {
+    timed_moves(game)
+        .iter()
+        .filter(|timed| side.includes(timed.player) && timed.remaining < threshold)
+        .count() as u64
+}
+
+// Whether the game was lost on time by a player the last evaluation had ahead by at least
+// `min_advantage` centipawns
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+pub fn flagged_while_winning(game: &Game, min_advantage: i32) -> bool {
+    if game.termination != Termination::TimeForfeit {
+        return false;
+    }
+    let evaluated = game.eval_advantage.len().min(game.eval_mate_in.len());
+    if evaluated == 0 {
+        return false;
+    }
+
+    let white_advantage = centipawns(game, evaluated - 1);
+    match game.result {
+        GameResult::White => -white_advantage >= min_advantage,
+        GameResult::Black => white_advantage >= min_advantage,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_pgn::PgnParser;
+
+    fn game(headers: &[&str], movetext: &str) -> Game {
+        let headers = headers.iter().map(|header| header.to_string()).collect();
+        let mut game = Game::default();
+        PgnParser::new()
+            .parse_game(&headers, movetext, &mut game)
+            .unwrap();
+        game
+    }
+
+    const BLITZ: &str = r#"[TimeControl "180+2"]"#;
+    const MOVES: &str = "1. e4 { [%clk 0:03:00] } 1... e5 { [%clk 0:02:55] } \
+                         2. Nf3 { [%clk 0:02:50] } 2... Nc6 { [%clk 0:00:20] }";
+
+    #[test]
+    fn test_time_spent_counts_increment() {
+        let spent: Vec<u64> = timed_moves(&game(&[BLITZ], &format!("{MOVES} *")))
+            .iter()
+            .map(|timed| timed.spent.as_secs())
+            .collect();
+        assert_eq!(spent, vec![2, 7, 12, 157]);
+    }
+
+    #[test]
+    fn test_average_think_time() {
+        let game = game(&[BLITZ], &format!("{MOVES} *"));
+        assert_eq!(average_think_time(&game, Side::White, None), 7000);
+        assert_eq!(average_think_time(&game, Side::Both, None), 44_500);
+        assert_eq!(
+            average_think_time(&game, Side::Black, Some(GamePhase::Opening)),
+            82_000
+        );
+        assert_eq!(
+            average_think_time(&game, Side::Both, Some(GamePhase::Endgame)),
+            0
+        );
+    }
+
+    #[test]
+    fn test_time_trouble_uses_time_before_move() {
+        let threshold = Duration::from_secs(30);
+        let game_before = game(&[BLITZ], &format!("{MOVES} *"));
+        assert_eq!(time_trouble_moves(&game_before, Side::Both, threshold), 0);
+
+        let movetext =
+            format!("{MOVES} 3. Bc4 {{ [%clk 0:02:45] }} 3... Bc5 {{ [%clk 0:00:15] }} *");
+        let game = game(&[BLITZ], &movetext);
+        assert_eq!(time_trouble_moves(&game, Side::Black, threshold), 1);
+        assert_eq!(time_trouble_moves(&game, Side::White, threshold), 0);
+    }
+
+    #[test]
+    fn test_phases() {
+        assert_eq!(phase(0, 12), GamePhase::Opening);
+        assert_eq!(phase(20, 12), GamePhase::Middlegame);
+        assert_eq!(phase(8, 10), GamePhase::Middlegame);
+        assert_eq!(phase(8, 6), GamePhase::Endgame);
+    }
+
+    #[test]
+    fn test_flagged_while_winning() {
+        let movetext = "1. e4 { [%eval 0.2] } 1... e5 { [%eval 3.5] } 0-1";
+        let flagged = game(
+            &[r#"[Result "0-1"]"#, r#"[Termination "Time forfeit"]"#],
+            movetext,
+        );
+        assert!(flagged_while_winning(&flagged, 200));
+        assert!(!flagged_while_winning(&flagged, 400));
+
+        let resigned = game(
+            &[r#"[Result "0-1"]"#, r#"[Termination "Normal"]"#],
+            movetext,
+        );
+        assert!(!flagged_while_winning(&resigned, 200));
+
+        let losing = game(
+            &[r#"[Result "1-0"]"#, r#"[Termination "Time forfeit"]"#],
+            "1. e4 { [%eval 0.2] } 1... e5 { [%eval #3] } 1-0",
+        );
+        assert!(!flagged_while_winning(&losing, 200));
+    }
+}
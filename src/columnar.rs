@@ -0,0 +1,562 @@
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::basic_types::{GameResult, Termination, TimeControl};
+use crate::error::Error;
+use crate::game::Game;
+use crate::step_param_utils::ParamType;
+
+// Columnar game files hold blocks of games, each column of a block encoded separately so
+// readers only decode the columns they need:
+//   magic (4 bytes) | format version (u16 LE) | blocks...
+// Every block starts with a postcard encoded `BlockHeader`, followed by its columns in
+// `GameColumn::ALL` order. Boards and tags aren't stored.
+pub const MAGIC: [u8; 4] = *b"CACG";
+pub const FORMAT_VERSION: u16 = 1;
+const HEADER_LEN: usize = 6;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GameColumn {
+    // Year, month and day
+    Date,
+    Site,
+    // Player names
+    Players,
+    // Player ratings and rating changes
+    Ratings,
+    TimeControl,
+    Eco,
+    // Result and termination
+    Result,
+    Moves,
+    Clock,
+    // Whether evaluations are available, and the evaluations
+    Evals,
+}
+
+impl GameColumn {
+    pub const ALL: [GameColumn; 10] = [
+        GameColumn::Date,
+        GameColumn::Site,
+        GameColumn::Players,
+        GameColumn::Ratings,
+        GameColumn::TimeControl,
+        GameColumn::Eco,
+        GameColumn::Result,
+        GameColumn::Moves,
+        GameColumn::Clock,
+        GameColumn::Evals,
+    ];
+}
+
+impl ParamType for GameColumn {
+    fn type_name() -> String {
+        "date | site | players | ratings | time_control | eco | result | moves | clock | evals"
+            .to_string()
+    }
+}
+
+// Smallest and largest value of a column in a block
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Range {
+    pub min: u32,
+    pub max: u32,
+}
+
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+impl Range {
+    fn of(values: impl Iterator<Item = u32>) -> Range {
+        values.fold(
+            Range {
+                min: u32::MAX,
+                max: u32::MIN,
+            },
+            |range, value| Range {
+                min: range.min.min(value),
+                max: range.max.max(value),
+            },
+        )
+    }
+
+    // Whether some value of the range lies within the bounds
+    fn overlaps(self, min: Option<u32>, max: Option<u32>) -> bool {
+        min.is_none_or(|min| self.max >= min) && max.is_none_or(|max| self.min <= max)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlockStats {
+    pub white_rating: Range,
+    pub black_rating: Range,
+    // Dates packed by `packed_date`
+    pub date: Range,
+    pub time_control_main: Range,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct BlockHeader {
+    rows: u64,
+    stats: BlockStats,
+    // Encoded length of each column, in `GameColumn::ALL` order
+    column_lens: Vec<u64>,
+}
+
+// Dates as YYYYMMDD, so they compare in calendar order
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+pub fn packed_date(year: u16, month: u8, day: u8) -> u32 {
+    u32::from(year) * 10_000 + u32::from(month) * 100 + u32::from(day)
+}
+
+// Packed date of a PGN style "YYYY.MM.DD" date
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+pub fn parse_date(date: &str) -> Option<u32> {
+    let mut parts = date.split('.');
+    let year = parts.next()?.parse().ok()?;
+    let month = parts.next()?.parse().ok()?;
+    let day = parts.next()?.parse().ok()?;
+    if parts.next().is_some() || !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+    Some(packed_date(year, month, day))
+}
+
+// Bounds on the games to read. Blocks whose statistics fall outside them are skipped
+// without decoding any of their columns.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct GameFilter {
+    // Bounds on the ratings of both players
+    pub min_elo: Option<u32>,
+    pub max_elo: Option<u32>,
+    // Bounds on packed dates
+    pub min_date: Option<u32>,
+    pub max_date: Option<u32>,
+    // Bounds on the main time of the time control, in seconds
+    pub min_time_control: Option<u32>,
+    pub max_time_control: Option<u32>,
+}
+
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+impl GameFilter {
+    // Columns games must be decoded with to be filtered
+    fn columns(&self) -> Vec<GameColumn> {
+        let mut columns = vec![];
+        if self.min_elo.is_some() || self.max_elo.is_some() {
+            columns.push(GameColumn::Ratings);
+        }
+        if self.min_date.is_some() || self.max_date.is_some() {
+            columns.push(GameColumn::Date);
+        }
+        if self.min_time_control.is_some() || self.max_time_control.is_some() {
+            columns.push(GameColumn::TimeControl);
+        }
+        columns
+    }
+
+    fn may_match(&self, stats: &BlockStats) -> bool {
+        stats.white_rating.overlaps(self.min_elo, self.max_elo)
+            && stats.black_rating.overlaps(self.min_elo, self.max_elo)
+            && stats.date.overlaps(self.min_date, self.max_date)
+            && stats
+                .time_control_main
+                .overlaps(self.min_time_control, self.max_time_control)
+    }
+
+    fn matches(&self, game: &Game) -> bool {
+        let within = |value: u32, min: Option<u32>, max: Option<u32>| {
+            min.is_none_or(|min| value >= min) && max.is_none_or(|max| value <= max)
+        };
+
+        within(u32::from(game.white_rating), self.min_elo, self.max_elo)
+            && within(u32::from(game.black_rating), self.min_elo, self.max_elo)
+            && within(
+                packed_date(game.year, game.month, game.day),
+                self.min_date,
+                self.max_date,
+            )
+            && within(
+                u32::from(game.time_control_main),
+                self.min_time_control,
+                self.max_time_control,
+            )
+    }
+}
+
+// Header every columnar file starts with
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+pub fn file_header() -> Vec<u8> {
+    let mut header = MAGIC.to_vec();
+    header.extend(FORMAT_VERSION.to_le_bytes());
+    header
+}
+
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+pub fn encode_block(games: &[Game]) -> Result<Vec<u8>, Error> {
+    let stats = BlockStats {
+        white_rating: Range::of(games.iter().map(|game| u32::from(game.white_rating))),
+        black_rating: Range::of(games.iter().map(|game| u32::from(game.black_rating))),
+        date: Range::of(
+            games
+                .iter()
+                .map(|game| packed_date(game.year, game.month, game.day)),
+        ),
+        time_control_main: Range::of(games.iter().map(|game| u32::from(game.time_control_main))),
+    };
+
+    let columns = GameColumn::ALL
+        .iter()
+        .map(|column| encode_column(*column, games))
+        .collect::<Result<Vec<Vec<u8>>, postcard::Error>>()?;
+
+    let header = BlockHeader {
+        rows: games.len() as u64,
+        stats,
+        column_lens: columns.iter().map(|column| column.len() as u64).collect(),
+    };
+    let mut block = postcard::to_allocvec(&header)?;
+    block.extend(columns.concat());
+    Ok(block)
+}
+
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+fn encode_column(column: GameColumn, games: &[Game]) -> postcard::Result<Vec<u8>> {
+    macro_rules! encode {
+        ($row:expr) => {
+            postcard::to_allocvec(&games.iter().map($row).collect::<Vec<_>>())
+        };
+    }
+
+    match column {
+        GameColumn::Date => encode!(|game| (game.year, game.month, game.day)),
+        GameColumn::Site => encode!(|game| &game.site),
+        GameColumn::Players => encode!(|game| (&game.white, &game.black)),
+        GameColumn::Ratings => encode!(|game| (
+            game.white_rating,
+            game.black_rating,
+            game.white_diff,
+            game.black_diff
+        )),
+        GameColumn::TimeControl => encode!(|game| (
+            game.time_control_main,
+            game.time_control_increment,
+            &game.time_control
+        )),
+        GameColumn::Eco => encode!(|game| (game.eco_category, game.eco_subcategory)),
+        GameColumn::Result => encode!(|game| (game.result, game.termination)),
+        GameColumn::Moves => encode!(|game| &game.moves),
+        GameColumn::Clock => encode!(|game| &game.clock),
+        GameColumn::Evals => encode!(|game| (
+            game.eval_available,
+            &game.eval_mate_in,
+            &game.eval_advantage
+        )),
+    }
+}
+
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+fn decode_column(column: GameColumn, bytes: &[u8], games: &mut [Game]) -> Result<(), Error> {
+    fn rows<T: DeserializeOwned>(bytes: &[u8], count: usize) -> Result<Vec<T>, Error> {
+        let rows: Vec<T> = postcard::from_bytes(bytes)?;
+        if rows.len() != count {
+            return Err(Error::decode(format!(
+                "Column holds {} rows instead of {count}",
+                rows.len()
+            )));
+        }
+        Ok(rows)
+    }
+
+    let count = games.len();
+    let games = games.iter_mut();
+    match column {
+        GameColumn::Date => {
+            for (game, (year, month, day)) in games.zip(rows::<(u16, u8, u8)>(bytes, count)?) {
+                (game.year, game.month, game.day) = (year, month, day);
+            }
+        }
+        GameColumn::Site => {
+            for (game, site) in games.zip(rows(bytes, count)?) {
+                game.site = site;
+            }
+        }
+        GameColumn::Players => {
+            for (game, (white, black)) in games.zip(rows::<(String, String)>(bytes, count)?) {
+                (game.white, game.black) = (white, black);
+            }
+        }
+        GameColumn::Ratings => {
+            for (game, ratings) in games.zip(rows::<(u16, u16, i16, i16)>(bytes, count)?) {
+                (
+                    game.white_rating,
+                    game.black_rating,
+                    game.white_diff,
+                    game.black_diff,
+                ) = ratings;
+            }
+        }
+        GameColumn::TimeControl => {
+            for (game, time_control) in games.zip(rows::<(u16, u8, TimeControl)>(bytes, count)?) {
+                (
+                    game.time_control_main,
+                    game.time_control_increment,
+                    game.time_control,
+                ) = time_control;
+            }
+        }
+        GameColumn::Eco => {
+            for (game, (category, subcategory)) in games.zip(rows::<(char, u8)>(bytes, count)?) {
+                (game.eco_category, game.eco_subcategory) = (category, subcategory);
+            }
+        }
+        GameColumn::Result => {
+            for (game, (result, termination)) in
+                games.zip(rows::<(GameResult, Termination)>(bytes, count)?)
+            {
+                (game.result, game.termination) = (result, termination);
+            }
+        }
+        GameColumn::Moves => {
+            for (game, moves) in games.zip(rows(bytes, count)?) {
+                game.moves = moves;
+            }
+        }
+        GameColumn::Clock => {
+            for (game, clock) in games.zip(rows(bytes, count)?) {
+                game.clock = clock;
+            }
+        }
+        GameColumn::Evals => {
+            for (game, evals) in games.zip(rows::<(bool, Vec<i16>, Vec<f32>)>(bytes, count)?) {
+                (game.eval_available, game.eval_mate_in, game.eval_advantage) = evals;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// Games of a columnar file that pass the filter, decoded with the given columns and the
+// columns the filter needs. Columns that aren't decoded keep their default values.
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+pub fn decode_games(
+    file_data: &[u8],
+    columns: &[GameColumn],
+    filter: &GameFilter,
+) -> Result<Vec<Game>, Error> {
+    if !file_data.starts_with(&MAGIC) || file_data.len() < HEADER_LEN {
+        return Err(Error::decode("Not a columnar game file"));
+    }
+    let version = u16::from_le_bytes([file_data[4], file_data[5]]);
+    if version != FORMAT_VERSION {
+        return Err(Error::decode(format!(
+            "Unsupported columnar format version {version}"
+        )));
+    }
+
+    let mut wanted = columns.to_vec();
+    wanted.extend(filter.columns());
+
+    let mut games = vec![];
+    let mut remaining = &file_data[HEADER_LEN..];
+    while !remaining.is_empty() {
+        let (header, rest) = postcard::take_from_bytes::<BlockHeader>(remaining)?;
+        let block_len = header
+            .column_lens
+            .iter()
+            .try_fold(0usize, |len, column_len| {
+                len.checked_add(usize::try_from(*column_len).ok()?)
+            })
+            .filter(|len| *len <= rest.len())
+            .ok_or_else(|| Error::decode("Truncated block"))?;
+        // Every row takes at least a byte in each column
+        if header.column_lens.len() != GameColumn::ALL.len() || header.rows > block_len as u64 {
+            return Err(Error::decode("Corrupt block header"));
+        }
+        let (block, rest) = rest.split_at(block_len);
+        remaining = rest;
+
+        if !filter.may_match(&header.stats) {
+            continue;
+        }
+
+        let mut block_games = vec![Game::default(); header.rows as usize];
+        let mut offset = 0;
+        for (column, column_len) in GameColumn::ALL.iter().zip(&header.column_lens) {
+            let column_len = *column_len as usize;
+            if wanted.contains(column) {
+                decode_column(
+                    *column,
+                    &block[offset..offset + column_len],
+                    &mut block_games,
+                )?;
+            }
+            offset += column_len;
+        }
+
+        games.extend(block_games.into_iter().filter(|game| filter.matches(game)));
+    }
+
+    Ok(games)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+    use crate::parse_pgn::PgnParser;
+
+    fn game(headers: &[&str], movetext: &str) -> Game {
+        let headers = headers.iter().map(|header| header.to_string()).collect();
+        let mut game = Game::default();
+        PgnParser::new()
+            .parse_game(&headers, movetext, &mut game)
+            .unwrap();
+        game
+    }
+
+    fn games() -> Vec<Game> {
+        vec![
+            game(
+                &[
+                    r#"[White "Alice"]"#,
+                    r#"[WhiteElo "1500"]"#,
+                    r#"[BlackElo "1600"]"#,
+                    r#"[UTCDate "2023.01.05"]"#,
+                    r#"[TimeControl "180+2"]"#,
+                ],
+                "1. e4 { [%eval 0.2] [%clk 0:03:00] } 1... e5 { [%eval 0.3] [%clk 0:02:58] } 1-0",
+            ),
+            game(
+                &[
+                    r#"[WhiteElo "2100"]"#,
+                    r#"[BlackElo "2000"]"#,
+                    r#"[UTCDate "2023.02.10"]"#,
+                    r#"[TimeControl "600+0"]"#,
+                ],
+                "1. d4 d5 0-1",
+            ),
+            game(
+                &[
+                    r#"[WhiteElo "1800"]"#,
+                    r#"[BlackElo "1900"]"#,
+                    r#"[UTCDate "2023.03.15"]"#,
+                    r#"[TimeControl "60+0"]"#,
+                ],
+                "1. c4 1/2-1/2",
+            ),
+        ]
+    }
+
+    // File with a block for the first game and one for the other two
+    fn file() -> Vec<u8> {
+        let games = games();
+        let mut file_data = file_header();
+        file_data.extend(encode_block(&games[..1]).unwrap());
+        file_data.extend(encode_block(&games[1..]).unwrap());
+        file_data
+    }
+
+    #[test]
+    fn round_trips_every_column() {
+        let decoded = decode_games(&file(), &GameColumn::ALL, &GameFilter::default()).unwrap();
+        assert_eq!(decoded, games());
+    }
+
+    #[test]
+    fn decodes_only_selected_columns() {
+        let decoded =
+            decode_games(&file(), &[GameColumn::Ratings], &GameFilter::default()).unwrap();
+        assert_eq!(decoded.len(), 3);
+        assert_eq!(
+            (decoded[0].white_rating, decoded[0].black_rating),
+            (1500, 1600)
+        );
+        assert_eq!(decoded[0].white, Game::default().white);
+        assert!(decoded[0].moves.is_empty());
+    }
+
+    #[test]
+    fn filters_games() {
+        let filter = GameFilter {
+            min_elo: Some(1700),
+            max_time_control: Some(300),
+            ..GameFilter::default()
+        };
+        let decoded = decode_games(&file(), &[], &filter).unwrap();
+        assert_eq!(decoded.len(), 1);
+        assert_eq!(decoded[0].time_control_main, 60);
+
+        let filter = GameFilter {
+            min_date: parse_date("2023.02.01"),
+            max_date: parse_date("2023.02.28"),
+            ..GameFilter::default()
+        };
+        let decoded = decode_games(&file(), &[GameColumn::Moves], &filter).unwrap();
+        assert_eq!(decoded.len(), 1);
+        assert_eq!(decoded[0].moves, games()[1].moves);
+    }
+
+    #[test]
+    fn skips_blocks_outside_filter() {
+        let first_block = encode_block(&games()[..1]).unwrap();
+        let columns_len: usize = GameColumn::ALL
+            .iter()
+            .map(|column| encode_column(*column, &games()[..1]).unwrap().len())
+            .sum();
+        // Corrupts the row count of the date column, the first after the block header
+        let mut file_data = file();
+        file_data[HEADER_LEN + first_block.len() - columns_len] = 0x7f;
+
+        // The first block can't match, so its corrupt column is never decoded
+        let filter = GameFilter {
+            min_elo: Some(1700),
+            ..GameFilter::default()
+        };
+        let decoded = decode_games(&file_data, &GameColumn::ALL, &filter).unwrap();
+        assert_eq!(decoded, games()[1..].to_vec());
+
+        let err = decode_games(&file_data, &GameColumn::ALL, &GameFilter::default()).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Decode);
+    }
+
+    #[test]
+    fn block_stats() {
+        let stats = BlockStats {
+            white_rating: Range {
+                min: 1800,
+                max: 2100,
+            },
+            black_rating: Range {
+                min: 1900,
+                max: 2000,
+            },
+            date: Range {
+                min: 20230210,
+                max: 20230315,
+            },
+            time_control_main: Range { min: 60, max: 600 },
+        };
+        let block = encode_block(&games()[1..]).unwrap();
+        let (header, _) = postcard::take_from_bytes::<BlockHeader>(&block).unwrap();
+        assert_eq!(header.stats, stats);
+        assert_eq!(header.rows, 2);
+    }
+
+    #[test]
+    fn parses_dates() {
+        assert_eq!(parse_date("2023.01.31"), Some(20230131));
+        assert_eq!(parse_date("2023.13.01"), None);
+        assert_eq!(parse_date("2023.01"), None);
+        assert_eq!(parse_date("2023.??.??"), None);
+    }
+
+    #[test]
+    fn rejects_other_files() {
+        let err = decode_games(b"CABG\x02\x00", &[], &GameFilter::default()).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Decode);
+
+        let mut file_data = file();
+        file_data.truncate(file_data.len() - 1);
+        assert!(decode_games(&file_data, &[], &GameFilter::default()).is_err());
+    }
+}
@@ -0,0 +1,383 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{sync_channel, Receiver, SyncSender};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::error::Error;
+use crate::game::Game;
+use crate::game_archive::write_file;
+use crate::parse_pgn::PgnParser;
+use crate::pgn_reader::{PgnReader, RawGame};
+
+const USAGE: &str = "convert: expected <pgn file> <output directory> [--shard-size <games>] \
+                     [--compression <1-9>] [--threads <count>] [--prefix <name>]";
+
+// How a PGN file is split into binary game files
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConvertOptions {
+    // Games read from the PGN file per binary file. Files of a shard hold fewer games when some
+    // of them could not be parsed
+    pub shard_size: usize,
+    // bz2 compression level, from 1 (fastest) to 9 (smallest)
+    pub compression: u32,
+    // Number of threads parsing and compressing shards, besides the one reading the file
+    pub threads: usize,
+    // Name shared by the written files, followed by the shard number
+    pub prefix: String,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ConvertStats {
+    pub games_read: usize,
+    pub games_written: usize,
+    pub games_skipped: usize,
+    pub files_written: usize,
+    pub bytes_written: u64,
+}
+
+// Games read from the PGN file, to be written to one binary file
+#[derive(Debug)]
+struct Shard {
+    number: usize,
+    // Index within the PGN file of the first game
+    first_game: usize,
+    games: Vec<RawGame>,
+}
+
+// `convert <pgn file> <output directory> [options]`: converts a PGN file, compressed or not,
+// to binary game files, then prints statistics on the conversion
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+pub fn convert_command<T, I>(args: T) -> Result<(), Error>
+where
+    T: Iterator<Item = I>,
+    I: AsRef<Path>,
+{
+    let args: Vec<String> = args
+        .map(|arg| arg.as_ref().to_string_lossy().into_owned())
+        .collect();
+    let (input, output, options) = parse_args(&args)?;
+
+    let start = Instant::now();
+    let stats = convert(&input, &output, &options)?;
+    print!("{}", report(&input, &output, &stats, start.elapsed()));
+    Ok(())
+}
+
+// Reads the PGN file on the calling thread while `options.threads` threads parse and write
+// its shards. Shards are numbered in file order, so the output doesn't depend on the threads.
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+pub fn convert(
+    input: &Path,
+    output: &Path,
+    options: &ConvertOptions,
+) -> Result<ConvertStats, Error> {
+    let mut reader = PgnReader::open(input)?;
+    fs::create_dir_all(output).map_err(|err| Error::from(err).in_file(output))?;
+
+    // Bounded so reading can't get ahead of the writers by more than a shard per thread
+    let (sender, receiver) = sync_channel(options.threads);
+    let receiver = Mutex::new(receiver);
+    let stats = Mutex::new(ConvertStats::default());
+    let failed = AtomicBool::new(false);
+
+    thread::scope(|scope| {
+        let workers: Vec<_> = (0..options.threads)
+            .map(|_| scope.spawn(|| write_shards(&receiver, output, options, &stats, &failed)))
+            .collect();
+
+        let mut result = read_shards(&mut reader, options.shard_size, &sender, &failed);
+        drop(sender);
+
+        for worker in workers {
+            let worker_result = worker
+                .join()
+                .unwrap_or_else(|_| Err(Error::config("convert: a conversion thread panicked")));
+            result = result.and(worker_result);
+        }
+        result
+    })?;
+
+    let mut stats = stats.into_inner().unwrap();
+    stats.games_read = reader.games_read();
+    Ok(stats)
+}
+
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+fn parse_args(args: &[String]) -> Result<(PathBuf, PathBuf, ConvertOptions), Error> {
+    let (input, output) = match args {
+        [input, output, ..] if !input.starts_with("--") && !output.starts_with("--") => {
+            (PathBuf::from(input), PathBuf::from(output))
+        }
+        _ => return Err(Error::config(USAGE)),
+    };
+
+    let mut options = ConvertOptions {
+        shard_size: 10000,
+        compression: 9,
+        threads: thread::available_parallelism().map_or(1, usize::from),
+        prefix: default_prefix(&input),
+    };
+
+    let mut option_args = args[2..].iter();
+    while let Some(name) = option_args.next() {
+        let value = option_args
+            .next()
+            .ok_or_else(|| Error::config(format!("convert: {name} needs a value")))?;
+        let number = || {
+            value.parse::<usize>().map_err(|_| {
+                Error::config(format!("convert: {name} expects a number, not '{value}'"))
+            })
+        };
+
+        match name.as_str() {
+            "--shard-size" => options.shard_size = number()?,
+            "--compression" => options.compression = u32::try_from(number()?).unwrap_or(0),
+            "--threads" => options.threads = number()?,
+            "--prefix" => options.prefix = value.clone(),
+            _ => return Err(Error::config(format!("convert: unknown option {name}"))),
+        }
+    }
+
+    if options.shard_size == 0 {
+        return Err(Error::config("convert: --shard-size must be at least 1"));
+    }
+    if !(1..=9).contains(&options.compression) {
+        return Err(Error::config(
+            "convert: --compression must be between 1 and 9",
+        ));
+    }
+    if options.threads == 0 {
+        return Err(Error::config("convert: --threads must be at least 1"));
+    }
+
+    Ok((input, output, options))
+}
+
+// Name of the PGN file without its extensions, such as "lichess_db_2013-01" for
+// "lichess_db_2013-01.pgn.zst"
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+fn default_prefix(input: &Path) -> String {
+    let name = input
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    match name.split('.').next() {
+        Some(prefix) if !prefix.is_empty() => prefix.to_string(),
+        _ => "games".to_string(),
+    }
+}
+
+// Sends the games of the file a shard at a time, until the file ends or a writer fails
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+fn read_shards(
+    reader: &mut PgnReader,
+    shard_size: usize,
+    sender: &SyncSender<Shard>,
+    failed: &AtomicBool,
+) -> Result<(), Error> {
+    let mut shard = Shard {
+        number: 0,
+        first_game: 0,
+        games: vec![],
+    };
+
+    while let Some(raw_game) = reader.next_game()? {
+        shard.games.push(raw_game);
+        if shard.games.len() < shard_size {
+            continue;
+        }
+
+        let next_shard = Shard {
+            number: shard.number + 1,
+            first_game: shard.first_game + shard_size,
+            games: vec![],
+        };
+        let full_shard = std::mem::replace(&mut shard, next_shard);
+        if failed.load(Ordering::Relaxed) || sender.send(full_shard).is_err() {
+            return Ok(());
+        }
+    }
+
+    if !shard.games.is_empty() {
+        let _ = sender.send(shard);
+    }
+    Ok(())
+}
+
+// Writes shards until the reader is done. After a failure, shards are still received but
+// dropped, so the reader is never left blocked on a full channel.
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+fn write_shards(
+    receiver: &Mutex<Receiver<Shard>>,
+    output: &Path,
+    options: &ConvertOptions,
+    stats: &Mutex<ConvertStats>,
+    failed: &AtomicBool,
+) -> Result<(), Error> {
+    let parser = PgnParser::new();
+    let mut result = Ok(());
+
+    loop {
+        let shard = match receiver.lock().unwrap().recv() {
+            Ok(shard) => shard,
+            Err(_) => return result,
+        };
+
+        if result.is_ok() && !failed.load(Ordering::Relaxed) {
+            result = write_shard(&parser, shard, output, options, stats);
+            if result.is_err() {
+                failed.store(true, Ordering::Relaxed);
+            }
+        }
+    }
+}
+
+// Games that can't be parsed are reported and left out of the file
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+fn write_shard(
+    parser: &PgnParser,
+    shard: Shard,
+    output: &Path,
+    options: &ConvertOptions,
+    stats: &Mutex<ConvertStats>,
+) -> Result<(), Error> {
+    let mut games = vec![];
+    let mut games_skipped = 0;
+    for (position, raw_game) in shard.games.iter().enumerate() {
+        let mut game = Game::default();
+        match parser.parse_game(&raw_game.headers, &raw_game.movetext, &mut game) {
+            Ok(()) => games.push(game),
+            Err(err) => {
+                eprintln!("Skipped: {}", err.in_game(shard.first_game + position));
+                games_skipped += 1;
+            }
+        }
+    }
+
+    let path = output.join(format!("{}_{:06}.bin.bz2", options.prefix, shard.number));
+    let bytes_written = write_file(&path, &games, options.compression)?;
+
+    let mut stats = stats.lock().unwrap();
+    stats.games_written += games.len();
+    stats.games_skipped += games_skipped;
+    stats.files_written += 1;
+    stats.bytes_written += bytes_written;
+    eprintln!(
+        "{}: {} games, {} games written so far",
+        path.display(),
+        games.len(),
+        stats.games_written
+    );
+
+    Ok(())
+}
+
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+fn report(input: &Path, output: &Path, stats: &ConvertStats, elapsed: Duration) -> String {
+    let seconds = elapsed.as_secs_f64();
+    let games_per_second = if seconds > 0.0 {
+        stats.games_read as f64 / seconds
+    } else {
+        0.0
+    };
+
+    format!(
+        "Converted {} to {}\n\
+         Games read: {}\n\
+         Games written: {}\n\
+         Games skipped: {}\n\
+         Files written: {}\n\
+         Bytes written: {}\n\
+         Time: {seconds:.1}s ({games_per_second:.0} games/s)\n",
+        input.display(),
+        output.display(),
+        stats.games_read,
+        stats.games_written,
+        stats.games_skipped,
+        stats.files_written,
+        stats.bytes_written,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_archive::{decode_games, read_file};
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| (*arg).to_string()).collect()
+    }
+
+    #[test]
+    fn parses_options() {
+        let (input, output, options) = parse_args(&args(&[
+            "dumps/lichess_db_2013-01.pgn.zst",
+            "out",
+            "--shard-size",
+            "500",
+            "--compression",
+            "3",
+            "--threads",
+            "2",
+        ]))
+        .unwrap();
+
+        assert_eq!(input, Path::new("dumps/lichess_db_2013-01.pgn.zst"));
+        assert_eq!(output, Path::new("out"));
+        assert_eq!(
+            options,
+            ConvertOptions {
+                shard_size: 500,
+                compression: 3,
+                threads: 2,
+                prefix: "lichess_db_2013-01".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_options() {
+        for invalid in [
+            &["games.pgn"][..],
+            &["games.pgn", "out", "--threads"],
+            &["games.pgn", "out", "--threads", "0"],
+            &["games.pgn", "out", "--shard-size", "many"],
+            &["games.pgn", "out", "--compression", "10"],
+            &["games.pgn", "out", "--level", "1"],
+        ] {
+            assert!(parse_args(&args(invalid)).is_err(), "{invalid:?}");
+        }
+    }
+
+    #[test]
+    fn converts_in_numbered_shards() {
+        let output = std::env::temp_dir().join("chess_analytics_convert_test");
+        let options = ConvertOptions {
+            shard_size: 4,
+            compression: 1,
+            threads: 3,
+            prefix: "test_set_1".to_string(),
+        };
+
+        let stats = convert(Path::new("test_data/test_set_1.pgn"), &output, &options).unwrap();
+        assert_eq!(stats.games_read, 10);
+        assert_eq!(stats.games_written, 10);
+        assert_eq!(stats.files_written, 3);
+
+        let shard_lens: Vec<usize> = (0..3)
+            .map(|number| {
+                let path = output.join(format!("test_set_1_{number:06}.bin.bz2"));
+                let data = read_file(&path).unwrap();
+                let (games, failure) = decode_games(&data);
+                assert!(failure.is_none());
+                games.len()
+            })
+            .collect();
+        assert_eq!(shard_lens, vec![4, 4, 2]);
+
+        let _ = fs::remove_dir_all(&output);
+    }
+}
@@ -0,0 +1,137 @@
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use crate::game::Game;
+use crate::opening::{position_hashes, Opening};
+
+// Tab separated ECO code, opening name and SAN moves, names following the
+// "Family: Variation, Subvariation" convention
+const ECO_TABLE: &str = include_str!("eco.tsv");
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct EcoEntry {
+    pub category: char,
+    pub subcategory: u8,
+    pub name: &'static str,
+}
+
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+impl EcoEntry {
+    pub fn code(&self) -> String {
+        format!("{}{:02}", self.category, self.subcategory)
+    }
+
+    // Name of the opening without its variation, like "Sicilian Defense"
+    pub fn family(&self) -> &'static str {
+        self.name.split(':').next().unwrap_or(self.name)
+    }
+}
+
+// Entries of the bundled table keyed by the hash of the position their moves lead to
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+fn eco_table() -> &'static HashMap<u64, EcoEntry> {
+    static TABLE: OnceLock<HashMap<u64, EcoEntry>> = OnceLock::new();
+
+    TABLE.get_or_init(|| {
+        let mut table = HashMap::new();
+        for line in ECO_TABLE.lines().skip(1) {
+            let mut columns = line.split('\t');
+            let (code, name, moves) = match (columns.next(), columns.next(), columns.next()) {
+                (Some(code), Some(name), Some(moves)) => (code, name, moves),
+                _ => panic!("Malformed ECO table line `{line}`"),
+            };
+            let hash = match Opening::parse(moves) {
+                Ok(Opening::Moves(hashes)) => *hashes.last().unwrap(),
+                _ => panic!("Invalid moves in ECO table line `{line}`"),
+            };
+
+            let mut chars = code.chars();
+            let entry = EcoEntry {
+                category: chars.next().unwrap(),
+                subcategory: chars.as_str().parse().unwrap(),
+                name,
+            };
+            // The first line reaching a position names it
+            table.entry(hash).or_insert(entry);
+        }
+        table
+    })
+}
+
+// Entry of the last position of the game found in the table, so transpositions are
+// classified the same as the main line
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+pub fn classify(game: &Game) -> Option<&'static EcoEntry> {
+    let table = eco_table();
+    position_hashes(game)
+        .iter()
+        .rev()
+        .find_map(|hash| table.get(hash))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_pgn::PgnParser;
+    use std::collections::HashSet;
+
+    fn game(movetext: &str) -> Game {
+        let mut game = Game::default();
+        PgnParser::new()
+            .parse_game(&Vec::new(), movetext, &mut game)
+            .unwrap();
+        game
+    }
+
+    #[test]
+    fn test_table_is_valid() {
+        assert_eq!(eco_table().len(), ECO_TABLE.lines().count() - 1);
+    }
+
+    #[test]
+    fn test_table_covers_every_code() {
+        let codes: HashSet<String> = eco_table().values().map(EcoEntry::code).collect();
+        for category in 'A'..='E' {
+            for subcategory in 0..100 {
+                let code = format!("{category}{subcategory:02}");
+                assert!(
+                    codes.contains(&code),
+                    "{code} is missing from the ECO table"
+                );
+            }
+        }
+    }
+
+    macro_rules! tests {
+        ($($name:ident: $value:expr,)*) => {
+        $(
+            #[test]
+            fn $name() {
+                let (movetext, expected) = $value;
+                let entry = classify(&game(movetext));
+                assert_eq!(entry.map(|entry| (entry.code(), entry.name)), expected);
+            }
+        )*
+        }
+    }
+
+    tests! {
+        test_exact_line: ("1. e4 c5 *", Some(("B20".to_string(), "Sicilian Defense"))),
+        test_deepest_known_position: (
+            "1. e4 c5 2. Nf3 d6 3. d4 cxd4 4. Nxd4 Nf6 5. Nc3 a6 6. Be3 e5 *",
+            Some(("B90".to_string(), "Sicilian Defense: Najdorf Variation")),
+        ),
+        test_transposition: (
+            "1. Nf3 Nf6 2. c4 e6 3. d4 *",
+            Some(("E10".to_string(), "Indian Defense: Anti-Nimzo-Indian")),
+        ),
+        test_no_moves: ("*", None),
+    }
+
+    #[test]
+    fn test_family() {
+        let entry = classify(&game("1. e4 e6 2. d4 d5 3. Nc3 Bb4 *")).unwrap();
+        assert_eq!(entry.code(), "C15");
+        assert_eq!(entry.family(), "French Defense");
+    }
+}
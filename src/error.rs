@@ -0,0 +1,191 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ErrorKind {
+    // Invalid workflow configuration or step parameters
+    Config,
+    // A file could not be read or written
+    Io,
+    // A binary game file could not be decoded
+    Decode,
+    // A PGN game could not be parsed
+    Pgn,
+    // A move could not be played on its board
+    IllegalMove,
+    // A data entry does not hold the expected type
+    DataType,
+}
+
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            ErrorKind::Config => "config",
+            ErrorKind::Io => "io",
+            ErrorKind::Decode => "decode",
+            ErrorKind::Pgn => "pgn",
+            ErrorKind::IllegalMove => "illegal_move",
+            ErrorKind::DataType => "data_type",
+        };
+
+        write!(f, "{name}")
+    }
+}
+
+// Error shared by the whole crate. Where it happened (step, file and game) is filled in
+// as it propagates; the innermost value of each is kept.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Error {
+    kind: ErrorKind,
+    message: String,
+    step: Option<String>,
+    file: Option<PathBuf>,
+    game_index: Option<usize>,
+}
+
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+impl Error {
+    pub fn new(kind: ErrorKind, message: impl Into<String>) -> Self {
+        Error {
+            kind,
+            message: message.into(),
+            step: None,
+            file: None,
+            game_index: None,
+        }
+    }
+
+    pub fn config(message: impl Into<String>) -> Self {
+        Self::new(ErrorKind::Config, message)
+    }
+
+    pub fn io(message: impl Into<String>) -> Self {
+        Self::new(ErrorKind::Io, message)
+    }
+
+    pub fn decode(message: impl Into<String>) -> Self {
+        Self::new(ErrorKind::Decode, message)
+    }
+
+    pub fn pgn(message: impl Into<String>) -> Self {
+        Self::new(ErrorKind::Pgn, message)
+    }
+
+    pub fn illegal_move(message: impl Into<String>) -> Self {
+        Self::new(ErrorKind::IllegalMove, message)
+    }
+
+    pub fn data_type(message: impl Into<String>) -> Self {
+        Self::new(ErrorKind::DataType, message)
+    }
+
+    pub fn kind(&self) -> ErrorKind {
+        self.kind
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    pub fn step(&self) -> Option<&str> {
+        self.step.as_deref()
+    }
+
+    pub fn file(&self) -> Option<&Path> {
+        self.file.as_deref()
+    }
+
+    pub fn game_index(&self) -> Option<usize> {
+        self.game_index
+    }
+
+    #[must_use]
+    pub fn in_step(mut self, step: &str) -> Self {
+        self.step.get_or_insert_with(|| step.to_string());
+        self
+    }
+
+    #[must_use]
+    pub fn in_file(mut self, file: &Path) -> Self {
+        self.file.get_or_insert_with(|| file.to_path_buf());
+        self
+    }
+
+    #[must_use]
+    pub fn in_game(mut self, game_index: usize) -> Self {
+        self.game_index.get_or_insert(game_index);
+        self
+    }
+}
+
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(step) = &self.step {
+            write!(f, "step '{step}': ")?;
+        }
+        if let Some(file) = &self.file {
+            write!(f, "{}: ", file.display())?;
+        }
+        if let Some(game_index) = self.game_index {
+            write!(f, "game {game_index}: ")?;
+        }
+
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<std::io::Error> for Error {
+    fn from(err: std::io::Error) -> Self {
+        Error::io(err.to_string())
+    }
+}
+
+impl From<postcard::Error> for Error {
+    fn from(err: postcard::Error) -> Self {
+        Error::decode(err.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display_without_context() {
+        assert_eq!(
+            Error::pgn("Unknown result: 2-0").to_string(),
+            "Unknown result: 2-0"
+        );
+    }
+
+    #[test]
+    fn display_with_context() {
+        let err = Error::illegal_move("No possible origins found")
+            .in_game(3)
+            .in_file(Path::new("games.pgn"))
+            .in_step("parse_games");
+
+        assert_eq!(
+            err.to_string(),
+            "step 'parse_games': games.pgn: game 3: No possible origins found"
+        );
+        assert_eq!(err.kind(), ErrorKind::IllegalMove);
+    }
+
+    #[test]
+    fn innermost_context_is_kept() {
+        let err = Error::decode("bad")
+            .in_step("parse_games")
+            .in_step("serial_step")
+            .in_game(1)
+            .in_game(2);
+
+        assert_eq!(err.step(), Some("parse_games"));
+        assert_eq!(err.game_index(), Some(1));
+        assert_eq!(err.file(), None);
+    }
+}
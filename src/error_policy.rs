@@ -0,0 +1,242 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use crate::error::Error;
+use crate::step_param_utils::ParamType;
+use crate::workflow_step::{SharedData, StepData};
+
+// Vector holding the category of every game skipped or quarantined during the run
+pub const GAME_ERRORS: &str = "game_errors";
+
+// What a parser or board-building step does with a game it cannot handle
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OnError {
+    #[default]
+    Abort,
+    Skip,
+    Quarantine,
+}
+
+impl ParamType for OnError {
+    fn type_name() -> String {
+        "abort | skip | quarantine".to_string()
+    }
+}
+
+// The offending game as it was read, written to the quarantine file
+#[derive(Debug, PartialEq, Eq)]
+pub enum Record {
+    Text(String),
+    Binary(Vec<u8>),
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ErrorPolicy {
+    on_error: OnError,
+    quarantine_file: Option<PathBuf>,
+}
+
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+impl ErrorPolicy {
+    pub fn try_new(
+        step_type: &str,
+        on_error: OnError,
+        quarantine_file: Option<String>,
+    ) -> Result<Self, Error> {
+        match (on_error, &quarantine_file) {
+            (OnError::Quarantine, None) => Err(Error::config(format!(
+                "{step_type}: on_error quarantine requires a quarantine_file"
+            ))),
+            (OnError::Abort | OnError::Skip, Some(_)) => Err(Error::config(format!(
+                "{step_type}: quarantine_file is only used when on_error is quarantine"
+            ))),
+            _ => Ok(ErrorPolicy {
+                on_error,
+                quarantine_file: quarantine_file.map(PathBuf::from),
+            }),
+        }
+    }
+
+    // Entries written by `handle`, to be declared among the outputs of the step
+    pub fn outputs(&self) -> Vec<String> {
+        match self.on_error {
+            OnError::Abort => vec![],
+            OnError::Skip | OnError::Quarantine => vec![GAME_ERRORS.to_string()],
+        }
+    }
+
+    // Returns the error when aborting. Otherwise the failure is counted, the record is
+    // quarantined if asked to, and the caller moves on to the next game.
+    pub fn handle(
+        &self,
+        err: Error,
+        record: Record,
+        data: &mut HashMap<String, SharedData>,
+    ) -> Result<(), Error> {
+        match (self.on_error, &self.quarantine_file) {
+            (OnError::Abort, _) => return Err(err),
+            (OnError::Quarantine, Some(path)) => quarantine(path, &err, &record)?,
+            _ => {}
+        }
+
+        data.init_vec_if_unset(GAME_ERRORS);
+        data.try_push_to_vec(GAME_ERRORS, SharedData::String(err.kind().to_string()))
+    }
+}
+
+// Appends the record, preceded by its error on a PGN escape line so quarantined PGN text
+// can be parsed again. Binary records are written as hex.
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+fn quarantine(path: &Path, err: &Error, record: &Record) -> Result<(), Error> {
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|io_err| Error::from(io_err).in_file(path))?;
+
+    let mut entry = format!("% {err}\n");
+    match record {
+        Record::Text(text) => entry.push_str(text.trim_end()),
+        Record::Binary(bytes) => {
+            let lines: Vec<String> = bytes
+                .chunks(32)
+                .map(|chunk| chunk.iter().map(|byte| format!("{byte:02x}")).collect())
+                .collect();
+            entry.push_str(&lines.join("\n"));
+        }
+    }
+    entry.push_str("\n\n");
+
+    file.write_all(entry.as_bytes())
+        .map_err(|io_err| Error::from(io_err).in_file(path))
+}
+
+// Number of skipped or quarantined games per error category, if any were
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+pub fn error_summary(data: &HashMap<String, SharedData>) -> Option<String> {
+    let kinds = match data.get(GAME_ERRORS) {
+        Some(SharedData::Vec(kinds)) => kinds,
+        _ => return None,
+    };
+
+    let mut counts: BTreeMap<&str, u64> = BTreeMap::new();
+    for kind in kinds {
+        if let SharedData::String(kind) = kind {
+            *counts.entry(kind.as_str()).or_default() += 1;
+        }
+    }
+
+    if counts.is_empty() {
+        return None;
+    }
+
+    let mut summary = format!(
+        "Games skipped because of errors: {}\n",
+        counts.values().sum::<u64>()
+    );
+    for (kind, count) in counts {
+        summary.push_str(&format!("    {kind}: {count}\n"));
+    }
+
+    Some(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quarantine_requires_a_file() {
+        assert_eq!(
+            ErrorPolicy::try_new("ParsePgnStep", OnError::Quarantine, None),
+            Err(Error::config(
+                "ParsePgnStep: on_error quarantine requires a quarantine_file"
+            ))
+        );
+        assert!(
+            ErrorPolicy::try_new("ParsePgnStep", OnError::Skip, Some("q.pgn".to_string())).is_err()
+        );
+        assert_eq!(
+            ErrorPolicy::try_new("ParsePgnStep", OnError::Abort, None),
+            Ok(ErrorPolicy::default())
+        );
+    }
+
+    #[test]
+    fn abort_returns_the_error() {
+        let mut data = HashMap::new();
+        let err = Error::pgn("Unknown result: 2-0");
+
+        assert_eq!(
+            ErrorPolicy::default().handle(err.clone(), Record::Text(String::new()), &mut data),
+            Err(err)
+        );
+        assert_eq!(error_summary(&data), None);
+    }
+
+    #[test]
+    fn skipped_games_are_summarized() {
+        let policy = ErrorPolicy::try_new("ParsePgnStep", OnError::Skip, None).unwrap();
+        let mut data = HashMap::new();
+
+        for err in [
+            Error::pgn("Unknown result: 2-0"),
+            Error::illegal_move("No possible origins found"),
+            Error::pgn("Malformed header: [Event"),
+        ] {
+            policy
+                .handle(err, Record::Text(String::new()), &mut data)
+                .unwrap();
+        }
+
+        assert_eq!(policy.outputs(), vec![GAME_ERRORS.to_string()]);
+        assert_eq!(
+            error_summary(&data),
+            Some(
+                "Games skipped because of errors: 3\n    illegal_move: 1\n    pgn: 2\n".to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn quarantined_records_are_appended() {
+        let path = std::env::temp_dir().join("chess_analytics_quarantine_test.pgn");
+        let _ = std::fs::remove_file(&path);
+
+        let policy = ErrorPolicy::try_new(
+            "ParsePgnStep",
+            OnError::Quarantine,
+            Some(path.to_string_lossy().to_string()),
+        )
+        .unwrap();
+        let mut data = HashMap::new();
+
+        policy
+            .handle(
+                Error::pgn("Unknown result: 2-0").in_game(4),
+                Record::Text("[Result \"2-0\"]\n\n1. e4 2-0\n".to_string()),
+                &mut data,
+            )
+            .unwrap();
+        policy
+            .handle(
+                Error::decode("unexpected end of input"),
+                Record::Binary(vec![0, 1, 254]),
+                &mut data,
+            )
+            .unwrap();
+
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "% game 4: Unknown result: 2-0\n[Result \"2-0\"]\n\n1. e4 2-0\n\n\
+             % unexpected end of input\n0001fe\n\n"
+        );
+
+        let _ = std::fs::remove_file(&path);
+    }
+}
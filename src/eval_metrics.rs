@@ -0,0 +1,247 @@
+use serde::Deserialize;
+
+use crate::{basic_types::Player, game::Game, step_param_utils::ParamType};
+
+// Evaluation of the starting position, which games only carry evaluations after each move for
+const INITIAL_CENTIPAWNS: i32 = 15;
+// Evaluations are capped so that mates and lost causes don't dwarf every other move
+const CENTIPAWN_CAP: i32 = 1000;
+
+// Drop in the mover's win percentage from which a move is judged, as Lichess does
+const INACCURACY_LOSS: f64 = 5.0;
+const MISTAKE_LOSS: f64 = 10.0;
+const BLUNDER_LOSS: f64 = 15.0;
+
+// Players of a game whose moves are measured
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Side {
+    White,
+    Black,
+    #[default]
+    Both,
+}
+
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+impl Side {
+    pub fn includes(self, player: Player) -> bool {
+        match self {
+            Side::White => player == Player::White,
+            Side::Black => player == Player::Black,
+            Side::Both => true,
+        }
+    }
+}
+
+impl ParamType for Side {
+    fn type_name() -> String {
+        "white | black | both".to_string()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Judgement {
+    Inaccuracy,
+    Mistake,
+    Blunder,
+}
+
+impl ParamType for Judgement {
+    fn type_name() -> String {
+        "inaccuracy | mistake | blunder".to_string()
+    }
+}
+
+// Totals over the evaluated moves of one side of a game
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct MoveMetrics {
+    pub moves: u64,
+    pub centipawn_loss: u64,
+    pub accuracy: f64,
+    pub inaccuracies: u64,
+    pub mistakes: u64,
+    pub blunders: u64,
+}
+
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+impl MoveMetrics {
+    pub fn average_centipawn_loss(&self) -> u64 {
+        if self.moves == 0 {
+            return 0;
+        }
+        (self.centipawn_loss as f64 / self.moves as f64).round() as u64
+    }
+
+    // Mean of the accuracy of every move, from 0 to 100
+    pub fn average_accuracy(&self) -> u64 {
+        if self.moves == 0 {
+            return 0;
+        }
+        (self.accuracy / self.moves as f64).round() as u64
+    }
+
+    pub fn count(&self, judgement: Judgement) -> u64 {
+        match judgement {
+            Judgement::Inaccuracy => self.inaccuracies,
+            Judgement::Mistake => self.mistakes,
+            Judgement::Blunder => self.blunders,
+        }
+    }
+
+    fn add_move(&mut self, centipawns_before: i32, centipawns_after: i32) {
+        let loss = win_percent(centipawns_before) - win_percent(centipawns_after);
+
+        self.moves += 1;
+        self.centipawn_loss += (centipawns_before - centipawns_after).max(0) as u64;
+        self.accuracy += move_accuracy(loss);
+        if loss >= BLUNDER_LOSS {
+            self.blunders += 1;
+        } else if loss >= MISTAKE_LOSS {
+            self.mistakes += 1;
+        } else if loss >= INACCURACY_LOSS {
+            self.inaccuracies += 1;
+        }
+    }
+
+    fn merge(self, other: MoveMetrics) -> MoveMetrics {
+        MoveMetrics {
+            moves: self.moves + other.moves,
+            centipawn_loss: self.centipawn_loss + other.centipawn_loss,
+            accuracy: self.accuracy + other.accuracy,
+            inaccuracies: self.inaccuracies + other.inaccuracies,
+            mistakes: self.mistakes + other.mistakes,
+            blunders: self.blunders + other.blunders,
+        }
+    }
+}
+
+// Chances of winning, from 0 to 100, of the side the centipawns are counted for
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+fn win_percent(centipawns: i32) -> f64 {
+    50.0 + 50.0 * (2.0 / (1.0 + (-0.003_682_08 * f64::from(centipawns)).exp()) - 1.0)
+}
+
+// Lichess' accuracy of a move losing the given win percentage, including its one point bonus
+// for the uncertainty of the evaluations
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+fn move_accuracy(win_percent_loss: f64) -> f64 {
+    let accuracy = 103.166_810_071_164_9 * (-0.043_544_153_867_539_51 * win_percent_loss).exp()
+        - 3.166_924_740_191_411;
+    (accuracy + 1.0).clamp(0.0, 100.0)
+}
+
+// White's advantage after the given ply, mates counting as the cap
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+pub fn centipawns(game: &Game, ply: usize) -> i32 {
+    match game.eval_mate_in[ply] {
+        0 => {
+            ((game.eval_advantage[ply] * 100.0).round() as i32).clamp(-CENTIPAWN_CAP, CENTIPAWN_CAP)
+        }
+        mate_in if mate_in > 0 => CENTIPAWN_CAP,
+        _ => -CENTIPAWN_CAP,
+    }
+}
+
+// Metrics of the moves of the given side that have an evaluation after them
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+pub fn move_metrics(game: &Game, side: Side) -> MoveMetrics {
+    let mut mover = game
+        .starting_board()
+        .map_or(Player::White, |board| board.to_move());
+    let evaluated = game
+        .moves
+        .len()
+        .min(game.eval_advantage.len())
+        .min(game.eval_mate_in.len());
+
+    let mut white = MoveMetrics::default();
+    let mut black = MoveMetrics::default();
+    let mut before = INITIAL_CENTIPAWNS;
+    for ply in 0..evaluated {
+        let after = centipawns(game, ply);
+        if mover == Player::White {
+            white.add_move(before, after);
+        } else {
+            black.add_move(-before, -after);
+        }
+
+        before = after;
+        mover = mover.get_opposing_player();
+    }
+
+    match side {
+        Side::White => white,
+        Side::Black => black,
+        Side::Both => white.merge(black),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_pgn::PgnParser;
+
+    fn game(movetext: &str) -> Game {
+        let mut game = Game::default();
+        PgnParser::new()
+            .parse_game(&Vec::new(), movetext, &mut game)
+            .unwrap();
+        game
+    }
+
+    #[test]
+    fn test_win_percent() {
+        assert_eq!(win_percent(0), 50.0);
+        assert!(win_percent(300) > 75.0);
+        assert!((win_percent(300) + win_percent(-300) - 100.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_move_accuracy() {
+        assert_eq!(move_accuracy(0.0), 100.0);
+        assert!(move_accuracy(10.0) < move_accuracy(5.0));
+        assert_eq!(move_accuracy(100.0), 0.0);
+    }
+
+    #[test]
+    fn test_centipawn_loss() {
+        let game = game(
+            "1. e4 { [%eval 0.15] } 1... e5 { [%eval 0.35] } 2. Qh5 { [%eval -0.25] } \
+             2... Ke7 { [%eval 2.75] } *",
+        );
+
+        let white = move_metrics(&game, Side::White);
+        assert_eq!((white.moves, white.centipawn_loss), (2, 60));
+        assert_eq!(white.average_centipawn_loss(), 30);
+
+        let black = move_metrics(&game, Side::Black);
+        assert_eq!((black.moves, black.centipawn_loss), (2, 320));
+        assert_eq!(black.count(Judgement::Blunder), 1);
+
+        let both = move_metrics(&game, Side::Both);
+        assert_eq!(both.centipawn_loss, 380);
+        assert_eq!(both.average_centipawn_loss(), 95);
+    }
+
+    #[test]
+    fn test_mates_are_capped() {
+        let game = game("1. f3 { [%eval -0.5] } 1... e5 { [%eval -0.4] } 2. g4 { [%eval #-1] } *");
+
+        let white = move_metrics(&game, Side::White);
+        assert_eq!(white.centipawn_loss, 65 + 960);
+        assert_eq!(white.count(Judgement::Blunder), 1);
+        assert_eq!(white.count(Judgement::Inaccuracy), 1);
+
+        let black = move_metrics(&game, Side::Black);
+        assert_eq!(black.centipawn_loss, 10);
+        assert_eq!(black.average_accuracy(), 97);
+    }
+
+    #[test]
+    fn test_no_evaluations() {
+        let metrics = move_metrics(&game("1. e4 e5 *"), Side::Both);
+        assert_eq!(metrics, MoveMetrics::default());
+        assert_eq!(metrics.average_accuracy(), 0);
+    }
+}
@@ -1,6 +1,8 @@
-use crate::basic_types::{GameResult, Move, Termination, TimeControl};
+use crate::basic_types::{GameEnding, GameResult, Move, Termination, TimeControl};
 use crate::board::Board;
+use crate::error::Error;
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
 use std::time::Duration;
 
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
@@ -33,6 +35,18 @@ pub struct Game {
     pub white_diff: i16,
     pub black_diff: i16,
     pub boards: Vec<Board>,
+
+    // Part of the binary format since version 2
+    pub tags: Vec<(String, String)>,
+    pub variations: Vec<Variation>,
+}
+
+// Moves played instead of the move at index `ply` of the line the variation branches from
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Variation {
+    pub ply: usize,
+    pub moves: Vec<Move>,
+    pub variations: Vec<Variation>,
 }
 
 #[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
@@ -42,16 +56,80 @@ impl Game {
         !self.clock.is_empty()
     }
 
-    pub fn build_boards(&self) -> Vec<Board> {
-        self.moves
+    // Position the game starts from, which is the standard one unless a FEN tag sets it up
+    pub fn starting_board(&self) -> Result<Board, Error> {
+        match self.tags.iter().find(|(name, _)| name == "FEN") {
+            Some((_, fen)) => Board::from_fen(fen)
+                .map_err(|err| Error::pgn(format!("Invalid FEN: {}", err.message()))),
+            None => Ok(Board::default()),
+        }
+    }
+
+    pub fn build_boards(&self) -> Result<Vec<Board>, Error> {
+        let mut boards = vec![self.starting_board()?];
+        for (move_index, curr_move) in self.moves.iter().enumerate() {
+            let mut new_board = boards[move_index];
+            new_board.move_piece(*curr_move).map_err(|err| {
+                let message = format!("move {}: {}", move_index + 1, err.message());
+                Error::new(err.kind(), message)
+            })?;
+            boards.push(new_board);
+        }
+
+        Ok(boards)
+    }
+
+    // Boards after every move, as set up by InitBoardsStep or else replayed from the moves
+    pub fn replayed_boards(&self) -> Result<Cow<'_, [Board]>, Error> {
+        if self.boards.len() == self.moves.len() + 1 {
+            Ok(Cow::Borrowed(&self.boards))
+        } else {
+            Ok(Cow::Owned(self.build_boards()?))
+        }
+    }
+
+    // Why the game ended, found by replaying it. Draws that a player has to claim are only
+    // reported when the game was actually drawn.
+    pub fn ending(&self) -> Result<GameEnding, Error> {
+        let boards = self.replayed_boards()?;
+        let last = boards[boards.len() - 1];
+
+        if last.is_checkmate() {
+            return Ok(GameEnding::Checkmate);
+        }
+        if last.is_stalemate() {
+            return Ok(GameEnding::Stalemate);
+        }
+        if last.has_insufficient_material() {
+            return Ok(GameEnding::InsufficientMaterial);
+        }
+
+        // Positions can only repeat since the last capture or pawn move, and only with the
+        // same side to move
+        let repetitions = boards
             .iter()
-            .fold(vec![Board::default()], |mut boards, curr_move| {
-                let mut new_board = boards.last().unwrap().clone();
-                new_board.move_piece(*curr_move);
-                boards.push(new_board);
+            .rev()
+            .take(usize::from(last.halfmove_clock()) + 1)
+            .step_by(2)
+            .filter(|board| board.is_repetition_of(&last))
+            .count();
+
+        if repetitions >= 5 {
+            return Ok(GameEnding::FivefoldRepetition);
+        }
+        if last.halfmove_clock() >= 150 {
+            return Ok(GameEnding::SeventyFiveMoveRule);
+        }
+        if self.result == GameResult::Draw {
+            if repetitions >= 3 {
+                return Ok(GameEnding::ThreefoldRepetition);
+            }
+            if last.halfmove_clock() >= 100 {
+                return Ok(GameEnding::FiftyMoveRule);
+            }
+        }
 
-                boards
-            })
+        Ok(GameEnding::Other)
     }
 
     pub fn eval_available(&self) -> bool {
@@ -86,6 +164,8 @@ impl Default for Game {
             white_diff: 0,
             black_diff: 0,
             boards: vec![],
+            tags: vec![],
+            variations: vec![],
         }
     }
 }
@@ -105,7 +185,7 @@ mod test_build_boards {
                 let mut test_game = Game::default();
                 test_game.moves = moves;
 
-                let actual_boards = test_game.build_boards();
+                let actual_boards = test_game.build_boards().unwrap();
                 let actual_fens: Vec<String> = actual_boards.iter().map(|board| board.to_fen()).collect();
 
                 assert_eq!(actual_fens.len(), expected_fens.len());
@@ -116,9 +196,177 @@ mod test_build_boards {
     }
 
     tests! {
-        test_no_moves: (vec![], vec!["rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w"]),
-        test_one_move: (vec![Move::new_to(File::_A, Rank::_4, Piece::Pawn)], vec!["rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w", "rnbqkbnr/pppppppp/8/8/P7/8/1PPPPPPP/RNBQKBNR b"]),
-        test_two_moves: (vec![Move::new_to(File::_F, Rank::_3, Piece::Knight), Move::new_to(File::_D, Rank::_6, Piece::Pawn)], vec!["rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w", "rnbqkbnr/pppppppp/8/8/8/5N2/PPPPPPPP/RNBQKB1R b", "rnbqkbnr/ppp1pppp/3p4/8/8/5N2/PPPPPPPP/RNBQKB1R w"]),
+        test_no_moves: (vec![], vec!["rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"]),
+        test_one_move: (vec![Move::new_to(File::_A, Rank::_4, Piece::Pawn)], vec!["rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", "rnbqkbnr/pppppppp/8/8/P7/8/1PPPPPPP/RNBQKBNR b KQkq a3 0 1"]),
+        test_two_moves: (vec![Move::new_to(File::_F, Rank::_3, Piece::Knight), Move::new_to(File::_D, Rank::_6, Piece::Pawn)], vec!["rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", "rnbqkbnr/pppppppp/8/8/8/5N2/PPPPPPPP/RNBQKB1R b KQkq - 1 1", "rnbqkbnr/ppp1pppp/3p4/8/8/5N2/PPPPPPPP/RNBQKB1R w KQkq - 0 2"]),
+    }
+
+    #[test]
+    fn test_fen_tag() {
+        let mut test_game = Game::default();
+        test_game.tags = vec![(
+            "FEN".to_string(),
+            "4k3/8/8/8/8/8/4P3/4K2R w K - 0 40".to_string(),
+        )];
+        test_game.moves = vec![
+            Move::new_to(File::_E, Rank::_4, Piece::Pawn),
+            Move::new_to(File::_E, Rank::_7, Piece::King),
+            Move::new_to_from(
+                Some(File::_E),
+                Some(Rank::_1),
+                File::_G,
+                Rank::_1,
+                Piece::King,
+            ),
+        ];
+
+        let actual_fens: Vec<String> = test_game
+            .build_boards()
+            .unwrap()
+            .iter()
+            .map(|board| board.to_fen())
+            .collect();
+        assert_eq!(
+            actual_fens,
+            vec![
+                "4k3/8/8/8/8/8/4P3/4K2R w K - 0 40",
+                "4k3/8/8/8/4P3/8/8/4K2R b K e3 0 40",
+                "8/4k3/8/8/4P3/8/8/4K2R w K - 1 41",
+                "8/4k3/8/8/4P3/8/8/5RK1 b - - 2 41",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_invalid_fen_tag() {
+        let mut test_game = Game::default();
+        test_game.tags = vec![("FEN".to_string(), "8/8 w - - 0 1".to_string())];
+
+        let err = test_game.build_boards().unwrap_err();
+        assert_eq!(
+            err.message(),
+            "Invalid FEN: Starting position has wrong number of rows"
+        );
+    }
+}
+
+#[cfg(test)]
+mod test_ending {
+    use super::*;
+    use crate::basic_types::{File, Piece, Rank};
+
+    fn knight_shuffles(count: usize) -> Vec<Move> {
+        [
+            Move::new_to(File::_F, Rank::_3, Piece::Knight),
+            Move::new_to(File::_F, Rank::_6, Piece::Knight),
+            Move::new_to(File::_G, Rank::_1, Piece::Knight),
+            Move::new_to(File::_G, Rank::_8, Piece::Knight),
+        ]
+        .into_iter()
+        .cycle()
+        .take(count * 4)
+        .collect()
+    }
+
+    fn game_from(fen: Option<&str>, moves: Vec<Move>, result: GameResult) -> Game {
+        let mut game = Game::default();
+        if let Some(fen) = fen {
+            game.tags = vec![("FEN".to_string(), fen.to_string())];
+        }
+        game.moves = moves;
+        game.result = result;
+        game
+    }
+
+    #[test]
+    fn test_checkmate() {
+        let game = game_from(
+            None,
+            vec![
+                Move::new_to(File::_F, Rank::_3, Piece::Pawn),
+                Move::new_to(File::_E, Rank::_5, Piece::Pawn),
+                Move::new_to(File::_G, Rank::_4, Piece::Pawn),
+                Move::new_to(File::_H, Rank::_4, Piece::Queen),
+            ],
+            GameResult::Black,
+        );
+        assert_eq!(game.ending(), Ok(GameEnding::Checkmate));
+    }
+
+    #[test]
+    fn test_stalemate() {
+        let game = game_from(
+            Some("7k/8/6K1/8/8/8/8/5Q2 w - - 0 1"),
+            vec![Move::new_to(File::_F, Rank::_7, Piece::Queen)],
+            GameResult::Draw,
+        );
+        assert_eq!(game.ending(), Ok(GameEnding::Stalemate));
+    }
+
+    #[test]
+    fn test_insufficient_material() {
+        let game = game_from(
+            Some("8/8/4k3/8/3q4/4K3/8/8 w - - 0 1"),
+            vec![Move::new_to(File::_D, Rank::_4, Piece::King)],
+            GameResult::Draw,
+        );
+        assert_eq!(game.ending(), Ok(GameEnding::InsufficientMaterial));
+    }
+
+    #[test]
+    fn test_threefold_repetition() {
+        let game = game_from(None, knight_shuffles(2), GameResult::Draw);
+        assert_eq!(game.ending(), Ok(GameEnding::ThreefoldRepetition));
+
+        let game = game_from(None, knight_shuffles(2), GameResult::White);
+        assert_eq!(game.ending(), Ok(GameEnding::Other));
+
+        let game = game_from(None, knight_shuffles(1), GameResult::Draw);
+        assert_eq!(game.ending(), Ok(GameEnding::Other));
+    }
+
+    #[test]
+    fn test_fivefold_repetition() {
+        let game = game_from(None, knight_shuffles(4), GameResult::White);
+        assert_eq!(game.ending(), Ok(GameEnding::FivefoldRepetition));
+    }
+
+    #[test]
+    fn test_fifty_move_rule() {
+        let fen = "4k3/8/8/8/8/8/8/R3K3 w - - 99 80";
+        let moves = vec![Move::new_to(File::_A, Rank::_2, Piece::Rook)];
+        let game = game_from(Some(fen), moves.clone(), GameResult::Draw);
+        assert_eq!(game.ending(), Ok(GameEnding::FiftyMoveRule));
+
+        let game = game_from(Some(fen), moves, GameResult::Star);
+        assert_eq!(game.ending(), Ok(GameEnding::Other));
+    }
+
+    #[test]
+    fn test_seventy_five_move_rule() {
+        let game = game_from(
+            Some("4k3/8/8/8/8/8/8/R3K3 w - - 149 80"),
+            vec![Move::new_to(File::_A, Rank::_2, Piece::Rook)],
+            GameResult::Star,
+        );
+        assert_eq!(game.ending(), Ok(GameEnding::SeventyFiveMoveRule));
+    }
+
+    #[test]
+    fn test_uses_initialized_boards() {
+        let mut game = game_from(None, knight_shuffles(2), GameResult::Draw);
+        game.boards = game.build_boards().unwrap();
+        assert_eq!(game.ending(), Ok(GameEnding::ThreefoldRepetition));
+    }
+
+    #[test]
+    fn test_illegal_move() {
+        let game = game_from(
+            None,
+            vec![Move::new_to(File::_E, Rank::_5, Piece::Pawn)],
+            GameResult::Draw,
+        );
+        assert!(game.ending().is_err());
     }
 }
 
@@ -148,7 +396,7 @@ mod test_debug_impl {
     fn test_default() {
         assert_eq!(
             format!("{:?}", Game::default()),
-            r#"Game { year: 0, month: 0, day: 0, site: "", white: "", black: "", white_rating: 0, black_rating: 0, time_control_main: 0, time_control_increment: 0, time_control: UltraBullet, eval_available: false, eco_category: '-', eco_subcategory: 0, moves: [], clock: [], eval_mate_in: [], eval_advantage: [], result: Draw, termination: Normal, white_diff: 0, black_diff: 0, boards: [] }"#
+            r#"Game { year: 0, month: 0, day: 0, site: "", white: "", black: "", white_rating: 0, black_rating: 0, time_control_main: 0, time_control_increment: 0, time_control: UltraBullet, eval_available: false, eco_category: '-', eco_subcategory: 0, moves: [], clock: [], eval_mate_in: [], eval_advantage: [], result: Draw, termination: Normal, white_diff: 0, black_diff: 0, boards: [], tags: [], variations: [] }"#
         );
     }
 }
@@ -0,0 +1,438 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::{Read, Write};
+use std::path::Path;
+use std::time::Duration;
+
+use bzip2::read::BzDecoder;
+use bzip2::write::BzEncoder;
+use bzip2::Compression;
+use serde::Deserialize;
+
+use crate::basic_types::{Cell, GameResult, Move, Player, PlayerPiece, Termination, TimeControl};
+use crate::error::Error;
+use crate::game::Game;
+
+// Binary game files start with a header, followed by each postcard encoded game:
+//   magic (4 bytes) | format version (u16 LE) | game count (u64 LE)
+// Files without the magic are version 1, the headerless postcard encoding of a Vec<Game>.
+pub const MAGIC: [u8; 4] = *b"CABG";
+pub const FORMAT_VERSION: u16 = 2;
+const HEADERLESS_VERSION: u16 = 1;
+const HEADER_LEN: usize = 14;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Header {
+    pub version: u16,
+    pub game_count: u64,
+}
+
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+impl Header {
+    // Header of a file, and the bytes of its games that follow it
+    pub fn read(file_data: &[u8]) -> Result<(Header, &[u8]), Error> {
+        if !file_data.starts_with(&MAGIC) {
+            let (game_count, games) = postcard::take_from_bytes::<u64>(file_data)?;
+            let header = Header {
+                version: HEADERLESS_VERSION,
+                game_count,
+            };
+            return Ok((header, games));
+        }
+
+        if file_data.len() < HEADER_LEN {
+            return Err(Error::decode("Truncated file header"));
+        }
+        let version = u16::from_le_bytes([file_data[4], file_data[5]]);
+        if version > FORMAT_VERSION {
+            return Err(Error::decode(format!(
+                "Format version {version} is newer than the supported version {FORMAT_VERSION}"
+            )));
+        }
+        if version <= HEADERLESS_VERSION {
+            return Err(Error::decode(format!("Invalid format version {version}")));
+        }
+
+        let game_count = u64::from_le_bytes(file_data[6..HEADER_LEN].try_into().unwrap());
+        Ok((
+            Header {
+                version,
+                game_count,
+            },
+            &file_data[HEADER_LEN..],
+        ))
+    }
+
+    fn to_bytes(self) -> Vec<u8> {
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend(self.version.to_le_bytes());
+        bytes.extend(self.game_count.to_le_bytes());
+        bytes
+    }
+}
+
+// Encodes games in the current format version
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+pub fn encode_games(games: &[Game]) -> Result<Vec<u8>, Error> {
+    let header = Header {
+        version: FORMAT_VERSION,
+        game_count: games.len() as u64,
+    };
+
+    let mut file_data = header.to_bytes();
+    for game in games {
+        file_data.extend(postcard::to_allocvec(game)?);
+    }
+    Ok(file_data)
+}
+
+// The first corrupt game of a file, with the bytes left undecoded from its start
+pub type DecodeFailure<'a> = (Error, &'a [u8]);
+
+// Decodes the games of a file of any supported format version. Games are decoded one at a
+// time so a corrupt one can be reported by index. Returns the games before the first corrupt
+// one, along with its error and the bytes left undecoded.
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+pub fn decode_games(file_data: &[u8]) -> (Vec<Game>, Option<DecodeFailure<'_>>) {
+    let (games, failure) = decode_games_with_offsets(file_data);
+    (games.into_iter().map(|(_, game)| game).collect(), failure)
+}
+
+// Same as `decode_games`, with the offset in the file each game starts at
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+pub fn decode_games_with_offsets(
+    file_data: &[u8],
+) -> (Vec<(usize, Game)>, Option<DecodeFailure<'_>>) {
+    let (header, mut remaining) = match Header::read(file_data) {
+        Ok(read) => read,
+        Err(err) => return (vec![], Some((err, file_data))),
+    };
+
+    let mut games = vec![];
+    for index in 0..header.game_count {
+        let index = usize::try_from(index).unwrap_or(usize::MAX);
+        let offset = file_data.len() - remaining.len();
+        match take_game(header.version, remaining) {
+            Ok((game, rest)) => {
+                games.push((offset, game));
+                remaining = rest;
+            }
+            Err(err) => return (games, Some((Error::from(err).in_game(index), remaining))),
+        }
+    }
+
+    (games, None)
+}
+
+// Decodes the single game starting at the given offset, as returned by
+// `decode_games_with_offsets`
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+pub fn decode_game_at(file_data: &[u8], offset: usize) -> Result<Game, Error> {
+    let (header, games) = Header::read(file_data)?;
+    if offset < file_data.len() - games.len() || offset >= file_data.len() {
+        return Err(Error::decode(format!("No game starts at offset {offset}")));
+    }
+    Ok(take_game(header.version, &file_data[offset..])?.0)
+}
+
+// Contents of a binary game file, decompressed when its extension is bz2
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+pub fn read_file(path: &Path) -> Result<Vec<u8>, Error> {
+    let file_data = fs::read(path).map_err(|err| Error::from(err).in_file(path))?;
+    if path.extension().is_none_or(|extension| extension != "bz2") {
+        return Ok(file_data);
+    }
+
+    let mut decompressed = vec![];
+    BzDecoder::new(file_data.as_slice())
+        .read_to_end(&mut decompressed)
+        .map_err(|err| Error::from(err).in_file(path))?;
+    Ok(decompressed)
+}
+
+// Writes games to a binary game file in the current format version, compressed at the given
+// bz2 level when its extension is bz2. Returns the size of the written file.
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+pub fn write_file(path: &Path, games: &[Game], compression: u32) -> Result<u64, Error> {
+    let mut file_data = encode_games(games)?;
+    if path.extension().is_some_and(|extension| extension == "bz2") {
+        let mut compressor = BzEncoder::new(vec![], Compression::new(compression));
+        compressor
+            .write_all(&file_data)
+            .map_err(|err| Error::from(err).in_file(path))?;
+        file_data = compressor
+            .finish()
+            .map_err(|err| Error::from(err).in_file(path))?;
+    }
+
+    fs::write(path, &file_data).map_err(|err| Error::from(err).in_file(path))?;
+    Ok(file_data.len() as u64)
+}
+
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+fn take_game(version: u16, bytes: &[u8]) -> postcard::Result<(Game, &[u8])> {
+    match version {
+        HEADERLESS_VERSION => {
+            postcard::take_from_bytes::<GameV1>(bytes).map(|(game, rest)| (game.into(), rest))
+        }
+        _ => postcard::take_from_bytes::<Game>(bytes),
+    }
+}
+
+// Board as encoded by version 1, before boards held castling rights and en passant
+#[allow(dead_code)]
+#[derive(Debug, Deserialize)]
+#[cfg_attr(test, derive(serde::Serialize))]
+struct BoardV1 {
+    board: HashMap<Cell, PlayerPiece>,
+    to_move: Player,
+}
+
+// Game as encoded by version 1
+#[derive(Debug, Deserialize)]
+#[cfg_attr(test, derive(serde::Serialize))]
+struct GameV1 {
+    year: u16,
+    month: u8,
+    day: u8,
+    site: String,
+    white: String,
+    black: String,
+    white_rating: u16,
+    black_rating: u16,
+    time_control_main: u16,
+    time_control_increment: u8,
+    time_control: TimeControl,
+    eval_available: bool,
+    eco_category: char,
+    eco_subcategory: u8,
+    moves: Vec<Move>,
+    clock: Vec<Duration>,
+    eval_mate_in: Vec<i16>,
+    eval_advantage: Vec<f32>,
+    result: GameResult,
+    termination: Termination,
+    white_diff: i16,
+    black_diff: i16,
+    // Only decoded to get past its bytes, From<GameV1> drops it
+    #[allow(dead_code)]
+    boards: Vec<BoardV1>,
+}
+
+// Version 1 boards can't be carried over, InitBoardsStep rebuilds them from the moves
+impl From<GameV1> for Game {
+    fn from(game: GameV1) -> Game {
+        Game {
+            year: game.year,
+            month: game.month,
+            day: game.day,
+            site: game.site,
+            white: game.white,
+            black: game.black,
+            white_rating: game.white_rating,
+            black_rating: game.black_rating,
+            time_control_main: game.time_control_main,
+            time_control_increment: game.time_control_increment,
+            time_control: game.time_control,
+            eval_available: game.eval_available,
+            eco_category: game.eco_category,
+            eco_subcategory: game.eco_subcategory,
+            moves: game.moves,
+            clock: game.clock,
+            eval_mate_in: game.eval_mate_in,
+            eval_advantage: game.eval_advantage,
+            result: game.result,
+            termination: game.termination,
+            white_diff: game.white_diff,
+            black_diff: game.black_diff,
+            ..Game::default()
+        }
+    }
+}
+
+// Encodes games as version 1 did, without their boards, tags or variations
+#[cfg(test)]
+pub fn encode_headerless(games: &[Game]) -> Vec<u8> {
+    let games: Vec<GameV1> = games.iter().cloned().map(GameV1::from).collect();
+    postcard::to_allocvec(&games).unwrap()
+}
+
+#[cfg(test)]
+impl From<Game> for GameV1 {
+    fn from(game: Game) -> GameV1 {
+        GameV1 {
+            year: game.year,
+            month: game.month,
+            day: game.day,
+            site: game.site,
+            white: game.white,
+            black: game.black,
+            white_rating: game.white_rating,
+            black_rating: game.black_rating,
+            time_control_main: game.time_control_main,
+            time_control_increment: game.time_control_increment,
+            time_control: game.time_control,
+            eval_available: game.eval_available,
+            eco_category: game.eco_category,
+            eco_subcategory: game.eco_subcategory,
+            moves: game.moves,
+            clock: game.clock,
+            eval_mate_in: game.eval_mate_in,
+            eval_advantage: game.eval_advantage,
+            result: game.result,
+            termination: game.termination,
+            white_diff: game.white_diff,
+            black_diff: game.black_diff,
+            boards: vec![],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+    use crate::parse_pgn::PgnParser;
+
+    fn games() -> Vec<Game> {
+        let mut game = Game::default();
+        PgnParser::new()
+            .parse_game(
+                &vec![r#"[White "Alice"]"#.to_string()],
+                "1. e4 { [%clk 0:03:00] } 1... e5 { [%clk 0:02:58] } 1-0",
+                &mut game,
+            )
+            .unwrap();
+        game.boards = game.build_boards().unwrap();
+        vec![game, Game::default()]
+    }
+
+    #[test]
+    fn round_trips_games() {
+        let file_data = encode_games(&games()).unwrap();
+        assert_eq!(&file_data[..4], b"CABG");
+
+        let (header, _) = Header::read(&file_data).unwrap();
+        assert_eq!(
+            header,
+            Header {
+                version: FORMAT_VERSION,
+                game_count: 2
+            }
+        );
+        assert_eq!(decode_games(&file_data), (games(), None));
+    }
+
+    #[test]
+    fn keeps_tags_and_variations() {
+        let mut game = Game::default();
+        PgnParser::new()
+            .keep_variations(true)
+            .parse_game(
+                &vec![
+                    r#"[Result "*"]"#.to_string(),
+                    r#"[SetUp "1"]"#.to_string(),
+                    r#"[FEN "4k3/8/8/8/8/8/4P3/4K3 w - - 0 1"]"#.to_string(),
+                    r#"[Opening "King's Pawn Game"]"#.to_string(),
+                ],
+                "1. e4 (1. e3 Kd7) 1... Kd7 *",
+                &mut game,
+            )
+            .unwrap();
+        assert_eq!(game.variations.len(), 1);
+
+        let file_data = encode_games(&[game.clone()]).unwrap();
+        let (decoded, failure) = decode_games(&file_data);
+        assert_eq!(failure, None);
+        assert_eq!(decoded, vec![game]);
+
+        // The FEN tag is what the moves of a set-up game are replayed from
+        assert!(decoded[0].build_boards().is_ok());
+    }
+
+    #[test]
+    fn reads_headerless_files() {
+        let mut game = games().remove(0);
+
+        // Version 1 games end with their boards, laid out as a map of pieces
+        let mut game_v1 = GameV1::from(game.clone());
+        game_v1.boards = vec![BoardV1 {
+            board: HashMap::new(),
+            to_move: Player::Black,
+        }];
+        let mut file_data = postcard::to_allocvec(&1u64).unwrap();
+        file_data.extend(postcard::to_allocvec(&game_v1).unwrap());
+
+        game.boards.clear();
+        game.tags.clear();
+
+        let (header, _) = Header::read(&file_data).unwrap();
+        assert_eq!(header.version, 1);
+        assert_eq!(decode_games(&file_data), (vec![game], None));
+    }
+
+    #[test]
+    fn rejects_newer_versions() {
+        let mut file_data = encode_games(&games()).unwrap();
+        file_data[4..6].copy_from_slice(&(FORMAT_VERSION + 1).to_le_bytes());
+
+        let (games, failure) = decode_games(&file_data);
+        let (err, undecoded) = failure.unwrap();
+        assert!(games.is_empty());
+        assert_eq!(err.kind(), ErrorKind::Decode);
+        assert!(err.message().contains("newer"));
+        assert_eq!(undecoded.len(), file_data.len());
+    }
+
+    #[test]
+    fn decodes_games_at_their_offsets() {
+        let file_data = encode_games(&games()).unwrap();
+        let (decoded, failure) = decode_games_with_offsets(&file_data);
+        assert!(failure.is_none());
+        assert_eq!(decoded[0].0, HEADER_LEN);
+
+        for (offset, game) in decoded {
+            assert_eq!(decode_game_at(&file_data, offset), Ok(game));
+        }
+        assert!(decode_game_at(&file_data, 0).is_err());
+        assert!(decode_game_at(&file_data, file_data.len()).is_err());
+    }
+
+    #[test]
+    fn writes_compressed_files() {
+        let path = std::env::temp_dir().join("chess_analytics_write_file_test.bin.bz2");
+        let size = write_file(&path, &games(), 1).unwrap();
+
+        assert_eq!(fs::metadata(&path).unwrap().len(), size);
+        assert_eq!(decode_games(&read_file(&path).unwrap()), (games(), None));
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn reports_truncated_header() {
+        let (_, failure) = decode_games(&MAGIC);
+        assert_eq!(failure.unwrap().0.kind(), ErrorKind::Decode);
+    }
+
+    #[test]
+    fn decodes_every_game() {
+        let games = vec![Game::default(), Game::default()];
+        let file_data = encode_headerless(&games);
+
+        assert_eq!(decode_games(&file_data), (games, None));
+    }
+
+    #[test]
+    fn reports_the_corrupt_game() {
+        let games = vec![Game::default(), Game::default()];
+        let mut file_data = encode_headerless(&games);
+        file_data.truncate(file_data.len() - 1);
+
+        let (decoded, failure) = decode_games(&file_data);
+        let (err, undecoded) = failure.unwrap();
+        assert_eq!(decoded, vec![Game::default()]);
+        assert_eq!(err.kind(), ErrorKind::Decode);
+        assert_eq!(err.game_index(), Some(1));
+        assert!(!undecoded.is_empty());
+    }
+}
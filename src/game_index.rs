@@ -0,0 +1,417 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::columnar::{packed_date, parse_date};
+use crate::error::Error;
+use crate::game::Game;
+use crate::game_archive::{decode_game_at, decode_games_with_offsets, read_file};
+use crate::pgn_writer::PgnWriter;
+
+// Index files start with a header, followed by the postcard encoded index:
+//   magic (4 bytes) | format version (u16 LE)
+pub const MAGIC: [u8; 4] = *b"CAGI";
+pub const FORMAT_VERSION: u16 = 1;
+const HEADER_LEN: usize = 6;
+
+// Where a game of an indexed binary file starts, along with the keys it can be looked up by
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndexedGame {
+    // Lichess game id, empty when the site isn't a game url
+    pub id: String,
+    pub white: String,
+    pub black: String,
+    // As YYYYMMDD
+    pub date: u32,
+    // Position of the file in `GameIndex::files`
+    pub file: u32,
+    pub offset: u64,
+}
+
+// Locations of the games of a set of binary game files. Games are kept in date order so a
+// date range is a slice of them.
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct GameIndex {
+    files: Vec<PathBuf>,
+    games: Vec<IndexedGame>,
+    #[serde(skip)]
+    by_id: HashMap<String, usize>,
+    // Keyed by lowercase name, as Lichess names are case insensitive
+    #[serde(skip)]
+    by_player: HashMap<String, Vec<usize>>,
+}
+
+// Games to look up. Every given key must match
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct IndexQuery {
+    pub id: Option<String>,
+    pub player: Option<String>,
+    pub min_date: Option<u32>,
+    pub max_date: Option<u32>,
+}
+
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+impl IndexQuery {
+    fn matches(&self, game: &IndexedGame) -> bool {
+        self.id.as_ref().is_none_or(|id| game.id == *id)
+            && self.player.as_ref().is_none_or(|player| {
+                game.white.eq_ignore_ascii_case(player) || game.black.eq_ignore_ascii_case(player)
+            })
+            && self.min_date.is_none_or(|min_date| game.date >= min_date)
+            && self.max_date.is_none_or(|max_date| game.date <= max_date)
+    }
+}
+
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+impl GameIndex {
+    // Indexes every game of the given binary game files, such as the files of ExportGames.
+    // The paths are stored as given, so relative paths are resolved when the index is used.
+    pub fn build(paths: &[PathBuf]) -> Result<GameIndex, Error> {
+        let mut index = GameIndex::default();
+        for path in paths {
+            let file_data = read_file(path)?;
+            let games = match decode_games_with_offsets(&file_data) {
+                (games, None) => games,
+                (_, Some((err, _))) => return Err(err.in_file(path)),
+            };
+
+            let file = u32::try_from(index.files.len())
+                .map_err(|_| Error::config("Too many files to index"))?;
+            index.files.push(path.clone());
+            index
+                .games
+                .extend(games.into_iter().map(|(offset, game)| IndexedGame {
+                    id: lichess_id(&game.site).unwrap_or_default().to_string(),
+                    date: packed_date(game.year, game.month, game.day),
+                    white: game.white,
+                    black: game.black,
+                    file,
+                    offset: offset as u64,
+                }));
+        }
+
+        index.games.sort_by_key(|game| game.date);
+        index.build_keys();
+        Ok(index)
+    }
+
+    pub fn read(path: &Path) -> Result<GameIndex, Error> {
+        let file_data = fs::read(path).map_err(|err| Error::from(err).in_file(path))?;
+        if file_data.len() < HEADER_LEN || !file_data.starts_with(&MAGIC) {
+            return Err(Error::decode("Not a game index file").in_file(path));
+        }
+        let version = u16::from_le_bytes([file_data[4], file_data[5]]);
+        if version != FORMAT_VERSION {
+            return Err(Error::decode(format!(
+                "Index format version {version} is not the supported version {FORMAT_VERSION}"
+            ))
+            .in_file(path));
+        }
+
+        let mut index: GameIndex = postcard::from_bytes(&file_data[HEADER_LEN..])
+            .map_err(|err| Error::from(err).in_file(path))?;
+        index.build_keys();
+        Ok(index)
+    }
+
+    pub fn write(&self, path: &Path) -> Result<(), Error> {
+        let mut file_data = MAGIC.to_vec();
+        file_data.extend(FORMAT_VERSION.to_le_bytes());
+        file_data.extend(postcard::to_allocvec(self)?);
+        fs::write(path, file_data).map_err(|err| Error::from(err).in_file(path))
+    }
+
+    pub fn game_count(&self) -> usize {
+        self.games.len()
+    }
+
+    // Matching games, in date order
+    pub fn find(&self, query: &IndexQuery) -> Vec<&IndexedGame> {
+        let candidates: Vec<usize> = if let Some(id) = &query.id {
+            self.by_id.get(id).copied().into_iter().collect()
+        } else if let Some(player) = &query.player {
+            let player = player.to_ascii_lowercase();
+            self.by_player.get(&player).cloned().unwrap_or_default()
+        } else {
+            let start = query.min_date.map_or(0, |min_date| {
+                self.games.partition_point(|game| game.date < min_date)
+            });
+            let end = query.max_date.map_or(self.games.len(), |max_date| {
+                self.games.partition_point(|game| game.date <= max_date)
+            });
+            (start..end).collect()
+        };
+
+        candidates
+            .into_iter()
+            .map(|position| &self.games[position])
+            .filter(|game| query.matches(game))
+            .collect()
+    }
+
+    // Decodes the given games from their files, reading each file once. Games are returned in
+    // file order.
+    pub fn fetch(&self, entries: &[&IndexedGame]) -> Result<Vec<Game>, Error> {
+        let mut entries = entries.to_vec();
+        entries.sort_by_key(|entry| (entry.file, entry.offset));
+
+        let mut games = vec![];
+        let mut loaded: Option<(u32, Vec<u8>)> = None;
+        for entry in entries {
+            let path = self
+                .files
+                .get(entry.file as usize)
+                .ok_or_else(|| Error::decode(format!("Unknown indexed file {}", entry.file)))?;
+
+            if loaded.as_ref().is_none_or(|(file, _)| *file != entry.file) {
+                loaded = Some((entry.file, read_file(path)?));
+            }
+            let (_, file_data) = loaded.as_ref().unwrap();
+
+            let offset = usize::try_from(entry.offset).unwrap_or(usize::MAX);
+            games.push(decode_game_at(file_data, offset).map_err(|err| err.in_file(path))?);
+        }
+
+        Ok(games)
+    }
+
+    // Lookup maps aren't stored, they are rebuilt from the games once loaded
+    fn build_keys(&mut self) {
+        self.by_id.clear();
+        self.by_player.clear();
+        for (position, game) in self.games.iter().enumerate() {
+            if !game.id.is_empty() {
+                self.by_id.entry(game.id.clone()).or_insert(position);
+            }
+
+            self.by_player
+                .entry(game.white.to_ascii_lowercase())
+                .or_default()
+                .push(position);
+            if !game.black.eq_ignore_ascii_case(&game.white) {
+                self.by_player
+                    .entry(game.black.to_ascii_lowercase())
+                    .or_default()
+                    .push(position);
+            }
+        }
+    }
+}
+
+// Game id of a Lichess game url, such as "https://lichess.org/j1dkb5dw"
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+pub fn lichess_id(site: &str) -> Option<&str> {
+    let id = site
+        .strip_prefix("https://lichess.org/")
+        .or_else(|| site.strip_prefix("http://lichess.org/"))?;
+    let id = id.split(['/', '?', '#']).next().unwrap_or_default();
+    (!id.is_empty()).then_some(id)
+}
+
+// Packed date of a "YYYY.MM.DD" argument or parameter
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+pub fn date_bound(context: &str, date: Option<&str>) -> Result<Option<u32>, Error> {
+    date.map(|date| {
+        parse_date(date).ok_or_else(|| {
+            Error::config(format!(
+                "{context}: invalid date '{date}', expected YYYY.MM.DD"
+            ))
+        })
+    })
+    .transpose()
+}
+
+// `index <index file> <file>...`: indexes the given binary game files
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+pub fn index_files<T, I>(mut args: T) -> Result<(), Error>
+where
+    T: Iterator<Item = I>,
+    I: AsRef<Path>,
+{
+    let index_path = match args.next() {
+        Some(path) => path.as_ref().to_path_buf(),
+        None => return Err(Error::config("index: index file path is required")),
+    };
+    let paths: Vec<PathBuf> = args.map(|path| path.as_ref().to_path_buf()).collect();
+    if paths.is_empty() {
+        return Err(Error::config("index: at least one file path is required"));
+    }
+
+    let index = GameIndex::build(&paths)?;
+    index.write(&index_path)?;
+    println!(
+        "{}: indexed {} games from {} files",
+        index_path.display(),
+        index.game_count(),
+        paths.len()
+    );
+    Ok(())
+}
+
+// `lookup <index file> id <id>`, `lookup <index file> player <name>` or
+// `lookup <index file> date <from> [<to>]`: prints the matching games as PGN
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+pub fn lookup_games<T, I>(args: T) -> Result<(), Error>
+where
+    T: Iterator<Item = I>,
+    I: AsRef<Path>,
+{
+    let args: Vec<String> = args
+        .map(|arg| arg.as_ref().to_string_lossy().into_owned())
+        .collect();
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+
+    let query = match args.get(1..) {
+        Some(["id", id]) => IndexQuery {
+            id: Some(id.to_string()),
+            ..IndexQuery::default()
+        },
+        Some(["player", player]) => IndexQuery {
+            player: Some(player.to_string()),
+            ..IndexQuery::default()
+        },
+        Some(["date", from]) => IndexQuery {
+            min_date: date_bound("lookup", Some(*from))?,
+            max_date: date_bound("lookup", Some(*from))?,
+            ..IndexQuery::default()
+        },
+        Some(["date", from, to]) => IndexQuery {
+            min_date: date_bound("lookup", Some(*from))?,
+            max_date: date_bound("lookup", Some(*to))?,
+            ..IndexQuery::default()
+        },
+        _ => {
+            return Err(Error::config(
+                "lookup: expected <index file> followed by id <id>, player <name> \
+                 or date <from> [<to>]",
+            ))
+        }
+    };
+
+    let index = GameIndex::read(Path::new(args[0]))?;
+    let writer = PgnWriter::new(true);
+    for game in index.fetch(&index.find(&query))? {
+        print!("{}", writer.write_game(&game)?);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_archive::encode_games;
+
+    fn game(site: &str, white: &str, black: &str, day: u8) -> Game {
+        Game {
+            site: site.to_string(),
+            white: white.to_string(),
+            black: black.to_string(),
+            year: 2023,
+            month: 1,
+            day,
+            ..Game::default()
+        }
+    }
+
+    fn write_files(name: &str) -> Vec<PathBuf> {
+        let files = [
+            vec![
+                game("https://lichess.org/aaaa1111", "Alice", "Bob", 3),
+                game("https://lichess.org/bbbb2222", "Carol", "alice", 1),
+            ],
+            vec![game("https://lichess.org/cccc3333", "Bob", "Carol", 2)],
+        ];
+
+        files
+            .iter()
+            .enumerate()
+            .map(|(position, games)| {
+                let path = std::env::temp_dir().join(format!("{name}_{position}.bin"));
+                fs::write(&path, encode_games(games).unwrap()).unwrap();
+                path
+            })
+            .collect()
+    }
+
+    fn ids(games: &[Game]) -> Vec<&str> {
+        games
+            .iter()
+            .map(|game| lichess_id(&game.site).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn finds_games_by_id_player_and_date() {
+        let paths = write_files("chess_analytics_index_find_test");
+        let index = GameIndex::build(&paths).unwrap();
+        assert_eq!(index.game_count(), 3);
+
+        let fetch = |query: IndexQuery| index.fetch(&index.find(&query)).unwrap();
+
+        let by_id = fetch(IndexQuery {
+            id: Some("cccc3333".to_string()),
+            ..IndexQuery::default()
+        });
+        assert_eq!(ids(&by_id), ["cccc3333"]);
+        assert_eq!(
+            by_id[0],
+            game("https://lichess.org/cccc3333", "Bob", "Carol", 2)
+        );
+
+        let by_player = fetch(IndexQuery {
+            player: Some("ALICE".to_string()),
+            ..IndexQuery::default()
+        });
+        assert_eq!(ids(&by_player), ["aaaa1111", "bbbb2222"]);
+
+        let by_date = fetch(IndexQuery {
+            min_date: Some(20230102),
+            max_date: Some(20230103),
+            ..IndexQuery::default()
+        });
+        assert_eq!(ids(&by_date), ["aaaa1111", "cccc3333"]);
+
+        let player_and_date = index.find(&IndexQuery {
+            player: Some("carol".to_string()),
+            max_date: Some(20230101),
+            ..IndexQuery::default()
+        });
+        assert_eq!(player_and_date.len(), 1);
+        assert_eq!(player_and_date[0].id, "bbbb2222");
+
+        for path in paths {
+            let _ = fs::remove_file(path);
+        }
+    }
+
+    #[test]
+    fn round_trips_index_files() {
+        let paths = write_files("chess_analytics_index_round_trip_test");
+        let index = GameIndex::build(&paths).unwrap();
+
+        let index_path = std::env::temp_dir().join("chess_analytics_index_round_trip_test.idx");
+        index.write(&index_path).unwrap();
+        assert_eq!(GameIndex::read(&index_path), Ok(index));
+
+        fs::write(&index_path, b"CABG").unwrap();
+        assert!(GameIndex::read(&index_path).is_err());
+
+        let _ = fs::remove_file(index_path);
+        for path in paths {
+            let _ = fs::remove_file(path);
+        }
+    }
+
+    #[test]
+    fn reads_lichess_ids() {
+        assert_eq!(lichess_id("https://lichess.org/j1dkb5dw"), Some("j1dkb5dw"));
+        assert_eq!(
+            lichess_id("https://lichess.org/j1dkb5dw/black"),
+            Some("j1dkb5dw")
+        );
+        assert_eq!(lichess_id("https://lichess.org/"), None);
+        assert_eq!(lichess_id("https://example.org/j1dkb5dw"), None);
+    }
+}
@@ -1,8 +1,8 @@
 use std::collections::HashMap;
 
 use crate::{
+    error::Error,
     game::Game,
-    step_param_utils::{get_parameter_with_default, get_required_parameter},
     workflow_step::{ProcessStatus, SharedData, StepData},
 };
 #[cfg(test)]
@@ -20,32 +20,27 @@ pub struct GenericFilter {
 #[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
 #[cfg_attr(test, automock)]
 impl GenericFilter {
-    pub fn try_new(configuration: Option<serde_yaml::Value>) -> Result<Box<Self>, String> {
-        let params = match configuration {
-            Some(value) => value,
-            None => return Err("GenericFilter: no parameters provided".to_string()),
-        };
-
-        let input_vec_name = get_required_parameter("GenericFilter", "input", &params)?;
-        let output_vec_name = get_required_parameter("GenericFilter", "output", &params)?;
-        let discard_vec_name = get_parameter_with_default("discard", "null", &params);
-
-        Ok(Box::new(GenericFilter {
+    pub fn from_params(
+        input_vec_name: String,
+        output_vec_name: String,
+        discard_vec_name: String,
+    ) -> Box<Self> {
+        Box::new(GenericFilter {
             input_vec_name,
             output_vec_name,
             discard_vec_name,
-        }))
+        })
     }
 
     pub fn process(
         &self,
         data: &mut HashMap<String, SharedData>,
         logic: &FilterFn,
-    ) -> Result<ProcessStatus, String> {
+    ) -> Result<ProcessStatus, Error> {
         data.init_vec_if_unset(&self.output_vec_name);
         data.init_vec_if_unset(&self.discard_vec_name);
 
-        let games = data.clear_vec(&self.input_vec_name).unwrap();
+        let games = data.clear_vec(&self.input_vec_name).unwrap_or_default();
 
         if games.is_empty() {
             return Ok(ProcessStatus::Complete);
@@ -60,12 +55,24 @@ impl GenericFilter {
                         data.try_push_to_vec(&self.discard_vec_name, SharedData::Game(game))?;
                     }
                 }
-                _ => return Err("Vector isn't of games!".to_string()),
+                _ => return Err(Error::data_type("Vector isn't of games!")),
             }
         }
 
         Ok(ProcessStatus::Incomplete)
     }
+
+    pub fn inputs(&self) -> Vec<String> {
+        vec![self.input_vec_name.clone()]
+    }
+
+    pub fn outputs(&self) -> Vec<String> {
+        if self.discard_vec_name == "null" {
+            vec![self.output_vec_name.clone()]
+        } else {
+            vec![self.output_vec_name.clone(), self.discard_vec_name.clone()]
+        }
+    }
 }
 
 #[cfg(test)]
@@ -184,95 +191,22 @@ mod test_process {
 }
 
 #[cfg(test)]
-mod test_try_new {
-    use serde_yaml::{Mapping, Value};
-
+mod test_from_params {
     use super::*;
 
     #[test]
-    fn no_params_returns_err() {
-        assert_eq!(
-            Err("GenericFilter: no parameters provided".to_string()),
-            GenericFilter::try_new(None)
-        );
-    }
-
-    #[test]
-    fn no_input_vector_parameter() {
-        let params = Mapping::new();
-        assert_eq!(
-            Err("GenericFilter: parameter 'input' is required".to_string()),
-            GenericFilter::try_new(Some(Value::Mapping(params)))
-        );
-    }
-
-    #[test]
-    fn no_output_vector_parameter() {
-        let mut params = Mapping::new();
-        params.insert(
-            Value::String("input".to_string()),
-            Value::String("input_vector".to_string()),
-        );
-
-        assert_eq!(
-            Err("GenericFilter: parameter 'output' is required".to_string()),
-            GenericFilter::try_new(Some(Value::Mapping(params)))
-        );
-    }
-
-    #[test]
-    fn discard_defaults_to_null() {
-        let mut params = Mapping::new();
-        params.insert(
-            Value::String("input".to_string()),
-            Value::String("input_vector".to_string()),
-        );
-        params.insert(
-            Value::String("output".to_string()),
-            Value::String("output_vector".to_string()),
-        );
-        params.insert(
-            Value::String("output_flag".to_string()),
-            Value::String("output_flag_value".to_string()),
-        );
-
-        assert_eq!(
-            Ok(Box::new(GenericFilter {
-                input_vec_name: "input_vector".to_string(),
-                output_vec_name: "output_vector".to_string(),
-                discard_vec_name: "null".to_string(),
-            })),
-            GenericFilter::try_new(Some(Value::Mapping(params)))
-        );
-    }
-
-    #[test]
-    fn discard_defaults_takes_value() {
-        let mut params = Mapping::new();
-        params.insert(
-            Value::String("input".to_string()),
-            Value::String("input_vector".to_string()),
-        );
-        params.insert(
-            Value::String("output".to_string()),
-            Value::String("output_vector".to_string()),
-        );
-        params.insert(
-            Value::String("discard".to_string()),
-            Value::String("discard_vector".to_string()),
-        );
-        params.insert(
-            Value::String("output_flag".to_string()),
-            Value::String("output_flag_value".to_string()),
-        );
-
+    fn takes_names() {
         assert_eq!(
-            Ok(Box::new(GenericFilter {
+            Box::new(GenericFilter {
                 input_vec_name: "input_vector".to_string(),
                 output_vec_name: "output_vector".to_string(),
                 discard_vec_name: "discard_vector".to_string(),
-            })),
-            GenericFilter::try_new(Some(Value::Mapping(params)))
+            }),
+            GenericFilter::from_params(
+                "input_vector".to_string(),
+                "output_vector".to_string(),
+                "discard_vector".to_string()
+            )
         );
     }
 }
@@ -11,73 +11,151 @@ use std::fs::File;
 
 #[macro_use]
 mod basic_types;
+#[macro_use]
+mod macros;
+mod bitboard;
 mod board;
 mod chess_utils;
+mod clock_metrics;
+mod columnar;
+mod convert;
+mod eco;
+mod error;
+mod error_policy;
+mod eval_metrics;
 mod game;
+mod game_archive;
+mod game_index;
 mod general_utils;
 mod generic_steps;
-#[macro_use]
-mod macros;
+mod migrate;
+mod notation;
+mod opening;
+mod opening_tree;
 mod parse_pgn;
+mod pgn_reader;
+mod pgn_writer;
+mod pipeline;
+mod polyglot;
+mod position;
+mod scheduler;
 mod step_param_utils;
 mod steps;
 mod steps_manager;
+mod validate;
 mod workflow_step;
+mod zobrist;
 
-use steps_manager::{add_step_description, get_step_description};
+pub use error::{Error, ErrorKind};
+use steps_manager::{add_step_description, get_step};
 use workflow_step::StepDescription;
 
-// TODO: global: Ok/Err
-// TODO: global: currently count 20 calls to 'panic!()'
-
 ///
-/// # Errors
+/// Runs the workflow in the configuration file given as first argument. With
+/// `validate <configuration>` the workflow is only checked and every problem found is
+/// reported, without reading any input file. With `describe [StepType]` the parameters
+/// of a step type, or the list of step types, are printed. With `migrate <file>...` binary
+/// game files written by older versions are rewritten in place in the current format.
+/// With `index <index file> <file>...` the games of binary game files are indexed by
+/// Lichess game id, player and date, and with `lookup <index file> id <id>`,
+/// `lookup <index file> player <name>` or `lookup <index file> date <from> [<to>]` the
+/// matching games are read through the index and printed as PGN. With
+/// `convert <pgn file> <output directory>` a PGN file, compressed or not, is converted to
+/// bz2 compressed binary game files of `--shard-size` games each, written by `--threads`
+/// threads at the `--compression` level, and statistics on the conversion are printed.
 ///
-/// Returns an error if unable to run with the provided arguments
+/// Games skipped or quarantined through the `on_error` parameter of a step are counted
+/// by error category, and the counts are printed once the workflow is done.
 ///
-/// # Panics
+/// # Errors
 ///
-/// Currently some failures panic. TODO: eliminate as many panics as possible
+/// Returns an error if unable to run with the provided arguments, or if a step fails.
+/// The error names the step, file and game it happened in when those are known.
 ///
 #[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
-pub fn run<T, I>(mut args: T) -> Result<(), String>
+pub fn run<T, I>(mut args: T) -> Result<(), Error>
 where
     T: Iterator<Item = I>,
     I: AsRef<std::path::Path>,
 {
     let config_path_string = match args.nth(1) {
         Some(path) => path,
-        None => return Err("First argument (configuration path) is required".to_string()),
+        None => {
+            return Err(Error::config(
+                "First argument (configuration path) is required",
+            ))
+        }
     };
 
+    if config_path_string.as_ref() == std::path::Path::new("validate") {
+        return match args.next() {
+            Some(path) => validate::validate_file(path.as_ref()),
+            None => Err(Error::config("validate: configuration path is required")),
+        };
+    }
+
+    if config_path_string.as_ref() == std::path::Path::new("migrate") {
+        return migrate::migrate_files(args);
+    }
+
+    if config_path_string.as_ref() == std::path::Path::new("convert") {
+        return convert::convert_command(args);
+    }
+
+    if config_path_string.as_ref() == std::path::Path::new("index") {
+        return game_index::index_files(args);
+    }
+
+    if config_path_string.as_ref() == std::path::Path::new("lookup") {
+        return game_index::lookup_games(args);
+    }
+
+    if config_path_string.as_ref() == std::path::Path::new("describe") {
+        let step_type = args.next();
+        return describe(
+            step_type
+                .as_ref()
+                .map(|name| name.as_ref().to_string_lossy())
+                .as_deref(),
+        );
+    }
+
     let file = match File::open(config_path_string) {
         Ok(file) => file,
-        Err(err) => return Err(format!("Could not open configuration file: {err:?}")),
+        Err(err) => {
+            return Err(Error::io(format!(
+                "Could not open configuration file: {err:?}"
+            )))
+        }
     };
 
     let mut config_doc_deserializer = serde_yaml::Deserializer::from_reader(file);
     let document = match config_doc_deserializer.next() {
         Some(document) => document,
-        None => return Err("No yaml document in the provided configuration file".to_string()),
+        None => {
+            return Err(Error::config(
+                "No yaml document in the provided configuration file",
+            ))
+        }
     };
 
     let config_data = match Value::deserialize(document) {
         Ok(data) => data,
         Err(err) => {
-            return Err(format!(
+            return Err(Error::config(format!(
                 "Could not deserialize document into yaml values: {err:?}",
-            ))
+            )))
         }
     };
 
     let steps_data = match config_data.get("steps") {
         Some(steps) => steps,
-        None => return Err("Could not find steps in configuration file".to_string()),
+        None => return Err(Error::config("Could not find steps in configuration file")),
     };
 
     let steps_map = match steps_data.as_mapping() {
         Some(map) => map,
-        None => return Err("Steps is not a map".to_string()),
+        None => return Err(Error::config("Steps is not a map")),
     };
 
     let mut data = HashMap::new();
@@ -85,20 +163,35 @@ where
     add_step_description(
         "noop".to_string(),
         StepDescription {
-            step_type: "Noop".to_string(),
+            step_type: "NoopStep".to_string(),
             parameters: None,
         },
         &mut data,
     );
 
     for (step_name, step_data) in steps_map.iter() {
-        let step_name = step_name.as_str().unwrap().to_string();
+        let step_name = match step_name.as_str() {
+            Some(step_name) => step_name.to_string(),
+            None => {
+                return Err(Error::config(format!(
+                    "Step name {step_name:?} is not a string"
+                )))
+            }
+        };
         let step_type = match step_data.get("type") {
             Some(step_type) => match step_type {
                 serde_yaml::Value::String(step_type) => step_type,
-                _ => return Err(format!("Step type for step {step_name:?} is not a string")),
+                _ => {
+                    return Err(Error::config(format!(
+                        "Step type for step {step_name:?} is not a string"
+                    )))
+                }
             },
-            None => return Err(format!("Step {step_name:?} does not have a type field")),
+            None => {
+                return Err(Error::config(format!(
+                    "Step {step_name:?} does not have a type field"
+                )))
+            }
         };
 
         let params = step_data.get("params").cloned();
@@ -110,9 +203,38 @@ where
         add_step_description(step_name, step, &mut data);
     }
 
-    let init_desc = get_step_description("init", &mut data);
-    let mut init = init_desc.to_step()?;
-    init.process(&mut data)?;
+    let mut init = get_step("init", &data)?;
+    init.process(&mut data).map_err(|err| err.in_step("init"))?;
+
+    if let Some(summary) = error_policy::error_summary(&data) {
+        eprint!("{summary}");
+    }
+
+    Ok(())
+}
+
+// Prints the parameters of the given step type, or every step type when none is given
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+fn describe(step_type: Option<&str>) -> Result<(), Error> {
+    match step_type {
+        Some(step_type) => match steps::get_step_schema(step_type) {
+            Some(schema) => print!("{schema}"),
+            None => {
+                return Err(Error::config(format!(
+                    "Step with name '{step_type}' not found"
+                )))
+            }
+        },
+        None => {
+            for step_type in steps::STEP_TYPES {
+                let schema = steps::get_step_schema(step_type).unwrap();
+                println!(
+                    "{step_type}: {}",
+                    schema.doc.lines().next().unwrap_or("").trim()
+                );
+            }
+        }
+    }
 
     Ok(())
 }
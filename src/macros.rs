@@ -14,88 +14,65 @@ macro_rules! bin_template {
         fn process(
             &mut self,
             data: &mut HashMap<String, SharedData>,
-        ) -> Result<ProcessStatus, String> {
-            {
-                data.insert(self.output_vec_name.clone(), SharedData::Vec(vec![]));
-            }
+        ) -> Result<ProcessStatus, crate::error::Error> {
+            use crate::error::Error;
+            use crate::workflow_step::StepData;
+
+            data.init_vec_if_unset(&self.output_vec_name);
 
-            let mut quit = false;
-            let mut final_loop = false;
-            loop {
-                if quit {
-                    final_loop = true;
-                }
+            // Read the flag before draining so games arriving alongside it are not left behind
+            let input_done = data
+                .get(&self.input_flag)
+                .and_then(SharedData::to_bool)
+                .unwrap_or(false);
 
-                let binned_games = {
-                    let potential_data = data.get(&self.input_vec_name);
-                    let shared_data = match potential_data {
-                        Some(shared_data) => shared_data,
-                        None => continue,
-                    };
-                    let vec_to_filter = shared_data.to_vec().unwrap();
+            let binned_games = data.clear_vec(&self.input_vec_name).unwrap_or_default();
 
-                    data.insert(self.input_vec_name.clone(), SharedData::Vec(vec![]));
+            for shared_binned_game in binned_games {
+                let binned_game = match shared_binned_game {
+                    SharedData::BinnedValue(game) => game,
+                    _ => return Err(Error::data_type("Vector isn't of binned games!")),
+                };
 
-                    vec_to_filter
+                let game = match *binned_game.0 {
+                    SharedData::Game(game) => game,
+                    _ => return Err(Error::data_type("Binned value isn't a game!")),
                 };
 
-                let mut new_binned_games = vec![];
-
-                for shared_binned_game in binned_games {
-                    let binned_game = match shared_binned_game {
-                        SharedData::BinnedValue(game) => game,
-                        _ => return Err("Vector isn't of binned games!".to_string()),
-                    };
-
-                    let game = match *binned_game.0 {
-                        SharedData::Game(game) => game,
-                        _ => return Err("Binned value isn't a game!".to_string()),
-                    };
-
-                    let mut bin_labels = binned_game.1;
-
-                    let bin_label = $logic(&game, self);
-                    bin_labels.push(bin_label);
-                    new_binned_games.push(SharedData::BinnedValue((
-                        Box::new(SharedData::Game(game)),
-                        bin_labels,
-                    )));
-                }
-
-                {
-                    let potential_data = data.get(&self.output_vec_name);
-                    let shared_data = match potential_data {
-                        Some(shared_data) => shared_data,
-                        None => continue,
-                    };
-                    let mut vec_to_append = shared_data.to_vec().unwrap();
-
-                    vec_to_append.append(&mut new_binned_games);
-                    data.insert(self.output_vec_name.clone(), SharedData::Vec(vec_to_append));
-                }
-
-                let flag = data
-                    .get(&self.input_flag)
-                    .unwrap_or(&SharedData::Bool(false));
-
-                let flag = flag.to_bool().unwrap();
-
-                if flag {
-                    quit = true;
-                }
-
-                if final_loop && quit {
-                    break;
-                }
+                let mut bin_labels = binned_game.1;
+
+                let bin_label = $logic(&game, self);
+                bin_labels.push(bin_label);
+                data.try_push_to_vec(
+                    &self.output_vec_name,
+                    SharedData::BinnedValue((Box::new(SharedData::Game(game)), bin_labels)),
+                )?;
             }
 
-            {
-                let d: bool = true;
-                data.insert(self.output_flag.clone(), SharedData::Bool(d));
+            if !input_done {
+                return Ok(ProcessStatus::Incomplete);
             }
 
+            data.insert(self.output_flag.clone(), SharedData::Bool(true));
+
             Ok(ProcessStatus::Complete)
         }
+
+        fn inputs(&self) -> Vec<String> {
+            vec![self.input_vec_name.clone()]
+        }
+
+        fn outputs(&self) -> Vec<String> {
+            vec![self.output_vec_name.clone()]
+        }
+
+        fn input_flags(&self) -> Vec<String> {
+            vec![self.input_flag.clone()]
+        }
+
+        fn output_flags(&self) -> Vec<String> {
+            vec![self.output_flag.clone()]
+        }
     };
 }
 
@@ -104,86 +81,119 @@ macro_rules! map_template {
         fn process(
             &mut self,
             data: &mut HashMap<String, SharedData>,
-        ) -> Result<ProcessStatus, String> {
-            {
-                data.insert(self.output_vec_name.clone(), SharedData::Vec(vec![]));
-            }
+        ) -> Result<ProcessStatus, crate::error::Error> {
+            use crate::error::Error;
+            use crate::workflow_step::StepData;
 
-            let mut quit = false;
-            let mut final_loop = false;
-            loop {
-                if quit {
-                    final_loop = true;
-                }
+            data.init_vec_if_unset(&self.output_vec_name);
 
-                let binned_games = {
-                    let potential_data = data.get(&self.input_vec_name);
-                    let shared_data = match potential_data {
-                        Some(shared_data) => shared_data,
-                        None => continue,
-                    };
-                    let vec_to_filter = shared_data.to_vec().unwrap();
+            // Read the flag before draining so games arriving alongside it are not left behind
+            let input_done = data
+                .get(&self.input_flag)
+                .and_then(SharedData::to_bool)
+                .unwrap_or(false);
 
-                    data.insert(self.input_vec_name.clone(), SharedData::Vec(vec![]));
+            let binned_games = data.clear_vec(&self.input_vec_name).unwrap_or_default();
 
-                    vec_to_filter
+            for shared_binned_game in binned_games {
+                let binned_game = match shared_binned_game {
+                    SharedData::BinnedValue(game) => game,
+                    _ => return Err(Error::data_type("Vector isn't of binned games!")),
                 };
 
-                let mut new_binned_games = vec![];
-
-                for shared_binned_game in binned_games {
-                    let binned_game = match shared_binned_game {
-                        SharedData::BinnedValue(game) => game,
-                        _ => return Err("Vector isn't of binned games!".to_string()),
-                    };
-
-                    let game = match *binned_game.0 {
-                        SharedData::Game(game) => game,
-                        o => return Err(format!("Binned value isn't a game! ({:?})", o)),
-                    };
-
-                    let bin_labels = binned_game.1;
-
-                    let mapped_value = $logic(&game, self);
-                    new_binned_games.push(SharedData::BinnedValue((
-                        Box::new(mapped_value),
-                        bin_labels,
-                    )));
-                }
-
-                {
-                    let potential_data = data.get(&self.output_vec_name);
-                    let shared_data = match potential_data {
-                        Some(shared_data) => shared_data,
-                        None => continue,
-                    };
-                    let mut vec_to_append = shared_data.to_vec().unwrap();
-
-                    vec_to_append.append(&mut new_binned_games);
-                    data.insert(self.output_vec_name.clone(), SharedData::Vec(vec_to_append));
-                }
-
-                let flag = data
-                    .get(&self.input_flag)
-                    .unwrap_or(&SharedData::Bool(false));
-
-                let flag = flag.to_bool().unwrap();
-
-                if flag {
-                    quit = true;
-                }
-
-                if final_loop && quit {
-                    break;
-                }
+                let game = match *binned_game.0 {
+                    SharedData::Game(game) => game,
+                    o => {
+                        return Err(Error::data_type(format!(
+                            "Binned value isn't a game! ({:?})",
+                            o
+                        )))
+                    }
+                };
+
+                let bin_labels = binned_game.1;
+
+                let mapped_value = $logic(&game, self);
+                data.try_push_to_vec(
+                    &self.output_vec_name,
+                    SharedData::BinnedValue((Box::new(mapped_value), bin_labels)),
+                )?;
             }
 
-            {
-                let d: bool = true;
-                data.insert(self.output_flag.clone(), SharedData::Bool(d));
+            if !input_done {
+                return Ok(ProcessStatus::Incomplete);
             }
 
+            data.insert(self.output_flag.clone(), SharedData::Bool(true));
+
             Ok(ProcessStatus::Complete)
         }
+
+        fn inputs(&self) -> Vec<String> {
+            vec![self.input_vec_name.clone()]
+        }
+
+        fn outputs(&self) -> Vec<String> {
+            vec![self.output_vec_name.clone()]
+        }
+
+        fn input_flags(&self) -> Vec<String> {
+            vec![self.input_flag.clone()]
+        }
+
+        fn output_flags(&self) -> Vec<String> {
+            vec![self.output_flag.clone()]
+        }
+    };
+}
+
+// Declares the parameter struct of a step. Every field needs a doc comment. The default
+// given after `=` is filled in by `parse_params` before deserializing, so such fields are
+// never missing; it may refer to another parameter as `<name>`.
+macro_rules! step_params {
+    (
+        $(#[doc = $doc:literal])+
+        $vis:vis struct $name:ident {
+            $(
+                $(#[doc = $field_doc:literal])+
+                $field:ident: $ty:ty $(= $default:literal)?,
+            )*
+        }
+    ) => {
+        $(#[doc = $doc])+
+        #[derive(Debug, PartialEq, serde::Deserialize)]
+        #[serde(deny_unknown_fields)]
+        $vis struct $name {
+            $(
+                $(#[doc = $field_doc])+
+                pub $field: $ty,
+            )*
+        }
+
+        impl crate::step_param_utils::StepParams for $name {
+            fn doc() -> &'static str {
+                concat!($($doc, "\n"),+)
+            }
+
+            fn schema() -> Vec<crate::step_param_utils::ParamSchema> {
+                vec![$({
+                    let default = step_params!(@default $($default)?);
+                    crate::step_param_utils::ParamSchema {
+                        name: stringify!($field),
+                        type_name: <$ty as crate::step_param_utils::ParamType>::type_name(),
+                        required: <$ty as crate::step_param_utils::ParamType>::REQUIRED
+                            && default.is_none(),
+                        default,
+                        doc: concat!($($field_doc, "\n"),+),
+                    }
+                }),*]
+            }
+        }
+    };
+    (@default) => {
+        None
+    };
+    (@default $default:literal) => {
+        Some($default)
     };
 }
@@ -4,8 +4,9 @@ use chess_analytics::run;
 use std::env;
 
 #[cfg(not(tarpaulin_include))]
-fn main() -> Result<(), String> {
-    run(env::args())?;
-
-    Ok(())
+fn main() {
+    if let Err(err) = run(env::args()) {
+        eprintln!("Error: {err}");
+        std::process::exit(1);
+    }
 }
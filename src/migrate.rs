@@ -0,0 +1,106 @@
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+
+use bzip2::write::BzEncoder;
+use bzip2::Compression;
+
+use crate::error::Error;
+use crate::game_archive::{decode_games, encode_games, read_file, Header, FORMAT_VERSION};
+
+// Rewrites every given binary game file in the current format version, in place and keeping
+// its bz2 compression. Files already in the current version are left untouched.
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+pub fn migrate_files<T, I>(paths: T) -> Result<(), Error>
+where
+    T: Iterator<Item = I>,
+    I: AsRef<Path>,
+{
+    let mut file_count = 0;
+    for path in paths {
+        let path = path.as_ref();
+        match migrate_file(path).map_err(|err| err.in_file(path))? {
+            Some((version, game_count)) => println!(
+                "{}: migrated {game_count} games from version {version} to {FORMAT_VERSION}",
+                path.display()
+            ),
+            None => println!("{}: already at version {FORMAT_VERSION}", path.display()),
+        }
+        file_count += 1;
+    }
+
+    if file_count == 0 {
+        return Err(Error::config("migrate: at least one file path is required"));
+    }
+    Ok(())
+}
+
+// Version and number of games the file was migrated from, None when it was already current
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+fn migrate_file(path: &Path) -> Result<Option<(u16, usize)>, Error> {
+    let compressed = path.extension().is_some_and(|extension| extension == "bz2");
+    let file_data = read_file(path)?;
+
+    let (header, _) = Header::read(&file_data)?;
+    if header.version == FORMAT_VERSION {
+        return Ok(None);
+    }
+
+    // A file with a corrupt game is left as it is rather than rewritten without its tail
+    let games = match decode_games(&file_data) {
+        (games, None) => games,
+        (_, Some((err, _))) => return Err(err),
+    };
+
+    let mut encoded_games = encode_games(&games)?;
+    if compressed {
+        let mut compressor = BzEncoder::new(vec![], Compression::best());
+        compressor.write_all(&encoded_games)?;
+        encoded_games = compressor.finish()?;
+    }
+
+    // Written next to the file first so an interrupted migration keeps the original
+    let mut temporary_path = path.as_os_str().to_owned();
+    temporary_path.push(".migrating");
+    fs::write(&temporary_path, encoded_games)?;
+    fs::rename(&temporary_path, path)?;
+
+    Ok(Some((header.version, games.len())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+    use crate::game::Game;
+    use crate::game_archive::encode_headerless;
+
+    #[test]
+    fn migrates_headerless_file() {
+        let path = std::env::temp_dir().join("chess_analytics_migrate_test.bin");
+        let games = vec![Game::default(), Game::default()];
+        fs::write(&path, encode_headerless(&games)).unwrap();
+
+        assert_eq!(migrate_file(&path), Ok(Some((1, 2))));
+        let file_data = fs::read(&path).unwrap();
+        assert_eq!(Header::read(&file_data).unwrap().0.version, FORMAT_VERSION);
+        assert_eq!(decode_games(&file_data), (games, None));
+
+        assert_eq!(migrate_file(&path), Ok(None));
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn keeps_corrupt_file() {
+        let path = std::env::temp_dir().join("chess_analytics_migrate_corrupt_test.bin");
+        let mut file_data = encode_headerless(&[Game::default()]);
+        file_data.pop();
+        fs::write(&path, &file_data).unwrap();
+
+        let err = migrate_files(std::iter::once(&path)).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Decode);
+        assert_eq!(err.file(), Some(path.as_path()));
+        assert_eq!(fs::read(&path).unwrap(), file_data);
+        let _ = fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,302 @@
+use crate::{
+    basic_types::{Annotation, Cell, File, Move, OptionalPiece, PartialCell, Piece, Rank},
+    board::Board,
+    error::Error,
+};
+
+// Standard Algebraic Notation of a move, with the least disambiguation it needs
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+pub fn move_to_san(board: &Board, curr_move: &Move) -> Result<String, Error> {
+    let piece = curr_move.piece_moved;
+    let dest = curr_move.to;
+    let origin = if curr_move.from.is_fully_defined() {
+        curr_move.from.to_cell()
+    } else {
+        board.find_origin(piece, dest, curr_move.from)?
+    };
+
+    let mut san = String::new();
+    if piece == Piece::King && (dest.file as i32 - origin.file as i32).abs() == 2 {
+        san.push_str(if dest.file == File::_G {
+            "O-O"
+        } else {
+            "O-O-O"
+        });
+    } else {
+        let captures =
+            !board.is_cell_empty(dest) || (piece == Piece::Pawn && dest.file != origin.file);
+
+        san.push_str(piece.to_pgn());
+        if piece == Piece::Pawn {
+            if captures {
+                san.push_str(origin.file.to_pgn());
+            }
+        } else {
+            let others: Vec<_> = board
+                .legal_origins(
+                    piece,
+                    dest,
+                    PartialCell {
+                        file: None,
+                        rank: None,
+                    },
+                )?
+                .cells()
+                .filter(|other| *other != origin)
+                .collect();
+
+            // The file is preferred, then the rank, then both
+            let file_is_enough = others.iter().all(|other| other.file != origin.file);
+            let rank_is_enough = others.iter().all(|other| other.rank != origin.rank);
+            if !others.is_empty() && (file_is_enough || !rank_is_enough) {
+                san.push_str(origin.file.to_pgn());
+            }
+            if !others.is_empty() && !file_is_enough {
+                san.push_str(origin.rank.to_pgn());
+            }
+        }
+
+        if captures {
+            san.push('x');
+        }
+        san.push_str(dest.file.to_pgn());
+        san.push_str(dest.rank.to_pgn());
+
+        if curr_move.promoted_to.is_some() {
+            san.push('=');
+            san.push_str(curr_move.promoted_to.unwrap().to_pgn());
+        }
+    }
+
+    if curr_move.mates {
+        san.push('#');
+    } else if curr_move.checks {
+        san.push('+');
+    }
+
+    san.push_str(match curr_move.nag {
+        Annotation::None => "",
+        Annotation::Questionable => "?!",
+        Annotation::Mistake => "?",
+        Annotation::Blunder => "??",
+    });
+
+    Ok(san)
+}
+
+// UCI long algebraic notation of a move, like e2e4, e1g1 for castling or e7e8q
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+pub fn move_to_uci(board: &Board, curr_move: &Move) -> Result<String, Error> {
+    let origin = if curr_move.from.is_fully_defined() {
+        curr_move.from.to_cell()
+    } else {
+        board.find_origin(curr_move.piece_moved, curr_move.to, curr_move.from)?
+    };
+
+    let mut uci = format!(
+        "{}{}{}{}",
+        origin.file.to_pgn(),
+        origin.rank.to_pgn(),
+        curr_move.to.file.to_pgn(),
+        curr_move.to.rank.to_pgn()
+    );
+    if curr_move.promoted_to.is_some() {
+        uci.push_str(&curr_move.promoted_to.unwrap().to_fen().to_ascii_lowercase());
+    }
+
+    Ok(uci)
+}
+
+// The legal move of the board written as `uci`, with its origin and flags filled in
+#[allow(dead_code)]
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+pub fn move_from_uci(board: &Board, uci: &str) -> Result<Move, Error> {
+    let invalid = || Error::illegal_move(format!("Invalid UCI move: {uci}"));
+
+    let chars: Vec<char> = uci.chars().collect();
+    let (from, to, promotion) = match chars.as_slice() {
+        [from_file, from_rank, to_file, to_rank, promotion @ ..] if promotion.len() <= 1 => (
+            parse_cell(*from_file, *from_rank).ok_or_else(invalid)?,
+            parse_cell(*to_file, *to_rank).ok_or_else(invalid)?,
+            promotion.first(),
+        ),
+        _ => return Err(invalid()),
+    };
+
+    let promoted_to = match promotion {
+        None => OptionalPiece::new_none(),
+        Some('q') => OptionalPiece::new_some(Piece::Queen),
+        Some('r') => OptionalPiece::new_some(Piece::Rook),
+        Some('b') => OptionalPiece::new_some(Piece::Bishop),
+        Some('n') => OptionalPiece::new_some(Piece::Knight),
+        Some(_) => return Err(invalid()),
+    };
+
+    let mut curr_move = board
+        .legal_moves()
+        .into_iter()
+        .find(|legal| {
+            legal.from.to_cell() == from && legal.to == to && legal.promoted_to == promoted_to
+        })
+        .ok_or_else(|| Error::illegal_move(format!("Illegal UCI move: {uci}")))?;
+
+    let mut after = *board;
+    after.move_piece(curr_move)?;
+    curr_move.mates = after.is_checkmate();
+
+    Ok(curr_move)
+}
+
+#[allow(dead_code)]
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+fn parse_cell(file: char, rank: char) -> Option<Cell> {
+    let file = File::try_from(u32::from(file).checked_sub(u32::from('a'))? + 1).ok()?;
+    let rank = Rank::try_from(u32::from(rank).checked_sub(u32::from('1'))? + 1).ok()?;
+    Some(cell!(file, rank))
+}
+
+#[cfg(test)]
+mod test_move_to_san {
+    use super::*;
+
+    #[test]
+    fn disambiguates_only_when_needed() {
+        let board = Board::from_fen("4k3/8/8/R7/8/8/8/RN2KN2 w - - 0 1").unwrap();
+
+        let rook = Move::new_to_from(
+            Some(File::_A),
+            Some(Rank::_1),
+            File::_A,
+            Rank::_3,
+            Piece::Rook,
+        );
+        assert_eq!(move_to_san(&board, &rook), Ok("R1a3".to_string()));
+
+        let knight = Move::new_to_from(Some(File::_B), None, File::_D, Rank::_2, Piece::Knight);
+        assert_eq!(move_to_san(&board, &knight), Ok("Nbd2".to_string()));
+
+        let king = Move::new_to(File::_D, Rank::_2, Piece::King);
+        assert_eq!(move_to_san(&board, &king), Ok("Kd2".to_string()));
+    }
+
+    #[test]
+    fn disambiguates_by_file_and_rank() {
+        let board = Board::from_fen("4k3/8/8/8/8/Q7/8/Q1Q1K3 w - - 0 1").unwrap();
+        let queen = Move::new_to_from(
+            Some(File::_A),
+            Some(Rank::_1),
+            File::_B,
+            Rank::_2,
+            Piece::Queen,
+        );
+        assert_eq!(move_to_san(&board, &queen), Ok("Qa1b2".to_string()));
+    }
+
+    #[test]
+    fn writes_castling_captures_and_promotions() {
+        let board = Board::from_fen("3r1k2/4P3/8/3p4/4P3/8/8/4K2R w K - 0 1").unwrap();
+
+        let castle = Move::new_to_from(
+            Some(File::_E),
+            Some(Rank::_1),
+            File::_G,
+            Rank::_1,
+            Piece::King,
+        );
+        assert_eq!(move_to_san(&board, &castle), Ok("O-O".to_string()));
+
+        let capture = Move::new_to(File::_D, Rank::_5, Piece::Pawn);
+        assert_eq!(move_to_san(&board, &capture), Ok("exd5".to_string()));
+
+        let mut promotion = Move::new_to(File::_D, Rank::_8, Piece::Pawn);
+        promotion.promoted_to = OptionalPiece::new_some(Piece::Queen);
+        promotion.checks = true;
+        assert_eq!(move_to_san(&board, &promotion), Ok("exd8=Q+".to_string()));
+    }
+}
+
+#[cfg(test)]
+mod test_uci {
+    use super::*;
+
+    #[test]
+    fn writes_resolved_origins() {
+        let board = Board::default();
+        let knight = Move::new_to(File::_F, Rank::_3, Piece::Knight);
+        assert_eq!(move_to_uci(&board, &knight), Ok("g1f3".to_string()));
+
+        let board = Board::from_fen("3r1k2/4P3/8/8/8/8/8/4K2R w K - 0 1").unwrap();
+        let castle = Move::new_to_from(
+            Some(File::_E),
+            Some(Rank::_1),
+            File::_G,
+            Rank::_1,
+            Piece::King,
+        );
+        assert_eq!(move_to_uci(&board, &castle), Ok("e1g1".to_string()));
+
+        let mut promotion = Move::new_to(File::_D, Rank::_8, Piece::Pawn);
+        promotion.promoted_to = OptionalPiece::new_some(Piece::Knight);
+        assert_eq!(move_to_uci(&board, &promotion), Ok("e7d8n".to_string()));
+    }
+
+    #[test]
+    fn parses_legal_moves() {
+        let board = Board::default();
+        let mut expected = Move::new_to_from(
+            Some(File::_E),
+            Some(Rank::_2),
+            File::_E,
+            Rank::_4,
+            Piece::Pawn,
+        );
+        assert_eq!(move_from_uci(&board, "e2e4"), Ok(expected));
+
+        let board = Board::from_fen("3r1k2/4P3/8/8/8/8/8/4K2R w K - 0 1").unwrap();
+        expected = Move::new_to_from(
+            Some(File::_E),
+            Some(Rank::_7),
+            File::_D,
+            Rank::_8,
+            Piece::Pawn,
+        );
+        expected.captures = true;
+        expected.checks = true;
+        expected.promoted_to = OptionalPiece::new_some(Piece::Queen);
+        assert_eq!(move_from_uci(&board, "e7d8q"), Ok(expected));
+    }
+
+    #[test]
+    fn flags_mates() {
+        let board =
+            Board::from_fen("rnbqkbnr/pppp1ppp/8/4p3/6P1/5P2/PPPPP2P/RNBQKBNR b KQkq g3 0 2")
+                .unwrap();
+        let mate = move_from_uci(&board, "d8h4").unwrap();
+        assert!(mate.checks);
+        assert!(mate.mates);
+    }
+
+    #[test]
+    fn rejects_illegal_and_malformed_moves() {
+        let board = Board::default();
+        for uci in [
+            "e2e5", "e1g1", "e2e4q", "e2", "e2e4qq", "i2i4", "e0e4", "e2e4x",
+        ] {
+            assert!(move_from_uci(&board, uci).is_err(), "{uci}");
+        }
+
+        let board = Board::from_fen("3r1k2/4P3/8/8/8/8/8/4K2R w K - 0 1").unwrap();
+        assert!(move_from_uci(&board, "e7e8").is_err());
+    }
+
+    #[test]
+    fn round_trips_every_legal_move() {
+        let board =
+            Board::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1")
+                .unwrap();
+        for curr_move in board.legal_moves() {
+            let uci = move_to_uci(&board, &curr_move).unwrap();
+            assert_eq!(move_from_uci(&board, &uci), Ok(curr_move), "{uci}");
+        }
+    }
+}
@@ -0,0 +1,126 @@
+use crate::{board::Board, error::Error, game::Game, parse_pgn::PgnParser};
+
+// A position to look for in games, given either as the SAN moves leading to it from the
+// standard starting position, like "1. e4 c5 2. Nf3", or as a FEN
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Opening {
+    // Hashes of the starting position and of the position after each move, which a game must
+    // go through in the same order
+    Moves(Vec<u64>),
+    // Hash of a position the game must reach by any move order, and the ply it is reached at
+    Position(u64, usize),
+}
+
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+impl Opening {
+    // FENs are told apart from moves by the slashes between their ranks
+    pub fn parse(text: &str) -> Result<Self, Error> {
+        let invalid =
+            |err: Error| Error::config(format!("Invalid opening `{text}`: {}", err.message()));
+
+        if text.contains('/') {
+            let board = Board::from_fen(text.trim()).map_err(invalid)?;
+            return Ok(Opening::Position(board.zobrist_hash(), board.ply()));
+        }
+
+        let mut game = Game::default();
+        PgnParser::new()
+            .parse_game(&Vec::new(), text, &mut game)
+            .and_then(|_| game.build_boards())
+            .map(|boards| Opening::Moves(boards.iter().map(Board::zobrist_hash).collect()))
+            .map_err(invalid)
+    }
+
+    // Number of plies it takes to reach the opening, so longer lines rank as more specific
+    pub fn depth(&self) -> usize {
+        match self {
+            Opening::Moves(hashes) => hashes.len() - 1,
+            Opening::Position(_, ply) => *ply,
+        }
+    }
+
+    // `hashes` are those of the positions of a game, as given by `position_hashes`
+    pub fn matches(&self, hashes: &[u64]) -> bool {
+        match self {
+            Opening::Moves(opening_hashes) => hashes.starts_with(opening_hashes),
+            Opening::Position(hash, _) => hashes.contains(hash),
+        }
+    }
+}
+
+// Zobrist hashes of the starting position of the game and of the position after each of its
+// moves, up to the first move that can't be played
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+pub fn position_hashes(game: &Game) -> Vec<u64> {
+    if game.boards.len() == game.moves.len() + 1 {
+        return game.boards.iter().map(Board::zobrist_hash).collect();
+    }
+
+    let mut board = match game.starting_board() {
+        Ok(board) => board,
+        Err(_) => return vec![],
+    };
+    let mut hashes = vec![board.zobrist_hash()];
+    for curr_move in &game.moves {
+        if board.move_piece(*curr_move).is_err() {
+            break;
+        }
+        hashes.push(board.zobrist_hash());
+    }
+
+    hashes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn game(movetext: &str) -> Game {
+        let mut game = Game::default();
+        PgnParser::new()
+            .parse_game(&Vec::new(), movetext, &mut game)
+            .unwrap();
+        game
+    }
+
+    #[test]
+    fn matches_moves_in_order() {
+        let sicilian = Opening::parse("1. e4 c5 2. Nf3").unwrap();
+        assert_eq!(sicilian.depth(), 3);
+
+        assert!(sicilian.matches(&position_hashes(&game("1. e4 c5 2. Nf3 d6 *"))));
+        assert!(sicilian.matches(&position_hashes(&game("e4 c5 Nf3 *"))));
+        assert!(!sicilian.matches(&position_hashes(&game("1. Nf3 c5 2. e4 *"))));
+        assert!(!sicilian.matches(&position_hashes(&game("1. e4 c5 *"))));
+    }
+
+    #[test]
+    fn matches_positions_by_any_move_order() {
+        let sicilian =
+            Opening::parse("rnbqkbnr/pp1ppppp/8/2p5/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2")
+                .unwrap();
+        assert_eq!(sicilian.depth(), 3);
+
+        assert!(sicilian.matches(&position_hashes(&game("1. e4 c5 2. Nf3 d6 *"))));
+        assert!(sicilian.matches(&position_hashes(&game("1. Nf3 c5 2. e4 *"))));
+        assert!(!sicilian.matches(&position_hashes(&game("1. e4 e5 2. Nf3 *"))));
+    }
+
+    #[test]
+    fn uses_the_moves_before_an_illegal_one() {
+        let mut game = game("1. e4 c5 *");
+        game.moves.push(game.moves[0]);
+
+        assert_eq!(position_hashes(&game).len(), 3);
+        assert!(Opening::parse("1. e4")
+            .unwrap()
+            .matches(&position_hashes(&game)));
+    }
+
+    #[test]
+    fn rejects_invalid_openings() {
+        assert!(Opening::parse("1. e5").is_err());
+        assert!(Opening::parse("1. e4 Zz9").is_err());
+        assert!(Opening::parse("8/8/8 w - - 0 1").is_err());
+    }
+}
@@ -0,0 +1,252 @@
+use serde_json::{json, Value};
+
+use crate::{
+    basic_types::{Annotation, GameResult, Move},
+    board::Board,
+    chess_utils::get_game_elo,
+    error::Error,
+    game::Game,
+    notation::{move_to_san, move_to_uci},
+};
+
+// Statistics of the games that reached a position, and the moves played from it
+#[derive(Debug, Default, PartialEq)]
+struct OpeningNode {
+    san: String,
+    uci: String,
+    games: u64,
+    white_wins: u64,
+    draws: u64,
+    black_wins: u64,
+    rated_games: u64,
+    rating_sum: u64,
+    children: Vec<OpeningNode>,
+}
+
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+impl OpeningNode {
+    fn add_game(&mut self, game: &Game) {
+        self.games += 1;
+        match game.result {
+            GameResult::White => self.white_wins += 1,
+            GameResult::Draw => self.draws += 1,
+            GameResult::Black => self.black_wins += 1,
+            GameResult::Star => {}
+        }
+
+        let elo = get_game_elo(game);
+        if elo > 0 {
+            self.rated_games += 1;
+            self.rating_sum += u64::from(elo);
+        }
+    }
+
+    fn child(&mut self, san: String, uci: String) -> &mut OpeningNode {
+        let index = match self.children.iter().position(|child| child.uci == uci) {
+            Some(index) => index,
+            None => {
+                self.children.push(OpeningNode {
+                    san,
+                    uci,
+                    ..OpeningNode::default()
+                });
+                self.children.len() - 1
+            }
+        };
+        &mut self.children[index]
+    }
+
+    fn prune(&mut self, min_games: u64) {
+        self.children.retain(|child| child.games >= min_games);
+        for child in &mut self.children {
+            child.prune(min_games);
+        }
+    }
+
+    // The most played moves come first
+    fn to_json(&self) -> Value {
+        let mut children: Vec<&OpeningNode> = self.children.iter().collect();
+        children.sort_by(|a, b| b.games.cmp(&a.games).then_with(|| a.san.cmp(&b.san)));
+
+        let average_rating = self.rating_sum.checked_div(self.rated_games);
+
+        let mut node = json!({
+            "games": self.games,
+            "white_wins": self.white_wins,
+            "draws": self.draws,
+            "black_wins": self.black_wins,
+            "average_rating": average_rating,
+            "children": children.into_iter().map(OpeningNode::to_json).collect::<Vec<_>>(),
+        });
+        if !self.uci.is_empty() {
+            node["san"] = json!(self.san);
+            node["uci"] = json!(self.uci);
+        }
+        node
+    }
+}
+
+// Trie of the opening moves of many games, counting the games and results through each move.
+// Only games from the standard starting position are added.
+#[derive(Debug, Default)]
+pub struct OpeningTree {
+    max_plies: usize,
+    root: OpeningNode,
+}
+
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+impl OpeningTree {
+    pub fn new(max_plies: usize) -> Self {
+        OpeningTree {
+            max_plies,
+            root: OpeningNode::default(),
+        }
+    }
+
+    // A game with an illegal move only counts towards the moves before it
+    pub fn add_game(&mut self, game: &Game) {
+        if game.tags.iter().any(|(name, _)| name == "FEN") {
+            return;
+        }
+
+        let mut board = Board::default();
+        let mut node = &mut self.root;
+        node.add_game(game);
+
+        for curr_move in game.moves.iter().take(self.max_plies) {
+            // Annotations are left out so that the same move always reads the same
+            let curr_move = Move {
+                nag: Annotation::None,
+                ..*curr_move
+            };
+            let (san, uci) = match (
+                move_to_san(&board, &curr_move),
+                move_to_uci(&board, &curr_move),
+            ) {
+                (Ok(san), Ok(uci)) => (san, uci),
+                _ => return,
+            };
+            if board.move_piece(curr_move).is_err() {
+                return;
+            }
+
+            node = node.child(san, uci);
+            node.add_game(game);
+        }
+    }
+
+    // Drops the moves played in fewer than `min_games` games, along with what follows them
+    pub fn prune(&mut self, min_games: u64) {
+        self.root.prune(min_games);
+    }
+
+    pub fn to_json(&self) -> Result<String, Error> {
+        serde_json::to_string_pretty(&self.root.to_json())
+            .map_err(|err| Error::data_type(format!("Could not write the opening tree: {err}")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_pgn::PgnParser;
+
+    fn parse(headers: &[&str], movetext: &str) -> Game {
+        let headers: Vec<String> = headers.iter().map(|header| header.to_string()).collect();
+        let mut game = Game::default();
+        PgnParser::new()
+            .parse_game(&headers, movetext, &mut game)
+            .unwrap();
+        game
+    }
+
+    fn tree(max_plies: usize) -> OpeningTree {
+        let mut tree = OpeningTree::new(max_plies);
+        for (result, movetext) in [
+            ("1-0", "1. e4 e5 2. Nf3 1-0"),
+            ("1/2-1/2", "1. e4 c5 1/2-1/2"),
+            ("0-1", "1. d4 d5 2. c4 0-1"),
+            ("1-0", "1. e4 e5 2. Bc4? 1-0"),
+        ] {
+            let mut game = parse(&[&format!("[Result \"{result}\"]")], movetext);
+            game.white_rating = 1500;
+            game.black_rating = 1700;
+            tree.add_game(&game);
+        }
+        tree
+    }
+
+    fn node_count(tree: &OpeningTree) -> usize {
+        fn count(node: &Value) -> usize {
+            1 + node["children"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(count)
+                .sum::<usize>()
+        }
+        count(&serde_json::from_str(&tree.to_json().unwrap()).unwrap())
+    }
+
+    #[test]
+    fn counts_games_and_results_per_move() {
+        let json: Value = serde_json::from_str(&tree(2).to_json().unwrap()).unwrap();
+
+        assert_eq!(json["games"], 4);
+        assert_eq!(json["white_wins"], 2);
+        assert_eq!(json["average_rating"], 1600);
+        assert!(json.get("san").is_none());
+
+        let e4 = &json["children"][0];
+        assert_eq!(e4["san"], "e4");
+        assert_eq!(e4["uci"], "e2e4");
+        assert_eq!(e4["games"], 3);
+        assert_eq!(e4["white_wins"], 2);
+        assert_eq!(e4["draws"], 1);
+        assert_eq!(e4["children"][0]["san"], "e5");
+        assert_eq!(e4["children"][0]["games"], 2);
+        assert_eq!(e4["children"][0]["children"], json!([]));
+        assert_eq!(e4["children"][1]["san"], "c5");
+
+        let d4 = &json["children"][1];
+        assert_eq!(d4["san"], "d4");
+        assert_eq!(d4["black_wins"], 1);
+    }
+
+    #[test]
+    fn merges_annotated_moves() {
+        let tree = tree(3);
+        assert_eq!(node_count(&tree), 9);
+
+        let json: Value = serde_json::from_str(&tree.to_json().unwrap()).unwrap();
+        let e5 = &json["children"][0]["children"][0];
+        assert_eq!(e5["children"][0]["san"], "Bc4");
+        assert_eq!(e5["children"][1]["san"], "Nf3");
+    }
+
+    #[test]
+    fn prunes_rare_moves() {
+        let mut tree = tree(3);
+        tree.prune(2);
+        assert_eq!(node_count(&tree), 3);
+
+        let json: Value = serde_json::from_str(&tree.to_json().unwrap()).unwrap();
+        assert_eq!(json["children"].as_array().unwrap().len(), 1);
+        assert_eq!(json["games"], 4);
+    }
+
+    #[test]
+    fn skips_games_from_other_positions() {
+        let mut tree = OpeningTree::new(2);
+        let game = parse(
+            &[
+                r#"[Result "*"]"#,
+                r#"[SetUp "1"]"#,
+                r#"[FEN "4k3/8/8/8/8/8/4P3/4K3 w - - 0 1"]"#,
+            ],
+            "1. e4 *",
+        );
+        tree.add_game(&game);
+        assert_eq!(node_count(&tree), 1);
+    }
+}
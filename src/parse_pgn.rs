@@ -1,3 +1,5 @@
+use std::str::FromStr;
+
 use regex::Regex;
 
 use crate::{
@@ -5,58 +7,90 @@ use crate::{
         Annotation, Cell, File, GameResult, Move, OptionalPiece, PartialCell, Piece, Rank,
         Termination, TimeControl,
     },
-    game::Game,
+    error::Error,
+    game::{Game, Variation},
     general_utils::hours_min_sec_to_duration,
 };
 
+pub const GAME_TERMINATION_MARKERS: [&str; 4] = ["1-0", "0-1", "1/2-1/2", "*"];
+
+// Lexical units of movetext, as defined by the PGN import format
+#[derive(Debug, PartialEq, Eq)]
+enum Token<'a> {
+    Word(&'a str),
+    Comment(&'a str),
+    Nag(u8),
+    VariationStart,
+    VariationEnd,
+}
+
+// The main line or a variation, while its moves are being parsed
+#[derive(Debug, Default)]
+struct Line {
+    // Number of plies played before the first move of the line
+    start_ply: usize,
+    // Index, in the parent line, of the move this line replaces
+    ply: usize,
+    moves: Vec<Move>,
+    variations: Vec<Variation>,
+}
+
 #[derive(Debug)]
 pub struct PgnParser {
     header_regex: Regex,
     eval_regex: Regex,
-    eval_advantage_regex: Regex,
-    eval_mate_regex: Regex,
     clock_regex: Regex,
+    move_number_regex: Regex,
     move_regex: Regex,
     coordinate_regex: Regex,
     castling_regex: Regex,
+    keep_variations: bool,
 }
 #[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
 impl PgnParser {
     pub fn new() -> Self {
         Self {
-            header_regex: Regex::new(r#"\[(.*) "(.*)"\]"#).unwrap(),
-            eval_regex: Regex::new(r#"-?\d+\.\d{1,2}|#-?\d+"#).unwrap(),
-            eval_advantage_regex: Regex::new(r#"-?\d+\.\d{1,2}"#).unwrap(),
-            eval_mate_regex: Regex::new(r#"#(-?\d+)"#).unwrap(),
-            clock_regex: Regex::new(r#"(\d+):(\d{2}):(\d{2})"#).unwrap(),
+            header_regex: Regex::new(r#"\[\s*([A-Za-z0-9_]+)\s+"((?:[^"\\]|\\.)*)"\s*\]"#).unwrap(),
+            eval_regex: Regex::new(r#"\[%eval\s+(#?)(-?\d+(?:\.\d+)?)"#).unwrap(),
+            clock_regex: Regex::new(r#"\[%clk\s+(\d+):(\d{2}):(\d{2})"#).unwrap(),
+            move_number_regex: Regex::new(r#"^\d+(?:\.+|$)"#).unwrap(),
             move_regex: Regex::new(
                 r#"^([NBRQK]?)([a-h1-9]{0,4})(x?)([a-h1-9]{2})(=?)([NBRQK]?)([+#]?)([?!]{0,2})$"#,
             )
             .unwrap(),
             coordinate_regex: Regex::new(r#"^([a-h]?)([1-8]?)$"#).unwrap(),
-            castling_regex: Regex::new(r#"^(O-O-?O?)([+#]?)([?!]{0,2})$"#).unwrap(),
+            castling_regex: Regex::new(r#"^([O0]-[O0](?:-[O0])?)([+#]?)([?!]{0,2})$"#).unwrap(),
+            keep_variations: false,
         }
     }
 
-    fn parse_date_field(value: &str, game: &mut Game) -> Result<(), String> {
-        let date_parts: Vec<&str> = value.split('.').collect();
-        let year = if let Ok(year) = date_parts[0].parse::<u16>() {
-            year
-        } else {
-            return Err("Invalid UTCDate value".to_string());
-        };
+    // Variations are discarded unless asked for, since only the main line is analysed
+    pub fn keep_variations(mut self, keep_variations: bool) -> Self {
+        self.keep_variations = keep_variations;
+        self
+    }
 
-        let month = if let Ok(month) = date_parts[1].parse::<u8>() {
-            month
-        } else {
-            return Err("Invalid UTCDate value".to_string());
-        };
+    fn parse_number<T: FromStr>(value: Option<&str>, field: &str) -> Result<T, Error> {
+        value
+            .and_then(|value| value.parse::<T>().ok())
+            .ok_or_else(|| Error::pgn(format!("Invalid {field} value")))
+    }
 
-        let day = if let Ok(day) = date_parts[2].parse::<u8>() {
-            day
-        } else {
-            return Err("Invalid UTCDate value".to_string());
-        };
+    // Unknown parts of a date are written as question marks, e.g. "2017.??.??"
+    fn parse_date_part<T: FromStr + Default>(value: Option<&str>, field: &str) -> Result<T, Error> {
+        match value {
+            Some(part) if !part.is_empty() && part.chars().all(|c| c == '?') => Ok(T::default()),
+            _ => Self::parse_number(value, field),
+        }
+    }
+
+    fn parse_date_field(value: &str, field: &str, game: &mut Game) -> Result<(), Error> {
+        let mut date_parts = value.split('.');
+
+        // Only touch the game once every part is valid
+        let year = Self::parse_date_part(date_parts.next(), field)?;
+        let month = Self::parse_date_part(date_parts.next(), field)?;
+        let day = Self::parse_date_part(date_parts.next(), field)?;
 
         game.year = year;
         game.month = month;
@@ -65,109 +99,151 @@ impl PgnParser {
         Ok(())
     }
 
-    fn parse_time_control_field(value: &str, game: &mut Game) {
+    // Returns false for values that don't fit `TimeControl`, such as "?" or "40/7200"
+    fn parse_time_control_field(value: &str, game: &mut Game) -> Result<bool, Error> {
         if value == "-" {
             game.time_control_main = 0;
             game.time_control_increment = 0;
             game.time_control = TimeControl::Correspondence;
+        } else if value == "?" || value.contains(['/', ':', '*']) {
+            return Ok(false);
         } else {
-            let time_control_parts: Vec<&str> = value.split('+').collect();
-            game.time_control_main = time_control_parts[0].parse::<u16>().unwrap();
-            game.time_control_increment = time_control_parts[1].parse::<u8>().unwrap();
+            let mut time_control_parts = value.split('+');
+            let main = Self::parse_number(time_control_parts.next(), "TimeControl")?;
+            let increment = match time_control_parts.next() {
+                Some(increment) => Self::parse_number(Some(increment), "TimeControl")?,
+                None => 0,
+            };
+
+            game.time_control_main = main;
+            game.time_control_increment = increment;
             game.time_control = TimeControl::from_base_and_increment(
                 game.time_control_main,
                 u16::from(game.time_control_increment),
             );
         }
-    }
 
-    fn parse_white_elo(value: &str, game: &mut Game) {
-        if value == "?" {
-            game.white_rating = 0;
-        } else {
-            game.white_rating = value.parse::<u16>().unwrap();
-        }
+        Ok(true)
     }
 
-    fn parse_black_elo(value: &str, game: &mut Game) {
-        if value == "?" {
-            game.black_rating = 0;
-        } else {
-            game.black_rating = value.parse::<u16>().unwrap();
+    fn parse_elo(value: &str, field: &str) -> Result<u16, Error> {
+        match value {
+            "?" | "-" | "" => Ok(0),
+            _ => Self::parse_number(Some(value), field),
         }
     }
 
-    // TODO consider error handling
-    fn parse_eco(value: &str, game: &mut Game) {
+    fn parse_eco(value: &str, game: &mut Game) -> Result<(), Error> {
         if value == "?" {
             game.eco_category = '\0';
             game.eco_subcategory = 0;
         } else {
-            let cat_char = value[..1].chars().next().unwrap();
-
-            let mut cat_char_vec: Vec<u8> = vec![0];
-            cat_char.encode_utf8(&mut cat_char_vec);
-
-            game.eco_category = cat_char_vec[0] as char;
-            game.eco_subcategory = value[1..].parse::<u8>().unwrap();
+            let mut chars = value.chars();
+            game.eco_category = match chars.next() {
+                Some(category) if category.is_ascii() => category,
+                _ => return Err(Error::pgn("Invalid ECO value")),
+            };
+            game.eco_subcategory = Self::parse_number(Some(chars.as_str()), "ECO")?;
         }
-    }
 
-    fn parse_termination(value: &str, game: &mut Game) -> Result<(), String> {
-        game.termination = match value {
-            "Normal" => Termination::Normal,
-            "Time forfeit" => Termination::TimeForfeit,
-            "Abandoned" => Termination::Abandoned,
-            "Rules infraction" => Termination::RulesInfraction,
-            "Unterminated" => Termination::Unterminated,
-            u => return Err(format!("Unknown termination: {u}")),
-        };
         Ok(())
     }
 
-    fn parse_result(value: &str, game: &mut Game) -> Result<(), String> {
+    // Values other than the ones `Termination` models, e.g. "adjudication", are kept as tags
+    fn parse_termination(value: &str) -> Option<Termination> {
+        match value.to_ascii_lowercase().as_str() {
+            "normal" => Some(Termination::Normal),
+            "time forfeit" => Some(Termination::TimeForfeit),
+            "abandoned" => Some(Termination::Abandoned),
+            "rules infraction" => Some(Termination::RulesInfraction),
+            "unterminated" => Some(Termination::Unterminated),
+            _ => None,
+        }
+    }
+
+    fn parse_result(value: &str, game: &mut Game) -> Result<(), Error> {
         game.result = match value {
             "1-0" => GameResult::White,
             "0-1" => GameResult::Black,
             "1/2-1/2" => GameResult::Draw,
             "*" => GameResult::Star,
-            u => return Err(format!("Unknown result: {u}")),
+            u => return Err(Error::pgn(format!("Unknown result: {u}"))),
         };
         Ok(())
     }
 
-    fn parse_header(&self, header: &str, game: &mut Game) -> Result<(), String> {
-        if header.is_empty() {
-            return Err("Header cannot be empty".to_string());
+    fn unescape(value: &str) -> String {
+        let mut unescaped = String::with_capacity(value.len());
+        let mut chars = value.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => unescaped.extend(chars.next()),
+                c => unescaped.push(c),
+            }
         }
+        unescaped
+    }
 
-        let captures = self.header_regex.captures(header).unwrap();
-        let field = captures.get(1).unwrap().as_str();
-        let value = captures.get(2).unwrap().as_str();
-
-        match field {
-            "UTCDate" => Self::parse_date_field(value, game)?,
-            "TimeControl" => Self::parse_time_control_field(value, game),
-            "WhiteElo" => Self::parse_white_elo(value, game),
-            "BlackElo" => Self::parse_black_elo(value, game),
-            "Site" => game.site = value.to_string(),
-            "White" => game.white = value.to_string(),
-            "Black" => game.black = value.to_string(),
-            "WhiteRatingDiff" => game.white_diff = value.parse::<i16>().unwrap(),
-            "BlackRatingDiff" => game.black_diff = value.parse::<i16>().unwrap(),
-            "ECO" => Self::parse_eco(value, game),
-            "Termination" => Self::parse_termination(value, game)?,
-            "Result" => Self::parse_result(value, game)?,
+    // Tags without a field of their own are kept in `Game::tags`
+    fn parse_tag(name: &str, value: String, game: &mut Game) -> Result<(), Error> {
+        match name {
+            "UTCDate" => Self::parse_date_field(&value, name, game)?,
+            // Only Lichess writes UTCDate, other exports only date games through the roster's
+            // Date. It is still kept as a tag so it is written back as it was.
+            "Date" => {
+                if (game.year, game.month, game.day) == (0, 0, 0) {
+                    Self::parse_date_field(&value, name, game)?;
+                }
+                game.tags.push((name.to_string(), value));
+            }
+            "TimeControl" => {
+                if !Self::parse_time_control_field(&value, game)? {
+                    game.tags.push((name.to_string(), value));
+                }
+            }
+            "WhiteElo" => game.white_rating = Self::parse_elo(&value, name)?,
+            "BlackElo" => game.black_rating = Self::parse_elo(&value, name)?,
+            "Site" => game.site = value,
+            "White" => game.white = value,
+            "Black" => game.black = value,
+            "WhiteRatingDiff" => game.white_diff = Self::parse_number(Some(&value), name)?,
+            "BlackRatingDiff" => game.black_diff = Self::parse_number(Some(&value), name)?,
+            "ECO" => Self::parse_eco(&value, game)?,
+            "Termination" => match Self::parse_termination(&value) {
+                Some(termination) => game.termination = termination,
+                None => game.tags.push((name.to_string(), value)),
+            },
+            "Result" => Self::parse_result(&value, game)?,
             "Variant" => {
-                if value != "Standard" {
-                    return Err("Variant must be Standard".to_string());
+                if !value.eq_ignore_ascii_case("standard") {
+                    return Err(Error::pgn("Variant must be Standard"));
                 }
             }
-            "Event" | "Date" | "WhiteTitle" | "BlackTitle" | "Opening" | "UTCTime"
-            | "Annotator" | "Round" => {}
-            f => {
-                return Err(format!("Unrecognized header field: {f}"));
+            _ => game.tags.push((name.to_string(), value)),
+        }
+
+        Ok(())
+    }
+
+    // A header line holds one or more tag pairs
+    fn parse_header(&self, header: &str, game: &mut Game) -> Result<(), Error> {
+        if header.trim().is_empty() {
+            return Err(Error::pgn("Header cannot be empty"));
+        }
+
+        let mut end = 0;
+        for captures in self.header_regex.captures_iter(header) {
+            let tag = captures.get(0).unwrap();
+            if !header[end..tag.start()].trim().is_empty() {
+                break;
             }
+            end = tag.end();
+
+            Self::parse_tag(&captures[1], Self::unescape(&captures[2]), game)?;
+        }
+
+        if end == 0 || !header[end..].trim().is_empty() {
+            return Err(Error::pgn(format!("Malformed header: {header}")));
         }
 
         Ok(())
@@ -183,7 +259,7 @@ impl PgnParser {
         promotion_piece: &str,
         check_str: &str,
         nag_str: &str,
-    ) -> Result<Move, String> {
+    ) -> Result<Move, Error> {
         let from = if let Some(coord_cap) = self.coordinate_regex.captures(disambiguation_str) {
             let optional_file = File::from_pgn(&coord_cap[1]);
             let optional_rank = Rank::from_pgn(&coord_cap[2]);
@@ -192,20 +268,22 @@ impl PgnParser {
                 rank: optional_rank,
             }
         } else {
-            return Err(format!(
+            return Err(Error::pgn(format!(
                 "Error parsing disambiguation string {disambiguation_str}"
-            ));
+            )));
         };
 
         let to = if let Some(coord_cap) = self.coordinate_regex.captures(dest_str) {
             let optional_file = File::from_pgn(&coord_cap[1]);
             let optional_rank = Rank::from_pgn(&coord_cap[2]);
-            Cell {
-                file: optional_file.unwrap(),
-                rank: optional_rank.unwrap(),
+            match (optional_file, optional_rank) {
+                (Some(file), Some(rank)) => Cell { file, rank },
+                _ => return Err(Error::pgn(format!("Incomplete destination {dest_str}"))),
             }
         } else {
-            return Err(format!("Error parsing destination string {dest_str}"));
+            return Err(Error::pgn(format!(
+                "Error parsing destination string {dest_str}"
+            )));
         };
 
         let piece_moved = Piece::from_pgn(piece_str);
@@ -220,7 +298,9 @@ impl PgnParser {
             "?" => Annotation::Mistake,
             "??" => Annotation::Blunder,
             "?!" => Annotation::Questionable,
-            s => return Err(format!("Unrecognized annotation: `{s}`")),
+            // Good moves have no `Annotation` of their own
+            "!" | "!!" | "!?" => Annotation::None,
+            s => return Err(Error::pgn(format!("Unrecognized annotation: `{s}`"))),
         };
 
         let promoted_to = match promotion_piece {
@@ -248,7 +328,7 @@ impl PgnParser {
         &self,
         token: &str,
         current_move_count: usize,
-    ) -> Result<Option<Move>, String> {
+    ) -> Result<Option<Move>, Error> {
         if let Some(cap) = self.castling_regex.captures(token) {
             let white = current_move_count % 2 == 0;
             let kingside = cap[1].len() == 3;
@@ -279,10 +359,7 @@ impl PgnParser {
             let disambiguation_str = &cap[2];
             let capture_str = &cap[3];
             let dest_str = &cap[4];
-            assert!(disambiguation_str.len() <= dest_str.len());
-            let promotion_str = &cap[5];
             let promotion_piece = &cap[6];
-            assert!(promotion_piece.len() == promotion_str.len());
             let check_str = &cap[7];
             let nag_str = &cap[8];
 
@@ -300,53 +377,202 @@ impl PgnParser {
         Ok(None)
     }
 
-    fn parse_potential_moves(&self, moves_str: &str, game: &mut Game) -> Result<(), String> {
-        let tokens = moves_str.split(' ');
+    fn end_of_line(movetext: &str, start: usize) -> usize {
+        movetext[start..]
+            .find('\n')
+            .map_or(movetext.len(), |end| start + end)
+    }
 
-        let mut in_comment = false;
+    fn end_of_word(movetext: &str, start: usize) -> usize {
+        movetext[start..]
+            .find(|c: char| c.is_whitespace() || "{}();$".contains(c))
+            .map_or(movetext.len(), |end| start + end)
+    }
 
-        for token in tokens {
-            if "{" == token {
-                in_comment = true;
+    fn tokenize(movetext: &str) -> Result<Vec<Token>, Error> {
+        let bytes = movetext.as_bytes();
+        let mut tokens = vec![];
+        let mut i = 0;
+
+        while i < bytes.len() {
+            match bytes[i] {
+                // Escaped lines and rest-of-line comments
+                b'%' if i == 0 || bytes[i - 1] == b'\n' => i = Self::end_of_line(movetext, i),
+                b';' => i = Self::end_of_line(movetext, i),
+                b'{' => {
+                    let end = movetext[i..]
+                        .find('}')
+                        .map(|end| i + end)
+                        .ok_or_else(|| Error::pgn("Unterminated comment"))?;
+                    tokens.push(Token::Comment(&movetext[i + 1..end]));
+                    i = end + 1;
+                }
+                b'}' => return Err(Error::pgn("Unexpected '}' in movetext")),
+                b'(' => {
+                    tokens.push(Token::VariationStart);
+                    i += 1;
+                }
+                b')' => {
+                    tokens.push(Token::VariationEnd);
+                    i += 1;
+                }
+                b'$' => {
+                    let end = Self::end_of_word(movetext, i + 1);
+                    let nag = &movetext[i + 1..end];
+                    let nag = nag
+                        .parse()
+                        .map_err(|_| Error::pgn(format!("Invalid NAG: ${nag}")))?;
+                    tokens.push(Token::Nag(nag));
+                    i = end;
+                }
+                byte if byte.is_ascii_whitespace() => i += 1,
+                _ => {
+                    let end = Self::end_of_word(movetext, i);
+                    tokens.push(Token::Word(&movetext[i..end]));
+                    i = end;
+                }
             }
+        }
 
-            if "}" == token {
-                in_comment = false;
+        Ok(tokens)
+    }
+
+    // Evaluations and clock times are read from the `[%eval]` and `[%clk]` commands
+    fn parse_comment(&self, comment: &str, game: &mut Game) -> Result<(), Error> {
+        for cap in self.eval_regex.captures_iter(comment) {
+            game.eval_available = true;
+
+            if cap[1].is_empty() {
+                game.eval_mate_in.push(0);
+                game.eval_advantage
+                    .push(Self::parse_number(Some(&cap[2]), "eval")?);
+            } else {
+                game.eval_advantage.push(0.0);
+                game.eval_mate_in
+                    .push(Self::parse_number(Some(&cap[2]), "eval")?);
             }
+        }
 
-            if in_comment {
-                for cap in self.eval_regex.captures_iter(token) {
-                    game.eval_available = true;
+        for cap in self.clock_regex.captures_iter(comment) {
+            let hours: u8 = Self::parse_number(Some(&cap[1]), "clock")?;
+            let minutes: u8 = Self::parse_number(Some(&cap[2]), "clock")?;
+            let seconds: u8 = Self::parse_number(Some(&cap[3]), "clock")?;
 
-                    let eval = &cap[0];
+            game.clock
+                .push(hours_min_sec_to_duration((&hours, &minutes, &seconds)));
+        }
 
-                    if let Some(cap) = self.eval_mate_regex.captures(eval) {
-                        game.eval_advantage.push(0.0);
-                        game.eval_mate_in.push(cap[1].parse::<i16>().unwrap());
-                    }
+        Ok(())
+    }
 
-                    if let Some(cap) = self.eval_advantage_regex.captures(eval) {
-                        game.eval_mate_in.push(0);
-                        game.eval_advantage.push(cap[0].parse::<f32>().unwrap());
-                    }
+    // Only the NAGs matching an `Annotation` are kept, the others are ignored
+    fn annotate(line: &mut Line, nag: u8) -> Result<(), Error> {
+        let last_move = line
+            .moves
+            .last_mut()
+            .ok_or_else(|| Error::pgn(format!("NAG ${nag} before any move")))?;
+
+        match nag {
+            2 => last_move.nag = Annotation::Mistake,
+            4 => last_move.nag = Annotation::Blunder,
+            6 => last_move.nag = Annotation::Questionable,
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    fn parse_word(&self, word: &str, line: &mut Line) -> Result<(), Error> {
+        // Move numbers may be glued to the move, as in "1.e4"
+        let san = match self.move_number_regex.find(word) {
+            Some(number) => &word[number.end()..],
+            None => word,
+        };
+        if san.is_empty() {
+            return Ok(());
+        }
+
+        match self.parse_potential_move(san, line.start_ply + line.moves.len())? {
+            Some(m) => line.moves.push(m),
+            None => return Err(Error::pgn(format!("Unrecognized movetext token: {word}"))),
+        }
+
+        Ok(())
+    }
+
+    fn parse_potential_moves(&self, moves_str: &str, game: &mut Game) -> Result<(), Error> {
+        // A FEN tag can hand the first move to black
+        let start_ply = game.starting_board()?.ply();
+        let mut lines = vec![Line {
+            start_ply,
+            ..Line::default()
+        }];
+        // Depth of the variation being discarded, if any
+        let mut discarded_depth = 0;
+
+        for token in Self::tokenize(moves_str)? {
+            if discarded_depth > 0 {
+                match token {
+                    Token::VariationStart => discarded_depth += 1,
+                    Token::VariationEnd => discarded_depth -= 1,
+                    _ => {}
                 }
+                continue;
+            }
 
-                for cap in self.clock_regex.captures_iter(token) {
-                    let hours = cap[1].parse::<u8>().unwrap();
-                    let minutes = cap[2].parse::<u8>().unwrap();
-                    let seconds = cap[3].parse::<u8>().unwrap();
+            let depth = lines.len();
+            let line = lines.last_mut().unwrap();
+            match token {
+                Token::Word(word) if GAME_TERMINATION_MARKERS.contains(&word) => {
+                    if depth > 1 {
+                        return Err(Error::pgn("Game result inside a variation"));
+                    }
+                    break;
+                }
+                Token::Word(word) => self.parse_word(word, line)?,
+                Token::Comment(comment) if depth == 1 => self.parse_comment(comment, game)?,
+                Token::Comment(_) => {}
+                Token::Nag(nag) => Self::annotate(line, nag)?,
+                Token::VariationStart => {
+                    if line.moves.is_empty() {
+                        return Err(Error::pgn("Variation before any move"));
+                    }
 
-                    game.clock
-                        .push(hours_min_sec_to_duration((&hours, &minutes, &seconds)));
+                    if self.keep_variations {
+                        let ply = line.moves.len() - 1;
+                        let start_ply = line.start_ply + ply;
+                        lines.push(Line {
+                            start_ply,
+                            ply,
+                            ..Line::default()
+                        });
+                    } else {
+                        discarded_depth = 1;
+                    }
                 }
-            } else {
-                let potential_move = self.parse_potential_move(token, game.moves.len())?;
-                if let Some(m) = potential_move {
-                    game.moves.push(m);
+                Token::VariationEnd => {
+                    if depth == 1 {
+                        return Err(Error::pgn("Unexpected ')' in movetext"));
+                    }
+
+                    let variation = lines.pop().unwrap();
+                    lines.last_mut().unwrap().variations.push(Variation {
+                        ply: variation.ply,
+                        moves: variation.moves,
+                        variations: variation.variations,
+                    });
                 }
             }
         }
 
+        if discarded_depth > 0 || lines.len() > 1 {
+            return Err(Error::pgn("Unterminated variation"));
+        }
+
+        let main_line = lines.pop().unwrap();
+        game.moves.extend(main_line.moves);
+        game.variations.extend(main_line.variations);
+
         Ok(())
     }
 
@@ -355,7 +581,7 @@ impl PgnParser {
         headers: &Vec<String>,
         move_str: &str,
         game: &mut Game,
-    ) -> Result<(), String> {
+    ) -> Result<(), Error> {
         for header in headers {
             self.parse_header(header, game)?;
         }
@@ -373,7 +599,7 @@ mod parse_header {
         let parser = PgnParser::new();
         assert_eq!(
             parser.parse_header("", &mut game),
-            Err("Header cannot be empty".to_string())
+            Err(Error::pgn("Header cannot be empty"))
         );
         assert_eq!(game, Game::default());
     }
@@ -396,7 +622,7 @@ mod parse_header {
         let parser = PgnParser::new();
         assert_eq!(
             parser.parse_header(header, &mut game),
-            Err("Invalid UTCDate value".to_string())
+            Err(Error::pgn("Invalid UTCDate value"))
         );
         assert_eq!(game, Game::default());
     }
@@ -408,7 +634,7 @@ mod parse_header {
         let parser = PgnParser::new();
         assert_eq!(
             parser.parse_header(header, &mut game),
-            Err("Invalid UTCDate value".to_string())
+            Err(Error::pgn("Invalid UTCDate value"))
         );
         assert_eq!(game, Game::default());
     }
@@ -420,21 +646,91 @@ mod parse_header {
         let parser = PgnParser::new();
         assert_eq!(
             parser.parse_header(header, &mut game),
-            Err("Invalid UTCDate value".to_string())
+            Err(Error::pgn("Invalid UTCDate value"))
         );
         assert_eq!(game, Game::default());
     }
 
     #[test]
-    fn event_header_returns_unsupported_error() {
+    fn unknown_header_is_kept_as_a_tag() {
         let header = r#"[Unsupported "Header"]"#;
         let mut game = Game::default();
         let parser = PgnParser::new();
+        assert_eq!(parser.parse_header(header, &mut game), Ok(()));
+        assert_eq!(
+            game.tags,
+            vec![("Unsupported".to_string(), "Header".to_string())]
+        );
+    }
+
+    #[test]
+    fn several_tags_on_one_line() {
+        let header = r#"[White "Abbot"] [Event "The \"Open\""]"#;
+        let mut game = Game::default();
+        let parser = PgnParser::new();
+        assert_eq!(parser.parse_header(header, &mut game), Ok(()));
+        assert_eq!(game.white, "Abbot".to_string());
+        assert_eq!(
+            game.tags,
+            vec![("Event".to_string(), r#"The "Open""#.to_string())]
+        );
+    }
+
+    #[test]
+    fn malformed_header() {
+        let header = r#"[White "Abbot"] Costello"#;
+        let mut game = Game::default();
+        let parser = PgnParser::new();
         assert_eq!(
             parser.parse_header(header, &mut game),
-            Err("Unrecognized header field: Unsupported".to_string())
+            Err(Error::pgn(format!("Malformed header: {header}")))
+        );
+    }
+
+    #[test]
+    fn date_header_updates_date_fields_without_utc_date() {
+        let header = r#"[Date "2022.07.30"]"#;
+        let mut game = Game::default();
+        let parser = PgnParser::new();
+        assert_eq!(parser.parse_header(header, &mut game), Ok(()));
+        assert_eq!((game.year, game.month, game.day), (2022, 7, 30));
+        assert_eq!(
+            game.tags,
+            vec![("Date".to_string(), "2022.07.30".to_string())]
         );
-        assert_eq!(game, Game::default());
+    }
+
+    #[test]
+    fn utc_date_header_takes_precedence_over_date() {
+        let parser = PgnParser::new();
+        for header in [
+            r#"[UTCDate "2022.07.31"] [Date "2022.07.30"]"#,
+            r#"[Date "2022.07.30"] [UTCDate "2022.07.31"]"#,
+        ] {
+            let mut game = Game::default();
+            assert_eq!(parser.parse_header(header, &mut game), Ok(()));
+            assert_eq!((game.year, game.month, game.day), (2022, 7, 31));
+        }
+    }
+
+    #[test]
+    fn invalid_date_header() {
+        let header = r#"[Date "22.O7.30"]"#; // note the letter 'O'
+        let mut game = Game::default();
+        let parser = PgnParser::new();
+        assert_eq!(
+            parser.parse_header(header, &mut game),
+            Err(Error::pgn("Invalid Date value"))
+        );
+    }
+
+    #[test]
+    fn unknown_date_parts_are_zero() {
+        let header = r#"[UTCDate "2017.??.??"]"#;
+        let mut game = Game::default();
+        let parser = PgnParser::new();
+        assert_eq!(parser.parse_header(header, &mut game), Ok(()));
+        assert_eq!((game.year, game.month, game.day), (2017, 0, 0));
     }
 
     #[test]
@@ -459,6 +755,29 @@ mod parse_header {
         assert_eq!(game.time_control_increment, 0);
     }
 
+    #[test]
+    fn time_control_header_updates_time_control_fields_3() {
+        let header = r#"[TimeControl "600"]"#;
+        let mut game = Game::default();
+        let parser = PgnParser::new();
+        assert_eq!(parser.parse_header(header, &mut game), Ok(()));
+        assert_eq!(game.time_control_main, 600);
+        assert_eq!(game.time_control_increment, 0);
+    }
+
+    #[test]
+    fn time_control_header_without_base_is_kept_as_a_tag() {
+        let header = r#"[TimeControl "40/7200:3600"]"#;
+        let mut game = Game::default();
+        let parser = PgnParser::new();
+        assert_eq!(parser.parse_header(header, &mut game), Ok(()));
+        assert_eq!(game.time_control, TimeControl::UltraBullet);
+        assert_eq!(
+            game.tags,
+            vec![("TimeControl".to_string(), "40/7200:3600".to_string())]
+        );
+    }
+
     #[test]
     fn white_elo_updates_white_rating_field() {
         let header = r#"[WhiteElo "2100"]"#;
@@ -541,6 +860,19 @@ mod parse_header {
         assert_eq!(game.termination, Termination::TimeForfeit);
     }
 
+    #[test]
+    fn unknown_termination_is_kept_as_a_tag() {
+        let header = r#"[Termination "adjudication"]"#;
+        let mut game = Game::default();
+        let parser = PgnParser::new();
+        assert_eq!(parser.parse_header(header, &mut game), Ok(()));
+        assert_eq!(game.termination, Termination::Normal);
+        assert_eq!(
+            game.tags,
+            vec![("Termination".to_string(), "adjudication".to_string())]
+        );
+    }
+
     #[test]
     fn result_header_sets_result_field() {
         let header = r#"[Result "0-1"]"#;
@@ -566,14 +898,33 @@ mod parse_header {
 
     ok_and_game_is_not_modified!(
         variant_standard: r#"[Variant "Standard"]"#,
-        event_header: r#"[Round "1"]"#,
-        date_header: r#"[Date "2022-07-30"]"#,
-        white_title_header: r#"[WhiteTitle "GM"]"#,
-        black_title_header: r#"[BlackTitle "IM"]"#,
-        opening_header: r#"[Opening "Sicilian"]"#,
-        utc_time_header: r#"[UTCTime "12:34:56"]"#,
-        annotator_header: r#"[Annotator "None"]"#,
-        round_header: r#"[Round "1"]"#,
+    );
+
+    macro_rules! kept_as_tag {
+        ($($name:ident: $value:expr, $tag_name:expr, $tag_value:expr,)*) => {
+        $(
+            #[test]
+            fn $name() {
+                let mut game = Game::default();
+                let parser = PgnParser::new();
+                assert_eq!(parser.parse_header($value, &mut game), Ok(()));
+                assert_eq!(
+                    game.tags,
+                    vec![($tag_name.to_string(), $tag_value.to_string())]
+                );
+            }
+        )*
+        }
+    }
+
+    kept_as_tag!(
+        event_header: r#"[Event "Rated Blitz game"]"#, "Event", "Rated Blitz game",
+        white_title_header: r#"[WhiteTitle "GM"]"#, "WhiteTitle", "GM",
+        black_title_header: r#"[BlackTitle "IM"]"#, "BlackTitle", "IM",
+        opening_header: r#"[Opening "Sicilian"]"#, "Opening", "Sicilian",
+        utc_time_header: r#"[UTCTime "12:34:56"]"#, "UTCTime", "12:34:56",
+        annotator_header: r#"[Annotator "None"]"#, "Annotator", "None",
+        round_header: r#"[Round "1"]"#, "Round", "1",
     );
 
     #[test]
@@ -771,4 +1122,100 @@ mod test_move_related {
         let parser = PgnParser::new();
         assert_eq!(Ok(Some(expected)), parser.parse_potential_move(token, 20));
     }
+
+    #[test]
+    fn test_multi_line_movetext_with_comments_and_nags() {
+        let move_str = "1.e4 $1 e5?! ; King's pawn\n% escaped line\n2. Nf3 $2 {a comment} 1-0";
+        let mut game = Game::default();
+        let parser = PgnParser::new();
+        assert_eq!(parser.parse_potential_moves(move_str, &mut game), Ok(()));
+
+        let mut e5 = Move::new_to(File::_E, Rank::_5, Piece::Pawn);
+        e5.nag = Annotation::Questionable;
+        let mut nf3 = Move::new_to(File::_F, Rank::_3, Piece::Knight);
+        nf3.nag = Annotation::Mistake;
+        assert_eq!(
+            game.moves,
+            vec![Move::new_to(File::_E, Rank::_4, Piece::Pawn), e5, nf3]
+        );
+    }
+
+    #[test]
+    fn test_variations_are_discarded_by_default() {
+        let move_str = "1. e4 e5 (1... c5 2. Nf3 (2. c3)) 2. Nf3 *";
+        let mut game = Game::default();
+        let parser = PgnParser::new();
+        assert_eq!(parser.parse_potential_moves(move_str, &mut game), Ok(()));
+        assert_eq!(game.moves.len(), 3);
+        assert_eq!(game.variations, vec![]);
+    }
+
+    #[test]
+    fn test_variations_are_kept() {
+        let move_str = "1. e4 e5 (1... c5 2. Nf3 (2. c3)) 2. Nf3 *";
+        let mut game = Game::default();
+        let parser = PgnParser::new().keep_variations(true);
+        assert_eq!(parser.parse_potential_moves(move_str, &mut game), Ok(()));
+        assert_eq!(game.moves.len(), 3);
+        assert_eq!(
+            game.variations,
+            vec![Variation {
+                ply: 1,
+                moves: vec![
+                    Move::new_to(File::_C, Rank::_5, Piece::Pawn),
+                    Move::new_to(File::_F, Rank::_3, Piece::Knight),
+                ],
+                variations: vec![Variation {
+                    ply: 1,
+                    moves: vec![Move::new_to(File::_C, Rank::_3, Piece::Pawn)],
+                    variations: vec![],
+                }],
+            }]
+        );
+    }
+
+    #[test]
+    fn test_castling_in_variation_uses_the_side_to_move() {
+        let mut game = Game::default();
+        let parser = PgnParser::new().keep_variations(true);
+        assert_eq!(
+            parser.parse_potential_moves("1. Nf3 Nf6 (1... O-O) *", &mut game),
+            Ok(())
+        );
+        assert_eq!(
+            game.variations[0].moves,
+            vec![Move::new_to_from(
+                Some(File::_E),
+                Some(Rank::_8),
+                File::_G,
+                Rank::_8,
+                Piece::King,
+            )]
+        );
+    }
+
+    macro_rules! movetext_err_tests {
+        ($($name:ident: $value:expr, $message:expr,)*) => {
+        $(
+            #[test]
+            fn $name() {
+                let mut game = Game::default();
+                let parser = PgnParser::new().keep_variations(true);
+                assert_eq!(
+                    parser.parse_potential_moves($value, &mut game),
+                    Err(Error::pgn($message))
+                );
+            }
+        )*
+        }
+    }
+
+    movetext_err_tests!(
+        unterminated_variation: "1. e4 (1. d4", "Unterminated variation",
+        unexpected_variation_end: "1. e4 )", "Unexpected ')' in movetext",
+        variation_before_any_move: "(1. d4) 1. e4", "Variation before any move",
+        unterminated_comment: "1. e4 { best by test", "Unterminated comment",
+        unrecognized_token: "1. e4 Zz9", "Unrecognized movetext token: Zz9",
+        result_inside_variation: "1. e4 (1. d4 1-0)", "Game result inside a variation",
+    );
 }
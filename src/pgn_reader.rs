@@ -0,0 +1,287 @@
+use std::ffi::OsStr;
+use std::fmt;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+
+use bzip2::read::MultiBzDecoder;
+use flate2::read::MultiGzDecoder;
+
+use crate::error::Error;
+use crate::parse_pgn::GAME_TERMINATION_MARKERS;
+
+// Headers and movetext of one game, as they appear in the file
+#[derive(Debug, PartialEq, Eq)]
+pub struct RawGame {
+    pub headers: Vec<String>,
+    pub movetext: String,
+}
+
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+impl RawGame {
+    pub fn to_pgn(&self) -> String {
+        format!("{}\n\n{}", self.headers.join("\n"), self.movetext)
+    }
+}
+
+// Follows comments and variations over the lines of a movetext, to find where it ends
+#[derive(Debug, Default)]
+struct MovetextScanner {
+    in_comment: bool,
+    depth: usize,
+}
+
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+impl MovetextScanner {
+    // Returns true if the line ends with the game termination marker
+    fn scan(&mut self, line: &str) -> bool {
+        let mut terminated = false;
+        let mut rest = line;
+
+        while let Some(c) = rest.chars().next() {
+            if self.in_comment {
+                match rest.find('}') {
+                    Some(end) => {
+                        self.in_comment = false;
+                        rest = &rest[end + 1..];
+                        continue;
+                    }
+                    None => break,
+                }
+            }
+
+            match c {
+                '{' => self.in_comment = true,
+                ';' => break,
+                '(' => self.depth += 1,
+                ')' => self.depth = self.depth.saturating_sub(1),
+                c if c.is_whitespace() => {}
+                _ => {
+                    let end = rest
+                        .find(|c: char| c.is_whitespace() || "{}();".contains(c))
+                        .unwrap_or(rest.len());
+                    terminated |=
+                        self.depth == 0 && GAME_TERMINATION_MARKERS.contains(&&rest[..end]);
+                    rest = &rest[end..];
+                    continue;
+                }
+            }
+            rest = &rest[c.len_utf8()..];
+        }
+
+        terminated
+    }
+}
+
+// Reads the games of a PGN file one at a time, so memory does not grow with the file.
+// Files ending in "bz2", "gz" or "zst" are decompressed as they are read.
+pub struct PgnReader {
+    path: PathBuf,
+    reader: Box<dyn BufRead + Send>,
+    games_read: usize,
+    // First line of the next game, read while looking for the end of the previous one
+    pending_line: Option<String>,
+}
+
+impl fmt::Debug for PgnReader {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("PgnReader")
+            .field("path", &self.path)
+            .field("games_read", &self.games_read)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+impl PgnReader {
+    pub fn open(path: &Path) -> Result<Self, Error> {
+        let file = File::open(path).map_err(|err| Error::from(err).in_file(path))?;
+
+        let reader: Box<dyn BufRead + Send> = match path.extension().and_then(OsStr::to_str) {
+            Some("bz2") => Box::new(BufReader::new(MultiBzDecoder::new(file))),
+            Some("gz") => Box::new(BufReader::new(MultiGzDecoder::new(file))),
+            Some("zst") => {
+                let decoder =
+                    zstd::Decoder::new(file).map_err(|err| Error::from(err).in_file(path))?;
+                Box::new(BufReader::new(decoder))
+            }
+            _ => Box::new(BufReader::new(file)),
+        };
+
+        Ok(Self::new(path, reader))
+    }
+
+    fn new(path: &Path, reader: Box<dyn BufRead + Send>) -> Self {
+        PgnReader {
+            path: path.to_path_buf(),
+            reader,
+            games_read: 0,
+            pending_line: None,
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    // Index within the file of the next game
+    pub fn games_read(&self) -> usize {
+        self.games_read
+    }
+
+    // Lines are read as Latin-1, the PGN standard's character set, when they aren't UTF-8
+    fn read_line(&mut self) -> Result<Option<String>, Error> {
+        if let Some(line) = self.pending_line.take() {
+            return Ok(Some(line));
+        }
+
+        let mut bytes = vec![];
+        let read = self.reader.read_until(b'\n', &mut bytes).map_err(|err| {
+            Error::from(err)
+                .in_game(self.games_read)
+                .in_file(&self.path)
+        })?;
+        if read == 0 {
+            return Ok(None);
+        }
+
+        let line = match String::from_utf8(bytes) {
+            Ok(line) => line,
+            Err(err) => err.into_bytes().into_iter().map(char::from).collect(),
+        };
+
+        Ok(Some(
+            line.trim_start_matches('\u{feff}').trim_end().to_string(),
+        ))
+    }
+
+    // A game ends with its termination marker, or where the tags of the next game start
+    pub fn next_game(&mut self) -> Result<Option<RawGame>, Error> {
+        let mut headers = vec![];
+        let mut movetext: Vec<String> = vec![];
+        let mut scanner = MovetextScanner::default();
+
+        while let Some(line) = self.read_line()? {
+            // Escaped lines are for other tools
+            if line.starts_with('%') {
+                continue;
+            }
+
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            if line.starts_with('[') && !scanner.in_comment {
+                if movetext.is_empty() {
+                    headers.push(line.to_string());
+                    continue;
+                }
+                self.pending_line = Some(line.to_string());
+                break;
+            }
+
+            movetext.push(line.to_string());
+            if scanner.scan(line) {
+                break;
+            }
+        }
+
+        if headers.is_empty() && movetext.is_empty() {
+            return Ok(None);
+        }
+
+        self.games_read += 1;
+        Ok(Some(RawGame {
+            headers,
+            movetext: movetext.join("\n"),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+    use std::io::{Cursor, Write};
+
+    const PGN: &str = "[Event \"Rated Blitz game\"]\n[Result \"1-0\"]\n\n1. e4 e5 1-0\n\n\
+                       [Event \"Rated Bullet game\"]\n\n1. d4 0-1\n";
+
+    #[test]
+    fn reads_one_game_at_a_time() {
+        let mut reader = PgnReader::new(Path::new("games.pgn"), Box::new(Cursor::new(PGN)));
+
+        let first = reader.next_game().unwrap().unwrap();
+        assert_eq!(
+            first.headers,
+            vec!["[Event \"Rated Blitz game\"]", "[Result \"1-0\"]"]
+        );
+        assert_eq!(first.movetext, "1. e4 e5 1-0");
+        assert_eq!(reader.games_read(), 1);
+
+        let second = reader.next_game().unwrap().unwrap();
+        assert_eq!(
+            second.to_pgn(),
+            "[Event \"Rated Bullet game\"]\n\n1. d4 0-1"
+        );
+
+        assert_eq!(reader.next_game(), Ok(None));
+        assert_eq!(reader.games_read(), 2);
+    }
+
+    #[test]
+    fn reads_movetext_over_several_lines() {
+        let pgn =
+            "% exported by hand\n[White \"Abbot\"] [Black \"Costello\"]\n\n1. e4 { a comment\n\
+                   [spanning lines] } e5\n2. Nf3 1-0\n1. d4 d5\n\n[White \"M\u{fc}ller\"]\n\n*\n";
+        let mut reader = PgnReader::new(
+            Path::new("games.pgn"),
+            Box::new(Cursor::new(
+                pgn.chars().map(|c| c as u8).collect::<Vec<u8>>(),
+            )),
+        );
+
+        let first = reader.next_game().unwrap().unwrap();
+        assert_eq!(
+            first.headers,
+            vec!["[White \"Abbot\"] [Black \"Costello\"]"]
+        );
+        assert_eq!(
+            first.movetext,
+            "1. e4 { a comment\n[spanning lines] } e5\n2. Nf3 1-0"
+        );
+
+        // Without a termination marker, the game ends where the next one's tags start
+        let second = reader.next_game().unwrap().unwrap();
+        assert_eq!(second.headers, Vec::<String>::new());
+        assert_eq!(second.movetext, "1. d4 d5");
+
+        let third = reader.next_game().unwrap().unwrap();
+        assert_eq!(third.headers, vec!["[White \"M\u{fc}ller\"]"]);
+        assert_eq!(reader.next_game(), Ok(None));
+    }
+
+    #[test]
+    fn decompresses_gzip() {
+        let path = std::env::temp_dir().join("chess_analytics_pgn_reader_test.pgn.gz");
+        let mut encoder =
+            flate2::write::GzEncoder::new(File::create(&path).unwrap(), Default::default());
+        encoder.write_all(PGN.as_bytes()).unwrap();
+        encoder.finish().unwrap();
+
+        let mut reader = PgnReader::open(&path).unwrap();
+        assert_eq!(
+            reader.next_game().unwrap().unwrap().movetext,
+            "1. e4 e5 1-0"
+        );
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn missing_file_is_an_io_error() {
+        let err = PgnReader::open(Path::new("does_not_exist.pgn")).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Io);
+        assert_eq!(err.file(), Some(Path::new("does_not_exist.pgn")));
+    }
+}
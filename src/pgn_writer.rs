@@ -0,0 +1,378 @@
+use std::time::Duration;
+
+use crate::{
+    basic_types::{GameResult, Move, Termination, TimeControl},
+    board::Board,
+    error::Error,
+    game::{Game, Variation},
+    notation::move_to_san,
+};
+
+// The export format keeps movetext lines under 80 columns
+const MAX_LINE_LENGTH: usize = 79;
+
+// Tags taken from `Game::tags` for the seven tag roster rather than written after it
+const ROSTER_TAGS: [&str; 3] = ["Event", "Date", "Round"];
+
+// Writes games in the PGN export format. Moves are rendered as SAN by replaying them on a
+// board, so they don't depend on how the game was first written.
+#[derive(Debug, Default)]
+pub struct PgnWriter {
+    comments: bool,
+}
+
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+impl PgnWriter {
+    // With `comments`, evaluations and clock times are written as `[%eval]` and `[%clk]`
+    pub fn new(comments: bool) -> Self {
+        PgnWriter { comments }
+    }
+
+    pub fn write_game(&self, game: &Game) -> Result<String, Error> {
+        let board = game.starting_board()?;
+        let mut pgn = String::new();
+        for (name, value) in tags(game) {
+            // A set-up position is written as the complete FEN of the board it parses to
+            let value = if name == "FEN" { board.to_fen() } else { value };
+            pgn.push_str(&format!("[{name} \"{}\"]\n", escape(&value)));
+        }
+        pgn.push('\n');
+
+        let mut tokens = vec![];
+        let comments_from = if self.comments { Some(game) } else { None };
+        write_line(
+            comments_from,
+            &board,
+            board.ply(),
+            &game.moves,
+            &game.variations,
+            &mut tokens,
+        )?;
+        tokens.push(result_to_pgn(game.result).to_string());
+
+        pgn.push_str(&wrap(&tokens));
+        pgn.push_str("\n\n");
+
+        Ok(pgn)
+    }
+}
+
+// Appends the tokens of a line of moves played from `board`, after `start_ply` plies. Only the
+// main line is given the game to take its comments from.
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+fn write_line(
+    comments_from: Option<&Game>,
+    board: &Board,
+    start_ply: usize,
+    moves: &[Move],
+    variations: &[Variation],
+    tokens: &mut Vec<String>,
+) -> Result<(), Error> {
+    let mut board = *board;
+    let mut needs_number = true;
+
+    for (index, curr_move) in moves.iter().enumerate() {
+        let ply = start_ply + index;
+        if ply % 2 == 0 {
+            tokens.push(format!("{}.", ply / 2 + 1));
+        } else if needs_number {
+            tokens.push(format!("{}...", ply / 2 + 1));
+        }
+
+        let with_context = |err: Error| {
+            let message = format!("move {}: {}", ply + 1, err.message());
+            Error::new(err.kind(), message)
+        };
+
+        tokens.push(move_to_san(&board, curr_move).map_err(with_context)?);
+        needs_number = false;
+
+        if let Some(comment) = comments_from.and_then(|game| comment(game, index)) {
+            tokens.push(comment);
+            needs_number = true;
+        }
+
+        for variation in variations.iter().filter(|variation| variation.ply == index) {
+            let mut variation_tokens = vec![];
+            write_line(
+                None,
+                &board,
+                ply,
+                &variation.moves,
+                &variation.variations,
+                &mut variation_tokens,
+            )?;
+
+            if let Some(last) = variation_tokens.last_mut() {
+                last.push(')');
+                variation_tokens[0].insert(0, '(');
+                tokens.extend(variation_tokens);
+                needs_number = true;
+            }
+        }
+
+        board.move_piece(*curr_move).map_err(with_context)?;
+    }
+
+    Ok(())
+}
+
+// The seven tag roster in its required order, then the tags the game has values for
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+fn tags(game: &Game) -> Vec<(String, String)> {
+    let tag = |name: &str| {
+        game.tags
+            .iter()
+            .find(|(tag_name, _)| tag_name == name)
+            .map(|(_, value)| value.clone())
+    };
+    let or_unknown = |value: &str| {
+        if value.is_empty() {
+            "?".to_string()
+        } else {
+            value.to_string()
+        }
+    };
+    let utc_date = format_date(game.year, game.month, game.day);
+
+    let mut tags = vec![
+        ("Event", tag("Event").unwrap_or_else(|| "?".to_string())),
+        ("Site", or_unknown(&game.site)),
+        ("Date", tag("Date").unwrap_or_else(|| utc_date.clone())),
+        ("Round", tag("Round").unwrap_or_else(|| "?".to_string())),
+        ("White", or_unknown(&game.white)),
+        ("Black", or_unknown(&game.black)),
+        ("Result", result_to_pgn(game.result).to_string()),
+    ];
+
+    if game.year != 0 {
+        tags.push(("UTCDate", utc_date));
+    }
+    if game.white_rating != 0 {
+        tags.push(("WhiteElo", game.white_rating.to_string()));
+    }
+    if game.black_rating != 0 {
+        tags.push(("BlackElo", game.black_rating.to_string()));
+    }
+    if game.white_diff != 0 {
+        tags.push(("WhiteRatingDiff", format!("{:+}", game.white_diff)));
+    }
+    if game.black_diff != 0 {
+        tags.push(("BlackRatingDiff", format!("{:+}", game.black_diff)));
+    }
+    if game.eco_category == '\0' {
+        tags.push(("ECO", "?".to_string()));
+    } else if game.eco_category.is_ascii_uppercase() {
+        tags.push((
+            "ECO",
+            format!("{}{:02}", game.eco_category, game.eco_subcategory),
+        ));
+    }
+    if tag("TimeControl").is_none() {
+        if game.time_control == TimeControl::Correspondence {
+            tags.push(("TimeControl", "-".to_string()));
+        } else if game.time_control_main != 0 || game.time_control_increment != 0 {
+            tags.push((
+                "TimeControl",
+                format!("{}+{}", game.time_control_main, game.time_control_increment),
+            ));
+        }
+    }
+    if tag("Termination").is_none() {
+        tags.push((
+            "Termination",
+            termination_to_pgn(game.termination).to_string(),
+        ));
+    }
+
+    let mut tags: Vec<(String, String)> = tags
+        .into_iter()
+        .map(|(name, value)| (name.to_string(), value))
+        .collect();
+    tags.extend(
+        game.tags
+            .iter()
+            .filter(|(name, _)| !ROSTER_TAGS.contains(&name.as_str()))
+            .cloned(),
+    );
+
+    tags
+}
+
+// `[%eval]` and `[%clk]` of the position after the move at `index`, if the game has them
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+fn comment(game: &Game, index: usize) -> Option<String> {
+    let mut commands = vec![];
+
+    if game.eval_available {
+        match (game.eval_mate_in.get(index), game.eval_advantage.get(index)) {
+            (Some(mate_in), _) if *mate_in != 0 => commands.push(format!("[%eval #{mate_in}]")),
+            (_, Some(advantage)) => commands.push(format!("[%eval {advantage}]")),
+            _ => {}
+        }
+    }
+
+    if let Some(clock) = game.clock.get(index) {
+        commands.push(format!("[%clk {}]", format_clock(*clock)));
+    }
+
+    if commands.is_empty() {
+        None
+    } else {
+        Some(format!("{{ {} }}", commands.join(" ")))
+    }
+}
+
+fn format_date(year: u16, month: u8, day: u8) -> String {
+    let part = |value: u16, width: usize| {
+        if value == 0 {
+            "?".repeat(width)
+        } else {
+            format!("{value:0width$}")
+        }
+    };
+
+    format!(
+        "{}.{}.{}",
+        part(year, 4),
+        part(u16::from(month), 2),
+        part(u16::from(day), 2)
+    )
+}
+
+fn format_clock(clock: Duration) -> String {
+    let seconds = clock.as_secs();
+    format!(
+        "{}:{:02}:{:02}",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
+fn result_to_pgn(result: GameResult) -> &'static str {
+    match result {
+        GameResult::White => "1-0",
+        GameResult::Black => "0-1",
+        GameResult::Draw => "1/2-1/2",
+        GameResult::Star => "*",
+    }
+}
+
+fn termination_to_pgn(termination: Termination) -> &'static str {
+    match termination {
+        Termination::Normal => "Normal",
+        Termination::TimeForfeit => "Time forfeit",
+        Termination::Abandoned => "Abandoned",
+        Termination::RulesInfraction => "Rules infraction",
+        Termination::Unterminated => "Unterminated",
+    }
+}
+
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn wrap(tokens: &[String]) -> String {
+    let mut text = String::new();
+    let mut line_length = 0;
+
+    for token in tokens {
+        if line_length > 0 && line_length + 1 + token.len() > MAX_LINE_LENGTH {
+            text.push('\n');
+            line_length = 0;
+        } else if line_length > 0 {
+            text.push(' ');
+            line_length += 1;
+        }
+        text.push_str(token);
+        line_length += token.len();
+    }
+
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_pgn::PgnParser;
+
+    fn parse(headers: &[&str], movetext: &str, keep_variations: bool) -> Game {
+        let headers: Vec<String> = headers.iter().map(|header| header.to_string()).collect();
+        let mut game = Game::default();
+        PgnParser::new()
+            .keep_variations(keep_variations)
+            .parse_game(&headers, movetext, &mut game)
+            .unwrap();
+        game
+    }
+
+    #[test]
+    fn writes_tags_moves_and_comments() {
+        let game = parse(
+            &[
+                r#"[Event "Rated Blitz game"]"#,
+                r#"[Site "https://lichess.org/abc"]"#,
+                r#"[White "Abbot"]"#,
+                r#"[Black "Costello"]"#,
+                r#"[Result "1-0"]"#,
+                r#"[UTCDate "2017.04.01"]"#,
+                r#"[WhiteElo "2100"]"#,
+                r#"[TimeControl "180+0"]"#,
+                r#"[Termination "Normal"]"#,
+            ],
+            "1. e4 { [%eval 0.17] [%clk 0:03:00] } 1... e5 { [%eval 0.2] [%clk 0:02:59] } \
+             2. Nf3 Nc6 3. Bb5 a6 4. Bxc6 dxc6 5. O-O 1-0",
+            false,
+        );
+
+        assert_eq!(
+            PgnWriter::new(true).write_game(&game),
+            Ok("[Event \"Rated Blitz game\"]\n\
+                [Site \"https://lichess.org/abc\"]\n\
+                [Date \"2017.04.01\"]\n\
+                [Round \"?\"]\n\
+                [White \"Abbot\"]\n\
+                [Black \"Costello\"]\n\
+                [Result \"1-0\"]\n\
+                [UTCDate \"2017.04.01\"]\n\
+                [WhiteElo \"2100\"]\n\
+                [TimeControl \"180+0\"]\n\
+                [Termination \"Normal\"]\n\
+                \n\
+                1. e4 { [%eval 0.17] [%clk 0:03:00] } 1... e5 { [%eval 0.2] [%clk 0:02:59] } 2.\n\
+                Nf3 Nc6 3. Bb5 a6 4. Bxc6 dxc6 5. O-O 1-0\n\n"
+                .to_string())
+        );
+
+        let pgn = PgnWriter::new(false).write_game(&game).unwrap();
+        assert!(pgn.ends_with("\n\n1. e4 e5 2. Nf3 Nc6 3. Bb5 a6 4. Bxc6 dxc6 5. O-O 1-0\n\n"));
+    }
+
+    #[test]
+    fn writes_variations() {
+        let game = parse(
+            &[r#"[Result "*"]"#],
+            "1. e4 e5 (1... c5 2. Nf3) 2. Nf3 *",
+            true,
+        );
+        let pgn = PgnWriter::new(true).write_game(&game).unwrap();
+        assert!(pgn.ends_with("\n\n1. e4 e5 (1... c5 2. Nf3) 2. Nf3 *\n\n"));
+    }
+
+    #[test]
+    fn numbers_moves_from_the_fen_tag() {
+        let game = parse(
+            &[
+                r#"[Result "*"]"#,
+                r#"[SetUp "1"]"#,
+                r#"[FEN "4k3/8/8/8/8/8/4P3/4K3 b - - 0 40"]"#,
+            ],
+            "40... Kd7 41. e4 *",
+            false,
+        );
+        let pgn = PgnWriter::new(true).write_game(&game).unwrap();
+        assert!(pgn.contains("[FEN \"4k3/8/8/8/8/8/4P3/4K3 b - - 0 40\"]\n"));
+        assert!(pgn.ends_with("\n\n40... Kd7 41. e4 *\n\n"));
+    }
+}
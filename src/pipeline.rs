@@ -0,0 +1,324 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::mpsc::{sync_channel, Receiver, SyncSender};
+use std::thread;
+
+use crate::error::Error;
+use crate::scheduler::Scheduler;
+use crate::workflow_step::{completion_flag, BoxedStep, ProcessStatus, SharedData, StepData};
+
+#[derive(Debug)]
+enum Message {
+    // An entry produced upstream; vectors are appended, anything else replaces
+    Data(String, SharedData),
+    // A flag raised upstream, applied once every upstream stage has finished
+    Flag(String, bool),
+}
+
+// Runs every step on its own thread. Steps are connected by bounded channels carrying
+// the entries they declare as inputs and outputs, so a full channel stalls its producer.
+#[derive(Debug)]
+pub struct Pipeline {
+    steps: Vec<(String, BoxedStep)>,
+    channel_capacity: usize,
+}
+
+#[derive(Debug)]
+struct Stage {
+    name: String,
+    step: BoxedStep,
+    data: HashMap<String, SharedData>,
+    receiver: Option<Receiver<Message>>,
+    routes: HashMap<String, Vec<SyncSender<Message>>>,
+}
+
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+impl Pipeline {
+    pub fn new(scheduler: Scheduler, channel_capacity: usize) -> Self {
+        Pipeline {
+            steps: scheduler.into_steps(),
+            channel_capacity,
+        }
+    }
+
+    pub fn run(self, data: &mut HashMap<String, SharedData>) -> Result<(), Error> {
+        let produced: HashSet<String> = self
+            .steps
+            .iter()
+            .flat_map(|(_, step)| step.outputs())
+            .collect();
+
+        let mut stages = vec![];
+        let mut consumer_senders = vec![];
+        for (name, step) in self.steps {
+            let mut stage_data = HashMap::new();
+            let mut sender = None;
+            let mut receiver = None;
+
+            for input in step.inputs() {
+                if produced.contains(&input) {
+                    if sender.is_none() {
+                        let (tx, rx) = sync_channel(self.channel_capacity);
+                        sender = Some(tx);
+                        receiver = Some(rx);
+                    }
+                } else if let Some(value) = data.get(&input) {
+                    stage_data.insert(input, value.clone());
+                }
+            }
+
+            consumer_senders.push(sender);
+            stages.push(Stage {
+                name,
+                step,
+                data: stage_data,
+                receiver,
+                routes: HashMap::new(),
+            });
+        }
+
+        let consumed: Vec<Vec<String>> = stages.iter().map(|stage| stage.step.inputs()).collect();
+        for stage in &mut stages {
+            for output in stage.step.outputs() {
+                let senders: Vec<SyncSender<Message>> = consumed
+                    .iter()
+                    .zip(&consumer_senders)
+                    .filter(|(inputs, _)| inputs.contains(&output))
+                    .filter_map(|(_, sender)| sender.clone())
+                    .collect();
+
+                if !senders.is_empty() {
+                    stage.routes.insert(output, senders);
+                }
+            }
+        }
+
+        // Only producers may hold senders, otherwise no channel would ever close
+        drop(consumer_senders);
+
+        let results: Vec<Result<HashMap<String, SharedData>, Error>> = thread::scope(|scope| {
+            let handles: Vec<_> = stages
+                .into_iter()
+                .map(|stage| {
+                    let name = stage.name.clone();
+                    (name, scope.spawn(move || stage.run()))
+                })
+                .collect();
+
+            handles
+                .into_iter()
+                .map(|(name, handle)| {
+                    handle.join().unwrap_or_else(|_| {
+                        Err(Error::config("PipelineStep: step panicked").in_step(&name))
+                    })
+                })
+                .collect()
+        });
+
+        for result in results {
+            for (name, value) in result? {
+                merge_entry(data, name, value);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+impl Stage {
+    // Returns the outputs no other stage consumes, along with every flag raised on the way
+    fn run(mut self) -> Result<HashMap<String, SharedData>, Error> {
+        let inputs = self.step.inputs();
+        let mut upstream_flags = vec![];
+
+        if let Some(receiver) = self.receiver.take() {
+            // Wait for upstream, then take whatever else is already queued before processing.
+            // A step that leaves input behind is called again once more arrives, or after
+            // upstream is done, rather than spun on.
+            while let Ok(message) = receiver.recv() {
+                for message in std::iter::once(message).chain(receiver.try_iter()) {
+                    match message {
+                        Message::Data(name, value) => merge_entry(&mut self.data, name, value),
+                        Message::Flag(name, flag) => upstream_flags.push((name, flag)),
+                    }
+                }
+
+                if has_pending_input(&self.data, &inputs) {
+                    self.process()?;
+                    if !self.forward(false) {
+                        return Ok(HashMap::new());
+                    }
+                }
+            }
+        }
+
+        for (name, flag) in upstream_flags {
+            self.data.insert(name, SharedData::Bool(flag));
+        }
+        for input in &inputs {
+            self.data
+                .insert(completion_flag(input), SharedData::Bool(true));
+        }
+
+        loop {
+            let status = self.process()?;
+            if !self.forward(false) {
+                return Ok(HashMap::new());
+            }
+            if status == ProcessStatus::Complete {
+                break;
+            }
+        }
+
+        for output in self.step.outputs() {
+            self.data
+                .insert(completion_flag(&output), SharedData::Bool(true));
+        }
+
+        if !self.forward(true) {
+            return Ok(HashMap::new());
+        }
+
+        let mut remaining: HashMap<String, SharedData> = self
+            .data
+            .iter()
+            .filter(|(_, value)| matches!(value, SharedData::Bool(_)))
+            .map(|(name, value)| (name.clone(), value.clone()))
+            .collect();
+        for output in self.step.outputs() {
+            if !self.routes.contains_key(&output) {
+                if let Some(value) = self.data.remove(&output) {
+                    remaining.insert(output, value);
+                }
+            }
+        }
+
+        Ok(remaining)
+    }
+
+    fn process(&mut self) -> Result<ProcessStatus, Error> {
+        self.step
+            .process(&mut self.data)
+            .map_err(|err| err.in_step(&self.name))
+    }
+
+    // Sends everything produced so far downstream. Vectors are streamed as they fill up,
+    // other entries once the step has finished. Returns false once no consumer is left.
+    fn forward(&mut self, finished: bool) -> bool {
+        for (output, senders) in &self.routes {
+            let is_vec = matches!(self.data.get(output), Some(SharedData::Vec(_)));
+
+            let value = if is_vec {
+                match self.data.clear_vec(output) {
+                    Some(values) if !values.is_empty() => SharedData::Vec(values),
+                    _ => continue,
+                }
+            } else if finished {
+                match self.data.remove(output) {
+                    Some(value) => value,
+                    None => continue,
+                }
+            } else {
+                continue;
+            };
+
+            for sender in senders {
+                if sender
+                    .send(Message::Data(output.clone(), value.clone()))
+                    .is_err()
+                {
+                    return false;
+                }
+            }
+        }
+
+        if finished {
+            let flags: Vec<(String, bool)> = self
+                .data
+                .iter()
+                .filter_map(|(name, value)| value.to_bool().map(|flag| (name.clone(), flag)))
+                .collect();
+
+            for sender in self.routes.values().flatten() {
+                for (name, flag) in &flags {
+                    if sender.send(Message::Flag(name.clone(), *flag)).is_err() {
+                        return false;
+                    }
+                }
+            }
+        }
+
+        true
+    }
+}
+
+fn has_pending_input(data: &HashMap<String, SharedData>, inputs: &[String]) -> bool {
+    inputs
+        .iter()
+        .any(|input| matches!(data.get(input), Some(SharedData::Vec(values)) if !values.is_empty()))
+}
+
+fn merge_entry(data: &mut HashMap<String, SharedData>, name: String, value: SharedData) {
+    if let SharedData::Vec(mut values) = value {
+        if let Some(SharedData::Vec(existing)) = data.get_mut(&name) {
+            existing.append(&mut values);
+            return;
+        }
+        data.insert(name, SharedData::Vec(values));
+    } else {
+        data.insert(name, value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merge_entry_appends_vectors() {
+        let mut data = HashMap::from([(
+            "games".to_string(),
+            SharedData::Vec(vec![SharedData::U64(1)]),
+        )]);
+
+        merge_entry(
+            &mut data,
+            "games".to_string(),
+            SharedData::Vec(vec![SharedData::U64(2)]),
+        );
+
+        assert_eq!(
+            data.get("games"),
+            Some(&SharedData::Vec(vec![
+                SharedData::U64(1),
+                SharedData::U64(2)
+            ]))
+        );
+    }
+
+    #[test]
+    fn merge_entry_replaces_other_values() {
+        let mut data = HashMap::from([("count".to_string(), SharedData::U64(1))]);
+
+        merge_entry(&mut data, "count".to_string(), SharedData::U64(2));
+
+        assert_eq!(data.get("count"), Some(&SharedData::U64(2)));
+    }
+
+    #[test]
+    fn pending_input_only_counts_non_empty_vectors() {
+        let inputs = vec!["games".to_string(), "totals".to_string()];
+        let mut data = HashMap::from([
+            ("games".to_string(), SharedData::Vec(vec![])),
+            ("totals".to_string(), SharedData::Map(HashMap::new())),
+        ]);
+
+        assert!(!has_pending_input(&data, &inputs));
+
+        data.insert(
+            "games".to_string(),
+            SharedData::Vec(vec![SharedData::U64(1)]),
+        );
+        assert!(has_pending_input(&data, &inputs));
+    }
+}
@@ -0,0 +1,142 @@
+use crate::{
+    basic_types::{Cell, Piece, Player, PlayerPiece},
+    bitboard::{self, Bitboard},
+};
+use serde::{Deserialize, Serialize};
+
+// Placement of the pieces as one bitboard per piece type and one per player
+#[derive(PartialEq, Clone, Debug, Copy, Eq, Default, Serialize, Deserialize)]
+pub struct Position {
+    pieces: [Bitboard; 6],
+    players: [Bitboard; 2],
+}
+
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+impl Position {
+    pub fn piece_at(&self, cell: Cell) -> Option<PlayerPiece> {
+        let player = if self.players[0].contains(cell) {
+            Player::White
+        } else if self.players[1].contains(cell) {
+            Player::Black
+        } else {
+            return None;
+        };
+
+        Piece::all_pieces()
+            .into_iter()
+            .find(|piece| self.pieces[*piece as usize - 1].contains(cell))
+            .map(|piece| PlayerPiece { piece, player })
+    }
+
+    pub fn set(&mut self, cell: Cell, piece: PlayerPiece) {
+        self.clear(cell);
+        self.pieces[piece.piece as usize - 1] |= Bitboard::from_cell(cell);
+        match piece.player {
+            Player::White => self.players[0] |= Bitboard::from_cell(cell),
+            Player::Black => self.players[1] |= Bitboard::from_cell(cell),
+            Player::NA => {}
+        }
+    }
+
+    pub fn clear(&mut self, cell: Cell) {
+        let mask = !Bitboard::from_cell(cell);
+        for bitboard in self.pieces.iter_mut().chain(self.players.iter_mut()) {
+            *bitboard &= mask;
+        }
+    }
+
+    pub fn occupied(&self) -> Bitboard {
+        self.players[0] | self.players[1]
+    }
+
+    pub fn player_pieces(&self, player: Player) -> Bitboard {
+        match player {
+            Player::White => self.players[0],
+            Player::Black => self.players[1],
+            Player::NA => Bitboard::EMPTY,
+        }
+    }
+
+    pub fn pieces(&self, player: Player, piece: Piece) -> Bitboard {
+        self.pieces[piece as usize - 1] & self.player_pieces(player)
+    }
+
+    // Pieces of `player` attacking the cell
+    pub fn attackers(&self, cell: Cell, player: Player) -> Bitboard {
+        if player == Player::NA {
+            return Bitboard::EMPTY;
+        }
+
+        let occupied = self.occupied();
+        let opponent = player.get_opposing_player();
+        let diagonal = self.pieces(player, Piece::Bishop) | self.pieces(player, Piece::Queen);
+        let straight = self.pieces(player, Piece::Rook) | self.pieces(player, Piece::Queen);
+
+        (bitboard::pawn_attacks(opponent, cell) & self.pieces(player, Piece::Pawn))
+            | (bitboard::attacks(Piece::Knight, player, cell, occupied)
+                & self.pieces(player, Piece::Knight))
+            | (bitboard::attacks(Piece::Bishop, player, cell, occupied) & diagonal)
+            | (bitboard::attacks(Piece::Rook, player, cell, occupied) & straight)
+            | (bitboard::attacks(Piece::King, player, cell, occupied)
+                & self.pieces(player, Piece::King))
+    }
+
+    pub fn is_attacked(&self, cell: Cell, player: Player) -> bool {
+        !self.attackers(cell, player).is_empty()
+    }
+}
+
+impl FromIterator<(Cell, PlayerPiece)> for Position {
+    fn from_iter<I: IntoIterator<Item = (Cell, PlayerPiece)>>(iter: I) -> Self {
+        let mut position = Position::default();
+        for (cell, piece) in iter {
+            position.set(cell, piece);
+        }
+        position
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::basic_types::{File, Rank};
+
+    #[test]
+    fn sets_and_clears_pieces() {
+        let e4 = cell!(File::_E, Rank::_4);
+        let mut position = Position::default();
+        position.set(e4, white!(Piece::Knight));
+        assert_eq!(position.piece_at(e4), Some(white!(Piece::Knight)));
+
+        position.set(e4, black!(Piece::Queen));
+        assert_eq!(position.piece_at(e4), Some(black!(Piece::Queen)));
+        assert!(position.pieces(Player::White, Piece::Knight).is_empty());
+
+        position.clear(e4);
+        assert_eq!(position.piece_at(e4), None);
+        assert_eq!(position, Position::default());
+    }
+
+    #[test]
+    fn finds_attackers() {
+        let position: Position = [
+            (cell!(File::_E, Rank::_1), white!(Piece::King)),
+            (cell!(File::_E, Rank::_8), black!(Piece::Rook)),
+            (cell!(File::_B, Rank::_4), black!(Piece::Bishop)),
+            (cell!(File::_D, Rank::_2), white!(Piece::Pawn)),
+            (cell!(File::_F, Rank::_2), black!(Piece::Pawn)),
+        ]
+        .into_iter()
+        .collect();
+
+        let e1 = cell!(File::_E, Rank::_1);
+        assert_eq!(
+            position
+                .attackers(e1, Player::Black)
+                .cells()
+                .collect::<Vec<_>>(),
+            vec![cell!(File::_F, Rank::_2), cell!(File::_E, Rank::_8)]
+        );
+        assert!(!position.is_attacked(cell!(File::_D, Rank::_1), Player::Black));
+    }
+}
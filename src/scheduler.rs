@@ -0,0 +1,223 @@
+use std::collections::{HashMap, VecDeque};
+
+use crate::error::Error;
+use crate::steps_manager::get_step;
+use crate::workflow_step::{completion_flag, BoxedStep, ProcessStatus, SharedData};
+
+// Orders steps by the data they declare as inputs and outputs, then drives each
+// one to completion, raising the completion flag of every output once its last
+// producer is done.
+#[derive(Debug)]
+pub struct Scheduler {
+    ordered_steps: Vec<(String, BoxedStep)>,
+    pending_producers: HashMap<String, usize>,
+}
+
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+impl Scheduler {
+    pub fn try_new(
+        step_names: &[String],
+        data: &mut HashMap<String, SharedData>,
+    ) -> Result<Self, Error> {
+        let mut steps = vec![];
+        for step_name in step_names {
+            let step = get_step(step_name, data)?;
+            steps.push((step_name.clone(), step));
+        }
+
+        Self::from_steps(steps, data)
+    }
+
+    pub fn from_steps(
+        steps: Vec<(String, BoxedStep)>,
+        data: &HashMap<String, SharedData>,
+    ) -> Result<Self, Error> {
+        let mut producers: HashMap<String, Vec<usize>> = HashMap::new();
+        for (index, (_, step)) in steps.iter().enumerate() {
+            for output in step.outputs() {
+                producers.entry(output).or_default().push(index);
+            }
+        }
+
+        // An edge runs from each producer to every step that consumes one of its outputs
+        let mut dependents: Vec<Vec<usize>> = vec![vec![]; steps.len()];
+        let mut in_degree = vec![0_usize; steps.len()];
+        for (index, (step_name, step)) in steps.iter().enumerate() {
+            for input in step.inputs() {
+                match producers.get(&input) {
+                    Some(producing_steps) => {
+                        for producer in producing_steps {
+                            dependents[*producer].push(index);
+                            in_degree[index] += 1;
+                        }
+                    }
+                    // Data provided before the scheduler started (e.g. by a parent step)
+                    None if data.contains_key(&input) => {}
+                    None => {
+                        return Err(Error::config(format!(
+                            "Scheduler: no step produces '{input}' (consumed by '{step_name}')"
+                        )))
+                    }
+                }
+            }
+        }
+
+        // Kahn's algorithm, seeded in declaration order so independent steps keep their order
+        let mut ready: VecDeque<usize> = (0..steps.len())
+            .filter(|index| in_degree[*index] == 0)
+            .collect();
+        let mut order = vec![];
+        while let Some(index) = ready.pop_front() {
+            order.push(index);
+            for dependent in &dependents[index] {
+                in_degree[*dependent] -= 1;
+                if in_degree[*dependent] == 0 {
+                    ready.push_back(*dependent);
+                }
+            }
+        }
+
+        if order.len() != steps.len() {
+            let cyclic_steps: Vec<&String> = steps
+                .iter()
+                .enumerate()
+                .filter(|(index, _)| in_degree[*index] > 0)
+                .map(|(_, (step_name, _))| step_name)
+                .collect();
+            return Err(Error::config(format!(
+                "Scheduler: dependency cycle involving steps {cyclic_steps:?}"
+            )));
+        }
+
+        let mut steps: Vec<Option<(String, BoxedStep)>> = steps.into_iter().map(Some).collect();
+        let ordered_steps = order
+            .into_iter()
+            .map(|index| steps[index].take().unwrap())
+            .collect();
+
+        Ok(Scheduler {
+            ordered_steps,
+            pending_producers: producers
+                .into_iter()
+                .map(|(name, producing_steps)| (name, producing_steps.len()))
+                .collect(),
+        })
+    }
+
+    pub fn into_steps(self) -> Vec<(String, BoxedStep)> {
+        self.ordered_steps
+    }
+
+    pub fn run(&mut self, data: &mut HashMap<String, SharedData>) -> Result<(), Error> {
+        for (step_name, step) in &mut self.ordered_steps {
+            while step.process(data).map_err(|err| err.in_step(step_name))?
+                == ProcessStatus::Incomplete
+            {}
+
+            for output in step.outputs() {
+                let pending = self.pending_producers.entry(output.clone()).or_insert(1);
+                *pending -= 1;
+                if *pending == 0 {
+                    data.insert(completion_flag(&output), SharedData::Bool(true));
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::workflow_step::MockStep;
+
+    fn mock_step(inputs: &[&str], outputs: &[&str]) -> BoxedStep {
+        let inputs: Vec<String> = inputs.iter().map(ToString::to_string).collect();
+        let outputs: Vec<String> = outputs.iter().map(ToString::to_string).collect();
+
+        let mut step = MockStep::new();
+        step.expect_inputs().return_const(inputs);
+        step.expect_outputs().return_const(outputs);
+        step.expect_process()
+            .returning(|_| Ok(ProcessStatus::Complete));
+        Box::new(step)
+    }
+
+    fn step_names(scheduler: &Scheduler) -> Vec<&str> {
+        scheduler
+            .ordered_steps
+            .iter()
+            .map(|(step_name, _)| step_name.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn orders_consumers_after_producers() {
+        let steps = vec![
+            ("reduce".to_string(), mock_step(&["mapped"], &["result"])),
+            ("map".to_string(), mock_step(&["parsed"], &["mapped"])),
+            ("parse".to_string(), mock_step(&[], &["parsed"])),
+        ];
+
+        let scheduler = Scheduler::from_steps(steps, &HashMap::new()).unwrap();
+        assert_eq!(step_names(&scheduler), vec!["parse", "map", "reduce"]);
+    }
+
+    #[test]
+    fn keeps_declaration_order_for_independent_steps() {
+        let steps = vec![
+            ("b".to_string(), mock_step(&[], &["x"])),
+            ("a".to_string(), mock_step(&[], &["y"])),
+        ];
+
+        let scheduler = Scheduler::from_steps(steps, &HashMap::new()).unwrap();
+        assert_eq!(step_names(&scheduler), vec!["b", "a"]);
+    }
+
+    #[test]
+    fn detects_cycles() {
+        let steps = vec![
+            ("source".to_string(), mock_step(&[], &["a"])),
+            ("first".to_string(), mock_step(&["a", "c"], &["b"])),
+            ("second".to_string(), mock_step(&["b"], &["c"])),
+        ];
+
+        assert_eq!(
+            Scheduler::from_steps(steps, &HashMap::new()).unwrap_err(),
+            Error::config("Scheduler: dependency cycle involving steps [\"first\", \"second\"]")
+        );
+    }
+
+    #[test]
+    fn detects_dangling_inputs() {
+        let steps = vec![("map".to_string(), mock_step(&["parsed"], &["mapped"]))];
+
+        assert_eq!(
+            Scheduler::from_steps(steps, &HashMap::new()).unwrap_err(),
+            Error::config("Scheduler: no step produces 'parsed' (consumed by 'map')")
+        );
+    }
+
+    #[test]
+    fn accepts_inputs_already_in_data() {
+        let steps = vec![("map".to_string(), mock_step(&["parsed"], &["mapped"]))];
+        let data = HashMap::from([("parsed".to_string(), SharedData::Vec(vec![]))]);
+
+        assert!(Scheduler::from_steps(steps, &data).is_ok());
+    }
+
+    #[test]
+    fn run_sets_completion_flags() {
+        let steps = vec![
+            ("parse".to_string(), mock_step(&[], &["parsed"])),
+            ("map".to_string(), mock_step(&["parsed"], &["mapped"])),
+        ];
+
+        let mut data = HashMap::new();
+        let mut scheduler = Scheduler::from_steps(steps, &data).unwrap();
+        assert_eq!(scheduler.run(&mut data), Ok(()));
+        assert_eq!(data.get("parsed_complete"), Some(&SharedData::Bool(true)));
+        assert_eq!(data.get("mapped_complete"), Some(&SharedData::Bool(true)));
+    }
+}
@@ -1,23 +1,266 @@
+use std::fmt;
+
+use serde::de::DeserializeOwned;
+use serde_yaml::{Mapping, Value};
+
+use crate::error::Error;
+
+// One parameter of a step, as reported by `describe`
+#[derive(Debug, PartialEq, Eq)]
+pub struct ParamSchema {
+    pub name: &'static str,
+    pub type_name: String,
+    pub required: bool,
+    pub default: Option<&'static str>,
+    pub doc: &'static str,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct StepSchema {
+    pub step_type: &'static str,
+    pub doc: &'static str,
+    pub params: Vec<ParamSchema>,
+}
+
+// Implemented by every parameter struct declared with `step_params!`
+pub trait StepParams: DeserializeOwned {
+    fn doc() -> &'static str;
+    fn schema() -> Vec<ParamSchema>;
+}
+
+// Maps the Rust type of a parameter to the name shown to workflow authors
+pub trait ParamType {
+    const REQUIRED: bool = true;
+
+    fn type_name() -> String;
+}
+
+impl ParamType for String {
+    fn type_name() -> String {
+        "string".to_string()
+    }
+}
+
+impl ParamType for bool {
+    fn type_name() -> String {
+        "bool".to_string()
+    }
+}
+
+impl ParamType for u32 {
+    fn type_name() -> String {
+        "integer".to_string()
+    }
+}
+
+impl ParamType for u64 {
+    fn type_name() -> String {
+        "integer".to_string()
+    }
+}
+
+impl ParamType for usize {
+    fn type_name() -> String {
+        "integer".to_string()
+    }
+}
+
+impl<T: ParamType> ParamType for Vec<T> {
+    fn type_name() -> String {
+        format!("list of {}", T::type_name())
+    }
+}
+
+impl<T: ParamType> ParamType for Option<T> {
+    const REQUIRED: bool = false;
+
+    fn type_name() -> String {
+        T::type_name()
+    }
+}
+
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+impl StepSchema {
+    pub fn of<P: StepParams>(step_type: &'static str) -> Self {
+        StepSchema {
+            step_type,
+            doc: P::doc(),
+            params: P::schema(),
+        }
+    }
+}
+
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+impl fmt::Display for StepSchema {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{}", self.step_type)?;
+        for line in self.doc.lines() {
+            writeln!(f, "    {}", line.trim())?;
+        }
+
+        if self.params.is_empty() {
+            return writeln!(f, "\n    No parameters");
+        }
+
+        writeln!(f, "\nParameters:")?;
+        for param in &self.params {
+            let requirement = match (param.required, param.default) {
+                (true, _) => "required".to_string(),
+                (false, Some(default)) => format!("default: {default}"),
+                (false, None) => "optional".to_string(),
+            };
+            writeln!(f, "    {} ({}, {requirement})", param.name, param.type_name)?;
+            for line in param.doc.lines() {
+                writeln!(f, "        {}", line.trim())?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+// Deserializes the `params` of a step, rejecting unknown keys, wrong types and missing
+// required parameters. Errors are prefixed with the step type.
 #[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
-pub fn get_required_parameter(
-    step_name: &str,
-    param: &str,
-    params: &serde_yaml::Value,
-) -> Result<String, String> {
-    match params.get(param) {
-        Some(v) => Ok(v.as_str().unwrap().to_string()),
-        None => Err(format!("{step_name}: parameter '{param}' is required")),
+pub fn parse_params<P: StepParams>(
+    step_type: &str,
+    configuration: Option<Value>,
+) -> Result<P, Error> {
+    let mut params = match configuration {
+        None | Some(Value::Null) => Value::Mapping(Mapping::new()),
+        Some(params) => params,
+    };
+
+    if let Value::Mapping(params) = &mut params {
+        apply_defaults(params, &P::schema())
+            .map_err(|err| Error::config(format!("{step_type}: {err}")))?;
     }
+
+    serde_yaml::from_value(params).map_err(|err| Error::config(format!("{step_type}: {err}")))
 }
 
+// Fills in the declared default of every parameter that was left out. Defaults of string
+// parameters are taken verbatim after substituting `<name>` with the value of parameter
+// `name`; any other default is read as YAML.
 #[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
-pub fn get_parameter_with_default(
-    param: &str,
-    default: &str,
-    params: &serde_yaml::Value,
-) -> String {
-    match params.get(param) {
-        Some(v) => v.as_str().unwrap().to_string(),
-        None => default.to_string(),
+fn apply_defaults(params: &mut Mapping, schema: &[ParamSchema]) -> Result<(), String> {
+    for param in schema {
+        let default = match param.default {
+            Some(default) if !params.contains_key(param.name) => default,
+            _ => continue,
+        };
+
+        let value = if param.type_name == String::type_name() {
+            let mut default = default.to_string();
+            for other in schema {
+                if let Some(Value::String(other_value)) = params.get(other.name) {
+                    default = default.replace(&format!("<{}>", other.name), other_value);
+                }
+            }
+            Value::String(default)
+        } else {
+            serde_yaml::from_str(default)
+                .map_err(|err| format!("invalid default for '{}': {err}", param.name))?
+        };
+
+        params.insert(Value::String(param.name.to_string()), value);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    step_params! {
+        /// Parameters used to test the macro
+        pub struct TestParams {
+            /// A required name
+            name: String,
+            /// How many of them
+            count: u64 = "3",
+            /// Names to skip
+            skip: Option<Vec<String>>,
+            /// Label derived from the name
+            label: String = "<name>_label",
+        }
+    }
+
+    #[test]
+    fn parses_params() {
+        let params: TestParams = parse_params(
+            "Test",
+            Some(serde_yaml::from_str("name: a\ncount: 2").unwrap()),
+        )
+        .unwrap();
+
+        assert_eq!(
+            params,
+            TestParams {
+                name: "a".to_string(),
+                count: 2,
+                skip: None,
+                label: "a_label".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn applies_defaults() {
+        let params: TestParams = parse_params(
+            "Test",
+            Some(serde_yaml::from_str("name: b\nlabel: mine").unwrap()),
+        )
+        .unwrap();
+
+        assert_eq!(params.count, 3);
+        assert_eq!(params.label, "mine");
+    }
+
+    #[test]
+    fn missing_required_param() {
+        assert_eq!(
+            parse_params::<TestParams>("Test", None).unwrap_err(),
+            Error::config("Test: missing field `name`")
+        );
+    }
+
+    #[test]
+    fn unknown_param() {
+        let err = parse_params::<TestParams>(
+            "Test",
+            Some(serde_yaml::from_str("name: a\ncounts: 2").unwrap()),
+        )
+        .unwrap_err()
+        .to_string();
+
+        assert!(err.starts_with("Test: "), "{err}");
+        assert!(err.contains("unknown field `counts`"), "{err}");
+    }
+
+    #[test]
+    fn wrong_type() {
+        let err = parse_params::<TestParams>(
+            "Test",
+            Some(serde_yaml::from_str("name: a\ncount: many").unwrap()),
+        )
+        .unwrap_err()
+        .to_string();
+
+        assert!(err.starts_with("Test: "), "{err}");
+        assert!(err.contains("invalid type: string \"many\""), "{err}");
+    }
+
+    #[test]
+    fn describes_params() {
+        assert_eq!(
+            format!("{}", StepSchema::of::<TestParams>("Test")),
+            "Test\n    Parameters used to test the macro\n\nParameters:\n    \
+             name (string, required)\n        A required name\n    \
+             count (integer, default: 3)\n        How many of them\n    \
+             skip (list of string, optional)\n        Names to skip\n    \
+             label (string, default: <name>_label)\n        Label derived from the name\n"
+        );
     }
 }
@@ -0,0 +1,179 @@
+use std::collections::HashMap;
+
+use crate::eco;
+use crate::error::Error;
+use crate::game::Game;
+use crate::step_param_utils::{parse_params, ParamType};
+use crate::workflow_step::{ProcessStatus, SharedData, Step};
+
+// How finely games are told apart by their opening
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EcoLevel {
+    // Volume letter, like "B"
+    Category,
+    // Full ECO code, like "B90"
+    #[default]
+    Code,
+    // Opening name without its variation, like "Sicilian Defense"
+    Family,
+}
+
+impl ParamType for EcoLevel {
+    fn type_name() -> String {
+        "category | code | family".to_string()
+    }
+}
+
+step_params! {
+    /// Adds the ECO category, code or opening family of each game to its bin labels, taken
+    /// from its headers or else the bundled ECO table, "Unknown" when neither knows it
+    pub struct EcoBinParams {
+        /// Vector of binned games to read
+        input: String,
+        /// Vector the binned games are written to
+        output: String,
+        /// Label games by ECO category, full ECO code or opening family
+        by: EcoLevel = "code",
+        /// Flag raised once the input is complete
+        input_flag: String = "<input>_complete",
+        /// Flag raised once this step is done
+        output_flag: String = "<output>_complete",
+    }
+}
+
+#[derive(Debug)]
+pub struct EcoBin {
+    input_vec_name: String,
+    output_vec_name: String,
+    by: EcoLevel,
+    input_flag: String,
+    output_flag: String,
+}
+
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+impl EcoBin {
+    pub fn try_new(configuration: Option<serde_yaml::Value>) -> Result<Box<dyn Step>, Error> {
+        let params: EcoBinParams = parse_params("EcoBin", configuration)?;
+
+        Ok(Box::new(EcoBin {
+            input_vec_name: params.input,
+            output_vec_name: params.output,
+            by: params.by,
+            input_flag: params.input_flag,
+            output_flag: params.output_flag,
+        }))
+    }
+
+    pub fn bin(game: &Game, bin: &EcoBin) -> SharedData {
+        let header_eco = game
+            .eco_category
+            .is_ascii_uppercase()
+            .then_some((game.eco_category, game.eco_subcategory));
+        let header_name = game
+            .tags
+            .iter()
+            .find(|(name, _)| name == "Opening")
+            .map(|(_, value)| value.split(':').next().unwrap_or(value).to_string());
+
+        let label = match bin.by {
+            EcoLevel::Category => header_eco
+                .map(|(category, _)| category)
+                .or_else(|| eco::classify(game).map(|entry| entry.category))
+                .map(String::from),
+            EcoLevel::Code => header_eco
+                .map(|(category, subcategory)| format!("{category}{subcategory:02}"))
+                .or_else(|| eco::classify(game).map(|entry| entry.code())),
+            EcoLevel::Family => {
+                header_name.or_else(|| eco::classify(game).map(|entry| entry.family().to_string()))
+            }
+        };
+        SharedData::String(label.unwrap_or_else(|| "Unknown".to_string()))
+    }
+}
+
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+impl Step for EcoBin {
+    bin_template!(EcoBin::bin);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_pgn::PgnParser;
+
+    fn bin_label(by: &str, headers: &[&str], movetext: &str) -> SharedData {
+        let headers = headers.iter().map(|header| header.to_string()).collect();
+        let mut game = Game::default();
+        PgnParser::new()
+            .parse_game(&headers, movetext, &mut game)
+            .unwrap();
+
+        let configuration =
+            serde_yaml::from_str(&format!("{{input: games, output: binned_games, by: {by}}}"))
+                .unwrap();
+        let mut step = EcoBin::try_new(Some(configuration)).unwrap();
+
+        let mut data = HashMap::new();
+        data.insert(
+            "games".to_string(),
+            SharedData::Vec(vec![SharedData::BinnedValue((
+                Box::new(SharedData::Game(game)),
+                vec![],
+            ))]),
+        );
+        data.insert("games_complete".to_string(), SharedData::Bool(true));
+        assert_eq!(step.process(&mut data), Ok(ProcessStatus::Complete));
+
+        match data
+            .remove("binned_games")
+            .unwrap()
+            .into_vec()
+            .unwrap()
+            .pop()
+        {
+            Some(SharedData::BinnedValue((_, mut labels))) => labels.pop().unwrap(),
+            other => panic!("Unexpected binned game: {other:?}"),
+        }
+    }
+
+    macro_rules! tests {
+        ($($name:ident: $value:expr,)*) => {
+        $(
+            #[test]
+            fn $name() {
+                let (by, headers, movetext, expected): (&str, &[&str], &str, &str) = $value;
+                assert_eq!(
+                    bin_label(by, headers, movetext),
+                    SharedData::String(expected.to_string())
+                );
+            }
+        )*
+        }
+    }
+
+    const NAJDORF: &str = "1. e4 c5 2. Nf3 d6 3. d4 cxd4 4. Nxd4 Nf6 5. Nc3 a6 *";
+
+    tests! {
+        test_category_from_table: ("category", &[], NAJDORF, "B"),
+        test_code_from_table: ("code", &[], NAJDORF, "B90"),
+        test_family_from_table: ("family", &[], NAJDORF, "Sicilian Defense"),
+        test_code_from_header: ("code", &[r#"[ECO "B07"]"#], NAJDORF, "B07"),
+        test_category_from_header: ("category", &[r#"[ECO "A00"]"#], NAJDORF, "A"),
+        test_family_from_header: (
+            "family",
+            &[r#"[Opening "Sicilian Defense: Open"]"#],
+            "1. a3 *",
+            "Sicilian Defense",
+        ),
+        test_unknown_eco_header: ("code", &[r#"[ECO "?"]"#], "1. c4 *", "A10"),
+        test_unclassified: ("code", &[], "*", "Unknown"),
+    }
+
+    #[test]
+    fn test_invalid_level() {
+        let configuration =
+            serde_yaml::from_str("{input: games, output: binned, by: variation}").unwrap();
+        assert!(EcoBin::try_new(Some(configuration)).is_err());
+    }
+}
@@ -1,9 +1,28 @@
 use std::collections::HashMap;
 
 use crate::chess_utils::get_game_elo;
+use crate::error::Error;
 use crate::game::Game;
+use crate::step_param_utils::parse_params;
 use crate::workflow_step::{ProcessStatus, SharedData, Step};
 
+step_params! {
+    /// Adds the average rating of both players, rounded down to a bucket, to the bin labels
+    /// of each game
+    pub struct GameEloBinParams {
+        /// Vector of binned games to read
+        input: String,
+        /// Vector the binned games are written to
+        output: String,
+        /// Flag raised once the input is complete
+        input_flag: String = "<input>_complete",
+        /// Flag raised once this step is done
+        output_flag: String = "<output>_complete",
+        /// Width of each rating bucket
+        bucket_size: u64,
+    }
+}
+
 #[derive(Debug)]
 pub struct GameEloBin {
     input_vec_name: String,
@@ -15,36 +34,15 @@ pub struct GameEloBin {
 
 #[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
 impl GameEloBin {
-    pub fn try_new(configuration: Option<serde_yaml::Value>) -> Result<Box<dyn Step>, String> {
-        let params = match configuration {
-            Some(value) => value,
-            None => return Err("GameEloBin: no parameters provided".to_string()),
-        };
-
-        // TODO: better error handling
-        let input_vec_name = params.get("input").unwrap().as_str().unwrap().to_string();
-        let output_vec_name = params.get("output").unwrap().as_str().unwrap().to_string();
-        let input_flag = params
-            .get("input_flag")
-            .unwrap()
-            .as_str()
-            .unwrap()
-            .to_string();
-        let output_flag = params
-            .get("output_flag")
-            .unwrap()
-            .as_str()
-            .unwrap()
-            .to_string();
-
-        let bucket_size = params.get("bucket_size").unwrap().as_u64().unwrap();
+    pub fn try_new(configuration: Option<serde_yaml::Value>) -> Result<Box<dyn Step>, Error> {
+        let params: GameEloBinParams = parse_params("GameEloBin", configuration)?;
 
         Ok(Box::new(GameEloBin {
-            input_vec_name,
-            output_vec_name,
-            input_flag,
-            output_flag,
-            bucket_size,
+            input_vec_name: params.input,
+            output_vec_name: params.output,
+            input_flag: params.input_flag,
+            output_flag: params.output_flag,
+            bucket_size: params.bucket_size,
         }))
     }
 
@@ -72,8 +70,8 @@ mod test_try_new {
         let result = GameEloBin::try_new(None);
         assert!(result.is_err());
         assert_eq!(
-            result.err().unwrap_or(String::new()),
-            "GameEloBin: no parameters provided".to_string()
+            result.err(),
+            Some(Error::config("GameEloBin: missing field `input`"))
         );
     }
 
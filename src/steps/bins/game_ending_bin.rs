@@ -0,0 +1,54 @@
+use std::collections::HashMap;
+
+use crate::error::Error;
+use crate::game::Game;
+use crate::step_param_utils::parse_params;
+use crate::workflow_step::{ProcessStatus, SharedData, Step};
+
+step_params! {
+    /// Adds the way each game ended to its bin labels, "Unknown" when it can't be replayed
+    pub struct GameEndingBinParams {
+        /// Vector of binned games to read
+        input: String,
+        /// Vector the binned games are written to
+        output: String,
+        /// Flag raised once the input is complete
+        input_flag: String = "<input>_complete",
+        /// Flag raised once this step is done
+        output_flag: String = "<output>_complete",
+    }
+}
+
+#[derive(Debug)]
+pub struct GameEndingBin {
+    input_vec_name: String,
+    output_vec_name: String,
+    input_flag: String,
+    output_flag: String,
+}
+
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+impl GameEndingBin {
+    pub fn try_new(configuration: Option<serde_yaml::Value>) -> Result<Box<dyn Step>, Error> {
+        let params: GameEndingBinParams = parse_params("GameEndingBin", configuration)?;
+
+        Ok(Box::new(GameEndingBin {
+            input_vec_name: params.input,
+            output_vec_name: params.output,
+            input_flag: params.input_flag,
+            output_flag: params.output_flag,
+        }))
+    }
+
+    pub fn bin(game: &Game, _filter: &GameEndingBin) -> SharedData {
+        match game.ending() {
+            Ok(ending) => SharedData::String(format!("{:?}", ending)),
+            Err(_) => SharedData::String("Unknown".to_string()),
+        }
+    }
+}
+
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+impl Step for GameEndingBin {
+    bin_template!(GameEndingBin::bin);
+}
@@ -1,7 +1,21 @@
 use std::collections::HashMap;
 
+use crate::error::Error;
+use crate::step_param_utils::parse_params;
 use crate::workflow_step::{ProcessStatus, SharedData, Step};
 
+step_params! {
+    /// Wraps every game in a binned value with no labels, ready for bin steps
+    pub struct InitBinStepParams {
+        /// Vector of games to read
+        input: String,
+        /// Vector the binned games are written to
+        output: String,
+        /// Flag raised once this step is done
+        output_flag: String = "<output>_complete",
+    }
+}
+
 #[derive(Debug)]
 pub struct InitBinStep {
     input_vec_name: String,
@@ -11,33 +25,20 @@ pub struct InitBinStep {
 
 #[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
 impl InitBinStep {
-    pub fn try_new(configuration: Option<serde_yaml::Value>) -> Result<Box<dyn Step>, String> {
-        let params = match configuration {
-            Some(value) => value,
-            None => return Err("InitBinStep: no parameters provided".to_string()),
-        };
-
-        // TODO: better error handling
-        let input_vec_name = params.get("input").unwrap().as_str().unwrap().to_string();
-        let output_vec_name = params.get("output").unwrap().as_str().unwrap().to_string();
-        let output_flag = params
-            .get("output_flag")
-            .unwrap()
-            .as_str()
-            .unwrap()
-            .to_string();
+    pub fn try_new(configuration: Option<serde_yaml::Value>) -> Result<Box<dyn Step>, Error> {
+        let params: InitBinStepParams = parse_params("InitBinStep", configuration)?;
 
         Ok(Box::new(InitBinStep {
-            input_vec_name,
-            output_vec_name,
-            output_flag,
+            input_vec_name: params.input,
+            output_vec_name: params.output,
+            output_flag: params.output_flag,
         }))
     }
 }
 
 #[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
 impl Step for InitBinStep {
-    fn process(&mut self, data: &mut HashMap<String, SharedData>) -> Result<ProcessStatus, String> {
+    fn process(&mut self, data: &mut HashMap<String, SharedData>) -> Result<ProcessStatus, Error> {
         data.insert(self.output_vec_name.clone(), SharedData::Vec(vec![]));
 
         let games = {
@@ -62,7 +63,7 @@ impl Step for InitBinStep {
         for shared_game in games {
             let game = match shared_game {
                 SharedData::Game(game) => game,
-                _ => return Err("Vector isn't of games!".to_string()),
+                _ => return Err(Error::data_type("Vector isn't of games!")),
             };
 
             output_games.push(SharedData::BinnedValue((
@@ -87,4 +88,16 @@ impl Step for InitBinStep {
 
         Ok(ProcessStatus::Complete)
     }
+
+    fn inputs(&self) -> Vec<String> {
+        vec![self.input_vec_name.clone()]
+    }
+
+    fn outputs(&self) -> Vec<String> {
+        vec![self.output_vec_name.clone()]
+    }
+
+    fn output_flags(&self) -> Vec<String> {
+        vec![self.output_flag.clone()]
+    }
 }
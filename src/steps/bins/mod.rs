@@ -1,7 +1,13 @@
+mod eco_bin;
 mod game_elo_bin;
+mod game_ending_bin;
 mod init_bins_step;
+mod opening_bin;
 mod time_control_bin;
 
-pub use game_elo_bin::GameEloBin;
-pub use init_bins_step::InitBinStep;
-pub use time_control_bin::TimeControlBin;
+pub use eco_bin::{EcoBin, EcoBinParams};
+pub use game_elo_bin::{GameEloBin, GameEloBinParams};
+pub use game_ending_bin::{GameEndingBin, GameEndingBinParams};
+pub use init_bins_step::{InitBinStep, InitBinStepParams};
+pub use opening_bin::{OpeningBin, OpeningBinParams};
+pub use time_control_bin::{TimeControlBin, TimeControlBinParams};
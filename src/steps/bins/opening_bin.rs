@@ -0,0 +1,172 @@
+use std::collections::HashMap;
+
+use crate::error::Error;
+use crate::game::Game;
+use crate::opening::{position_hashes, Opening};
+use crate::step_param_utils::{parse_params, ParamType};
+use crate::workflow_step::{ProcessStatus, SharedData, Step};
+
+#[derive(Debug, PartialEq, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NamedOpening {
+    name: String,
+    opening: String,
+}
+
+impl ParamType for NamedOpening {
+    fn type_name() -> String {
+        "map of name and opening".to_string()
+    }
+}
+
+step_params! {
+    /// Adds the name of the deepest opening each game follows to its bin labels, "Other" when
+    /// it follows none
+    pub struct OpeningBinParams {
+        /// Vector of binned games to read
+        input: String,
+        /// Vector the binned games are written to
+        output: String,
+        /// Openings given as SAN moves like "1. e4 c5" or as a FEN, the first listed wins ties
+        openings: Vec<NamedOpening>,
+        /// Flag raised once the input is complete
+        input_flag: String = "<input>_complete",
+        /// Flag raised once this step is done
+        output_flag: String = "<output>_complete",
+    }
+}
+
+#[derive(Debug)]
+pub struct OpeningBin {
+    input_vec_name: String,
+    output_vec_name: String,
+    // Deepest first
+    openings: Vec<(String, Opening)>,
+    input_flag: String,
+    output_flag: String,
+}
+
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+impl OpeningBin {
+    pub fn try_new(configuration: Option<serde_yaml::Value>) -> Result<Box<dyn Step>, Error> {
+        let params: OpeningBinParams = parse_params("OpeningBin", configuration)?;
+
+        let mut openings = params
+            .openings
+            .into_iter()
+            .map(|named| Opening::parse(&named.opening).map(|opening| (named.name, opening)))
+            .collect::<Result<Vec<_>, Error>>()?;
+        // Stable, so ties keep the order they were listed in
+        openings.sort_by_key(|(_, opening)| std::cmp::Reverse(opening.depth()));
+
+        Ok(Box::new(OpeningBin {
+            input_vec_name: params.input,
+            output_vec_name: params.output,
+            openings,
+            input_flag: params.input_flag,
+            output_flag: params.output_flag,
+        }))
+    }
+
+    pub fn bin(game: &Game, bin: &OpeningBin) -> SharedData {
+        let hashes = position_hashes(game);
+
+        let name = bin
+            .openings
+            .iter()
+            .find(|(_, opening)| opening.matches(&hashes))
+            .map_or("Other", |(name, _)| name);
+        SharedData::String(name.to_string())
+    }
+}
+
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+impl Step for OpeningBin {
+    bin_template!(OpeningBin::bin);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_pgn::PgnParser;
+
+    fn game(movetext: &str) -> Game {
+        let mut game = Game::default();
+        PgnParser::new()
+            .parse_game(&Vec::new(), movetext, &mut game)
+            .unwrap();
+        game
+    }
+
+    fn bin_label(movetext: &str) -> SharedData {
+        let configuration = serde_yaml::from_str(
+            r#"
+            input: games
+            output: binned_games
+            openings:
+              - name: King's Pawn
+                opening: "1. e4"
+              - name: Sicilian
+                opening: "1. e4 c5"
+              - name: French
+                opening: "1. e4 e6"
+              - name: Open Sicilian
+                opening: "rnbqkbnr/pp1ppppp/8/2p5/3PP3/8/PPP2PPP/RNBQKBNR b KQkq - 0 2"
+            "#,
+        )
+        .unwrap();
+        let mut step = OpeningBin::try_new(Some(configuration)).unwrap();
+
+        let mut data = HashMap::new();
+        data.insert(
+            "games".to_string(),
+            SharedData::Vec(vec![SharedData::BinnedValue((
+                Box::new(SharedData::Game(game(movetext))),
+                vec![],
+            ))]),
+        );
+        data.insert("games_complete".to_string(), SharedData::Bool(true));
+        assert_eq!(step.process(&mut data), Ok(ProcessStatus::Complete));
+
+        match data
+            .remove("binned_games")
+            .unwrap()
+            .into_vec()
+            .unwrap()
+            .pop()
+        {
+            Some(SharedData::BinnedValue((_, mut labels))) => labels.pop().unwrap(),
+            other => panic!("Unexpected binned game: {other:?}"),
+        }
+    }
+
+    macro_rules! tests {
+        ($($name:ident: $value:expr,)*) => {
+        $(
+            #[test]
+            fn $name() {
+                let (movetext, expected) = $value;
+                assert_eq!(bin_label(movetext), SharedData::String(expected.to_string()));
+            }
+        )*
+        }
+    }
+
+    tests! {
+        test_deepest_match: ("1. e4 c5 2. Nf3 *", "Sicilian"),
+        test_shallow_match: ("1. e4 e5 *", "King's Pawn"),
+        test_position_match: ("1. e4 c5 2. d4 *", "Open Sicilian"),
+        test_transposed_position_match: ("1. d4 c5 2. e4 *", "Open Sicilian"),
+        test_no_match: ("1. d4 d5 *", "Other"),
+        test_no_moves: ("*", "Other"),
+    }
+
+    #[test]
+    fn test_invalid_opening() {
+        let configuration = serde_yaml::from_str(
+            "{input: games, output: binned, openings: [{name: Bad, opening: '1. e5'}]}",
+        )
+        .unwrap();
+        assert!(OpeningBin::try_new(Some(configuration)).is_err());
+    }
+}
@@ -1,10 +1,24 @@
 use std::collections::HashMap;
 
 use crate::basic_types::Termination;
+use crate::error::Error;
 use crate::game::Game;
 use crate::generic_steps::{FilterFn, GenericFilter};
+use crate::step_param_utils::parse_params;
 use crate::workflow_step::{ProcessStatus, SharedData, Step};
 
+step_params! {
+    /// Keeps the games that ended in checkmate
+    pub struct CheckmateFilterParams {
+        /// Vector of games to read
+        input: String,
+        /// Vector the games that pass are written to
+        output: String,
+        /// Vector the games that fail are written to, "null" drops them
+        discard: String = "null",
+    }
+}
+
 #[derive(Debug)]
 pub struct CheckmateFilter {
     generic_filter: GenericFilter,
@@ -12,9 +26,15 @@ pub struct CheckmateFilter {
 
 #[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
 impl CheckmateFilter {
-    pub fn try_new(configuration: Option<serde_yaml::Value>) -> Result<Box<dyn Step>, String> {
+    pub fn try_new(configuration: Option<serde_yaml::Value>) -> Result<Box<dyn Step>, Error> {
+        let params: CheckmateFilterParams = parse_params("CheckmateFilter", configuration)?;
+
         Ok(Box::new(CheckmateFilter {
-            generic_filter: *GenericFilter::try_new(configuration)?,
+            generic_filter: *GenericFilter::from_params(
+                params.input,
+                params.output,
+                params.discard,
+            ),
         }))
     }
 
@@ -29,8 +49,16 @@ impl CheckmateFilter {
 
 #[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
 impl Step for CheckmateFilter {
-    fn process(&mut self, data: &mut HashMap<String, SharedData>) -> Result<ProcessStatus, String> {
+    fn process(&mut self, data: &mut HashMap<String, SharedData>) -> Result<ProcessStatus, Error> {
         self.generic_filter
             .process(data, CheckmateFilter::create_filter())
     }
+
+    fn inputs(&self) -> Vec<String> {
+        self.generic_filter.inputs()
+    }
+
+    fn outputs(&self) -> Vec<String> {
+        self.generic_filter.outputs()
+    }
 }
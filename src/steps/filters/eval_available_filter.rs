@@ -1,11 +1,25 @@
 use std::collections::HashMap;
 
+use crate::error::Error;
 use crate::generic_steps::FilterFn;
 #[mockall_double::double]
 use crate::generic_steps::GenericFilter;
+use crate::step_param_utils::parse_params;
 use crate::workflow_step::{SharedData, Step};
 use crate::{game::Game, workflow_step::ProcessStatus};
 
+step_params! {
+    /// Keeps the games that have engine evaluations
+    pub struct EvalAvailableFilterParams {
+        /// Vector of games to read
+        input: String,
+        /// Vector the games that pass are written to
+        output: String,
+        /// Vector the games that fail are written to, "null" drops them
+        discard: String = "null",
+    }
+}
+
 #[derive(Debug)]
 pub struct EvalAvailableFilter {
     generic_filter: GenericFilter,
@@ -13,9 +27,15 @@ pub struct EvalAvailableFilter {
 
 #[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
 impl EvalAvailableFilter {
-    pub fn try_new(configuration: Option<serde_yaml::Value>) -> Result<Box<dyn Step>, String> {
+    pub fn try_new(configuration: Option<serde_yaml::Value>) -> Result<Box<dyn Step>, Error> {
+        let params: EvalAvailableFilterParams = parse_params("EvalAvailableFilter", configuration)?;
+
         Ok(Box::new(EvalAvailableFilter {
-            generic_filter: *GenericFilter::try_new(configuration)?,
+            generic_filter: *GenericFilter::from_params(
+                params.input,
+                params.output,
+                params.discard,
+            ),
         }))
     }
 
@@ -26,9 +46,17 @@ impl EvalAvailableFilter {
 
 #[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
 impl Step for EvalAvailableFilter {
-    fn process(&mut self, data: &mut HashMap<String, SharedData>) -> Result<ProcessStatus, String> {
+    fn process(&mut self, data: &mut HashMap<String, SharedData>) -> Result<ProcessStatus, Error> {
         self.generic_filter.process(data, Self::create_filter())
     }
+
+    fn inputs(&self) -> Vec<String> {
+        self.generic_filter.inputs()
+    }
+
+    fn outputs(&self) -> Vec<String> {
+        self.generic_filter.outputs()
+    }
 }
 
 #[cfg(test)]
@@ -91,26 +119,37 @@ mod test_try_new {
     #[test]
     fn test_no_params() {
         let _m = get_lock(&MTX);
-        let ctx = MockGenericFilter::try_new_context();
-        ctx.expect()
-            .with(mockall::predicate::eq(None))
-            .returning(|_| Err("Test error".to_string()));
         let result = EvalAvailableFilter::try_new(None);
 
         assert!(result.is_err());
-        assert_eq!(result.unwrap_err(), "Test error".to_string());
+        assert_eq!(
+            result.unwrap_err(),
+            Error::config("EvalAvailableFilter: missing field `input`")
+        );
     }
 
     #[test]
     fn test_nominal() {
         let _m = get_lock(&MTX);
 
-        let params = Mapping::new();
-        let ctx = MockGenericFilter::try_new_context();
+        let mut params = Mapping::new();
+        params.insert(
+            Value::String("input".to_string()),
+            Value::String("games".to_string()),
+        );
+        params.insert(
+            Value::String("output".to_string()),
+            Value::String("kept_games".to_string()),
+        );
+        let ctx = MockGenericFilter::from_params_context();
 
         ctx.expect()
-            .with(mockall::predicate::eq(Some(Value::Mapping(params.clone()))))
-            .returning(|_| Ok(Box::new(MockGenericFilter::new())));
+            .with(
+                mockall::predicate::eq("games".to_string()),
+                mockall::predicate::eq("kept_games".to_string()),
+                mockall::predicate::eq("null".to_string()),
+            )
+            .returning(|_, _, _| Box::new(MockGenericFilter::new()));
 
         let result = EvalAvailableFilter::try_new(Some(Value::Mapping(params)));
         assert!(result.is_ok());
@@ -0,0 +1,70 @@
+use std::collections::HashMap;
+
+use crate::basic_types::GameEnding;
+use crate::error::Error;
+use crate::game::Game;
+use crate::generic_steps::{FilterFn, GenericFilter};
+use crate::step_param_utils::parse_params;
+use crate::workflow_step::{ProcessStatus, SharedData, Step};
+
+step_params! {
+    /// Keeps the games that ended in one of the given ways, found by replaying their moves
+    pub struct GameEndingFilterParams {
+        /// Vector of games to read
+        input: String,
+        /// Vector the games that pass are written to
+        output: String,
+        /// Vector the games that fail are written to, "null" drops them
+        discard: String = "null",
+        /// Endings to keep, games that can't be replayed are discarded
+        endings: Vec<GameEnding>,
+    }
+}
+
+#[derive(Debug)]
+pub struct GameEndingFilter {
+    generic_filter: GenericFilter,
+    endings: Vec<GameEnding>,
+}
+
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+impl GameEndingFilter {
+    pub fn try_new(configuration: Option<serde_yaml::Value>) -> Result<Box<dyn Step>, Error> {
+        let params: GameEndingFilterParams = parse_params("GameEndingFilter", configuration)?;
+
+        Ok(Box::new(GameEndingFilter {
+            generic_filter: *GenericFilter::from_params(
+                params.input,
+                params.output,
+                params.discard,
+            ),
+            endings: params.endings,
+        }))
+    }
+
+    pub fn create_filter(&self) -> Box<FilterFn> {
+        let endings = self.endings.clone();
+
+        let filter = move |game: &Game| match game.ending() {
+            Ok(ending) => endings.contains(&ending),
+            Err(_) => false,
+        };
+
+        Box::new(filter)
+    }
+}
+
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+impl Step for GameEndingFilter {
+    fn process(&mut self, data: &mut HashMap<String, SharedData>) -> Result<ProcessStatus, Error> {
+        self.generic_filter.process(data, &*self.create_filter())
+    }
+
+    fn inputs(&self) -> Vec<String> {
+        self.generic_filter.inputs()
+    }
+
+    fn outputs(&self) -> Vec<String> {
+        self.generic_filter.outputs()
+    }
+}
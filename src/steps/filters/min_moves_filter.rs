@@ -1,9 +1,25 @@
 use std::collections::HashMap;
 
+use crate::error::Error;
 use crate::game::Game;
 use crate::generic_steps::{FilterFn, GenericFilter};
+use crate::step_param_utils::parse_params;
 use crate::workflow_step::{ProcessStatus, SharedData, Step};
 
+step_params! {
+    /// Keeps the games with at least `min_moves` moves
+    pub struct MinMovesFilterParams {
+        /// Vector of games to read
+        input: String,
+        /// Vector the games that pass are written to
+        output: String,
+        /// Vector the games that fail are written to, "null" drops them
+        discard: String = "null",
+        /// Smallest number of moves a game may have
+        min_moves: u64,
+    }
+}
+
 #[derive(Debug)]
 pub struct MinMovesFilter {
     generic_filter: GenericFilter,
@@ -12,18 +28,16 @@ pub struct MinMovesFilter {
 
 #[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
 impl MinMovesFilter {
-    pub fn try_new(configuration: Option<serde_yaml::Value>) -> Result<Box<dyn Step>, String> {
-        let params = match configuration {
-            Some(value) => value,
-            None => return Err("MinMovesFilter: no parameters provided".to_string()),
-        };
-
-        // TODO: better error handling
-        let min_moves = params.get("min_moves").unwrap().as_u64().unwrap();
+    pub fn try_new(configuration: Option<serde_yaml::Value>) -> Result<Box<dyn Step>, Error> {
+        let params: MinMovesFilterParams = parse_params("MinMovesFilter", configuration)?;
 
         Ok(Box::new(MinMovesFilter {
-            generic_filter: *GenericFilter::try_new(Some(params))?,
-            min_moves,
+            generic_filter: *GenericFilter::from_params(
+                params.input,
+                params.output,
+                params.discard,
+            ),
+            min_moves: params.min_moves,
         }))
     }
 
@@ -36,7 +50,15 @@ impl MinMovesFilter {
 
 #[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
 impl Step for MinMovesFilter {
-    fn process(&mut self, data: &mut HashMap<String, SharedData>) -> Result<ProcessStatus, String> {
+    fn process(&mut self, data: &mut HashMap<String, SharedData>) -> Result<ProcessStatus, Error> {
         self.generic_filter.process(data, &*self.create_filter())
     }
+
+    fn inputs(&self) -> Vec<String> {
+        self.generic_filter.inputs()
+    }
+
+    fn outputs(&self) -> Vec<String> {
+        self.generic_filter.outputs()
+    }
 }
@@ -1,11 +1,15 @@
 mod checkmate_filter;
 mod clock_available_filter;
 mod eval_available_filter;
+mod game_ending_filter;
 mod min_moves_filter;
+mod opening_filter;
 mod player_elo_filter;
 
-pub use checkmate_filter::CheckmateFilter;
-pub use clock_available_filter::ClockAvailableFilter;
-pub use eval_available_filter::EvalAvailableFilter;
-pub use min_moves_filter::MinMovesFilter;
-pub use player_elo_filter::PlayerEloFilter;
+pub use checkmate_filter::{CheckmateFilter, CheckmateFilterParams};
+pub use clock_available_filter::{ClockAvailableFilter, ClockAvailableFilterParams};
+pub use eval_available_filter::{EvalAvailableFilter, EvalAvailableFilterParams};
+pub use game_ending_filter::{GameEndingFilter, GameEndingFilterParams};
+pub use min_moves_filter::{MinMovesFilter, MinMovesFilterParams};
+pub use opening_filter::{OpeningFilter, OpeningFilterParams};
+pub use player_elo_filter::{PlayerEloFilter, PlayerEloFilterParams};
@@ -0,0 +1,68 @@
+use std::collections::HashMap;
+
+use crate::error::Error;
+use crate::game::Game;
+use crate::generic_steps::{FilterFn, GenericFilter};
+use crate::opening::{position_hashes, Opening};
+use crate::step_param_utils::parse_params;
+use crate::workflow_step::{ProcessStatus, SharedData, Step};
+
+step_params! {
+    /// Keeps the games that follow an opening, given as SAN moves or as a FEN of a position
+    /// that may be reached by any move order
+    pub struct OpeningFilterParams {
+        /// Vector of games to read
+        input: String,
+        /// Vector the games that pass are written to
+        output: String,
+        /// Vector the games that fail are written to, "null" drops them
+        discard: String = "null",
+        /// Moves from the starting position like "1. e4 c5 2. Nf3", or a FEN
+        opening: String,
+    }
+}
+
+#[derive(Debug)]
+pub struct OpeningFilter {
+    generic_filter: GenericFilter,
+    opening: Opening,
+}
+
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+impl OpeningFilter {
+    pub fn try_new(configuration: Option<serde_yaml::Value>) -> Result<Box<dyn Step>, Error> {
+        let params: OpeningFilterParams = parse_params("OpeningFilter", configuration)?;
+
+        Ok(Box::new(OpeningFilter {
+            generic_filter: *GenericFilter::from_params(
+                params.input,
+                params.output,
+                params.discard,
+            ),
+            opening: Opening::parse(&params.opening)?,
+        }))
+    }
+
+    pub fn create_filter(&self) -> Box<FilterFn> {
+        let opening = self.opening.clone();
+
+        let filter = move |game: &Game| opening.matches(&position_hashes(game));
+
+        Box::new(filter)
+    }
+}
+
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+impl Step for OpeningFilter {
+    fn process(&mut self, data: &mut HashMap<String, SharedData>) -> Result<ProcessStatus, Error> {
+        self.generic_filter.process(data, &*self.create_filter())
+    }
+
+    fn inputs(&self) -> Vec<String> {
+        self.generic_filter.inputs()
+    }
+
+    fn outputs(&self) -> Vec<String> {
+        self.generic_filter.outputs()
+    }
+}
@@ -1,9 +1,31 @@
 use std::collections::HashMap;
 
+use crate::error::Error;
 use crate::game::Game;
 use crate::generic_steps::{FilterFn, GenericFilter};
+use crate::step_param_utils::parse_params;
 use crate::workflow_step::{ProcessStatus, SharedData, Step};
 
+step_params! {
+    /// Keeps the games whose selected players are rated within the given bounds
+    pub struct PlayerEloFilterParams {
+        /// Vector of games to read
+        input: String,
+        /// Vector the games that pass are written to
+        output: String,
+        /// Vector the games that fail are written to, "null" drops them
+        discard: String = "null",
+        /// Whether the bounds apply to the white player
+        white: bool,
+        /// Whether the bounds apply to the black player
+        black: bool,
+        /// Lowest rating allowed, unbounded when unset
+        min_elo: Option<u64>,
+        /// Highest rating allowed, unbounded when unset
+        max_elo: Option<u64>,
+    }
+}
+
 #[derive(Debug)]
 pub struct PlayerEloFilter {
     generic_filter: GenericFilter,
@@ -15,36 +37,19 @@ pub struct PlayerEloFilter {
 
 #[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
 impl PlayerEloFilter {
-    pub fn try_new(configuration: Option<serde_yaml::Value>) -> Result<Box<dyn Step>, String> {
-        let params = match configuration.clone() {
-            Some(value) => value,
-            None => return Err("PlayerEloFilter: no parameters provided".to_string()),
-        };
-
-        // TODO: better error handling
-        let filter_white = params.get("white").unwrap().as_bool().unwrap();
-        let filter_black = params.get("black").unwrap().as_bool().unwrap();
-        let min_elo = match params.get("min_elo") {
-            Some(val) => match val.as_u64() {
-                Some(val) => Some(val),
-                None => return Err("Could not parse min_elo".to_string()),
-            },
-            None => None,
-        };
-        let max_elo = match params.get("max_elo") {
-            Some(val) => match val.as_u64() {
-                Some(val) => Some(val),
-                None => return Err("Could not parse max_elo".to_string()),
-            },
-            None => None,
-        };
+    pub fn try_new(configuration: Option<serde_yaml::Value>) -> Result<Box<dyn Step>, Error> {
+        let params: PlayerEloFilterParams = parse_params("PlayerEloFilter", configuration)?;
 
         Ok(Box::new(PlayerEloFilter {
-            generic_filter: *GenericFilter::try_new(configuration)?,
-            min_elo,
-            max_elo,
-            filter_white,
-            filter_black,
+            generic_filter: *GenericFilter::from_params(
+                params.input,
+                params.output,
+                params.discard,
+            ),
+            min_elo: params.min_elo,
+            max_elo: params.max_elo,
+            filter_white: params.white,
+            filter_black: params.black,
         }))
     }
 
@@ -82,7 +87,15 @@ impl PlayerEloFilter {
 
 #[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
 impl Step for PlayerEloFilter {
-    fn process(&mut self, data: &mut HashMap<String, SharedData>) -> Result<ProcessStatus, String> {
+    fn process(&mut self, data: &mut HashMap<String, SharedData>) -> Result<ProcessStatus, Error> {
         self.generic_filter.process(data, &*self.create_filter())
     }
+
+    fn inputs(&self) -> Vec<String> {
+        self.generic_filter.inputs()
+    }
+
+    fn outputs(&self) -> Vec<String> {
+        self.generic_filter.outputs()
+    }
 }
@@ -1,3 +1,5 @@
+use crate::error::Error;
+use crate::step_param_utils::parse_params;
 use crate::workflow_step::{ProcessStatus, SharedData, Step, StepData};
 
 use bzip2::read::BzDecoder;
@@ -5,6 +7,15 @@ use std::collections::HashMap;
 use std::fs::File;
 use std::io::Read;
 
+step_params! {
+    /// Reads every file in `file_path_bufs` into `raw_file_data`, decompressing files with a
+    /// "bz2" extension
+    pub struct Bz2DecompressStepParams {
+        /// Number of files read per call
+        max_queue_size: u64 = "1",
+    }
+}
+
 #[derive(Debug)]
 pub struct Bz2DecompressStep {
     max_queue_size: u64,
@@ -13,16 +24,11 @@ pub struct Bz2DecompressStep {
 
 #[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
 impl Bz2DecompressStep {
-    pub fn try_new(configuration: Option<serde_yaml::Value>) -> Result<Box<dyn Step>, String> {
-        let params = match configuration {
-            Some(value) => value,
-            None => return Err("Bz2DecompressStep: no parameters provided".to_string()),
-        };
-
-        // TODO: better error handling
-        let max_queue_size = params.get("max_queue_size").unwrap().as_u64().unwrap();
+    pub fn try_new(configuration: Option<serde_yaml::Value>) -> Result<Box<dyn Step>, Error> {
+        let params: Bz2DecompressStepParams = parse_params("Bz2DecompressStep", configuration)?;
+
         Ok(Box::new(Bz2DecompressStep {
-            max_queue_size,
+            max_queue_size: params.max_queue_size,
             paths: None,
         }))
     }
@@ -30,13 +36,16 @@ impl Bz2DecompressStep {
 
 #[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
 impl Step for Bz2DecompressStep {
-    fn process(&mut self, data: &mut HashMap<String, SharedData>) -> Result<ProcessStatus, String> {
+    fn process(&mut self, data: &mut HashMap<String, SharedData>) -> Result<ProcessStatus, Error> {
         data.init_vec_if_unset("raw_file_data");
 
         if self.paths.is_none() {
-            let bufs = { data.remove("file_path_bufs").unwrap() };
+            let bufs = data
+                .remove("file_path_bufs")
+                .and_then(SharedData::into_vec)
+                .ok_or_else(|| Error::data_type("Bz2DecompressStep: no file_path_bufs vector"))?;
 
-            self.paths = Some(bufs.to_vec().unwrap());
+            self.paths = Some(bufs);
         }
 
         let paths = self.paths.as_mut().unwrap();
@@ -47,9 +56,11 @@ impl Step for Bz2DecompressStep {
 
         for _ in 0..((paths.len() as u64).min(self.max_queue_size)) {
             let path = paths.remove(0);
-            let path = path.to_path_buf().unwrap();
+            let path = path
+                .to_path_buf()
+                .ok_or_else(|| Error::data_type("Bz2DecompressStep: path isn't a PathBuf"))?;
 
-            let mut file = File::open(&path).expect("Could not open file");
+            let mut file = File::open(&path).map_err(|err| Error::from(err).in_file(&path))?;
             let mut file_data = Vec::new();
 
             // Assume uncompressed unless extension is "bz2"
@@ -58,18 +69,24 @@ impl Step for Bz2DecompressStep {
                 None => false,
             };
 
-            if compressed {
+            let read = if compressed {
                 let mut decompressor = BzDecoder::new(file);
-                decompressor
-                    .read_to_end(&mut file_data)
-                    .expect("Could not decompress file");
+                decompressor.read_to_end(&mut file_data)
             } else {
                 file.read_to_end(&mut file_data)
-                    .expect("Could not read file");
-            }
+            };
+            read.map_err(|err| Error::from(err).in_file(&path))?;
 
             data.try_push_to_vec("raw_file_data", SharedData::FileData(file_data))?;
         }
         Ok(ProcessStatus::Incomplete)
     }
+
+    fn inputs(&self) -> Vec<String> {
+        vec!["file_path_bufs".to_string()]
+    }
+
+    fn outputs(&self) -> Vec<String> {
+        vec!["raw_file_data".to_string()]
+    }
 }
@@ -0,0 +1,137 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    columnar::{encode_block, file_header},
+    error::Error,
+    game::Game,
+    step_param_utils::parse_params,
+    workflow_step::{ProcessStatus, SharedData, Step, StepData},
+};
+
+step_params! {
+    /// Writes games to a columnar file, in blocks whose columns can be read separately and
+    /// whose rating, date and time control ranges let readers skip them
+    pub struct ExportColumnarGamesParams {
+        /// Vector of games to read
+        input: String,
+        /// Flag raised once the input is complete
+        input_flag: String = "<input>_complete",
+        /// File the games are written to, replaced if it exists
+        file: String,
+        /// Number of games per block
+        block_size: usize = "4096",
+    }
+}
+
+#[derive(Debug)]
+pub struct ExportColumnarGames {
+    input_vec_name: String,
+    input_flag: String,
+    path: PathBuf,
+    block_size: usize,
+    pending_games: Vec<Game>,
+    writer: Option<BufWriter<File>>,
+}
+
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+impl ExportColumnarGames {
+    pub fn try_new(configuration: Option<serde_yaml::Value>) -> Result<Box<dyn Step>, Error> {
+        let params: ExportColumnarGamesParams = parse_params("ExportColumnarGames", configuration)?;
+
+        if params.block_size == 0 {
+            return Err(Error::config(
+                "ExportColumnarGames: block_size must be at least 1",
+            ));
+        }
+
+        Ok(Box::new(ExportColumnarGames {
+            input_vec_name: params.input,
+            input_flag: params.input_flag,
+            path: PathBuf::from(params.file),
+            block_size: params.block_size,
+            pending_games: vec![],
+            writer: None,
+        }))
+    }
+
+    // Created here rather than in `try_new` so validating a workflow touches no files
+    fn open(path: &Path) -> Result<BufWriter<File>, Error> {
+        if let Some(parent) = path
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+        {
+            std::fs::create_dir_all(parent).map_err(|err| Error::from(err).in_file(parent))?;
+        }
+
+        let file = File::create(path).map_err(|err| Error::from(err).in_file(path))?;
+        let mut writer = BufWriter::new(file);
+        writer
+            .write_all(&file_header())
+            .map_err(|err| Error::from(err).in_file(path))?;
+        Ok(writer)
+    }
+
+    fn write_block(&mut self, block_len: usize) -> Result<(), Error> {
+        let games: Vec<Game> = self.pending_games.drain(0..block_len).collect();
+        let block = encode_block(&games)?;
+
+        self.writer
+            .as_mut()
+            .unwrap()
+            .write_all(&block)
+            .map_err(|err| Error::from(err).in_file(&self.path))
+    }
+}
+
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+impl Step for ExportColumnarGames {
+    fn process(&mut self, data: &mut HashMap<String, SharedData>) -> Result<ProcessStatus, Error> {
+        // Read the flag before draining so games arriving alongside it are not left behind
+        let input_done = data
+            .get(&self.input_flag)
+            .and_then(SharedData::to_bool)
+            .unwrap_or(false);
+
+        if self.writer.is_none() {
+            self.writer = Some(Self::open(&self.path)?);
+        }
+
+        for shared_game in data.clear_vec(&self.input_vec_name).unwrap_or_default() {
+            if let SharedData::Game(game) = shared_game {
+                self.pending_games.push(game);
+            }
+        }
+
+        while self.pending_games.len() >= self.block_size {
+            self.write_block(self.block_size)?;
+        }
+
+        if !input_done {
+            return Ok(ProcessStatus::Incomplete);
+        }
+
+        if !self.pending_games.is_empty() {
+            self.write_block(self.pending_games.len())?;
+        }
+        self.writer
+            .as_mut()
+            .unwrap()
+            .flush()
+            .map_err(|err| Error::from(err).in_file(&self.path))?;
+
+        Ok(ProcessStatus::Complete)
+    }
+
+    fn inputs(&self) -> Vec<String> {
+        vec![self.input_vec_name.clone()]
+    }
+
+    fn input_flags(&self) -> Vec<String> {
+        vec![self.input_flag.clone()]
+    }
+}
@@ -1,65 +1,74 @@
-use std::{collections::HashMap, fs::File, io::Write};
+use std::{collections::HashMap, path::Path};
 
 use crate::{
+    error::Error,
     game::Game,
-    workflow_step::{ProcessStatus, SharedData, Step},
+    game_archive::write_file,
+    step_param_utils::parse_params,
+    workflow_step::{ProcessStatus, SharedData, Step, StepData},
 };
-use bzip2::write::BzEncoder;
-use bzip2::Compression;
 
-#[derive(Debug, PartialEq, Eq)]
+step_params! {
+    /// Writes games to bz2 compressed binary files of up to `games_per_file` games each, in
+    /// the current binary format version
+    pub struct ExportGamesParams {
+        /// Vector of games to read
+        input: String,
+        /// Flag raised once the input is complete
+        input_flag: String = "<input>_complete",
+        /// Name shared by the written files
+        file_prefix: String,
+        /// Directory the files are written to, created if missing
+        output_path: String,
+        /// Number of games per file
+        games_per_file: usize = "10000",
+        /// bz2 compression level, from 1 (fastest) to 9 (smallest)
+        compression: u32 = "9",
+    }
+}
+
+#[derive(Debug, PartialEq)]
 pub struct ExportGames {
     input_vec_name: String,
     input_flag: String,
     games_per_file: usize,
+    compression: u32,
     file_prefix: String,
     output_path: String,
+    pending_games: Vec<Game>,
+    file_count: i32,
 }
 
 #[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
 impl ExportGames {
-    pub fn try_new(configuration: Option<serde_yaml::Value>) -> Result<Box<dyn Step>, String> {
-        let params = match configuration {
-            Some(value) => value,
-            None => return Err("ExportGames: no parameters provided".to_string()),
-        };
+    pub fn try_new(configuration: Option<serde_yaml::Value>) -> Result<Box<dyn Step>, Error> {
+        let params: ExportGamesParams = parse_params("ExportGames", configuration)?;
 
-        // TODO: better error handling
-        let input_vec_name = params.get("input").unwrap().as_str().unwrap().to_string();
-        let input_flag = params
-            .get("input_flag")
-            .unwrap()
-            .as_str()
-            .unwrap()
-            .to_string();
-        let file_prefix = params
-            .get("file_prefix")
-            .unwrap()
-            .as_str()
-            .unwrap()
-            .to_string();
-
-        let output_path = params
-            .get("output_path")
-            .unwrap()
-            .as_str()
-            .unwrap()
-            .to_string();
-
-        std::fs::create_dir_all(output_path.clone()).unwrap();
+        if params.games_per_file == 0 {
+            return Err(Error::config(
+                "ExportGames: games_per_file must be at least 1",
+            ));
+        }
+
+        if !(1..=9).contains(&params.compression) {
+            return Err(Error::config(
+                "ExportGames: compression must be between 1 and 9",
+            ));
+        }
 
         Ok(Box::new(ExportGames {
-            input_vec_name,
-            input_flag,
-            games_per_file: 10000,
-            file_prefix,
-            output_path,
+            input_vec_name: params.input,
+            input_flag: params.input_flag,
+            games_per_file: params.games_per_file,
+            compression: params.compression,
+            file_prefix: params.file_prefix,
+            output_path: params.output_path,
+            pending_games: vec![],
+            file_count: 0,
         }))
     }
 
-    fn save_games(&self, games: &[Game], count: i32) {
-        let encoded_games = postcard::to_allocvec(&games).unwrap();
-
+    fn save_games(&self, games: &[Game], count: i32) -> Result<(), Error> {
         let path = if count >= 0 {
             format!(
                 "{}/{}_{count:06}.bin.bz2",
@@ -69,75 +78,83 @@ impl ExportGames {
             format!("{}/{}.bin.bz2", self.output_path, self.file_prefix)
         };
 
-        let mut pos = 0;
-        let buffer = File::create(path).unwrap();
+        // Created here rather than in `try_new` so validating a workflow touches no files
+        std::fs::create_dir_all(&self.output_path)
+            .map_err(|err| Error::from(err).in_file(Path::new(&self.output_path)))?;
 
-        let mut compressor = BzEncoder::new(buffer, Compression::best());
+        write_file(Path::new(&path), games, self.compression)?;
 
-        while pos < encoded_games.len() {
-            let bytes_written = compressor.write(&encoded_games[pos..]).unwrap();
-            pos += bytes_written;
-        }
+        Ok(())
     }
 }
 
 #[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
 impl Step for ExportGames {
-    fn process(&mut self, data: &mut HashMap<String, SharedData>) -> Result<ProcessStatus, String> {
-        let mut quit = false;
-        let mut final_loop = false;
-        let mut games = vec![];
-        let mut count = 0;
-        loop {
-            if quit {
-                final_loop = true;
+    fn process(&mut self, data: &mut HashMap<String, SharedData>) -> Result<ProcessStatus, Error> {
+        // Read the flag before draining so games arriving alongside it are not left behind
+        let input_done = data
+            .get(&self.input_flag)
+            .and_then(SharedData::to_bool)
+            .unwrap_or(false);
+
+        if let Some(shared_games) = data.clear_vec(&self.input_vec_name) {
+            for possible_game in shared_games {
+                if let SharedData::Game(game) = possible_game {
+                    self.pending_games.push(game);
+                }
             }
+        }
 
-            {
-                let potential_data = data.get(&self.input_vec_name);
-                let shared_data = match potential_data {
-                    Some(data) => data,
-                    None => continue,
-                };
-                let vec_to_filter = shared_data.to_vec().unwrap();
-
-                data.insert(self.input_vec_name.clone(), SharedData::Vec(vec![]));
+        while self.pending_games.len() >= self.games_per_file {
+            let to_save: Vec<Game> = self.pending_games.drain(0..self.games_per_file).collect();
 
-                for possible_game in vec_to_filter {
-                    if let SharedData::Game(game) = possible_game {
-                        games.push(game);
-                    }
-                }
-            }
+            self.save_games(&to_save, self.file_count)?;
 
-            while games.len() >= self.games_per_file {
-                let to_save: Vec<Game> = games.drain(0..self.games_per_file).collect();
+            self.file_count += 1;
+        }
 
-                self.save_games(&to_save, count);
+        if !input_done {
+            return Ok(ProcessStatus::Incomplete);
+        }
 
-                count += 1;
-            }
+        let count = if self.file_count == 0 {
+            -1
+        } else {
+            self.file_count
+        };
 
-            let flag = data
-                .get(&self.input_flag)
-                .unwrap_or(&SharedData::Bool(false));
+        let games = std::mem::take(&mut self.pending_games);
+        self.save_games(&games, count)?;
 
-            let flag = flag.to_bool().unwrap();
+        Ok(ProcessStatus::Complete)
+    }
 
-            if flag {
-                quit = true;
-            }
+    fn inputs(&self) -> Vec<String> {
+        vec![self.input_vec_name.clone()]
+    }
 
-            if final_loop && quit {
-                if count == 0 {
-                    count = -1;
-                }
+    fn input_flags(&self) -> Vec<String> {
+        vec![self.input_flag.clone()]
+    }
+}
 
-                self.save_games(&games, count);
-                break;
-            }
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_invalid_file_settings() {
+        let base = "{input: games, file_prefix: games, output_path: out";
+        for params in [
+            ", games_per_file: 0}",
+            ", compression: 0}",
+            ", compression: 10}",
+        ] {
+            let configuration = serde_yaml::from_str(&format!("{base}{params}")).unwrap();
+            assert!(ExportGames::try_new(Some(configuration)).is_err());
         }
 
-        Ok(ProcessStatus::Complete)
+        let configuration = serde_yaml::from_str(&format!("{base}, compression: 1}}")).unwrap();
+        assert!(ExportGames::try_new(Some(configuration)).is_ok());
     }
 }
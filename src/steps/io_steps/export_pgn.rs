@@ -0,0 +1,113 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    error::Error,
+    pgn_writer::PgnWriter,
+    step_param_utils::parse_params,
+    workflow_step::{ProcessStatus, SharedData, Step, StepData},
+};
+
+step_params! {
+    /// Writes games to a PGN file that can be opened in any chess GUI
+    pub struct ExportPgnParams {
+        /// Vector of games to read
+        input: String,
+        /// Flag raised once the input is complete
+        input_flag: String = "<input>_complete",
+        /// File the games are written to, replaced if it exists
+        file: String,
+        /// Write evaluations and clock times as `[%eval]` and `[%clk]` comments
+        comments: bool = "true",
+    }
+}
+
+#[derive(Debug)]
+pub struct ExportPgn {
+    input_vec_name: String,
+    input_flag: String,
+    path: PathBuf,
+    pgn_writer: PgnWriter,
+    writer: Option<BufWriter<File>>,
+    games_written: usize,
+}
+
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+impl ExportPgn {
+    pub fn try_new(configuration: Option<serde_yaml::Value>) -> Result<Box<dyn Step>, Error> {
+        let params: ExportPgnParams = parse_params("ExportPgn", configuration)?;
+
+        Ok(Box::new(ExportPgn {
+            input_vec_name: params.input,
+            input_flag: params.input_flag,
+            path: PathBuf::from(params.file),
+            pgn_writer: PgnWriter::new(params.comments),
+            writer: None,
+            games_written: 0,
+        }))
+    }
+
+    // Created here rather than in `try_new` so validating a workflow touches no files
+    fn open(path: &Path) -> Result<BufWriter<File>, Error> {
+        if let Some(parent) = path
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+        {
+            std::fs::create_dir_all(parent).map_err(|err| Error::from(err).in_file(parent))?;
+        }
+
+        let file = File::create(path).map_err(|err| Error::from(err).in_file(path))?;
+        Ok(BufWriter::new(file))
+    }
+}
+
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+impl Step for ExportPgn {
+    fn process(&mut self, data: &mut HashMap<String, SharedData>) -> Result<ProcessStatus, Error> {
+        // Read the flag before draining so games arriving alongside it are not left behind
+        let input_done = data
+            .get(&self.input_flag)
+            .and_then(SharedData::to_bool)
+            .unwrap_or(false);
+
+        if self.writer.is_none() {
+            self.writer = Some(Self::open(&self.path)?);
+        }
+        let writer = self.writer.as_mut().unwrap();
+
+        for shared_game in data.clear_vec(&self.input_vec_name).unwrap_or_default() {
+            if let SharedData::Game(game) = shared_game {
+                let pgn = self
+                    .pgn_writer
+                    .write_game(&game)
+                    .map_err(|err| err.in_game(self.games_written))?;
+                writer
+                    .write_all(pgn.as_bytes())
+                    .map_err(|err| Error::from(err).in_file(&self.path))?;
+                self.games_written += 1;
+            }
+        }
+
+        if !input_done {
+            return Ok(ProcessStatus::Incomplete);
+        }
+
+        writer
+            .flush()
+            .map_err(|err| Error::from(err).in_file(&self.path))?;
+
+        Ok(ProcessStatus::Complete)
+    }
+
+    fn inputs(&self) -> Vec<String> {
+        vec![self.input_vec_name.clone()]
+    }
+
+    fn input_flags(&self) -> Vec<String> {
+        vec![self.input_flag.clone()]
+    }
+}
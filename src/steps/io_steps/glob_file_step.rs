@@ -1,10 +1,22 @@
 use std::collections::HashMap;
 
-use crate::steps_manager::get_step_description;
+use crate::error::Error;
+use crate::step_param_utils::parse_params;
+use crate::steps_manager::get_step;
 use crate::workflow_step::{ProcessStatus, SharedData, Step};
 
 use glob::glob;
 
+step_params! {
+    /// Lists the files matching a glob into `file_path_bufs`, then runs its child step
+    pub struct GlobFileStepParams {
+        /// Pattern of the files to list
+        glob: String,
+        /// Step to run once the files are listed
+        child: String,
+    }
+}
+
 #[derive(Debug)]
 pub struct GlobFileStep {
     glob_string: String,
@@ -13,19 +25,12 @@ pub struct GlobFileStep {
 
 #[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
 impl GlobFileStep {
-    pub fn try_new(configuration: Option<serde_yaml::Value>) -> Result<Box<dyn Step>, String> {
-        let params = match configuration {
-            Some(value) => value,
-            None => return Err("GlobFileStep: no parameters provided".to_string()),
-        };
-
-        let glob_string = params.get("glob").unwrap().as_str().unwrap().to_string();
-
-        let child_string = params.get("child").unwrap().as_str().unwrap().to_string();
+    pub fn try_new(configuration: Option<serde_yaml::Value>) -> Result<Box<dyn Step>, Error> {
+        let params: GlobFileStepParams = parse_params("GlobFileStep", configuration)?;
 
         let step = GlobFileStep {
-            glob_string,
-            child_name: child_string,
+            glob_string: params.glob,
+            child_name: params.child,
         };
 
         Ok(Box::new(step))
@@ -34,19 +39,25 @@ impl GlobFileStep {
 
 #[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
 impl Step for GlobFileStep {
-    fn process(&mut self, data: &mut HashMap<String, SharedData>) -> Result<ProcessStatus, String> {
+    fn process(&mut self, data: &mut HashMap<String, SharedData>) -> Result<ProcessStatus, Error> {
         let glob_result = glob(&self.glob_string);
 
         let file_glob = if let Ok(file_glob) = glob_result {
             file_glob
         } else {
-            return Err(format!("Could not process glob: {}", self.glob_string));
+            return Err(Error::config(format!(
+                "Could not process glob: {}",
+                self.glob_string
+            )));
         };
 
-        let files: Vec<SharedData> = file_glob
-            .map(Result::unwrap)
-            .map(SharedData::PathBuf)
-            .collect();
+        let mut files: Vec<SharedData> = vec![];
+        for entry in file_glob {
+            match entry {
+                Ok(path) => files.push(SharedData::PathBuf(path)),
+                Err(err) => return Err(Error::io(err.error().to_string()).in_file(err.path())),
+            }
+        }
 
         {
             data.insert(
@@ -56,7 +67,17 @@ impl Step for GlobFileStep {
             data.insert("file_path_bufs".to_string(), SharedData::Vec(files));
         }
 
-        let mut child = get_step_description(&self.child_name, data).to_step()?;
-        child.process(data)
+        let mut child = get_step(&self.child_name, data)?;
+        child
+            .process(data)
+            .map_err(|err| err.in_step(&self.child_name))
+    }
+
+    fn outputs(&self) -> Vec<String> {
+        vec!["file_path_bufs".to_string()]
+    }
+
+    fn child_steps(&self) -> Vec<String> {
+        vec![self.child_name.clone()]
     }
 }
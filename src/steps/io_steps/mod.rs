@@ -1,9 +1,13 @@
 mod bz2_decompress_step;
+mod export_columnar_games;
 mod export_games;
+mod export_pgn;
 mod glob_file_step;
 mod save_data_step;
 
-pub use bz2_decompress_step::Bz2DecompressStep;
-pub use export_games::ExportGames;
-pub use glob_file_step::GlobFileStep;
-pub use save_data_step::SaveDataStep;
+pub use bz2_decompress_step::{Bz2DecompressStep, Bz2DecompressStepParams};
+pub use export_columnar_games::{ExportColumnarGames, ExportColumnarGamesParams};
+pub use export_games::{ExportGames, ExportGamesParams};
+pub use export_pgn::{ExportPgn, ExportPgnParams};
+pub use glob_file_step::{GlobFileStep, GlobFileStepParams};
+pub use save_data_step::{SaveDataStep, SaveDataStepParams};
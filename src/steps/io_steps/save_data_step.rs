@@ -1,44 +1,52 @@
+use crate::error::Error;
+use crate::step_param_utils::parse_params;
 use crate::workflow_step::{ProcessStatus, SharedData, Step};
-use serde_yaml::Value;
-use std::{collections::HashMap, fs, io::Write};
+use std::{collections::HashMap, fs, io::Write, path::Path};
+
+step_params! {
+    /// Writes the given data entries to a text file
+    pub struct SaveDataStepParams {
+        /// Path of the file to write, replaced if it exists
+        file: String,
+        /// Names of the data entries to write
+        fields: Vec<String>,
+    }
+}
 
 #[derive(Debug)]
 pub struct SaveDataStep {
     file: String,
-    fields: Vec<Value>,
+    fields: Vec<String>,
 }
 
 #[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
 impl SaveDataStep {
-    pub fn try_new(configuration: Option<serde_yaml::Value>) -> Result<Box<dyn Step>, String> {
-        let params = match configuration {
-            Some(value) => value,
-            None => return Err("SaveDataStep: no parameters provided".to_string()),
-        };
-
-        // TODO: better error handling
-        let file = params.get("file").unwrap().as_str().unwrap();
-        let fields = params.get("fields").unwrap().as_sequence().unwrap();
+    pub fn try_new(configuration: Option<serde_yaml::Value>) -> Result<Box<dyn Step>, Error> {
+        let params: SaveDataStepParams = parse_params("SaveDataStep", configuration)?;
 
         Ok(Box::new(SaveDataStep {
-            file: file.to_string(),
-            fields: fields.clone(),
+            file: params.file,
+            fields: params.fields,
         }))
     }
 }
 
 #[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
 impl Step for SaveDataStep {
-    fn process(&mut self, data: &mut HashMap<String, SharedData>) -> Result<ProcessStatus, String> {
-        // TODO: better error handling
-        let mut file = fs::File::create(self.file.clone()).unwrap();
+    fn process(&mut self, data: &mut HashMap<String, SharedData>) -> Result<ProcessStatus, Error> {
+        let path = Path::new(&self.file);
+        let mut file = fs::File::create(path).map_err(|err| Error::from(err).in_file(path))?;
 
         for field in &self.fields {
             let default = SharedData::String("<Field Not Present>".to_string());
-            let value = data.get(field.as_str().unwrap()).unwrap_or(&default);
-            writeln!(file, "{}: \n{value}", field.as_str().unwrap()).unwrap();
+            let value = data.get(field).unwrap_or(&default);
+            writeln!(file, "{field}: \n{value}").map_err(|err| Error::from(err).in_file(path))?;
         }
 
         Ok(ProcessStatus::Complete)
     }
+
+    fn inputs(&self) -> Vec<String> {
+        self.fields.clone()
+    }
 }
@@ -0,0 +1,57 @@
+use std::collections::HashMap;
+
+use crate::error::Error;
+use crate::eval_metrics::{move_metrics, Side};
+use crate::game::Game;
+use crate::step_param_utils::parse_params;
+use crate::workflow_step::{ProcessStatus, SharedData, Step};
+
+step_params! {
+    /// Maps every binned game to the Lichess-style accuracy, from 0 to 100, of the moves of the
+    /// selected players, from the win percentage each move gives up
+    pub struct AccuracyMapParams {
+        /// Vector of binned games to read
+        input: String,
+        /// Vector the mapped values are written to
+        output: String,
+        /// Player whose moves are measured: white, black or both
+        player: Side = "both",
+        /// Flag raised once the input is complete
+        input_flag: String = "<input>_complete",
+        /// Flag raised once this step is done
+        output_flag: String = "<output>_complete",
+    }
+}
+
+#[derive(Debug)]
+pub struct AccuracyMap {
+    input_vec_name: String,
+    output_vec_name: String,
+    player: Side,
+    input_flag: String,
+    output_flag: String,
+}
+
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+impl AccuracyMap {
+    pub fn try_new(configuration: Option<serde_yaml::Value>) -> Result<Box<dyn Step>, Error> {
+        let params: AccuracyMapParams = parse_params("AccuracyMap", configuration)?;
+
+        Ok(Box::new(AccuracyMap {
+            input_vec_name: params.input,
+            output_vec_name: params.output,
+            player: params.player,
+            input_flag: params.input_flag,
+            output_flag: params.output_flag,
+        }))
+    }
+
+    pub fn map(game: &Game, map: &AccuracyMap) -> SharedData {
+        SharedData::U64(move_metrics(game, map.player).average_accuracy())
+    }
+}
+
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+impl Step for AccuracyMap {
+    map_template!(AccuracyMap::map);
+}
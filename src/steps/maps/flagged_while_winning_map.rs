@@ -0,0 +1,112 @@
+use std::collections::HashMap;
+
+use crate::clock_metrics::flagged_while_winning;
+use crate::error::Error;
+use crate::game::Game;
+use crate::step_param_utils::parse_params;
+use crate::workflow_step::{ProcessStatus, SharedData, Step};
+
+step_params! {
+    /// Maps every binned game to 1 when it was lost on time by the player its last evaluation
+    /// had ahead, and to 0 otherwise
+    pub struct FlaggedWhileWinningMapParams {
+        /// Vector of binned games to read
+        input: String,
+        /// Vector the mapped values are written to
+        output: String,
+        /// Centipawns the flagged player must have been ahead by, mates counting as 1000
+        min_advantage: u64 = "200",
+        /// Flag raised once the input is complete
+        input_flag: String = "<input>_complete",
+        /// Flag raised once this step is done
+        output_flag: String = "<output>_complete",
+    }
+}
+
+#[derive(Debug)]
+pub struct FlaggedWhileWinningMap {
+    input_vec_name: String,
+    output_vec_name: String,
+    min_advantage: i32,
+    input_flag: String,
+    output_flag: String,
+}
+
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+impl FlaggedWhileWinningMap {
+    pub fn try_new(configuration: Option<serde_yaml::Value>) -> Result<Box<dyn Step>, Error> {
+        let params: FlaggedWhileWinningMapParams =
+            parse_params("FlaggedWhileWinningMap", configuration)?;
+
+        Ok(Box::new(FlaggedWhileWinningMap {
+            input_vec_name: params.input,
+            output_vec_name: params.output,
+            // Evaluations are capped at 1000 centipawns
+            min_advantage: params.min_advantage.min(1000) as i32,
+            input_flag: params.input_flag,
+            output_flag: params.output_flag,
+        }))
+    }
+
+    pub fn map(game: &Game, map: &FlaggedWhileWinningMap) -> SharedData {
+        SharedData::U64(u64::from(flagged_while_winning(game, map.min_advantage)))
+    }
+}
+
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+impl Step for FlaggedWhileWinningMap {
+    map_template!(FlaggedWhileWinningMap::map);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_pgn::PgnParser;
+
+    fn flagged(min_advantage: &str, movetext: &str) -> SharedData {
+        let result = movetext.split_whitespace().last().unwrap();
+        let headers = vec![
+            format!("[Result \"{result}\"]"),
+            r#"[Termination "Time forfeit"]"#.to_string(),
+        ];
+        let mut game = Game::default();
+        PgnParser::new()
+            .parse_game(&headers, movetext, &mut game)
+            .unwrap();
+
+        let configuration = serde_yaml::from_str(&format!(
+            "{{input: games, output: flagged, min_advantage: {min_advantage}}}"
+        ))
+        .unwrap();
+        let mut step = FlaggedWhileWinningMap::try_new(Some(configuration)).unwrap();
+
+        let mut data = HashMap::new();
+        data.insert(
+            "games".to_string(),
+            SharedData::Vec(vec![SharedData::BinnedValue((
+                Box::new(SharedData::Game(game)),
+                vec![],
+            ))]),
+        );
+        data.insert("games_complete".to_string(), SharedData::Bool(true));
+        assert_eq!(step.process(&mut data), Ok(ProcessStatus::Complete));
+
+        match data.remove("flagged").unwrap().into_vec().unwrap().pop() {
+            Some(SharedData::BinnedValue((value, _))) => *value,
+            other => panic!("Unexpected mapped value: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_flagged_while_winning() {
+        let movetext = "1. e4 { [%eval 0.3] } 1... e5 { [%eval 2.5] } 0-1";
+        assert_eq!(flagged("200", movetext), SharedData::U64(1));
+        assert_eq!(flagged("300", movetext), SharedData::U64(0));
+    }
+
+    #[test]
+    fn test_mate_beats_any_advantage() {
+        let movetext = "1. e4 { [%eval 0.3] } 1... e5 { [%eval #2] } 0-1";
+        assert_eq!(flagged("5000", movetext), SharedData::U64(1));
+    }
+}
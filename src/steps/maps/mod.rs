@@ -1,5 +1,19 @@
+mod accuracy_map;
+mod centipawn_loss_map;
 mod count_map;
+mod flagged_while_winning_map;
+mod move_judgement_map;
 mod perfect_checkmate_map;
+mod position_hash_map;
+mod think_time_map;
+mod time_trouble_map;
 
-pub use count_map::CountMap;
-pub use perfect_checkmate_map::PerfectCheckmateMap;
+pub use accuracy_map::{AccuracyMap, AccuracyMapParams};
+pub use centipawn_loss_map::{CentipawnLossMap, CentipawnLossMapParams};
+pub use count_map::{CountMap, CountMapParams};
+pub use flagged_while_winning_map::{FlaggedWhileWinningMap, FlaggedWhileWinningMapParams};
+pub use move_judgement_map::{MoveJudgementMap, MoveJudgementMapParams};
+pub use perfect_checkmate_map::{PerfectCheckmateMap, PerfectCheckmateMapParams};
+pub use position_hash_map::{PositionHashMap, PositionHashMapParams};
+pub use think_time_map::{ThinkTimeMap, ThinkTimeMapParams};
+pub use time_trouble_map::{TimeTroubleMap, TimeTroubleMapParams};
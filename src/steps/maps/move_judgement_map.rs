@@ -0,0 +1,114 @@
+use std::collections::HashMap;
+
+use crate::error::Error;
+use crate::eval_metrics::{move_metrics, Judgement, Side};
+use crate::game::Game;
+use crate::step_param_utils::parse_params;
+use crate::workflow_step::{ProcessStatus, SharedData, Step};
+
+step_params! {
+    /// Maps every binned game to the number of inaccuracies, mistakes or blunders of the
+    /// selected players, judged from the win percentage each move gives up as Lichess does
+    pub struct MoveJudgementMapParams {
+        /// Vector of binned games to read
+        input: String,
+        /// Vector the mapped values are written to
+        output: String,
+        /// Player whose moves are measured: white, black or both
+        player: Side = "both",
+        /// Moves counted: inaccuracy, mistake or blunder
+        judgement: Judgement,
+        /// Flag raised once the input is complete
+        input_flag: String = "<input>_complete",
+        /// Flag raised once this step is done
+        output_flag: String = "<output>_complete",
+    }
+}
+
+#[derive(Debug)]
+pub struct MoveJudgementMap {
+    input_vec_name: String,
+    output_vec_name: String,
+    player: Side,
+    judgement: Judgement,
+    input_flag: String,
+    output_flag: String,
+}
+
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+impl MoveJudgementMap {
+    pub fn try_new(configuration: Option<serde_yaml::Value>) -> Result<Box<dyn Step>, Error> {
+        let params: MoveJudgementMapParams = parse_params("MoveJudgementMap", configuration)?;
+
+        Ok(Box::new(MoveJudgementMap {
+            input_vec_name: params.input,
+            output_vec_name: params.output,
+            player: params.player,
+            judgement: params.judgement,
+            input_flag: params.input_flag,
+            output_flag: params.output_flag,
+        }))
+    }
+
+    pub fn map(game: &Game, map: &MoveJudgementMap) -> SharedData {
+        SharedData::U64(move_metrics(game, map.player).count(map.judgement))
+    }
+}
+
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+impl Step for MoveJudgementMap {
+    map_template!(MoveJudgementMap::map);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_pgn::PgnParser;
+
+    fn map_value(configuration: &str) -> SharedData {
+        let mut game = Game::default();
+        PgnParser::new()
+            .parse_game(
+                &Vec::new(),
+                "1. f3 { [%eval -0.5] } 1... e5 { [%eval -0.4] } 2. g4 { [%eval #-1] } *",
+                &mut game,
+            )
+            .unwrap();
+
+        let mut step =
+            MoveJudgementMap::try_new(serde_yaml::from_str(configuration).unwrap()).unwrap();
+        let mut data = HashMap::new();
+        data.insert(
+            "games".to_string(),
+            SharedData::Vec(vec![SharedData::BinnedValue((
+                Box::new(SharedData::Game(game)),
+                vec![],
+            ))]),
+        );
+        data.insert("games_complete".to_string(), SharedData::Bool(true));
+        assert_eq!(step.process(&mut data), Ok(ProcessStatus::Complete));
+
+        match data.remove("counts").unwrap().into_vec().unwrap().pop() {
+            Some(SharedData::BinnedValue((value, _))) => *value,
+            other => panic!("Unexpected mapped value: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_counts_selected_player() {
+        let white = "{input: games, output: counts, player: white, judgement: blunder}";
+        assert_eq!(map_value(white), SharedData::U64(1));
+
+        let black = "{input: games, output: counts, player: black, judgement: blunder}";
+        assert_eq!(map_value(black), SharedData::U64(0));
+
+        let both = "{input: games, output: counts, judgement: inaccuracy}";
+        assert_eq!(map_value(both), SharedData::U64(1));
+    }
+
+    #[test]
+    fn test_judgement_is_required() {
+        let configuration = serde_yaml::from_str("{input: games, output: counts}").unwrap();
+        assert!(MoveJudgementMap::try_new(configuration).is_err());
+    }
+}
@@ -1,9 +1,26 @@
 use std::collections::HashMap;
 
 use crate::basic_types::GameResult;
+use crate::error::Error;
 use crate::game::Game;
+use crate::step_param_utils::parse_params;
 use crate::workflow_step::{ProcessStatus, SharedData, Step};
 
+step_params! {
+    /// Maps every binned game to the number of moves, counted back from the mate, played
+    /// with the best mating sequence. Games must have evaluations available.
+    pub struct PerfectCheckmateMapParams {
+        /// Vector of binned games to read
+        input: String,
+        /// Vector the mapped values are written to
+        output: String,
+        /// Flag raised once the input is complete
+        input_flag: String = "<input>_complete",
+        /// Flag raised once this step is done
+        output_flag: String = "<output>_complete",
+    }
+}
+
 #[derive(Debug)]
 pub struct PerfectCheckmateMap {
     input_vec_name: String,
@@ -14,33 +31,14 @@ pub struct PerfectCheckmateMap {
 
 #[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
 impl PerfectCheckmateMap {
-    pub fn try_new(configuration: Option<serde_yaml::Value>) -> Result<Box<dyn Step>, String> {
-        let params = match configuration {
-            Some(value) => value,
-            None => return Err("PerfectCheckmateMap: no parameters provided".to_string()),
-        };
-
-        // TODO: better error handling
-        let input_vec_name = params.get("input").unwrap().as_str().unwrap().to_string();
-        let output_vec_name = params.get("output").unwrap().as_str().unwrap().to_string();
-        let input_flag = params
-            .get("input_flag")
-            .unwrap()
-            .as_str()
-            .unwrap()
-            .to_string();
-        let output_flag = params
-            .get("output_flag")
-            .unwrap()
-            .as_str()
-            .unwrap()
-            .to_string();
+    pub fn try_new(configuration: Option<serde_yaml::Value>) -> Result<Box<dyn Step>, Error> {
+        let params: PerfectCheckmateMapParams = parse_params("PerfectCheckmateMap", configuration)?;
 
         Ok(Box::new(PerfectCheckmateMap {
-            input_vec_name,
-            output_vec_name,
-            input_flag,
-            output_flag,
+            input_vec_name: params.input,
+            output_vec_name: params.output,
+            input_flag: params.input_flag,
+            output_flag: params.output_flag,
         }))
     }
 
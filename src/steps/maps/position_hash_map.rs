@@ -0,0 +1,141 @@
+use std::collections::HashMap;
+
+use crate::error::Error;
+use crate::game::Game;
+use crate::step_param_utils::parse_params;
+use crate::workflow_step::{ProcessStatus, SharedData, Step};
+
+step_params! {
+    /// Maps every binned game to the Zobrist hash and FEN of the positions reached in it, as a
+    /// vector of [hash, fen] pairs
+    pub struct PositionHashMapParams {
+        /// Vector of binned games to read
+        input: String,
+        /// Vector the mapped values are written to
+        output: String,
+        /// Only the position after this many plies, every position from the start when unset
+        ply: Option<usize>,
+        /// Flag raised once the input is complete
+        input_flag: String = "<input>_complete",
+        /// Flag raised once this step is done
+        output_flag: String = "<output>_complete",
+    }
+}
+
+#[derive(Debug)]
+pub struct PositionHashMap {
+    input_vec_name: String,
+    output_vec_name: String,
+    ply: Option<usize>,
+    input_flag: String,
+    output_flag: String,
+}
+
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+impl PositionHashMap {
+    pub fn try_new(configuration: Option<serde_yaml::Value>) -> Result<Box<dyn Step>, Error> {
+        let params: PositionHashMapParams = parse_params("PositionHashMap", configuration)?;
+
+        Ok(Box::new(PositionHashMap {
+            input_vec_name: params.input,
+            output_vec_name: params.output,
+            ply: params.ply,
+            input_flag: params.input_flag,
+            output_flag: params.output_flag,
+        }))
+    }
+
+    // Games that can't be replayed, or end before the ply, map to no positions
+    pub fn map(game: &Game, map: &PositionHashMap) -> SharedData {
+        let boards = match game.replayed_boards() {
+            Ok(boards) => boards,
+            Err(_) => return SharedData::Vec(vec![]),
+        };
+
+        let selected = match map.ply {
+            Some(ply) => boards.get(ply..=ply).unwrap_or_default(),
+            None => &boards[..],
+        };
+
+        SharedData::Vec(
+            selected
+                .iter()
+                .map(|board| {
+                    SharedData::Vec(vec![
+                        SharedData::U64(board.zobrist_hash()),
+                        SharedData::String(board.to_fen()),
+                    ])
+                })
+                .collect(),
+        )
+    }
+}
+
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+impl Step for PositionHashMap {
+    map_template!(PositionHashMap::map);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::basic_types::{File, Move, Piece, Rank};
+
+    fn step(ply: Option<usize>) -> PositionHashMap {
+        PositionHashMap {
+            input_vec_name: "games".to_string(),
+            output_vec_name: "hashes".to_string(),
+            ply,
+            input_flag: "games_complete".to_string(),
+            output_flag: "hashes_complete".to_string(),
+        }
+    }
+
+    fn fens(mapped: SharedData) -> Vec<String> {
+        mapped
+            .into_vec()
+            .unwrap()
+            .into_iter()
+            .map(|pair| pair.into_vec().unwrap()[1].to_string().unwrap().clone())
+            .collect()
+    }
+
+    #[test]
+    fn maps_every_position() {
+        let mut game = Game::default();
+        game.moves = vec![Move::new_to(File::_E, Rank::_4, Piece::Pawn)];
+
+        assert_eq!(
+            fens(PositionHashMap::map(&game, &step(None))),
+            vec![
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+                "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1",
+            ]
+        );
+    }
+
+    #[test]
+    fn maps_the_position_at_the_ply() {
+        let mut game = Game::default();
+        game.moves = vec![Move::new_to(File::_E, Rank::_4, Piece::Pawn)];
+
+        let mapped = PositionHashMap::map(&game, &step(Some(1)))
+            .into_vec()
+            .unwrap();
+        assert_eq!(mapped.len(), 1);
+        assert_eq!(
+            mapped[0].to_vec().unwrap()[0],
+            SharedData::U64(game.build_boards().unwrap()[1].zobrist_hash())
+        );
+
+        assert_eq!(fens(PositionHashMap::map(&game, &step(Some(2)))).len(), 0);
+    }
+
+    #[test]
+    fn maps_illegal_games_to_nothing() {
+        let mut game = Game::default();
+        game.moves = vec![Move::new_to(File::_E, Rank::_5, Piece::Pawn)];
+
+        assert_eq!(fens(PositionHashMap::map(&game, &step(None))).len(), 0);
+    }
+}
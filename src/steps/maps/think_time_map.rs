@@ -0,0 +1,62 @@
+use std::collections::HashMap;
+
+use crate::clock_metrics::{average_think_time, GamePhase};
+use crate::error::Error;
+use crate::eval_metrics::Side;
+use crate::game::Game;
+use crate::step_param_utils::parse_params;
+use crate::workflow_step::{ProcessStatus, SharedData, Step};
+
+step_params! {
+    /// Maps every binned game to the average time, in milliseconds, the selected players spent
+    /// on their moves, the increment given back. Moves without a clock time are left out
+    pub struct ThinkTimeMapParams {
+        /// Vector of binned games to read
+        input: String,
+        /// Vector the mapped values are written to
+        output: String,
+        /// Player whose moves are timed: white, black or both
+        player: Side = "both",
+        /// Only time the moves of one phase: opening, middlegame or endgame
+        phase: Option<GamePhase>,
+        /// Flag raised once the input is complete
+        input_flag: String = "<input>_complete",
+        /// Flag raised once this step is done
+        output_flag: String = "<output>_complete",
+    }
+}
+
+#[derive(Debug)]
+pub struct ThinkTimeMap {
+    input_vec_name: String,
+    output_vec_name: String,
+    player: Side,
+    phase: Option<GamePhase>,
+    input_flag: String,
+    output_flag: String,
+}
+
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+impl ThinkTimeMap {
+    pub fn try_new(configuration: Option<serde_yaml::Value>) -> Result<Box<dyn Step>, Error> {
+        let params: ThinkTimeMapParams = parse_params("ThinkTimeMap", configuration)?;
+
+        Ok(Box::new(ThinkTimeMap {
+            input_vec_name: params.input,
+            output_vec_name: params.output,
+            player: params.player,
+            phase: params.phase,
+            input_flag: params.input_flag,
+            output_flag: params.output_flag,
+        }))
+    }
+
+    pub fn map(game: &Game, map: &ThinkTimeMap) -> SharedData {
+        SharedData::U64(average_think_time(game, map.player, map.phase))
+    }
+}
+
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+impl Step for ThinkTimeMap {
+    map_template!(ThinkTimeMap::map);
+}
@@ -0,0 +1,63 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::clock_metrics::time_trouble_moves;
+use crate::error::Error;
+use crate::eval_metrics::Side;
+use crate::game::Game;
+use crate::step_param_utils::parse_params;
+use crate::workflow_step::{ProcessStatus, SharedData, Step};
+
+step_params! {
+    /// Maps every binned game to the number of moves the selected players started with less
+    /// than the given time on their clock
+    pub struct TimeTroubleMapParams {
+        /// Vector of binned games to read
+        input: String,
+        /// Vector the mapped values are written to
+        output: String,
+        /// Player whose moves are counted: white, black or both
+        player: Side = "both",
+        /// Seconds left on the clock under which a player is in time trouble
+        seconds: u64 = "30",
+        /// Flag raised once the input is complete
+        input_flag: String = "<input>_complete",
+        /// Flag raised once this step is done
+        output_flag: String = "<output>_complete",
+    }
+}
+
+#[derive(Debug)]
+pub struct TimeTroubleMap {
+    input_vec_name: String,
+    output_vec_name: String,
+    player: Side,
+    threshold: Duration,
+    input_flag: String,
+    output_flag: String,
+}
+
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+impl TimeTroubleMap {
+    pub fn try_new(configuration: Option<serde_yaml::Value>) -> Result<Box<dyn Step>, Error> {
+        let params: TimeTroubleMapParams = parse_params("TimeTroubleMap", configuration)?;
+
+        Ok(Box::new(TimeTroubleMap {
+            input_vec_name: params.input,
+            output_vec_name: params.output,
+            player: params.player,
+            threshold: Duration::from_secs(params.seconds),
+            input_flag: params.input_flag,
+            output_flag: params.output_flag,
+        }))
+    }
+
+    pub fn map(game: &Game, map: &TimeTroubleMap) -> SharedData {
+        SharedData::U64(time_trouble_moves(game, map.player, map.threshold))
+    }
+}
+
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+impl Step for TimeTroubleMap {
+    map_template!(TimeTroubleMap::map);
+}
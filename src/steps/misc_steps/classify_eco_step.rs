@@ -0,0 +1,204 @@
+use std::collections::HashMap;
+
+use crate::{
+    eco,
+    error::Error,
+    step_param_utils::parse_params,
+    workflow_step::{ProcessStatus, SharedData, Step},
+};
+
+step_params! {
+    /// Fills the ECO code and "Opening" tag of each game from the bundled ECO table
+    pub struct ClassifyEcoStepParams {
+        /// Vector of games to read
+        input: String,
+        /// Vector the classified games are written to
+        output: String,
+        /// Flag raised once this step is done
+        output_flag: String = "<output>_complete",
+        /// Replace the ECO code and opening name the game headers already hold
+        overwrite: bool = "false",
+    }
+}
+
+#[derive(Debug)]
+pub struct ClassifyEcoStep {
+    input_vec_name: String,
+    output_vec_name: String,
+    output_flag: String,
+    overwrite: bool,
+}
+
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+impl ClassifyEcoStep {
+    pub fn try_new(configuration: Option<serde_yaml::Value>) -> Result<Box<dyn Step>, Error> {
+        let params: ClassifyEcoStepParams = parse_params("ClassifyEcoStep", configuration)?;
+
+        Ok(Box::new(ClassifyEcoStep {
+            input_vec_name: params.input,
+            output_vec_name: params.output,
+            output_flag: params.output_flag,
+            overwrite: params.overwrite,
+        }))
+    }
+}
+
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+impl Step for ClassifyEcoStep {
+    fn process(&mut self, data: &mut HashMap<String, SharedData>) -> Result<ProcessStatus, Error> {
+        data.insert(self.output_vec_name.clone(), SharedData::Vec(vec![]));
+
+        let games = {
+            let potential_data = data.get(&self.input_vec_name);
+            let shared_data = match potential_data {
+                Some(shared_data) => shared_data,
+                None => return Ok(ProcessStatus::Complete),
+            };
+            let vec_to_classify = shared_data.to_vec().unwrap();
+
+            data.insert(self.input_vec_name.clone(), SharedData::Vec(vec![]));
+
+            vec_to_classify
+        };
+
+        if games.is_empty() {
+            data.insert(self.output_flag.clone(), SharedData::Bool(true));
+            return Ok(ProcessStatus::Complete);
+        }
+
+        let mut output_games: Vec<SharedData> = vec![];
+
+        for shared_game in games {
+            let mut game = match shared_game {
+                SharedData::Game(game) => game,
+                _ => return Err(Error::data_type("Vector isn't of games!")),
+            };
+
+            if let Some(entry) = eco::classify(&game) {
+                // Headers without an ECO code leave the category at '-', or '\0' for "?"
+                if self.overwrite || !game.eco_category.is_ascii_uppercase() {
+                    game.eco_category = entry.category;
+                    game.eco_subcategory = entry.subcategory;
+                }
+
+                match game.tags.iter_mut().find(|(name, _)| name == "Opening") {
+                    Some((_, value)) if self.overwrite => *value = entry.name.to_string(),
+                    Some(_) => {}
+                    None => game
+                        .tags
+                        .push(("Opening".to_string(), entry.name.to_string())),
+                }
+            }
+
+            output_games.push(SharedData::Game(game));
+        }
+
+        let potential_data = data.get(&self.output_vec_name);
+        let shared_data = match potential_data {
+            Some(shared_data) => shared_data,
+            None => return Err(Error::data_type("ClassifyEcoStep: no output vector")),
+        };
+        let mut vec_to_append = shared_data.to_vec().unwrap();
+
+        vec_to_append.append(&mut output_games);
+        data.insert(self.output_vec_name.clone(), SharedData::Vec(vec_to_append));
+
+        Ok(ProcessStatus::Incomplete)
+    }
+
+    fn inputs(&self) -> Vec<String> {
+        vec![self.input_vec_name.clone()]
+    }
+
+    fn outputs(&self) -> Vec<String> {
+        vec![self.output_vec_name.clone()]
+    }
+
+    fn output_flags(&self) -> Vec<String> {
+        vec![self.output_flag.clone()]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Game;
+    use crate::parse_pgn::PgnParser;
+
+    fn classify(headers: &[&str], overwrite: bool) -> Game {
+        let headers = headers.iter().map(|header| header.to_string()).collect();
+        let mut game = Game::default();
+        PgnParser::new()
+            .parse_game(&headers, "1. e4 c5 2. Nf3 e6 *", &mut game)
+            .unwrap();
+
+        let configuration = serde_yaml::from_str(&format!(
+            "{{input: games, output: out, overwrite: {overwrite}}}"
+        ))
+        .unwrap();
+        let mut step = ClassifyEcoStep::try_new(Some(configuration)).unwrap();
+
+        let mut data = HashMap::new();
+        data.insert(
+            "games".to_string(),
+            SharedData::Vec(vec![SharedData::Game(game)]),
+        );
+        assert_eq!(step.process(&mut data), Ok(ProcessStatus::Incomplete));
+
+        match data.remove("out").unwrap().into_vec().unwrap().pop() {
+            Some(SharedData::Game(game)) => game,
+            other => panic!("Unexpected output: {other:?}"),
+        }
+    }
+
+    fn opening_tag(game: &Game) -> Option<&str> {
+        game.tags
+            .iter()
+            .find(|(name, _)| name == "Opening")
+            .map(|(_, value)| value.as_str())
+    }
+
+    #[test]
+    fn test_missing_headers_are_filled() {
+        let game = classify(&[], false);
+        assert_eq!((game.eco_category, game.eco_subcategory), ('B', 40));
+        assert_eq!(
+            opening_tag(&game),
+            Some("Sicilian Defense: French Variation")
+        );
+    }
+
+    #[test]
+    fn test_unknown_eco_is_filled() {
+        let game = classify(&[r#"[ECO "?"]"#], false);
+        assert_eq!((game.eco_category, game.eco_subcategory), ('B', 40));
+    }
+
+    #[test]
+    fn test_headers_are_kept() {
+        let game = classify(&[r#"[ECO "B20"]"#, r#"[Opening "Sicilian"]"#], false);
+        assert_eq!((game.eco_category, game.eco_subcategory), ('B', 20));
+        assert_eq!(opening_tag(&game), Some("Sicilian"));
+    }
+
+    #[test]
+    fn test_headers_are_overwritten() {
+        let game = classify(&[r#"[ECO "B20"]"#, r#"[Opening "Sicilian"]"#], true);
+        assert_eq!((game.eco_category, game.eco_subcategory), ('B', 40));
+        assert_eq!(
+            opening_tag(&game),
+            Some("Sicilian Defense: French Variation")
+        );
+    }
+
+    #[test]
+    fn test_empty_input_raises_flag() {
+        let mut step =
+            ClassifyEcoStep::try_new(Some(serde_yaml::from_str("{input: a, output: b}").unwrap()))
+                .unwrap();
+        let mut data = HashMap::new();
+        data.insert("a".to_string(), SharedData::Vec(vec![]));
+        assert_eq!(step.process(&mut data), Ok(ProcessStatus::Complete));
+        assert_eq!(data.get("b_complete"), Some(&SharedData::Bool(true)));
+    }
+}
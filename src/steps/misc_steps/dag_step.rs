@@ -0,0 +1,102 @@
+use std::collections::HashMap;
+
+use crate::error::Error;
+use crate::scheduler::Scheduler;
+use crate::step_param_utils::parse_params;
+use crate::steps_manager::get_step;
+use crate::workflow_step::{ProcessStatus, SharedData, Step};
+
+step_params! {
+    /// Runs its child steps in the order implied by the data they read and write,
+    /// whatever order they are listed in
+    pub struct DagStepParams {
+        /// Steps to run
+        children: Vec<String>,
+        /// Step to run once every child is done
+        post: String = "noop",
+    }
+}
+
+#[derive(Debug)]
+pub struct DagStep {
+    children_names: Vec<String>,
+    post_name: String,
+}
+
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+impl DagStep {
+    pub fn try_new(configuration: Option<serde_yaml::Value>) -> Result<Box<dyn Step>, Error> {
+        let params: DagStepParams = parse_params("DagStep", configuration)?;
+
+        Ok(Box::new(DagStep {
+            children_names: params.children,
+            post_name: params.post,
+        }))
+    }
+}
+
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+impl Step for DagStep {
+    fn process(&mut self, data: &mut HashMap<String, SharedData>) -> Result<ProcessStatus, Error> {
+        let mut scheduler = Scheduler::try_new(&self.children_names, data)?;
+        scheduler.run(data)?;
+
+        let mut post = get_step(&self.post_name, data)?;
+        post.process(data)
+            .map_err(|err| err.in_step(&self.post_name))?;
+
+        Ok(ProcessStatus::Complete)
+    }
+
+    fn child_steps(&self) -> Vec<String> {
+        let mut child_steps = self.children_names.clone();
+        child_steps.push(self.post_name.clone());
+        child_steps
+    }
+}
+
+#[cfg(test)]
+mod test_try_new {
+    use serde_yaml::{Mapping, Value};
+
+    use super::*;
+
+    #[test]
+    fn no_params() {
+        assert_eq!(
+            DagStep::try_new(None).unwrap_err(),
+            Error::config("DagStep: missing field `children`")
+        );
+    }
+
+    #[test]
+    fn children_must_be_a_list() {
+        let mut params = Mapping::new();
+        params.insert(
+            Value::String("children".to_string()),
+            Value::String("a".to_string()),
+        );
+
+        assert!(DagStep::try_new(Some(Value::Mapping(params)))
+            .unwrap_err()
+            .to_string()
+            .starts_with("DagStep: invalid type: string \"a\""));
+    }
+
+    #[test]
+    fn post_defaults_to_noop() {
+        let mut params = Mapping::new();
+        params.insert(
+            Value::String("children".to_string()),
+            Value::Sequence(vec![Value::String("a".to_string())]),
+        );
+
+        assert_eq!(
+            format!(
+                "{:?}",
+                DagStep::try_new(Some(Value::Mapping(params))).unwrap()
+            ),
+            r#"DagStep { children_names: ["a"], post_name: "noop" }"#
+        );
+    }
+}
@@ -1,41 +1,58 @@
 use std::collections::HashMap;
 
 use crate::{
-    step_param_utils::get_required_parameter,
+    error::Error,
+    error_policy::{ErrorPolicy, OnError, Record},
+    step_param_utils::parse_params,
     workflow_step::{ProcessStatus, SharedData, Step},
 };
 
+step_params! {
+    /// Builds the board after every move of each game
+    pub struct InitBoardsStepParams {
+        /// Vector of games to read
+        input: String,
+        /// Vector the games with their boards are written to
+        output: String,
+        /// Flag raised once this step is done
+        output_flag: String = "<output>_complete",
+        /// What to do with a game holding an illegal move: abort, skip or quarantine
+        on_error: OnError = "abort",
+        /// File games holding an illegal move are appended to, with their error
+        quarantine_file: Option<String>,
+    }
+}
+
 #[derive(Debug)]
 pub struct InitBoardsStep {
     input_vec_name: String,
     output_vec_name: String,
     output_flag: String,
+    games_seen: usize,
+    error_policy: ErrorPolicy,
 }
 
 #[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
 impl InitBoardsStep {
-    pub fn try_new(configuration: Option<serde_yaml::Value>) -> Result<Box<dyn Step>, String> {
-        let params = match configuration {
-            Some(value) => value,
-            None => return Err("GenericFilter: no parameters provided".to_string()),
-        };
-
-        let input_vec_name = get_required_parameter("InitBoardsStep", "input", &params)?;
-        let output_vec_name = get_required_parameter("InitBoardsStep", "output", &params)?;
+    pub fn try_new(configuration: Option<serde_yaml::Value>) -> Result<Box<dyn Step>, Error> {
+        let params: InitBoardsStepParams = parse_params("InitBoardsStep", configuration)?;
 
-        let output_flag = get_required_parameter("InitBoardsStep", "output_flag", &params)?;
+        let error_policy =
+            ErrorPolicy::try_new("InitBoardsStep", params.on_error, params.quarantine_file)?;
 
         Ok(Box::new(InitBoardsStep {
-            input_vec_name,
-            output_vec_name,
-            output_flag,
+            input_vec_name: params.input,
+            output_vec_name: params.output,
+            output_flag: params.output_flag,
+            games_seen: 0,
+            error_policy,
         }))
     }
 }
 
 #[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
 impl Step for InitBoardsStep {
-    fn process(&mut self, data: &mut HashMap<String, SharedData>) -> Result<ProcessStatus, String> {
+    fn process(&mut self, data: &mut HashMap<String, SharedData>) -> Result<ProcessStatus, Error> {
         data.insert(self.output_vec_name.clone(), SharedData::Vec(vec![]));
 
         let games = {
@@ -62,17 +79,29 @@ impl Step for InitBoardsStep {
         for shared_game in games {
             let mut game = match shared_game.clone() {
                 SharedData::Game(game) => game,
-                _ => return Err("Vector isn't of games!".to_string()),
+                _ => return Err(Error::data_type("Vector isn't of games!")),
             };
 
-            game.boards = game.build_boards();
-            output_games.push(SharedData::Game(game));
+            let game_index = self.games_seen;
+            self.games_seen += 1;
+
+            match game.build_boards() {
+                Ok(boards) => {
+                    game.boards = boards;
+                    output_games.push(SharedData::Game(game));
+                }
+                Err(err) => {
+                    let record = Record::Binary(postcard::to_allocvec(&game)?);
+                    self.error_policy
+                        .handle(err.in_game(game_index), record, data)?;
+                }
+            }
         }
 
         let potential_data = data.get(&self.output_vec_name);
         let shared_data = match potential_data {
             Some(shared_data) => shared_data,
-            None => return Err("GenericFilter: no output vector".to_string()),
+            None => return Err(Error::data_type("InitBoardsStep: no output vector")),
         };
         let mut vec_to_append = shared_data.to_vec().unwrap();
 
@@ -81,6 +110,20 @@ impl Step for InitBoardsStep {
 
         Ok(ProcessStatus::Incomplete)
     }
+
+    fn inputs(&self) -> Vec<String> {
+        vec![self.input_vec_name.clone()]
+    }
+
+    fn outputs(&self) -> Vec<String> {
+        let mut outputs = vec![self.output_vec_name.clone()];
+        outputs.extend(self.error_policy.outputs());
+        outputs
+    }
+
+    fn output_flags(&self) -> Vec<String> {
+        vec![self.output_flag.clone()]
+    }
 }
 
 #[cfg(test)]
@@ -96,7 +139,7 @@ mod tests {
         assert!(res.is_err());
         assert_eq!(
             res.unwrap_err(),
-            "InitBoardsStep: parameter 'input' is required".to_string()
+            Error::config("InitBoardsStep: missing field `input`")
         );
     }
 }
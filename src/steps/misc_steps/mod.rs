@@ -1,11 +1,19 @@
+mod classify_eco_step;
+mod dag_step;
 mod init_boards_step;
 mod noop_step;
 mod parallel_step;
+mod pipeline_step;
 mod serial_step;
+mod tag_book_exit_step;
 mod ui_monitor_step;
 
-pub use init_boards_step::InitBoardsStep;
-pub use noop_step::NoopStep;
-pub use parallel_step::ParallelStep;
-pub use serial_step::SerialStep;
-pub use ui_monitor_step::UiMonitorStep;
+pub use classify_eco_step::{ClassifyEcoStep, ClassifyEcoStepParams};
+pub use dag_step::{DagStep, DagStepParams};
+pub use init_boards_step::{InitBoardsStep, InitBoardsStepParams};
+pub use noop_step::{NoopStep, NoopStepParams};
+pub use parallel_step::{ParallelStep, ParallelStepParams};
+pub use pipeline_step::{PipelineStep, PipelineStepParams};
+pub use serial_step::{SerialStep, SerialStepParams};
+pub use tag_book_exit_step::{TagBookExitStep, TagBookExitStepParams};
+pub use ui_monitor_step::{UiMonitorStep, UiMonitorStepParams};
@@ -1,12 +1,25 @@
 use std::collections::HashMap;
 
+use crate::error::Error;
+use crate::step_param_utils::parse_params;
 use crate::workflow_step::{BoxedStep, ProcessStatus, SharedData, Step};
 
+step_params! {
+    /// Does nothing, used where a step is required but no work is needed
+    pub struct NoopStepParams {}
+}
+
 #[derive(Debug)]
 pub struct NoopStep {}
 
 #[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
 impl NoopStep {
+    pub fn try_new(configuration: Option<serde_yaml::Value>) -> Result<BoxedStep, Error> {
+        let _: NoopStepParams = parse_params("NoopStep", configuration)?;
+
+        Ok(NoopStep::boxed_new())
+    }
+
     pub fn boxed_new() -> BoxedStep {
         Box::new(NoopStep {})
     }
@@ -14,10 +27,7 @@ impl NoopStep {
 
 #[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
 impl Step for NoopStep {
-    fn process(
-        &mut self,
-        _data: &mut HashMap<String, SharedData>,
-    ) -> Result<ProcessStatus, String> {
+    fn process(&mut self, _data: &mut HashMap<String, SharedData>) -> Result<ProcessStatus, Error> {
         Ok(ProcessStatus::Complete)
     }
 }
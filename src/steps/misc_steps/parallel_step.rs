@@ -1,10 +1,22 @@
 use std::collections::HashMap;
 
-use crate::steps_manager::get_step_description;
+use crate::error::Error;
+use crate::step_param_utils::parse_params;
+use crate::steps_manager::get_step;
 use crate::workflow_step::{ProcessStatus, SharedData, Step};
 
 use super::noop_step::NoopStep;
 
+step_params! {
+    /// Runs its child steps in turn, one call each, until all of them are complete
+    pub struct ParallelStepParams {
+        /// Steps to run
+        children: Vec<String>,
+        /// Step to run once every child is done
+        post: String = "noop",
+    }
+}
+
 #[derive(Debug)]
 pub struct ParallelStep {
     children_names: Vec<String>,
@@ -13,33 +25,19 @@ pub struct ParallelStep {
 
 #[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
 impl ParallelStep {
-    pub fn try_new(configuration: Option<serde_yaml::Value>) -> Result<Box<dyn Step>, String> {
-        let params = match configuration {
-            Some(value) => value,
-            None => return Err("ParallelStep: no parameters provided".to_string()),
-        };
-
-        // TODO: better error handling
-        let children = params.get("children").unwrap().as_sequence().unwrap();
+    pub fn try_new(configuration: Option<serde_yaml::Value>) -> Result<Box<dyn Step>, Error> {
+        let params: ParallelStepParams = parse_params("ParallelStep", configuration)?;
 
         Ok(Box::new(ParallelStep {
-            children_names: children
-                .iter()
-                .map(|config_str| config_str.as_str().unwrap().to_string())
-                .collect(),
-            post_name: params
-                .get("post")
-                .unwrap_or(&serde_yaml::Value::String("noop".to_string()))
-                .as_str()
-                .unwrap()
-                .to_string(),
+            children_names: params.children,
+            post_name: params.post,
         }))
     }
 }
 
 #[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
 impl Step for ParallelStep {
-    fn process(&mut self, data: &mut HashMap<String, SharedData>) -> Result<ProcessStatus, String> {
+    fn process(&mut self, data: &mut HashMap<String, SharedData>) -> Result<ProcessStatus, Error> {
         // TODO make own step
         {
             let d: bool = false;
@@ -50,27 +48,39 @@ impl Step for ParallelStep {
 
         let mut children = vec![];
         for child_name in &self.children_names {
-            let child = get_step_description(child_name, data);
-            let step = child.to_step().expect("ok");
-            children.push(step);
+            children.push((child_name, get_step(child_name, data)?));
         }
 
         let mut any_not_done = true;
         while any_not_done {
             any_not_done = false;
-            for step in &mut children {
-                let res = (step.as_mut()).process(data).unwrap();
+            for (child_name, step) in &mut children {
+                let res = step
+                    .process(data)
+                    .map_err(|err| err.in_step(child_name.as_str()))?;
                 if res == ProcessStatus::Complete {
                     any_not_done = true;
                 }
             }
         }
 
-        let mut post = get_step_description(&self.post_name, data)
-            .to_step()
-            .unwrap_or_else(|_| Box::new(NoopStep {}));
-        post.process(data)?;
+        let mut post = get_step(&self.post_name, data).unwrap_or_else(|_| Box::new(NoopStep {}));
+        post.process(data)
+            .map_err(|err| err.in_step(&self.post_name))?;
 
         Ok(ProcessStatus::Complete)
     }
+
+    fn output_flags(&self) -> Vec<String> {
+        vec![
+            "done_reading_files".to_string(),
+            "done_parsing_games".to_string(),
+        ]
+    }
+
+    fn child_steps(&self) -> Vec<String> {
+        let mut child_steps = self.children_names.clone();
+        child_steps.push(self.post_name.clone());
+        child_steps
+    }
 }
@@ -0,0 +1,116 @@
+use std::collections::HashMap;
+
+use crate::error::Error;
+use crate::pipeline::Pipeline;
+use crate::scheduler::Scheduler;
+use crate::step_param_utils::parse_params;
+use crate::steps_manager::get_step;
+use crate::workflow_step::{ProcessStatus, SharedData, Step};
+
+step_params! {
+    /// Runs every child step on its own thread, passing data between them through bounded
+    /// channels so that slow steps hold back the ones feeding them
+    pub struct PipelineStepParams {
+        /// Steps to run
+        children: Vec<String>,
+        /// Step to run once every child is done
+        post: String = "noop",
+        /// Number of batches each channel holds before its producer waits
+        channel_capacity: usize = "16",
+    }
+}
+
+#[derive(Debug)]
+pub struct PipelineStep {
+    children_names: Vec<String>,
+    post_name: String,
+    channel_capacity: usize,
+}
+
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+impl PipelineStep {
+    pub fn try_new(configuration: Option<serde_yaml::Value>) -> Result<Box<dyn Step>, Error> {
+        let params: PipelineStepParams = parse_params("PipelineStep", configuration)?;
+
+        if params.channel_capacity == 0 {
+            return Err(Error::config(
+                "PipelineStep: parameter 'channel_capacity' must be a positive integer",
+            ));
+        }
+
+        Ok(Box::new(PipelineStep {
+            children_names: params.children,
+            post_name: params.post,
+            channel_capacity: params.channel_capacity,
+        }))
+    }
+}
+
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+impl Step for PipelineStep {
+    fn process(&mut self, data: &mut HashMap<String, SharedData>) -> Result<ProcessStatus, Error> {
+        let scheduler = Scheduler::try_new(&self.children_names, data)?;
+        Pipeline::new(scheduler, self.channel_capacity).run(data)?;
+
+        let mut post = get_step(&self.post_name, data)?;
+        post.process(data)
+            .map_err(|err| err.in_step(&self.post_name))?;
+
+        Ok(ProcessStatus::Complete)
+    }
+
+    fn child_steps(&self) -> Vec<String> {
+        let mut child_steps = self.children_names.clone();
+        child_steps.push(self.post_name.clone());
+        child_steps
+    }
+}
+
+#[cfg(test)]
+mod test_try_new {
+    use serde_yaml::{Mapping, Value};
+
+    use super::*;
+
+    fn params_with_children() -> Mapping {
+        let mut params = Mapping::new();
+        params.insert(
+            Value::String("children".to_string()),
+            Value::Sequence(vec![Value::String("a".to_string())]),
+        );
+        params
+    }
+
+    #[test]
+    fn no_params() {
+        assert_eq!(
+            PipelineStep::try_new(None).unwrap_err(),
+            Error::config("PipelineStep: missing field `children`")
+        );
+    }
+
+    #[test]
+    fn defaults() {
+        assert_eq!(
+            format!(
+                "{:?}",
+                PipelineStep::try_new(Some(Value::Mapping(params_with_children()))).unwrap()
+            ),
+            r#"PipelineStep { children_names: ["a"], post_name: "noop", channel_capacity: 16 }"#
+        );
+    }
+
+    #[test]
+    fn zero_capacity_is_rejected() {
+        let mut params = params_with_children();
+        params.insert(
+            Value::String("channel_capacity".to_string()),
+            Value::Number(serde_yaml::Number::from(0)),
+        );
+
+        assert_eq!(
+            PipelineStep::try_new(Some(Value::Mapping(params))).unwrap_err(),
+            Error::config("PipelineStep: parameter 'channel_capacity' must be a positive integer")
+        );
+    }
+}
@@ -1,36 +1,43 @@
 use std::collections::HashMap;
 
-use crate::steps_manager::get_step_description;
+use crate::error::Error;
+use crate::step_param_utils::parse_params;
+use crate::steps_manager::get_step;
 use crate::workflow_step::{ProcessStatus, SharedData, Step};
 
+use super::noop_step::NoopStep;
+
+step_params! {
+    /// Runs each child step to completion, one after the other
+    pub struct SerialStepParams {
+        /// Steps to run, in order
+        children: Vec<String>,
+        /// Step to run once every child is done
+        post: String = "noop",
+    }
+}
+
 #[derive(Debug)]
 pub struct SerialStep {
     children_names: Vec<String>,
+    post_name: String,
 }
 
 #[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
 impl SerialStep {
-    pub fn try_new(configuration: Option<serde_yaml::Value>) -> Result<Box<dyn Step>, String> {
-        let params = match configuration {
-            Some(value) => value,
-            None => return Err("SerialStep: no parameters provided".to_string()),
-        };
-
-        // TODO: better error handling
-        let children = params.get("children").unwrap().as_sequence().unwrap();
+    pub fn try_new(configuration: Option<serde_yaml::Value>) -> Result<Box<dyn Step>, Error> {
+        let params: SerialStepParams = parse_params("SerialStep", configuration)?;
 
         Ok(Box::new(SerialStep {
-            children_names: children
-                .iter()
-                .map(|config_str| config_str.as_str().unwrap().to_string())
-                .collect(),
+            children_names: params.children,
+            post_name: params.post,
         }))
     }
 }
 
 #[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
 impl Step for SerialStep {
-    fn process(&mut self, data: &mut HashMap<String, SharedData>) -> Result<ProcessStatus, String> {
+    fn process(&mut self, data: &mut HashMap<String, SharedData>) -> Result<ProcessStatus, Error> {
         // TODO make own step
         {
             let d: bool = false;
@@ -40,11 +47,29 @@ impl Step for SerialStep {
         }
 
         for child_name in self.children_names.clone() {
-            let child = get_step_description(&child_name, data);
-            let mut step = child.to_step().expect("ok");
-            while step.process(data)? == ProcessStatus::Incomplete {}
+            let mut step = get_step(&child_name, data)?;
+            while step.process(data).map_err(|err| err.in_step(&child_name))?
+                == ProcessStatus::Incomplete
+            {}
         }
 
+        let mut post = get_step(&self.post_name, data).unwrap_or_else(|_| Box::new(NoopStep {}));
+        post.process(data)
+            .map_err(|err| err.in_step(&self.post_name))?;
+
         Ok(ProcessStatus::Complete)
     }
+
+    fn output_flags(&self) -> Vec<String> {
+        vec![
+            "done_reading_files".to_string(),
+            "done_parsing_games".to_string(),
+        ]
+    }
+
+    fn child_steps(&self) -> Vec<String> {
+        let mut child_steps = self.children_names.clone();
+        child_steps.push(self.post_name.clone());
+        child_steps
+    }
 }
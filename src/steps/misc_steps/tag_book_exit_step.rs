@@ -0,0 +1,129 @@
+use std::{collections::HashMap, path::Path};
+
+use crate::{
+    error::Error,
+    polyglot::PolyglotBook,
+    step_param_utils::parse_params,
+    workflow_step::{ProcessStatus, SharedData, Step},
+};
+
+step_params! {
+    /// Tags each game with the ply of its first move missing from a Polyglot opening book,
+    /// leaving games that never leave the book untagged
+    pub struct TagBookExitStepParams {
+        /// Vector of games to read
+        input: String,
+        /// Vector the tagged games are written to
+        output: String,
+        /// Polyglot `.bin` book the games are checked against
+        book: String,
+        /// Name of the tag holding the ply, replaced if the game already has it
+        tag: String = "BookExitPly",
+        /// Flag raised once this step is done
+        output_flag: String = "<output>_complete",
+    }
+}
+
+#[derive(Debug)]
+pub struct TagBookExitStep {
+    input_vec_name: String,
+    output_vec_name: String,
+    book: PolyglotBook,
+    tag: String,
+    output_flag: String,
+}
+
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+impl TagBookExitStep {
+    pub fn try_new(configuration: Option<serde_yaml::Value>) -> Result<Box<dyn Step>, Error> {
+        let params: TagBookExitStepParams = parse_params("TagBookExitStep", configuration)?;
+
+        let book = PolyglotBook::read(Path::new(&params.book))?;
+
+        Ok(Box::new(TagBookExitStep {
+            input_vec_name: params.input,
+            output_vec_name: params.output,
+            book,
+            tag: params.tag,
+            output_flag: params.output_flag,
+        }))
+    }
+}
+
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+impl Step for TagBookExitStep {
+    fn process(&mut self, data: &mut HashMap<String, SharedData>) -> Result<ProcessStatus, Error> {
+        data.insert(self.output_vec_name.clone(), SharedData::Vec(vec![]));
+
+        let games = {
+            let potential_data = data.get(&self.input_vec_name);
+            let shared_data = match potential_data {
+                Some(shared_data) => shared_data,
+                None => return Ok(ProcessStatus::Complete),
+            };
+            let vec_to_tag = shared_data.to_vec().unwrap();
+
+            data.insert(self.input_vec_name.clone(), SharedData::Vec(vec![]));
+
+            vec_to_tag
+        };
+
+        if games.is_empty() {
+            data.insert(self.output_flag.clone(), SharedData::Bool(true));
+            return Ok(ProcessStatus::Complete);
+        }
+
+        let mut output_games: Vec<SharedData> = vec![];
+
+        for shared_game in games {
+            let mut game = match shared_game {
+                SharedData::Game(game) => game,
+                _ => return Err(Error::data_type("Vector isn't of games!")),
+            };
+
+            game.tags.retain(|(name, _)| *name != self.tag);
+            if let Some(ply) = self.book.exit_ply(&game) {
+                game.tags.push((self.tag.clone(), ply.to_string()));
+            }
+
+            output_games.push(SharedData::Game(game));
+        }
+
+        let potential_data = data.get(&self.output_vec_name);
+        let shared_data = match potential_data {
+            Some(shared_data) => shared_data,
+            None => return Err(Error::data_type("TagBookExitStep: no output vector")),
+        };
+        let mut vec_to_append = shared_data.to_vec().unwrap();
+
+        vec_to_append.append(&mut output_games);
+        data.insert(self.output_vec_name.clone(), SharedData::Vec(vec_to_append));
+
+        Ok(ProcessStatus::Incomplete)
+    }
+
+    fn inputs(&self) -> Vec<String> {
+        vec![self.input_vec_name.clone()]
+    }
+
+    fn outputs(&self) -> Vec<String> {
+        vec![self.output_vec_name.clone()]
+    }
+
+    fn output_flags(&self) -> Vec<String> {
+        vec![self.output_flag.clone()]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_missing_book() {
+        let configuration =
+            serde_yaml::from_str("{input: games, output: tagged, book: missing/book.bin}").unwrap();
+        let err = TagBookExitStep::try_new(Some(configuration)).unwrap_err();
+        assert_eq!(err.file(), Some(Path::new("missing/book.bin")));
+    }
+}
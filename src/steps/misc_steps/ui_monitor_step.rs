@@ -13,10 +13,40 @@ use tui::{
     widgets::{Block, Borders, List, ListItem},
 };
 
+use crate::error::Error;
+use crate::step_param_utils::{parse_params, ParamType};
 use crate::workflow_step::{ProcessStatus, SharedData, Step};
 
+#[derive(Debug, PartialEq, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MonitoredField {
+    display_name: String,
+    field: String,
+}
+
+impl ParamType for MonitoredField {
+    fn type_name() -> String {
+        "map of display_name and field".to_string()
+    }
+}
+
+step_params! {
+    /// Shows the given data entries in the terminal until 'q' is pressed
+    pub struct UiMonitorStepParams {
+        /// Flag that stops the timer once raised
+        finish_flag: String,
+        /// Map whose entries are all shown
+        final_results: String,
+        /// Entries shown as they are
+        raw: Option<Vec<MonitoredField>>,
+        /// Vectors whose length is shown
+        length: Option<Vec<MonitoredField>>,
+    }
+}
+
 pub struct UiMonitorStep {
-    terminal: Terminal<CrosstermBackend<RawTerminal<Stdout>>>,
+    // Opened on the first call to `process` so constructing the step has no side effects
+    terminal: Option<Terminal<CrosstermBackend<RawTerminal<Stdout>>>>,
     raw_fields: Vec<(String, String)>,
     length_fields: Vec<(String, String)>,
     finish_flag_name: String,
@@ -27,82 +57,41 @@ pub struct UiMonitorStep {
 
 #[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
 impl UiMonitorStep {
-    pub fn try_new(configuration: Option<serde_yaml::Value>) -> Result<Box<dyn Step>, String> {
-        let stdout = stdout().into_raw_mode().expect("Could not init stdout");
-
-        let backend = CrosstermBackend::new(stdout);
-        let terminal = Terminal::new(backend).expect("Could not create terminal");
-
-        let params = match configuration {
-            Some(value) => value,
-            None => return Err("UiMonitorStep: no parameters provided".to_string()),
-        };
-
-        let finish_flag_name = params
-            .get("finish_flag")
-            .unwrap()
-            .as_str()
-            .unwrap()
-            .to_string();
-
-        let final_results = params
-            .get("final_results")
-            .unwrap()
-            .as_str()
-            .unwrap()
-            .to_string();
-
-        let raw = match params.get("raw").unwrap().as_sequence() {
-            Some(values) => values
-                .iter()
-                .map(|val| {
-                    (
-                        val.get("display_name")
-                            .unwrap()
-                            .as_str()
-                            .unwrap()
-                            .to_string(),
-                        val.get("field").unwrap().as_str().unwrap().to_string(),
-                    )
-                })
-                .collect(),
-            None => vec![],
-        };
-
-        let length = match params.get("length").unwrap().as_sequence() {
-            Some(values) => values
-                .iter()
-                .map(|val| {
-                    (
-                        val.get("display_name")
-                            .unwrap()
-                            .as_str()
-                            .unwrap()
-                            .to_string(),
-                        val.get("field").unwrap().as_str().unwrap().to_string(),
-                    )
-                })
-                .collect(),
-            None => vec![],
+    pub fn try_new(configuration: Option<serde_yaml::Value>) -> Result<Box<dyn Step>, Error> {
+        let params: UiMonitorStepParams = parse_params("UiMonitorStep", configuration)?;
+
+        let to_pairs = |fields: Option<Vec<MonitoredField>>| -> Vec<(String, String)> {
+            fields
+                .unwrap_or_default()
+                .into_iter()
+                .map(|field| (field.display_name, field.field))
+                .collect()
         };
 
         Ok(Box::new(UiMonitorStep {
-            terminal,
-            raw_fields: raw,
-            length_fields: length,
-            finish_flag_name,
+            terminal: None,
+            raw_fields: to_pairs(params.raw),
+            length_fields: to_pairs(params.length),
+            finish_flag_name: params.finish_flag,
             elapsed: std::time::Duration::from_millis(0),
             start_time: std::time::Instant::now(),
-            final_results_field_name: final_results,
+            final_results_field_name: params.final_results,
         }))
     }
 }
 
 #[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
 impl Step for UiMonitorStep {
-    fn process(&mut self, data: &mut HashMap<String, SharedData>) -> Result<ProcessStatus, String> {
+    fn process(&mut self, data: &mut HashMap<String, SharedData>) -> Result<ProcessStatus, Error> {
+        if self.terminal.is_none() {
+            let stdout = stdout().into_raw_mode()?;
+            let backend = CrosstermBackend::new(stdout);
+            self.terminal = Some(Terminal::new(backend)?);
+        }
+        let terminal = self.terminal.as_mut().unwrap();
+
         self.start_time = std::time::Instant::now();
-        self.terminal.clear().unwrap();
+        terminal.clear()?;
         loop {
             let mut done = false;
 
@@ -169,11 +158,9 @@ impl Step for UiMonitorStep {
                 .highlight_style(Style::default().add_modifier(Modifier::BOLD))
                 .highlight_symbol("> ");
 
-            self.terminal
-                .draw(|f| {
-                    f.render_widget(list, f.size());
-                })
-                .expect("Could not draw");
+            terminal.draw(|f| {
+                f.render_widget(list, f.size());
+            })?;
 
             let mut quit = false;
             if event::poll(std::time::Duration::from_millis(30)).unwrap_or(false) {
@@ -185,7 +172,7 @@ impl Step for UiMonitorStep {
             }
 
             if quit {
-                self.terminal.clear().unwrap();
+                terminal.clear()?;
                 break;
             }
             std::thread::sleep(std::time::Duration::from_millis(332));
@@ -193,6 +180,10 @@ impl Step for UiMonitorStep {
 
         Ok(ProcessStatus::Complete)
     }
+
+    fn input_flags(&self) -> Vec<String> {
+        vec![self.finish_flag_name.clone()]
+    }
 }
 
 impl std::fmt::Debug for UiMonitorStep {
@@ -6,48 +6,247 @@ mod misc_steps;
 mod parsers;
 mod reducers;
 
+use crate::error::Error;
+use crate::step_param_utils::StepSchema;
 use crate::workflow_step::BoxedStep;
 
-use bins::{GameEloBin, InitBinStep, TimeControlBin};
+use bins::{
+    EcoBin, EcoBinParams, GameEloBin, GameEloBinParams, GameEndingBin, GameEndingBinParams,
+    InitBinStep, InitBinStepParams, OpeningBin, OpeningBinParams, TimeControlBin,
+    TimeControlBinParams,
+};
 use filters::{
-    CheckmateFilter, ClockAvailableFilter, EvalAvailableFilter, MinMovesFilter, PlayerEloFilter,
+    CheckmateFilter, CheckmateFilterParams, ClockAvailableFilter, ClockAvailableFilterParams,
+    EvalAvailableFilter, EvalAvailableFilterParams, GameEndingFilter, GameEndingFilterParams,
+    MinMovesFilter, MinMovesFilterParams, OpeningFilter, OpeningFilterParams, PlayerEloFilter,
+    PlayerEloFilterParams,
+};
+use io_steps::{
+    Bz2DecompressStep, Bz2DecompressStepParams, ExportColumnarGames, ExportColumnarGamesParams,
+    ExportGames, ExportGamesParams, ExportPgn, ExportPgnParams, GlobFileStep, GlobFileStepParams,
+    SaveDataStep, SaveDataStepParams,
+};
+use maps::{
+    AccuracyMap, AccuracyMapParams, CentipawnLossMap, CentipawnLossMapParams, CountMap,
+    CountMapParams, FlaggedWhileWinningMap, FlaggedWhileWinningMapParams, MoveJudgementMap,
+    MoveJudgementMapParams, PerfectCheckmateMap, PerfectCheckmateMapParams, PositionHashMap,
+    PositionHashMapParams, ThinkTimeMap, ThinkTimeMapParams, TimeTroubleMap, TimeTroubleMapParams,
+};
+use misc_steps::{
+    ClassifyEcoStep, ClassifyEcoStepParams, DagStep, DagStepParams, InitBoardsStep,
+    InitBoardsStepParams, NoopStep, NoopStepParams, ParallelStep, ParallelStepParams, PipelineStep,
+    PipelineStepParams, SerialStep, SerialStepParams, TagBookExitStep, TagBookExitStepParams,
+    UiMonitorStep, UiMonitorStepParams,
+};
+use parsers::{
+    LookupGames, LookupGamesParams, ParseBinGame, ParseBinGameParams, ParseColumnarGame,
+    ParseColumnarGameParams, ParsePgnStep, ParsePgnStepParams,
 };
-use io_steps::{Bz2DecompressStep, ExportGames, GlobFileStep, SaveDataStep};
-use maps::{CountMap, PerfectCheckmateMap};
-use misc_steps::{InitBoardsStep, NoopStep, ParallelStep, SerialStep, UiMonitorStep};
-use parsers::{ParseBinGame, ParsePgnStep};
-use reducers::{AvgReduce, MaxReduce, SumReduce};
+use reducers::{
+    AvgReduce, AvgReduceParams, MaxReduce, MaxReduceParams, OpeningTreeReduce,
+    OpeningTreeReduceParams, PolyglotBookReduce, PolyglotBookReduceParams, PositionFrequencyReduce,
+    PositionFrequencyReduceParams, SumReduce, SumReduceParams,
+};
+
+pub const STEP_TYPES: [&str; 47] = [
+    "AccuracyMap",
+    "AvgReduce",
+    "Bz2DecompressStep",
+    "CentipawnLossMap",
+    "CheckmateFilter",
+    "ClassifyEcoStep",
+    "ClockAvailableFilter",
+    "CountMap",
+    "DagStep",
+    "EcoBin",
+    "EvalAvailableFilter",
+    "ExportColumnarGames",
+    "ExportGames",
+    "ExportPgn",
+    "FlaggedWhileWinningMap",
+    "GameEloBin",
+    "GameEndingBin",
+    "GameEndingFilter",
+    "GlobFileStep",
+    "InitBinStep",
+    "InitBoardsStep",
+    "LookupGames",
+    "MaxReduce",
+    "MinMovesFilter",
+    "MoveJudgementMap",
+    "NoopStep",
+    "OpeningBin",
+    "OpeningFilter",
+    "OpeningTreeReduce",
+    "ParallelStep",
+    "ParseBinGame",
+    "ParseColumnarGame",
+    "ParsePgnStep",
+    "PerfectCheckmateMap",
+    "PipelineStep",
+    "PlayerEloFilter",
+    "PolyglotBookReduce",
+    "PositionFrequencyReduce",
+    "PositionHashMap",
+    "SaveDataStep",
+    "SerialStep",
+    "SumReduce",
+    "TagBookExitStep",
+    "ThinkTimeMap",
+    "TimeControlBin",
+    "TimeTroubleMap",
+    "UiMonitorStep",
+];
 
 #[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
 pub fn get_step_by_name_and_params(
     name: &str,
     params: std::option::Option<serde_yaml::Value>,
-) -> Result<BoxedStep, String> {
+) -> Result<BoxedStep, Error> {
     match name {
         "Bz2DecompressStep" => Bz2DecompressStep::try_new(params),
         "InitBinStep" => InitBinStep::try_new(params),
         "GameEloBin" => GameEloBin::try_new(params),
+        "GameEndingBin" => GameEndingBin::try_new(params),
         "TimeControlBin" => TimeControlBin::try_new(params),
         "AvgReduce" => AvgReduce::try_new(params),
         "SumReduce" => SumReduce::try_new(params),
         "CountMap" => CountMap::try_new(params),
+        "CentipawnLossMap" => CentipawnLossMap::try_new(params),
+        "AccuracyMap" => AccuracyMap::try_new(params),
+        "MoveJudgementMap" => MoveJudgementMap::try_new(params),
+        "ThinkTimeMap" => ThinkTimeMap::try_new(params),
+        "TimeTroubleMap" => TimeTroubleMap::try_new(params),
+        "FlaggedWhileWinningMap" => FlaggedWhileWinningMap::try_new(params),
         "MinMovesFilter" => MinMovesFilter::try_new(params),
         "MaxReduce" => MaxReduce::try_new(params),
+        "OpeningTreeReduce" => OpeningTreeReduce::try_new(params),
+        "PolyglotBookReduce" => PolyglotBookReduce::try_new(params),
+        "EcoBin" => EcoBin::try_new(params),
+        "OpeningBin" => OpeningBin::try_new(params),
+        "OpeningFilter" => OpeningFilter::try_new(params),
         "SaveDataStep" => SaveDataStep::try_new(params),
         "ParallelStep" => ParallelStep::try_new(params),
         "SerialStep" => SerialStep::try_new(params),
-        "NoopStep" => Ok(NoopStep::boxed_new()),
+        "DagStep" => DagStep::try_new(params),
+        "PipelineStep" => PipelineStep::try_new(params),
+        "NoopStep" => NoopStep::try_new(params),
         "UiMonitorStep" => UiMonitorStep::try_new(params),
         "PlayerEloFilter" => PlayerEloFilter::try_new(params),
         "PerfectCheckmateMap" => PerfectCheckmateMap::try_new(params),
+        "PositionHashMap" => PositionHashMap::try_new(params),
+        "PositionFrequencyReduce" => PositionFrequencyReduce::try_new(params),
         "CheckmateFilter" => CheckmateFilter::try_new(params),
         "EvalAvailableFilter" => EvalAvailableFilter::try_new(params),
+        "GameEndingFilter" => GameEndingFilter::try_new(params),
         "ClockAvailableFilter" => ClockAvailableFilter::try_new(params),
-        "ParseBinGame" => Ok(ParseBinGame::boxed_new()),
+        "LookupGames" => LookupGames::try_new(params),
+        "ParseBinGame" => ParseBinGame::try_new(params),
+        "ParseColumnarGame" => ParseColumnarGame::try_new(params),
         "GlobFileStep" => GlobFileStep::try_new(params),
         "ExportGames" => ExportGames::try_new(params),
+        "ExportColumnarGames" => ExportColumnarGames::try_new(params),
+        "ExportPgn" => ExportPgn::try_new(params),
         "ParsePgnStep" => ParsePgnStep::try_new(params),
         "InitBoardsStep" => InitBoardsStep::try_new(params),
-        _ => Err(format!("Step with name '{name}' not found")),
+        "ClassifyEcoStep" => ClassifyEcoStep::try_new(params),
+        "TagBookExitStep" => TagBookExitStep::try_new(params),
+        _ => Err(Error::config(format!("Step with name '{name}' not found"))),
+    }
+}
+
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+pub fn get_step_schema(name: &str) -> Option<StepSchema> {
+    let schema = match name {
+        "Bz2DecompressStep" => StepSchema::of::<Bz2DecompressStepParams>("Bz2DecompressStep"),
+        "InitBinStep" => StepSchema::of::<InitBinStepParams>("InitBinStep"),
+        "GameEloBin" => StepSchema::of::<GameEloBinParams>("GameEloBin"),
+        "GameEndingBin" => StepSchema::of::<GameEndingBinParams>("GameEndingBin"),
+        "TimeControlBin" => StepSchema::of::<TimeControlBinParams>("TimeControlBin"),
+        "AvgReduce" => StepSchema::of::<AvgReduceParams>("AvgReduce"),
+        "SumReduce" => StepSchema::of::<SumReduceParams>("SumReduce"),
+        "CountMap" => StepSchema::of::<CountMapParams>("CountMap"),
+        "CentipawnLossMap" => StepSchema::of::<CentipawnLossMapParams>("CentipawnLossMap"),
+        "AccuracyMap" => StepSchema::of::<AccuracyMapParams>("AccuracyMap"),
+        "MoveJudgementMap" => StepSchema::of::<MoveJudgementMapParams>("MoveJudgementMap"),
+        "ThinkTimeMap" => StepSchema::of::<ThinkTimeMapParams>("ThinkTimeMap"),
+        "TimeTroubleMap" => StepSchema::of::<TimeTroubleMapParams>("TimeTroubleMap"),
+        "FlaggedWhileWinningMap" => {
+            StepSchema::of::<FlaggedWhileWinningMapParams>("FlaggedWhileWinningMap")
+        }
+        "MinMovesFilter" => StepSchema::of::<MinMovesFilterParams>("MinMovesFilter"),
+        "MaxReduce" => StepSchema::of::<MaxReduceParams>("MaxReduce"),
+        "OpeningTreeReduce" => StepSchema::of::<OpeningTreeReduceParams>("OpeningTreeReduce"),
+        "PolyglotBookReduce" => StepSchema::of::<PolyglotBookReduceParams>("PolyglotBookReduce"),
+        "EcoBin" => StepSchema::of::<EcoBinParams>("EcoBin"),
+        "OpeningBin" => StepSchema::of::<OpeningBinParams>("OpeningBin"),
+        "OpeningFilter" => StepSchema::of::<OpeningFilterParams>("OpeningFilter"),
+        "SaveDataStep" => StepSchema::of::<SaveDataStepParams>("SaveDataStep"),
+        "ParallelStep" => StepSchema::of::<ParallelStepParams>("ParallelStep"),
+        "SerialStep" => StepSchema::of::<SerialStepParams>("SerialStep"),
+        "DagStep" => StepSchema::of::<DagStepParams>("DagStep"),
+        "PipelineStep" => StepSchema::of::<PipelineStepParams>("PipelineStep"),
+        "NoopStep" => StepSchema::of::<NoopStepParams>("NoopStep"),
+        "UiMonitorStep" => StepSchema::of::<UiMonitorStepParams>("UiMonitorStep"),
+        "PlayerEloFilter" => StepSchema::of::<PlayerEloFilterParams>("PlayerEloFilter"),
+        "PerfectCheckmateMap" => StepSchema::of::<PerfectCheckmateMapParams>("PerfectCheckmateMap"),
+        "PositionHashMap" => StepSchema::of::<PositionHashMapParams>("PositionHashMap"),
+        "PositionFrequencyReduce" => {
+            StepSchema::of::<PositionFrequencyReduceParams>("PositionFrequencyReduce")
+        }
+        "CheckmateFilter" => StepSchema::of::<CheckmateFilterParams>("CheckmateFilter"),
+        "EvalAvailableFilter" => StepSchema::of::<EvalAvailableFilterParams>("EvalAvailableFilter"),
+        "GameEndingFilter" => StepSchema::of::<GameEndingFilterParams>("GameEndingFilter"),
+        "ClockAvailableFilter" => {
+            StepSchema::of::<ClockAvailableFilterParams>("ClockAvailableFilter")
+        }
+        "LookupGames" => StepSchema::of::<LookupGamesParams>("LookupGames"),
+        "ParseBinGame" => StepSchema::of::<ParseBinGameParams>("ParseBinGame"),
+        "ParseColumnarGame" => StepSchema::of::<ParseColumnarGameParams>("ParseColumnarGame"),
+        "GlobFileStep" => StepSchema::of::<GlobFileStepParams>("GlobFileStep"),
+        "ExportGames" => StepSchema::of::<ExportGamesParams>("ExportGames"),
+        "ExportColumnarGames" => StepSchema::of::<ExportColumnarGamesParams>("ExportColumnarGames"),
+        "ExportPgn" => StepSchema::of::<ExportPgnParams>("ExportPgn"),
+        "ParsePgnStep" => StepSchema::of::<ParsePgnStepParams>("ParsePgnStep"),
+        "InitBoardsStep" => StepSchema::of::<InitBoardsStepParams>("InitBoardsStep"),
+        "ClassifyEcoStep" => StepSchema::of::<ClassifyEcoStepParams>("ClassifyEcoStep"),
+        "TagBookExitStep" => StepSchema::of::<TagBookExitStepParams>("TagBookExitStep"),
+        _ => return None,
+    };
+
+    Some(schema)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_step_type_is_registered() {
+        for step_type in STEP_TYPES {
+            assert!(get_step_schema(step_type).is_some(), "{step_type}");
+            assert_ne!(
+                get_step_by_name_and_params(step_type, None).err(),
+                Some(Error::config(format!(
+                    "Step with name '{step_type}' not found"
+                )))
+            );
+        }
+    }
+
+    #[test]
+    fn every_param_is_documented() {
+        for step_type in STEP_TYPES {
+            let schema = get_step_schema(step_type).unwrap();
+            assert!(!schema.doc.trim().is_empty(), "{step_type}");
+            for param in schema.params {
+                assert!(!param.doc.trim().is_empty(), "{step_type}.{}", param.name);
+            }
+        }
+    }
+
+    #[test]
+    fn unknown_step_has_no_schema() {
+        assert_eq!(get_step_schema("NotAStep"), None);
     }
 }
@@ -0,0 +1,88 @@
+use std::{collections::HashMap, path::PathBuf};
+
+use crate::{
+    error::Error,
+    game_index::{date_bound, GameIndex, IndexQuery},
+    step_param_utils::parse_params,
+    workflow_step::{BoxedStep, ProcessStatus, SharedData, Step, StepData},
+};
+
+step_params! {
+    /// Reads the games matching the given keys into `parsed_games`, through an index built
+    /// with the `index` command. Only the matching games are decoded
+    pub struct LookupGamesParams {
+        /// Index file to look the games up in
+        index: String,
+        /// Lichess game id, as found at the end of the game url
+        id: Option<String>,
+        /// Name of either player, case insensitive
+        player: Option<String>,
+        /// Earliest date allowed, as "YYYY.MM.DD", unbounded when unset
+        min_date: Option<String>,
+        /// Latest date allowed, as "YYYY.MM.DD", unbounded when unset
+        max_date: Option<String>,
+    }
+}
+
+#[derive(Debug)]
+pub struct LookupGames {
+    index_path: PathBuf,
+    query: IndexQuery,
+}
+
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+impl LookupGames {
+    pub fn try_new(configuration: Option<serde_yaml::Value>) -> Result<BoxedStep, Error> {
+        let params: LookupGamesParams = parse_params("LookupGames", configuration)?;
+
+        Ok(Box::new(LookupGames {
+            index_path: PathBuf::from(params.index),
+            query: IndexQuery {
+                min_date: date_bound("LookupGames", params.min_date.as_deref())?,
+                max_date: date_bound("LookupGames", params.max_date.as_deref())?,
+                id: params.id,
+                player: params.player,
+            },
+        }))
+    }
+}
+
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+impl Step for LookupGames {
+    fn process(&mut self, data: &mut HashMap<String, SharedData>) -> Result<ProcessStatus, Error> {
+        data.init_vec_if_unset("parsed_games");
+
+        let index = GameIndex::read(&self.index_path)?;
+        for game in index.fetch(&index.find(&self.query))? {
+            data.try_push_to_vec("parsed_games", SharedData::Game(game))?;
+        }
+
+        data.insert("done_parsing_games".to_string(), SharedData::Bool(true));
+
+        Ok(ProcessStatus::Complete)
+    }
+
+    fn outputs(&self) -> Vec<String> {
+        vec!["parsed_games".to_string()]
+    }
+
+    fn output_flags(&self) -> Vec<String> {
+        vec!["done_parsing_games".to_string()]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_invalid_dates() {
+        let configuration =
+            serde_yaml::from_str("{index: games.idx, max_date: 2023-01-31}").unwrap();
+        assert!(LookupGames::try_new(Some(configuration)).is_err());
+
+        let configuration =
+            serde_yaml::from_str("{index: games.idx, max_date: '2023.01.31'}").unwrap();
+        assert!(LookupGames::try_new(Some(configuration)).is_ok());
+    }
+}
@@ -1,5 +1,9 @@
+mod lookup_games_step;
 mod parse_bin_game_step;
+mod parse_columnar_game_step;
 mod parse_pgn_step;
 
-pub use parse_bin_game_step::ParseBinGame;
-pub use parse_pgn_step::ParsePgnStep;
+pub use lookup_games_step::{LookupGames, LookupGamesParams};
+pub use parse_bin_game_step::{ParseBinGame, ParseBinGameParams};
+pub use parse_columnar_game_step::{ParseColumnarGame, ParseColumnarGameParams};
+pub use parse_pgn_step::{ParsePgnStep, ParsePgnStepParams};
@@ -1,24 +1,45 @@
 use std::collections::HashMap;
 
-// use crate::steps_manager::get_step_description;
 use crate::{
-    game::Game,
+    error::Error,
+    error_policy::{ErrorPolicy, OnError, Record},
+    game_archive::decode_games,
+    step_param_utils::parse_params,
     workflow_step::{BoxedStep, ProcessStatus, SharedData, Step, StepData},
 };
 
+step_params! {
+    /// Decodes the games of every file in `raw_file_data` into `parsed_games`, reading every
+    /// binary format version
+    pub struct ParseBinGameParams {
+        /// What to do with a game that cannot be decoded: abort, skip or quarantine. The
+        /// rest of its file cannot be decoded either and is skipped with it.
+        on_error: OnError = "abort",
+        /// File the undecodable bytes are appended to, with their error
+        quarantine_file: Option<String>,
+    }
+}
+
 #[derive(Debug)]
-pub struct ParseBinGame {}
+pub struct ParseBinGame {
+    error_policy: ErrorPolicy,
+}
 
 #[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
 impl ParseBinGame {
-    pub fn boxed_new() -> BoxedStep {
-        Box::new(ParseBinGame {})
+    pub fn try_new(configuration: Option<serde_yaml::Value>) -> Result<BoxedStep, Error> {
+        let params: ParseBinGameParams = parse_params("ParseBinGame", configuration)?;
+
+        let error_policy =
+            ErrorPolicy::try_new("ParseBinGame", params.on_error, params.quarantine_file)?;
+
+        Ok(Box::new(ParseBinGame { error_policy }))
     }
 }
 
 #[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
 impl Step for ParseBinGame {
-    fn process(&mut self, data: &mut HashMap<String, SharedData>) -> Result<ProcessStatus, String> {
+    fn process(&mut self, data: &mut HashMap<String, SharedData>) -> Result<ProcessStatus, Error> {
         data.init_vec_if_unset("parsed_games");
 
         let remaining_files;
@@ -33,15 +54,17 @@ impl Step for ParseBinGame {
                 Some(data) => data,
                 None => return Ok(ProcessStatus::Complete),
             };
-            let mut file_data_vec = raw_file_data.into_vec().unwrap();
+            let mut file_data_vec = raw_file_data
+                .into_vec()
+                .ok_or_else(|| Error::data_type("ParseBinGame: raw_file_data isn't a Vec"))?;
 
             remaining_files = file_data_vec.len();
             if remaining_files == 0 {
                 return Ok(ProcessStatus::Complete);
             }
-            let ret = match file_data_vec.pop().unwrap() {
-                SharedData::FileData(data) => data,
-                _ => panic!(), // TODO
+            let ret = match file_data_vec.pop() {
+                Some(SharedData::FileData(data)) => data,
+                _ => return Err(Error::data_type("ParseBinGame: Vector isn't of file data!")),
             };
             data.insert("raw_file_data".to_string(), SharedData::Vec(file_data_vec));
 
@@ -49,17 +72,14 @@ impl Step for ParseBinGame {
         };
 
         if !file_data.is_empty() {
-            let games: Vec<Game> = postcard::from_bytes(&file_data).unwrap();
-            let mut games = games
-                .into_iter()
-                .map(SharedData::Game)
-                .collect::<Vec<SharedData>>();
-
-            let game_list = data.remove("parsed_games").unwrap();
-            let mut game_list: Vec<SharedData> = game_list.into_vec().unwrap();
-
-            game_list.append(&mut games);
-            data.insert("parsed_games".to_string(), SharedData::Vec(game_list));
+            let (games, failure) = decode_games(&file_data);
+            for game in games {
+                data.try_push_to_vec("parsed_games", SharedData::Game(game))?;
+            }
+            if let Some((err, undecoded)) = failure {
+                self.error_policy
+                    .handle(err, Record::Binary(undecoded.to_vec()), data)?;
+            }
         }
 
         let d: bool = true;
@@ -67,4 +87,18 @@ impl Step for ParseBinGame {
 
         Ok(ProcessStatus::Incomplete)
     }
+
+    fn inputs(&self) -> Vec<String> {
+        vec!["raw_file_data".to_string()]
+    }
+
+    fn outputs(&self) -> Vec<String> {
+        let mut outputs = vec!["parsed_games".to_string()];
+        outputs.extend(self.error_policy.outputs());
+        outputs
+    }
+
+    fn output_flags(&self) -> Vec<String> {
+        vec!["done_parsing_games".to_string()]
+    }
 }
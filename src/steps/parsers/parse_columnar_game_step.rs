@@ -0,0 +1,135 @@
+use std::collections::HashMap;
+
+use crate::{
+    columnar::{decode_games, parse_date, GameColumn, GameFilter},
+    error::Error,
+    step_param_utils::parse_params,
+    workflow_step::{BoxedStep, ProcessStatus, SharedData, Step, StepData},
+};
+
+step_params! {
+    /// Decodes the games of every columnar file in `raw_file_data` into `parsed_games`,
+    /// reading only the selected columns and skipping blocks that can't pass the bounds
+    pub struct ParseColumnarGameParams {
+        /// Columns to decode, every column when unset. Other fields keep their default value
+        columns: Option<Vec<GameColumn>>,
+        /// Lowest rating allowed for both players, unbounded when unset
+        min_elo: Option<u64>,
+        /// Highest rating allowed for both players, unbounded when unset
+        max_elo: Option<u64>,
+        /// Earliest date allowed, as "YYYY.MM.DD", unbounded when unset
+        min_date: Option<String>,
+        /// Latest date allowed, as "YYYY.MM.DD", unbounded when unset
+        max_date: Option<String>,
+        /// Lowest main time of the time control allowed, in seconds, unbounded when unset
+        min_time_control: Option<u64>,
+        /// Highest main time of the time control allowed, in seconds, unbounded when unset
+        max_time_control: Option<u64>,
+    }
+}
+
+#[derive(Debug)]
+pub struct ParseColumnarGame {
+    columns: Vec<GameColumn>,
+    filter: GameFilter,
+}
+
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+impl ParseColumnarGame {
+    pub fn try_new(configuration: Option<serde_yaml::Value>) -> Result<BoxedStep, Error> {
+        let params: ParseColumnarGameParams = parse_params("ParseColumnarGame", configuration)?;
+
+        let bound =
+            |value: Option<u64>| value.map(|value| u32::try_from(value).unwrap_or(u32::MAX));
+        let date = |date: Option<String>| match date {
+            Some(date) => parse_date(&date).map(Some).ok_or_else(|| {
+                Error::config(format!(
+                    "ParseColumnarGame: invalid date '{date}', expected YYYY.MM.DD"
+                ))
+            }),
+            None => Ok(None),
+        };
+
+        Ok(Box::new(ParseColumnarGame {
+            columns: params.columns.unwrap_or_else(|| GameColumn::ALL.to_vec()),
+            filter: GameFilter {
+                min_elo: bound(params.min_elo),
+                max_elo: bound(params.max_elo),
+                min_date: date(params.min_date)?,
+                max_date: date(params.max_date)?,
+                min_time_control: bound(params.min_time_control),
+                max_time_control: bound(params.max_time_control),
+            },
+        }))
+    }
+}
+
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+impl Step for ParseColumnarGame {
+    fn process(&mut self, data: &mut HashMap<String, SharedData>) -> Result<ProcessStatus, Error> {
+        data.init_vec_if_unset("parsed_games");
+
+        let file_data = {
+            let mut file_data_vec = match data.remove("raw_file_data") {
+                Some(raw_file_data) => raw_file_data.into_vec().ok_or_else(|| {
+                    Error::data_type("ParseColumnarGame: raw_file_data isn't a Vec")
+                })?,
+                None => return Ok(ProcessStatus::Complete),
+            };
+            let file_data = match file_data_vec.pop() {
+                Some(SharedData::FileData(file_data)) => file_data,
+                Some(_) => {
+                    return Err(Error::data_type(
+                        "ParseColumnarGame: Vector isn't of file data!",
+                    ))
+                }
+                None => return Ok(ProcessStatus::Complete),
+            };
+            data.insert("raw_file_data".to_string(), SharedData::Vec(file_data_vec));
+
+            file_data
+        };
+
+        if !file_data.is_empty() {
+            for game in decode_games(&file_data, &self.columns, &self.filter)? {
+                data.try_push_to_vec("parsed_games", SharedData::Game(game))?;
+            }
+        }
+
+        data.insert("done_parsing_games".to_string(), SharedData::Bool(true));
+
+        Ok(ProcessStatus::Incomplete)
+    }
+
+    fn inputs(&self) -> Vec<String> {
+        vec!["raw_file_data".to_string()]
+    }
+
+    fn outputs(&self) -> Vec<String> {
+        vec!["parsed_games".to_string()]
+    }
+
+    fn output_flags(&self) -> Vec<String> {
+        vec!["done_parsing_games".to_string()]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_invalid_dates() {
+        let configuration = serde_yaml::from_str("{min_date: 2023-01-01}").unwrap();
+        assert!(ParseColumnarGame::try_new(Some(configuration)).is_err());
+
+        let configuration = serde_yaml::from_str("{min_date: '2023.01.01'}").unwrap();
+        assert!(ParseColumnarGame::try_new(Some(configuration)).is_ok());
+    }
+
+    #[test]
+    fn rejects_unknown_columns() {
+        let configuration = serde_yaml::from_str("{columns: [ratings, boards]}").unwrap();
+        assert!(ParseColumnarGame::try_new(Some(configuration)).is_err());
+    }
+}
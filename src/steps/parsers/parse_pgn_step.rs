@@ -1,101 +1,200 @@
-use std::{
-    collections::HashMap,
-    io::{BufRead, Read},
-};
+use std::collections::HashMap;
 
 use crate::{
+    error::Error,
+    error_policy::{ErrorPolicy, OnError, Record},
     game::Game,
     parse_pgn::PgnParser,
-    workflow_step::{ProcessStatus, SharedData, Step},
+    pgn_reader::PgnReader,
+    step_param_utils::parse_params,
+    workflow_step::{ProcessStatus, SharedData, Step, StepData},
 };
 
+step_params! {
+    /// Parses the games of every PGN file in `file_path_bufs` into `parsed_games`, a batch
+    /// at a time. Files ending in "bz2", "gz" or "zst" are decompressed as they are read.
+    pub struct ParsePgnStepParams {
+        /// Number of games parsed per call
+        batch_size: u64 = "1000",
+        /// What to do with a game that cannot be parsed: abort, skip or quarantine
+        on_error: OnError = "abort",
+        /// File games that cannot be parsed are appended to, with their error
+        quarantine_file: Option<String>,
+        /// Keep recursive annotation variations in `Game::variations` instead of discarding them
+        keep_variations: bool = "false",
+    }
+}
+
 #[derive(Debug)]
 pub struct ParsePgnStep {
-    pgn_filename: String,
+    batch_size: u64,
     pgn_parser: PgnParser,
+    error_policy: ErrorPolicy,
+    paths: Option<Vec<SharedData>>,
+    reader: Option<PgnReader>,
 }
 
 #[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
 impl ParsePgnStep {
-    pub fn try_new(configuration: Option<serde_yaml::Value>) -> Result<Box<dyn Step>, String> {
-        let params = match configuration {
-            Some(value) => value,
-            None => return Err("ParsePgnStep: no parameters provided".to_string()),
-        };
+    pub fn try_new(configuration: Option<serde_yaml::Value>) -> Result<Box<dyn Step>, Error> {
+        let params: ParsePgnStepParams = parse_params("ParsePgnStep", configuration)?;
 
-        let pgn_filename = params
-            .get("pgn_filename")
-            .unwrap()
-            .as_str()
-            .unwrap()
-            .to_string();
+        if params.batch_size == 0 {
+            return Err(Error::config("ParsePgnStep: batch_size must be at least 1"));
+        }
+
+        let error_policy =
+            ErrorPolicy::try_new("ParsePgnStep", params.on_error, params.quarantine_file)?;
 
         let step = ParsePgnStep {
-            pgn_filename,
-            pgn_parser: PgnParser::new(),
+            batch_size: params.batch_size,
+            pgn_parser: PgnParser::new().keep_variations(params.keep_variations),
+            error_policy,
+            paths: None,
+            reader: None,
         };
 
         Ok(Box::new(step))
     }
 
-    fn parse_next_game_from_file<R>(
-        &self,
-        reader: &mut std::io::BufReader<R>,
-    ) -> Result<Option<Game>, String>
-    where
-        R: Read,
-    {
-        let mut headers = vec![];
-        let mut buffer = String::new();
+    // Returns false once every file has been opened
+    fn open_next_file(&mut self) -> Result<bool, Error> {
+        let paths = match self.paths.as_mut() {
+            Some(paths) if !paths.is_empty() => paths,
+            _ => return Ok(false),
+        };
 
-        loop {
-            if let Ok(bytes) = reader.read_line(&mut buffer) {
-                if bytes == 0 {
-                    return Ok(None);
+        let path = paths
+            .remove(0)
+            .to_path_buf()
+            .ok_or_else(|| Error::data_type("ParsePgnStep: path isn't a PathBuf"))?;
+        self.reader = Some(PgnReader::open(&path)?);
+
+        Ok(true)
+    }
+}
+
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+impl Step for ParsePgnStep {
+    fn process(&mut self, data: &mut HashMap<String, SharedData>) -> Result<ProcessStatus, Error> {
+        data.init_vec_if_unset("parsed_games");
+
+        if self.paths.is_none() {
+            let bufs = data
+                .remove("file_path_bufs")
+                .and_then(SharedData::into_vec)
+                .ok_or_else(|| Error::data_type("ParsePgnStep: no file_path_bufs vector"))?;
+
+            self.paths = Some(bufs);
+        }
+
+        let mut games_parsed = 0;
+        while games_parsed < self.batch_size {
+            let reader = match self.reader.as_mut() {
+                Some(reader) => reader,
+                None => {
+                    if self.open_next_file()? {
+                        continue;
+                    }
+
+                    data.insert("done_parsing_games".to_string(), SharedData::Bool(true));
+                    return Ok(ProcessStatus::Complete);
                 }
-                let line = buffer.trim_end();
-                if line.starts_with('[') {
-                    headers.push(line.to_string());
-                } else if !line.is_empty() {
-                    let mut game = Game::default();
-                    self.pgn_parser.parse_game(&headers, line, &mut game)?;
-                    return Ok(Some(game));
+            };
+
+            let game_index = reader.games_read();
+            let raw_game = match reader.next_game()? {
+                Some(raw_game) => raw_game,
+                None => {
+                    self.reader = None;
+                    continue;
                 }
-                buffer.clear();
+            };
+
+            let mut game = Game::default();
+            match self
+                .pgn_parser
+                .parse_game(&raw_game.headers, &raw_game.movetext, &mut game)
+            {
+                Ok(()) => data.try_push_to_vec("parsed_games", SharedData::Game(game))?,
+                Err(err) => self.error_policy.handle(
+                    err.in_game(game_index).in_file(reader.path()),
+                    Record::Text(raw_game.to_pgn()),
+                    data,
+                )?,
             }
+            games_parsed += 1;
         }
+
+        Ok(ProcessStatus::Incomplete)
+    }
+
+    fn inputs(&self) -> Vec<String> {
+        vec!["file_path_bufs".to_string()]
+    }
+
+    fn outputs(&self) -> Vec<String> {
+        let mut outputs = vec!["parsed_games".to_string()];
+        outputs.extend(self.error_policy.outputs());
+        outputs
+    }
+
+    fn output_flags(&self) -> Vec<String> {
+        vec!["done_parsing_games".to_string()]
     }
 }
 
-#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
-impl Step for ParsePgnStep {
-    fn process(&mut self, data: &mut HashMap<String, SharedData>) -> Result<ProcessStatus, String> {
-        {
-            let vec: Vec<SharedData> = vec![];
-            data.insert("parsed_games".to_string(), SharedData::Vec(vec));
-        }
+#[cfg(test)]
+mod tests {
+    use serde_yaml::{Mapping, Value};
 
-        let file = std::fs::File::open(&self.pgn_filename).unwrap();
-        let mut reader = std::io::BufReader::new(file);
+    use super::*;
 
+    #[test]
+    fn zero_batch_size() {
+        let mut params = Mapping::new();
+        params.insert(Value::from("batch_size"), Value::from(0));
+
+        assert_eq!(
+            ParsePgnStep::try_new(Some(Value::Mapping(params))).unwrap_err(),
+            Error::config("ParsePgnStep: batch_size must be at least 1")
+        );
+    }
+
+    #[test]
+    fn parses_in_batches() {
+        let mut params = Mapping::new();
+        params.insert(Value::from("batch_size"), Value::from(4));
+        let mut step = ParsePgnStep::try_new(Some(Value::Mapping(params))).unwrap();
+
+        let mut data = HashMap::from([(
+            "file_path_bufs".to_string(),
+            SharedData::Vec(vec![SharedData::PathBuf("test_data/test_set_1.pgn".into())]),
+        )]);
+
+        let mut statuses = vec![];
+        let mut batch_lengths = vec![];
         loop {
-            let next = self.parse_next_game_from_file(&mut reader);
-            if Ok(None) == next {
+            let status = step.process(&mut data).unwrap();
+            batch_lengths.push(data.clear_vec("parsed_games").unwrap().len());
+            statuses.push(status);
+            if status == ProcessStatus::Complete {
                 break;
-            } else if let Ok(Some(game)) = next {
-                let game_list = data.get("parsed_games").unwrap();
-                let mut game_list: Vec<SharedData> = game_list.to_vec().unwrap();
-
-                game_list.push(SharedData::Game(game));
-                data.insert("parsed_games".to_string(), SharedData::Vec(game_list));
-            } else {
-                next.unwrap();
             }
         }
 
-        let d: bool = true;
-        data.insert("done_parsing_games".to_string(), SharedData::Bool(d));
-
-        Ok(ProcessStatus::Complete)
+        assert_eq!(batch_lengths, vec![4, 4, 2]);
+        assert_eq!(
+            statuses,
+            vec![
+                ProcessStatus::Incomplete,
+                ProcessStatus::Incomplete,
+                ProcessStatus::Complete
+            ]
+        );
+        assert_eq!(
+            data.get("done_parsing_games"),
+            Some(&SharedData::Bool(true))
+        );
     }
 }
@@ -1,7 +1,23 @@
+use crate::error::Error;
+use crate::step_param_utils::parse_params;
 use crate::workflow_step::{ProcessStatus, SharedData, Step, StepData};
 
 use std::collections::HashMap;
 
+step_params! {
+    /// Averages the mapped values of each bin into a map keyed by the bin labels
+    pub struct AvgReduceParams {
+        /// Vector of mapped values to read
+        input: String,
+        /// Map the averages are written to
+        output: String,
+        /// Flag raised once the input is complete
+        input_flag: String = "<input>_complete",
+        /// Flag raised once this step is done
+        output_flag: String = "<output>_complete",
+    }
+}
+
 #[derive(Debug)]
 pub struct AvgReduce {
     input_vec_name: String,
@@ -12,33 +28,14 @@ pub struct AvgReduce {
 
 #[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
 impl AvgReduce {
-    pub fn try_new(configuration: Option<serde_yaml::Value>) -> Result<Box<dyn Step>, String> {
-        let params = match configuration {
-            Some(value) => value,
-            None => return Err("AvgReduce: no parameters provided".to_string()),
-        };
-
-        // TODO: better error handling
-        let input_vec_name = params.get("input").unwrap().as_str().unwrap().to_string();
-        let output_map_name = params.get("output").unwrap().as_str().unwrap().to_string();
-        let input_flag = params
-            .get("input_flag")
-            .unwrap()
-            .as_str()
-            .unwrap()
-            .to_string();
-        let output_flag = params
-            .get("output_flag")
-            .unwrap()
-            .as_str()
-            .unwrap()
-            .to_string();
+    pub fn try_new(configuration: Option<serde_yaml::Value>) -> Result<Box<dyn Step>, Error> {
+        let params: AvgReduceParams = parse_params("AvgReduce", configuration)?;
 
         Ok(Box::new(AvgReduce {
-            input_vec_name,
-            output_map_name,
-            input_flag,
-            output_flag,
+            input_vec_name: params.input,
+            output_map_name: params.output,
+            input_flag: params.input_flag,
+            output_flag: params.output_flag,
         }))
     }
 }
@@ -46,93 +43,73 @@ impl AvgReduce {
 #[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
 impl Step for AvgReduce {
     #[allow(clippy::cast_precision_loss)]
-    fn process(&mut self, data: &mut HashMap<String, SharedData>) -> Result<ProcessStatus, String> {
+    #[allow(clippy::cast_precision_loss)]
+    fn process(&mut self, data: &mut HashMap<String, SharedData>) -> Result<ProcessStatus, Error> {
         data.init_map_if_unset(&self.output_map_name);
 
-        let mut quit = false;
-        let mut final_loop = false;
-        loop {
-            if quit {
-                final_loop = true;
-            }
-
-            let binned_games = data.clear_vec(&self.input_vec_name).unwrap();
+        // Read the flag before draining so values arriving alongside it are not left behind
+        let input_done = data
+            .get(&self.input_flag)
+            .and_then(SharedData::to_bool)
+            .unwrap_or(false);
 
-            let mut new_data: HashMap<String, Vec<u64>> = HashMap::new();
+        let binned_games = data.clear_vec(&self.input_vec_name).unwrap_or_default();
 
-            for shared_binned_game in binned_games {
-                let binned_game = match shared_binned_game {
-                    SharedData::BinnedValue(game) => game,
-                    _ => return Err("Vector isn't of binned values!".to_string()),
-                };
+        let mut new_data: HashMap<String, Vec<u64>> = HashMap::new();
 
-                let value = match *binned_game.0 {
-                    SharedData::U64(v) => v,
-                    SharedData::USize(v) => v as u64,
-                    _ => return Err("Value isn't an integer!".to_string()),
-                };
+        for shared_binned_game in binned_games {
+            let binned_game = match shared_binned_game {
+                SharedData::BinnedValue(game) => game,
+                _ => return Err(Error::data_type("Vector isn't of binned values!")),
+            };
 
-                let bin_labels = binned_game.1;
-                let bin_str_labels: Vec<String> =
-                    bin_labels.iter().map(|b| format!("{b}")).collect();
-                let combined_label = bin_str_labels.join(".");
+            let value = match *binned_game.0 {
+                SharedData::U64(v) => v,
+                SharedData::USize(v) => v as u64,
+                _ => return Err(Error::data_type("Value isn't an integer!")),
+            };
 
-                if !new_data.contains_key(&combined_label) {
-                    new_data.insert(combined_label.clone(), vec![0, 0]);
-                }
+            let bin_labels = binned_game.1;
+            let bin_str_labels: Vec<String> = bin_labels.iter().map(|b| format!("{b}")).collect();
+            let combined_label = bin_str_labels.join(".");
 
-                new_data.get_mut(&combined_label).unwrap()[0] += value;
-                new_data.get_mut(&combined_label).unwrap()[1] += 1;
+            if !new_data.contains_key(&combined_label) {
+                new_data.insert(combined_label.clone(), vec![0, 0]);
             }
 
-            {
-                let potential_data = data.get(&self.output_map_name);
-                let shared_data = match potential_data {
-                    Some(shared_data) => shared_data,
-                    None => continue,
-                };
-                let mut map = shared_data.to_map().unwrap();
-
-                for key in new_data.keys() {
-                    if !map.contains_key(key) {
-                        map.insert(
-                            key.to_string(),
-                            SharedData::Vec(vec![SharedData::U64(0), SharedData::U64(0)]),
-                        );
-                    }
-
-                    let shared_vec: Vec<SharedData> = map.get_mut(key).unwrap().to_vec().unwrap();
-
-                    let original_total = shared_vec[0].to_u64().unwrap();
-                    let new_total = new_data.get(key).unwrap()[0] + original_total;
+            new_data.get_mut(&combined_label).unwrap()[0] += value;
+            new_data.get_mut(&combined_label).unwrap()[1] += 1;
+        }
 
-                    let original_count = shared_vec[1].to_u64().unwrap();
-                    let new_count = new_data.get(key).unwrap()[1] + original_count;
+        {
+            let mut map = data.get(&self.output_map_name).unwrap().to_map().unwrap();
 
+            for key in new_data.keys() {
+                if !map.contains_key(key) {
                     map.insert(
                         key.to_string(),
-                        SharedData::Vec(vec![
-                            SharedData::U64(new_total),
-                            SharedData::U64(new_count),
-                        ]),
+                        SharedData::Vec(vec![SharedData::U64(0), SharedData::U64(0)]),
                     );
                 }
-                data.insert(self.output_map_name.clone(), SharedData::Map(map));
-            }
 
-            let flag = data
-                .get(&self.input_flag)
-                .unwrap_or(&SharedData::Bool(false));
+                let shared_vec: Vec<SharedData> = map.get_mut(key).unwrap().to_vec().unwrap();
 
-            let flag = flag.to_bool().unwrap();
+                let original_total = shared_vec[0].to_u64().unwrap();
+                let new_total = new_data.get(key).unwrap()[0] + original_total;
 
-            if flag {
-                quit = true;
-            }
+                let original_count = shared_vec[1].to_u64().unwrap();
+                let new_count = new_data.get(key).unwrap()[1] + original_count;
 
-            if final_loop && quit {
-                break;
+                map.insert(
+                    key.to_string(),
+                    SharedData::Vec(vec![SharedData::U64(new_total), SharedData::U64(new_count)]),
+                );
             }
+            data.insert(self.output_map_name.clone(), SharedData::Map(map));
+        }
+
+        if !input_done {
+            return Ok(ProcessStatus::Incomplete);
         }
 
         {
@@ -142,7 +119,11 @@ impl Step for AvgReduce {
             let potential_data = data.get(&self.output_map_name);
             let shared_data = match potential_data {
                 Some(shared_data) => shared_data,
-                None => panic!("AvgReduce: data not found for some reason!"),
+                None => {
+                    return Err(Error::data_type(
+                        "AvgReduce: data not found for some reason!",
+                    ))
+                }
             };
             let mut map = shared_data.to_map().unwrap();
 
@@ -160,4 +141,20 @@ impl Step for AvgReduce {
 
         Ok(ProcessStatus::Complete)
     }
+
+    fn inputs(&self) -> Vec<String> {
+        vec![self.input_vec_name.clone()]
+    }
+
+    fn outputs(&self) -> Vec<String> {
+        vec![self.output_map_name.clone()]
+    }
+
+    fn input_flags(&self) -> Vec<String> {
+        vec![self.input_flag.clone()]
+    }
+
+    fn output_flags(&self) -> Vec<String> {
+        vec![self.output_flag.clone()]
+    }
 }
@@ -1,7 +1,23 @@
+use crate::error::Error;
+use crate::step_param_utils::parse_params;
 use crate::workflow_step::{ProcessStatus, SharedData, Step, StepData};
 
 use std::collections::{hash_map::Entry, HashMap};
 
+step_params! {
+    /// Keeps the largest mapped value of each bin in a map keyed by the bin labels
+    pub struct MaxReduceParams {
+        /// Vector of mapped values to read
+        input: String,
+        /// Map the maxima are written to
+        output: String,
+        /// Flag raised once the input is complete
+        input_flag: String = "<input>_complete",
+        /// Flag raised once this step is done
+        output_flag: String = "<output>_complete",
+    }
+}
+
 #[derive(Debug)]
 pub struct MaxReduce {
     input_vec_name: String,
@@ -12,115 +28,91 @@ pub struct MaxReduce {
 
 #[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
 impl MaxReduce {
-    pub fn try_new(configuration: Option<serde_yaml::Value>) -> Result<Box<dyn Step>, String> {
-        let params = match configuration {
-            Some(value) => value,
-            None => return Err("MaxReduce: no parameters provided".to_string()),
-        };
-
-        // TODO: better error handling
-        let input_vec_name = params.get("input").unwrap().as_str().unwrap().to_string();
-        let output_map_name = params.get("output").unwrap().as_str().unwrap().to_string();
-        let input_flag = params
-            .get("input_flag")
-            .unwrap()
-            .as_str()
-            .unwrap()
-            .to_string();
-        let output_flag = params
-            .get("output_flag")
-            .unwrap()
-            .as_str()
-            .unwrap()
-            .to_string();
+    pub fn try_new(configuration: Option<serde_yaml::Value>) -> Result<Box<dyn Step>, Error> {
+        let params: MaxReduceParams = parse_params("MaxReduce", configuration)?;
 
         Ok(Box::new(MaxReduce {
-            input_vec_name,
-            output_map_name,
-            input_flag,
-            output_flag,
+            input_vec_name: params.input,
+            output_map_name: params.output,
+            input_flag: params.input_flag,
+            output_flag: params.output_flag,
         }))
     }
 }
 
 #[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
 impl Step for MaxReduce {
-    fn process(&mut self, data: &mut HashMap<String, SharedData>) -> Result<ProcessStatus, String> {
+    fn process(&mut self, data: &mut HashMap<String, SharedData>) -> Result<ProcessStatus, Error> {
         data.init_map_if_unset(&self.output_map_name);
 
-        let mut quit = false;
-        let mut final_loop = false;
-        loop {
-            if quit {
-                final_loop = true;
-            }
-
-            let binned_games = data.clear_vec(&self.input_vec_name).unwrap();
-
-            let mut new_data: HashMap<String, SharedData> = HashMap::new();
+        // Read the flag before draining so values arriving alongside it are not left behind
+        let input_done = data
+            .get(&self.input_flag)
+            .and_then(SharedData::to_bool)
+            .unwrap_or(false);
 
-            for shared_binned_game in binned_games {
-                let binned_game = match shared_binned_game {
-                    SharedData::BinnedValue(game) => game,
-                    _ => return Err("Vector isn't of binned values!".to_string()),
-                };
+        let binned_games = data.clear_vec(&self.input_vec_name).unwrap_or_default();
 
-                let value = *binned_game.0;
+        let mut new_data: HashMap<String, SharedData> = HashMap::new();
 
-                let bin_labels = binned_game.1;
-                let bin_str_labels: Vec<String> =
-                    bin_labels.iter().map(|b| format!("{b}")).collect();
-                let combined_label = bin_str_labels.join(".");
+        for shared_binned_game in binned_games {
+            let binned_game = match shared_binned_game {
+                SharedData::BinnedValue(game) => game,
+                _ => return Err(Error::data_type("Vector isn't of binned values!")),
+            };
 
-                if let Entry::Vacant(entry) = new_data.entry(combined_label.clone()) {
-                    entry.insert(value);
-                } else {
-                    let original_value = new_data.get_mut(&combined_label).unwrap();
-                    *(original_value) = original_value.max(&value);
-                }
-            }
+            let value = *binned_game.0;
 
-            {
-                let potential_data = data.get(&self.output_map_name);
-                let shared_data = match potential_data {
-                    Some(shared_data) => shared_data,
-                    None => continue,
-                };
-                let mut map = shared_data.to_map().unwrap();
-
-                for key in new_data.keys() {
-                    if !map.contains_key(key) {
-                        map.insert(key.to_string(), SharedData::U64(0));
-                    }
-
-                    let original = map.get_mut(key).unwrap();
-                    let new = new_data.get(key).unwrap();
-                    *original = original.max(new);
-                }
+            let bin_labels = binned_game.1;
+            let bin_str_labels: Vec<String> = bin_labels.iter().map(|b| format!("{b}")).collect();
+            let combined_label = bin_str_labels.join(".");
 
-                data.insert(self.output_map_name.clone(), SharedData::Map(map));
+            if let Entry::Vacant(entry) = new_data.entry(combined_label.clone()) {
+                entry.insert(value);
+            } else {
+                let original_value = new_data.get_mut(&combined_label).unwrap();
+                *(original_value) = original_value.max(&value)?;
             }
+        }
 
-            let flag = data
-                .get(&self.input_flag)
-                .unwrap_or(&SharedData::Bool(false));
+        {
+            let mut map = data.get(&self.output_map_name).unwrap().to_map().unwrap();
 
-            let flag = flag.to_bool().unwrap();
+            for key in new_data.keys() {
+                if !map.contains_key(key) {
+                    map.insert(key.to_string(), SharedData::U64(0));
+                }
 
-            if flag {
-                quit = true;
+                let original = map.get_mut(key).unwrap();
+                let new = new_data.get(key).unwrap();
+                *original = original.max(new)?;
             }
 
-            if final_loop && quit {
-                break;
-            }
+            data.insert(self.output_map_name.clone(), SharedData::Map(map));
         }
 
-        {
-            let d: bool = true;
-            data.insert(self.output_flag.clone(), SharedData::Bool(d));
+        if !input_done {
+            return Ok(ProcessStatus::Incomplete);
         }
 
+        data.insert(self.output_flag.clone(), SharedData::Bool(true));
+
         Ok(ProcessStatus::Complete)
     }
+
+    fn inputs(&self) -> Vec<String> {
+        vec![self.input_vec_name.clone()]
+    }
+
+    fn outputs(&self) -> Vec<String> {
+        vec![self.output_map_name.clone()]
+    }
+
+    fn input_flags(&self) -> Vec<String> {
+        vec![self.input_flag.clone()]
+    }
+
+    fn output_flags(&self) -> Vec<String> {
+        vec![self.output_flag.clone()]
+    }
 }
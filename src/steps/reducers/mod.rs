@@ -1,7 +1,13 @@
 mod avg_reduce;
 mod max_reduce;
+mod opening_tree_reduce;
+mod polyglot_book_reduce;
+mod position_frequency_reduce;
 mod sum_reduce;
 
-pub use avg_reduce::AvgReduce;
-pub use max_reduce::MaxReduce;
-pub use sum_reduce::SumReduce;
+pub use avg_reduce::{AvgReduce, AvgReduceParams};
+pub use max_reduce::{MaxReduce, MaxReduceParams};
+pub use opening_tree_reduce::{OpeningTreeReduce, OpeningTreeReduceParams};
+pub use polyglot_book_reduce::{PolyglotBookReduce, PolyglotBookReduceParams};
+pub use position_frequency_reduce::{PositionFrequencyReduce, PositionFrequencyReduceParams};
+pub use sum_reduce::{SumReduce, SumReduceParams};
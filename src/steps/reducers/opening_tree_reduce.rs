@@ -0,0 +1,97 @@
+use std::{collections::HashMap, fs, path::PathBuf};
+
+use crate::{
+    error::Error,
+    opening_tree::OpeningTree,
+    step_param_utils::parse_params,
+    workflow_step::{ProcessStatus, SharedData, Step, StepData},
+};
+
+step_params! {
+    /// Builds a tree of the opening moves of the games, with the number of games, results and
+    /// average rating through each move, and writes it to a JSON file
+    pub struct OpeningTreeReduceParams {
+        /// Vector of games to read
+        input: String,
+        /// JSON file the tree is written to, replaced if it exists
+        file: String,
+        /// Number of plies from the start of each game added to the tree
+        max_plies: usize = "10",
+        /// Moves played in fewer games are left out, along with what follows them
+        min_games: u64 = "1",
+        /// Flag raised once the input is complete
+        input_flag: String = "<input>_complete",
+    }
+}
+
+#[derive(Debug)]
+pub struct OpeningTreeReduce {
+    input_vec_name: String,
+    path: PathBuf,
+    min_games: u64,
+    input_flag: String,
+    tree: OpeningTree,
+}
+
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+impl OpeningTreeReduce {
+    pub fn try_new(configuration: Option<serde_yaml::Value>) -> Result<Box<dyn Step>, Error> {
+        let params: OpeningTreeReduceParams = parse_params("OpeningTreeReduce", configuration)?;
+
+        Ok(Box::new(OpeningTreeReduce {
+            input_vec_name: params.input,
+            path: PathBuf::from(params.file),
+            min_games: params.min_games,
+            input_flag: params.input_flag,
+            tree: OpeningTree::new(params.max_plies),
+        }))
+    }
+
+    fn save(&mut self) -> Result<(), Error> {
+        self.tree.prune(self.min_games);
+        let json = self.tree.to_json()?;
+
+        if let Some(parent) = self
+            .path
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+        {
+            fs::create_dir_all(parent).map_err(|err| Error::from(err).in_file(parent))?;
+        }
+        fs::write(&self.path, json).map_err(|err| Error::from(err).in_file(&self.path))
+    }
+}
+
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+impl Step for OpeningTreeReduce {
+    fn process(&mut self, data: &mut HashMap<String, SharedData>) -> Result<ProcessStatus, Error> {
+        // Read the flag before draining so games arriving alongside it are not left behind
+        let input_done = data
+            .get(&self.input_flag)
+            .and_then(SharedData::to_bool)
+            .unwrap_or(false);
+
+        for shared_game in data.clear_vec(&self.input_vec_name).unwrap_or_default() {
+            match shared_game {
+                SharedData::Game(game) => self.tree.add_game(&game),
+                _ => return Err(Error::data_type("Vector isn't of games!")),
+            }
+        }
+
+        if !input_done {
+            return Ok(ProcessStatus::Incomplete);
+        }
+
+        self.save()?;
+
+        Ok(ProcessStatus::Complete)
+    }
+
+    fn inputs(&self) -> Vec<String> {
+        vec![self.input_vec_name.clone()]
+    }
+
+    fn input_flags(&self) -> Vec<String> {
+        vec![self.input_flag.clone()]
+    }
+}
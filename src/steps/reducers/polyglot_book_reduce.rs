@@ -0,0 +1,96 @@
+use std::{collections::HashMap, fs, path::PathBuf};
+
+use crate::{
+    error::Error,
+    polyglot::BookBuilder,
+    step_param_utils::parse_params,
+    workflow_step::{ProcessStatus, SharedData, Step, StepData},
+};
+
+step_params! {
+    /// Builds a Polyglot opening book from the moves of the games, each weighted two points
+    /// per win and one per draw for the side playing it, and writes it to a `.bin` file
+    pub struct PolyglotBookReduceParams {
+        /// Vector of games to read
+        input: String,
+        /// Book file written, replaced if it exists
+        file: String,
+        /// Number of plies from the start of each game added to the book
+        max_plies: usize = "16",
+        /// Moves played in fewer games are left out of the book
+        min_games: u64 = "1",
+        /// Flag raised once the input is complete
+        input_flag: String = "<input>_complete",
+    }
+}
+
+#[derive(Debug)]
+pub struct PolyglotBookReduce {
+    input_vec_name: String,
+    path: PathBuf,
+    min_games: u64,
+    input_flag: String,
+    builder: BookBuilder,
+}
+
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+impl PolyglotBookReduce {
+    pub fn try_new(configuration: Option<serde_yaml::Value>) -> Result<Box<dyn Step>, Error> {
+        let params: PolyglotBookReduceParams = parse_params("PolyglotBookReduce", configuration)?;
+
+        Ok(Box::new(PolyglotBookReduce {
+            input_vec_name: params.input,
+            path: PathBuf::from(params.file),
+            min_games: params.min_games,
+            input_flag: params.input_flag,
+            builder: BookBuilder::new(params.max_plies),
+        }))
+    }
+
+    fn save(&self) -> Result<(), Error> {
+        let bytes = self.builder.build(self.min_games).to_bytes();
+
+        if let Some(parent) = self
+            .path
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+        {
+            fs::create_dir_all(parent).map_err(|err| Error::from(err).in_file(parent))?;
+        }
+        fs::write(&self.path, bytes).map_err(|err| Error::from(err).in_file(&self.path))
+    }
+}
+
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+impl Step for PolyglotBookReduce {
+    fn process(&mut self, data: &mut HashMap<String, SharedData>) -> Result<ProcessStatus, Error> {
+        // Read the flag before draining so games arriving alongside it are not left behind
+        let input_done = data
+            .get(&self.input_flag)
+            .and_then(SharedData::to_bool)
+            .unwrap_or(false);
+
+        for shared_game in data.clear_vec(&self.input_vec_name).unwrap_or_default() {
+            match shared_game {
+                SharedData::Game(game) => self.builder.add_game(&game),
+                _ => return Err(Error::data_type("Vector isn't of games!")),
+            }
+        }
+
+        if !input_done {
+            return Ok(ProcessStatus::Incomplete);
+        }
+
+        self.save()?;
+
+        Ok(ProcessStatus::Complete)
+    }
+
+    fn inputs(&self) -> Vec<String> {
+        vec![self.input_vec_name.clone()]
+    }
+
+    fn input_flags(&self) -> Vec<String> {
+        vec![self.input_flag.clone()]
+    }
+}
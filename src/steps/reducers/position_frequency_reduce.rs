@@ -0,0 +1,274 @@
+use crate::error::Error;
+use crate::step_param_utils::parse_params;
+use crate::workflow_step::{ProcessStatus, SharedData, Step, StepData};
+
+use std::cmp::Reverse;
+use std::collections::{BTreeSet, HashMap};
+
+step_params! {
+    /// Counts how often each position hashed by PositionHashMap occurs in each bin, and writes
+    /// the most frequent ones to a map keyed by the bin labels and their rank, as
+    /// "<count> <hash> <fen>" with the FEN of one of the games the position was seen in
+    pub struct PositionFrequencyReduceParams {
+        /// Vector of mapped positions to read
+        input: String,
+        /// Map the most frequent positions are written to
+        output: String,
+        /// Number of positions kept per bin
+        top: usize = "10",
+        /// Flag raised once the input is complete
+        input_flag: String = "<input>_complete",
+        /// Flag raised once this step is done
+        output_flag: String = "<output>_complete",
+    }
+}
+
+#[derive(Debug)]
+pub struct PositionFrequencyReduce {
+    input_vec_name: String,
+    output_map_name: String,
+    top: usize,
+    input_flag: String,
+    output_flag: String,
+    // Positions seen so far, per bin label
+    bins: HashMap<String, BinCounts>,
+}
+
+// Counts of the positions seen in a bin. Only the ones currently among the most frequent keep
+// a FEN, so memory grows by a hash and a count per distinct position.
+#[derive(Debug, Default)]
+struct BinCounts {
+    counts: HashMap<u64, u64>,
+    // Most frequent first, ties broken by hash
+    top: BTreeSet<(Reverse<u64>, u64)>,
+    fens: HashMap<u64, String>,
+}
+
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+impl PositionFrequencyReduce {
+    pub fn try_new(configuration: Option<serde_yaml::Value>) -> Result<Box<dyn Step>, Error> {
+        let params: PositionFrequencyReduceParams =
+            parse_params("PositionFrequencyReduce", configuration)?;
+
+        Ok(Box::new(PositionFrequencyReduce {
+            input_vec_name: params.input,
+            output_map_name: params.output,
+            top: params.top,
+            input_flag: params.input_flag,
+            output_flag: params.output_flag,
+            bins: HashMap::new(),
+        }))
+    }
+
+    fn count_positions(&mut self, label: String, positions: Vec<SharedData>) -> Result<(), Error> {
+        let top = self.top;
+        let bin = self.bins.entry(label).or_default();
+
+        for position in positions {
+            let pair = position.into_vec();
+            let (hash, fen) = match pair.as_deref() {
+                Some([SharedData::U64(hash), SharedData::String(fen)]) => (*hash, fen),
+                _ => return Err(Error::data_type("Position isn't a [hash, fen] pair!")),
+            };
+
+            let count = bin.counts.entry(hash).or_insert(0);
+            let previous = (Reverse(*count), hash);
+            *count += 1;
+            let current = (Reverse(*count), hash);
+
+            // Counts only go up, so a position can only join the most frequent when it is seen
+            if bin.top.remove(&previous) {
+                bin.top.insert(current);
+            } else if bin.top.len() < top || bin.top.last().is_some_and(|last| current < *last) {
+                bin.top.insert(current);
+                bin.fens.insert(hash, fen.clone());
+                if bin.top.len() > top {
+                    let (_, evicted) = bin.top.pop_last().unwrap();
+                    bin.fens.remove(&evicted);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn most_frequent(&self) -> HashMap<String, SharedData> {
+        let width = self.top.to_string().len();
+        let mut map = HashMap::new();
+
+        for (label, bin) in &self.bins {
+            for (rank, (Reverse(count), hash)) in bin.top.iter().enumerate() {
+                let key = if label.is_empty() {
+                    format!("{:0width$}", rank + 1)
+                } else {
+                    format!("{label}.{:0width$}", rank + 1)
+                };
+                map.insert(
+                    key,
+                    SharedData::String(format!("{count} {hash:016x} {}", bin.fens[hash])),
+                );
+            }
+        }
+
+        map
+    }
+}
+
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+impl Step for PositionFrequencyReduce {
+    fn process(&mut self, data: &mut HashMap<String, SharedData>) -> Result<ProcessStatus, Error> {
+        data.init_map_if_unset(&self.output_map_name);
+
+        // Read the flag before draining so values arriving alongside it are not left behind
+        let input_done = data
+            .get(&self.input_flag)
+            .and_then(SharedData::to_bool)
+            .unwrap_or(false);
+
+        let binned_values = data.clear_vec(&self.input_vec_name).unwrap_or_default();
+
+        for shared_binned_value in binned_values {
+            let binned_value = match shared_binned_value {
+                SharedData::BinnedValue(value) => value,
+                _ => return Err(Error::data_type("Vector isn't of binned values!")),
+            };
+
+            let positions = match *binned_value.0 {
+                SharedData::Vec(positions) => positions,
+                _ => return Err(Error::data_type("Value isn't a vector of positions!")),
+            };
+
+            let bin_labels = binned_value.1;
+            let bin_str_labels: Vec<String> = bin_labels.iter().map(|b| format!("{b}")).collect();
+            self.count_positions(bin_str_labels.join("."), positions)?;
+        }
+
+        if !input_done {
+            return Ok(ProcessStatus::Incomplete);
+        }
+
+        // Ranking every position seen is costly, so it is only done once all of them are in
+        data.insert(
+            self.output_map_name.clone(),
+            SharedData::Map(self.most_frequent()),
+        );
+        data.insert(self.output_flag.clone(), SharedData::Bool(true));
+
+        Ok(ProcessStatus::Complete)
+    }
+
+    fn inputs(&self) -> Vec<String> {
+        vec![self.input_vec_name.clone()]
+    }
+
+    fn outputs(&self) -> Vec<String> {
+        vec![self.output_map_name.clone()]
+    }
+
+    fn input_flags(&self) -> Vec<String> {
+        vec![self.input_flag.clone()]
+    }
+
+    fn output_flags(&self) -> Vec<String> {
+        vec![self.output_flag.clone()]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn positions(pairs: &[(u64, &str)]) -> SharedData {
+        SharedData::Vec(
+            pairs
+                .iter()
+                .map(|(hash, fen)| {
+                    SharedData::Vec(vec![
+                        SharedData::U64(*hash),
+                        SharedData::String(fen.to_string()),
+                    ])
+                })
+                .collect(),
+        )
+    }
+
+    fn binned(value: SharedData, label: &str) -> SharedData {
+        SharedData::BinnedValue((Box::new(value), vec![SharedData::String(label.to_string())]))
+    }
+
+    #[test]
+    fn ranks_the_most_frequent_positions_per_bin() {
+        let mut step = PositionFrequencyReduce::try_new(Some(
+            serde_yaml::from_str("{input: hashes, output: top, top: 2}").unwrap(),
+        ))
+        .unwrap();
+        let mut data = HashMap::new();
+
+        data.insert(
+            "hashes".to_string(),
+            SharedData::Vec(vec![
+                binned(positions(&[(1, "a"), (2, "b"), (3, "c")]), "Blitz"),
+                binned(positions(&[(1, "a"), (3, "c")]), "Blitz"),
+            ]),
+        );
+        assert_eq!(step.process(&mut data), Ok(ProcessStatus::Incomplete));
+        assert_eq!(data.get("top"), Some(&SharedData::Map(HashMap::new())));
+
+        data.insert(
+            "hashes".to_string(),
+            SharedData::Vec(vec![
+                binned(positions(&[(1, "a")]), "Blitz"),
+                binned(positions(&[(2, "b")]), "Rapid"),
+            ]),
+        );
+        data.insert("hashes_complete".to_string(), SharedData::Bool(true));
+        assert_eq!(step.process(&mut data), Ok(ProcessStatus::Complete));
+
+        assert_eq!(
+            format!("{}", data.get("top").unwrap()),
+            "\t\"Blitz.1\": 3 0000000000000001 a\n\
+             \t\"Blitz.2\": 2 0000000000000003 c\n\
+             \t\"Rapid.1\": 1 0000000000000002 b\n"
+        );
+        assert_eq!(data.get("top_complete"), Some(&SharedData::Bool(true)));
+    }
+
+    #[test]
+    fn keeps_the_fen_of_positions_that_become_frequent() {
+        let mut step = PositionFrequencyReduce::try_new(Some(
+            serde_yaml::from_str("{input: hashes, output: top, top: 1}").unwrap(),
+        ))
+        .unwrap();
+        let mut data = HashMap::new();
+
+        data.insert(
+            "hashes".to_string(),
+            SharedData::Vec(vec![
+                binned(positions(&[(5, "e"), (6, "f")]), ""),
+                binned(positions(&[(6, "f")]), ""),
+            ]),
+        );
+        data.insert("hashes_complete".to_string(), SharedData::Bool(true));
+        assert_eq!(step.process(&mut data), Ok(ProcessStatus::Complete));
+
+        assert_eq!(
+            format!("{}", data.get("top").unwrap()),
+            "\t\"1\": 2 0000000000000006 f\n"
+        );
+    }
+
+    #[test]
+    fn rejects_values_that_are_not_positions() {
+        let mut step = PositionFrequencyReduce::try_new(Some(
+            serde_yaml::from_str("{input: hashes, output: top}").unwrap(),
+        ))
+        .unwrap();
+        let mut data = HashMap::new();
+        data.insert(
+            "hashes".to_string(),
+            SharedData::Vec(vec![binned(SharedData::U64(1), "Blitz")]),
+        );
+
+        assert!(step.process(&mut data).is_err());
+    }
+}
@@ -1,7 +1,23 @@
+use crate::error::Error;
+use crate::step_param_utils::parse_params;
 use crate::workflow_step::{ProcessStatus, SharedData, Step, StepData};
 
 use std::collections::HashMap;
 
+step_params! {
+    /// Sums the mapped values of each bin into a map keyed by the bin labels
+    pub struct SumReduceParams {
+        /// Vector of mapped values to read
+        input: String,
+        /// Map the sums are written to
+        output: String,
+        /// Flag raised once the input is complete
+        input_flag: String = "<input>_complete",
+        /// Flag raised once this step is done
+        output_flag: String = "<output>_complete",
+    }
+}
+
 #[derive(Debug)]
 pub struct SumReduce {
     input_vec_name: String,
@@ -12,116 +28,92 @@ pub struct SumReduce {
 
 #[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
 impl SumReduce {
-    pub fn try_new(configuration: Option<serde_yaml::Value>) -> Result<Box<dyn Step>, String> {
-        let params = match configuration {
-            Some(value) => value,
-            None => return Err("SumReduce: no parameters provided".to_string()),
-        };
-
-        // TODO: better error handling
-        let input_vec_name = params.get("input").unwrap().as_str().unwrap().to_string();
-        let output_map_name = params.get("output").unwrap().as_str().unwrap().to_string();
-        let input_flag = params
-            .get("input_flag")
-            .unwrap()
-            .as_str()
-            .unwrap()
-            .to_string();
-        let output_flag = params
-            .get("output_flag")
-            .unwrap()
-            .as_str()
-            .unwrap()
-            .to_string();
+    pub fn try_new(configuration: Option<serde_yaml::Value>) -> Result<Box<dyn Step>, Error> {
+        let params: SumReduceParams = parse_params("SumReduce", configuration)?;
 
         Ok(Box::new(SumReduce {
-            input_vec_name,
-            output_map_name,
-            input_flag,
-            output_flag,
+            input_vec_name: params.input,
+            output_map_name: params.output,
+            input_flag: params.input_flag,
+            output_flag: params.output_flag,
         }))
     }
 }
 
 #[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
 impl Step for SumReduce {
-    fn process(&mut self, data: &mut HashMap<String, SharedData>) -> Result<ProcessStatus, String> {
+    fn process(&mut self, data: &mut HashMap<String, SharedData>) -> Result<ProcessStatus, Error> {
         data.init_map_if_unset(&self.output_map_name);
 
-        let mut quit = false;
-        let mut final_loop = false;
-        loop {
-            if quit {
-                final_loop = true;
-            }
+        // Read the flag before draining so values arriving alongside it are not left behind
+        let input_done = data
+            .get(&self.input_flag)
+            .and_then(SharedData::to_bool)
+            .unwrap_or(false);
 
-            let binned_games = data.clear_vec(&self.input_vec_name).unwrap();
+        let binned_games = data.clear_vec(&self.input_vec_name).unwrap_or_default();
 
-            let mut new_data: HashMap<String, u64> = HashMap::new();
+        let mut new_data: HashMap<String, u64> = HashMap::new();
 
-            for shared_binned_game in binned_games {
-                let binned_game = match shared_binned_game {
-                    SharedData::BinnedValue(game) => game,
-                    _ => return Err("Vector isn't of binned values!".to_string()),
-                };
+        for shared_binned_game in binned_games {
+            let binned_game = match shared_binned_game {
+                SharedData::BinnedValue(game) => game,
+                _ => return Err(Error::data_type("Vector isn't of binned values!")),
+            };
 
-                let value = match *binned_game.0 {
-                    SharedData::U64(v) => v,
-                    SharedData::USize(v) => v as u64,
-                    _ => return Err("Value isn't an integer!".to_string()),
-                };
+            let value = match *binned_game.0 {
+                SharedData::U64(v) => v,
+                SharedData::USize(v) => v as u64,
+                _ => return Err(Error::data_type("Value isn't an integer!")),
+            };
 
-                let bin_labels = binned_game.1;
-                let bin_str_labels: Vec<String> =
-                    bin_labels.iter().map(|b| format!("{b}")).collect();
-                let combined_label = bin_str_labels.join(".");
+            let bin_labels = binned_game.1;
+            let bin_str_labels: Vec<String> = bin_labels.iter().map(|b| format!("{b}")).collect();
+            let combined_label = bin_str_labels.join(".");
 
-                if new_data.contains_key(&combined_label) {
-                    *(new_data.get_mut(&combined_label).unwrap()) += value;
-                } else {
-                    new_data.insert(combined_label.clone(), value);
-                }
+            if new_data.contains_key(&combined_label) {
+                *(new_data.get_mut(&combined_label).unwrap()) += value;
+            } else {
+                new_data.insert(combined_label.clone(), value);
             }
+        }
 
-            {
-                let potential_data = data.get(&self.output_map_name);
-                let shared_data = match potential_data {
-                    Some(shared_data) => shared_data,
-                    None => continue,
-                };
-                let mut map = shared_data.to_map().unwrap();
-
-                for key in new_data.keys() {
-                    if !map.contains_key(key) {
-                        map.insert(key.to_string(), SharedData::U64(0));
-                    }
-                    let original_count = map.get(key).unwrap().to_u64().unwrap();
-                    let new_count = new_data.get(key).unwrap() + original_count;
-                    map.insert(key.to_string(), SharedData::U64(new_count));
+        {
+            let mut map = data.get(&self.output_map_name).unwrap().to_map().unwrap();
+
+            for key in new_data.keys() {
+                if !map.contains_key(key) {
+                    map.insert(key.to_string(), SharedData::U64(0));
                 }
-                data.insert(self.output_map_name.clone(), SharedData::Map(map));
+                let original_count = map.get(key).unwrap().to_u64().unwrap();
+                let new_count = new_data.get(key).unwrap() + original_count;
+                map.insert(key.to_string(), SharedData::U64(new_count));
             }
+            data.insert(self.output_map_name.clone(), SharedData::Map(map));
+        }
 
-            let flag = data
-                .get(&self.input_flag)
-                .unwrap_or(&SharedData::Bool(false));
+        if !input_done {
+            return Ok(ProcessStatus::Incomplete);
+        }
 
-            let flag = flag.to_bool().unwrap();
+        data.insert(self.output_flag.clone(), SharedData::Bool(true));
 
-            if flag {
-                quit = true;
-            }
+        Ok(ProcessStatus::Complete)
+    }
 
-            if final_loop && quit {
-                break;
-            }
-        }
+    fn inputs(&self) -> Vec<String> {
+        vec![self.input_vec_name.clone()]
+    }
 
-        {
-            let d: bool = true;
-            data.insert(self.output_flag.clone(), SharedData::Bool(d));
-        }
+    fn outputs(&self) -> Vec<String> {
+        vec![self.output_map_name.clone()]
+    }
 
-        Ok(ProcessStatus::Complete)
+    fn input_flags(&self) -> Vec<String> {
+        vec![self.input_flag.clone()]
+    }
+
+    fn output_flags(&self) -> Vec<String> {
+        vec![self.output_flag.clone()]
     }
 }
@@ -1,4 +1,5 @@
-use crate::workflow_step::{SharedData, StepDescription};
+use crate::error::Error;
+use crate::workflow_step::{BoxedStep, SharedData, StepDescription};
 use std::collections::HashMap;
 
 // TODO use result
@@ -21,15 +22,49 @@ pub fn add_step_description(
     data.insert("step_descriptions".to_string(), SharedData::Map(step_vec));
 }
 
-// TODO use Result
 #[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
-pub fn get_step_description(name: &str, data: &mut HashMap<String, SharedData>) -> StepDescription {
-    let descs = data.get("step_descriptions").unwrap().to_map().unwrap();
-    let desc = descs
-        .get(name)
-        .unwrap()
-        .to_step_description()
-        .unwrap()
-        .clone();
-    desc
+pub fn get_step_description(
+    name: &str,
+    data: &HashMap<String, SharedData>,
+) -> Result<StepDescription, Error> {
+    data.get("step_descriptions")
+        .and_then(|descs| match descs {
+            SharedData::Map(descs) => descs.get(name),
+            _ => None,
+        })
+        .and_then(SharedData::to_step_description)
+        .cloned()
+        .ok_or_else(|| Error::config(format!("No step named '{name}'")))
+}
+
+// Builds the step declared under `name`; its errors are tagged with that name
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+pub fn get_step(name: &str, data: &HashMap<String, SharedData>) -> Result<BoxedStep, Error> {
+    get_step_description(name, data)
+        .and_then(|description| description.to_step())
+        .map_err(|err| err.in_step(name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unknown_step_is_an_error() {
+        let mut data = HashMap::new();
+        add_step_description(
+            "init".to_string(),
+            StepDescription {
+                step_type: "NoopStep".to_string(),
+                parameters: None,
+            },
+            &mut data,
+        );
+
+        assert!(get_step("init", &data).is_ok());
+        assert_eq!(
+            get_step("other", &data).unwrap_err().to_string(),
+            "step 'other': No step named 'other'"
+        );
+    }
 }
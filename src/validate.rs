@@ -0,0 +1,243 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+
+use serde_yaml::Value;
+
+use crate::error::Error;
+use crate::workflow_step::{completion_flag, BoxedStep, StepDescription};
+
+// Checks a workflow without running it: every step is constructed, every referenced
+// child step must exist and every consumed entry or flag must have a producer.
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+pub fn validate_file(config_path: &Path) -> Result<(), Error> {
+    let config = match fs::read_to_string(config_path) {
+        Ok(config) => config,
+        Err(err) => {
+            return Err(Error::io(format!(
+                "Could not open configuration file: {err:?}"
+            )))
+        }
+    };
+
+    let problems = validate_config(&config);
+    if problems.is_empty() {
+        println!("{}: workflow is valid", config_path.display());
+        return Ok(());
+    }
+
+    for problem in &problems {
+        eprintln!("{}: {problem}", config_path.display());
+    }
+
+    Err(Error::config(format!(
+        "Found {} problem(s) in {}",
+        problems.len(),
+        config_path.display()
+    )))
+}
+
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+fn validate_config(config: &str) -> Vec<String> {
+    let config_data: Value = match serde_yaml::from_str(config) {
+        Ok(data) => data,
+        Err(err) => return vec![format!("Could not parse configuration: {err}")],
+    };
+
+    let steps_map = match config_data.get("steps").map(Value::as_mapping) {
+        Some(Some(map)) => map,
+        Some(None) => return vec!["Steps is not a map".to_string()],
+        None => return vec!["Could not find steps in configuration file".to_string()],
+    };
+
+    let lines = step_line_numbers(config);
+    let problem = |step_name: &str, message: String| match lines.get(step_name) {
+        Some(line) => format!("line {line}: step '{step_name}': {message}"),
+        None => format!("step '{step_name}': {message}"),
+    };
+
+    let mut problems = vec![];
+    let mut steps: Vec<(String, BoxedStep)> = vec![];
+    let mut step_names: HashSet<String> = HashSet::from(["noop".to_string()]);
+
+    for (step_name, step_data) in steps_map {
+        let step_name = match step_name.as_str() {
+            Some(name) => name.to_string(),
+            None => {
+                problems.push(format!("Step name {step_name:?} is not a string"));
+                continue;
+            }
+        };
+        step_names.insert(step_name.clone());
+
+        let step_type = match step_data.get("type") {
+            Some(Value::String(step_type)) => step_type.clone(),
+            Some(_) => {
+                problems.push(problem(&step_name, "type is not a string".to_string()));
+                continue;
+            }
+            None => {
+                problems.push(problem(&step_name, "no type field".to_string()));
+                continue;
+            }
+        };
+
+        let description = StepDescription {
+            step_type,
+            parameters: step_data.get("params").cloned(),
+        };
+
+        match description.to_step() {
+            Ok(step) => steps.push((step_name, step)),
+            Err(err) => problems.push(problem(&step_name, err.to_string())),
+        }
+    }
+
+    if !step_names.contains("init") {
+        problems.push("No 'init' step; workflows start from it".to_string());
+    }
+
+    let mut produced: HashSet<String> = HashSet::new();
+    for (_, step) in &steps {
+        for output in step.outputs() {
+            produced.insert(completion_flag(&output));
+            produced.insert(output);
+        }
+        produced.extend(step.output_flags());
+    }
+
+    for (step_name, step) in &steps {
+        for child in step.child_steps() {
+            if !step_names.contains(&child) {
+                problems.push(problem(step_name, format!("unknown child step '{child}'")));
+            }
+        }
+
+        for input in step.inputs() {
+            if !produced.contains(&input) {
+                problems.push(problem(
+                    step_name,
+                    format!("reads '{input}' but no step produces it"),
+                ));
+            }
+        }
+
+        for flag in step.input_flags() {
+            if !produced.contains(&flag) {
+                problems.push(problem(
+                    step_name,
+                    format!("waits on flag '{flag}' but no step raises it"),
+                ));
+            }
+        }
+    }
+
+    problems
+}
+
+// serde_yaml values carry no positions, so find the line of each key directly under `steps`
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+fn step_line_numbers(config: &str) -> HashMap<String, usize> {
+    let mut lines = HashMap::new();
+    let mut in_steps = false;
+    let mut step_indent = None;
+
+    for (index, line) in config.lines().enumerate() {
+        let trimmed = line.trim_start();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+
+        let indent = line.len() - trimmed.len();
+        if indent == 0 {
+            in_steps = trimmed.starts_with("steps:");
+            continue;
+        }
+
+        if in_steps && indent == *step_indent.get_or_insert(indent) {
+            if let Some((key, _)) = trimmed.split_once(':') {
+                let key = key.trim().trim_matches(|c| c == '"' || c == '\'');
+                lines.insert(key.to_string(), index + 1);
+            }
+        }
+    }
+
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VALID: &str = r#"
+steps:
+  init:
+    type: "GlobFileStep"
+    params:
+      glob: "*.bin.bz2"
+      child: serial_step
+  serial_step:
+    type: SerialStep
+    params:
+      children:
+        - load_files
+        - save_results
+  load_files:
+    type: Bz2DecompressStep
+  save_results:
+    type: SaveDataStep
+    params:
+      file: out.txt
+      fields:
+        - raw_file_data
+"#;
+
+    #[test]
+    fn valid_workflow_has_no_problems() {
+        assert_eq!(validate_config(VALID), Vec::<String>::new());
+    }
+
+    #[test]
+    fn step_lines_are_found() {
+        let lines = step_line_numbers(VALID);
+        assert_eq!(lines.get("init"), Some(&3));
+        assert_eq!(lines.get("serial_step"), Some(&8));
+        assert_eq!(lines.get("save_results"), Some(&16));
+        assert_eq!(lines.get("params"), None);
+    }
+
+    #[test]
+    fn reports_every_problem() {
+        let config = VALID
+            .replace("- save_results", "- save_result")
+            .replace("- raw_file_data", "- raw_data")
+            .replace("type: Bz2DecompressStep", "type: Bz2Step");
+
+        assert_eq!(
+            validate_config(&config),
+            vec![
+                "line 14: step 'load_files': Step with name 'Bz2Step' not found",
+                "line 8: step 'serial_step': unknown child step 'save_result'",
+                "line 16: step 'save_results': reads 'raw_data' but no step produces it",
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_invalid_params() {
+        let config = VALID.replace("      file: out.txt\n", "");
+
+        assert_eq!(
+            validate_config(&config),
+            vec!["line 16: step 'save_results': SaveDataStep: missing field `file`"]
+        );
+    }
+
+    #[test]
+    fn reports_missing_init() {
+        assert_eq!(
+            validate_config("steps:\n  other:\n    type: NoopStep\n"),
+            vec!["No 'init' step; workflows start from it"]
+        );
+    }
+}
@@ -1,3 +1,4 @@
+use crate::error::Error;
 use crate::game::Game;
 use crate::steps::get_step_by_name_and_params;
 use itertools::Itertools;
@@ -11,38 +12,14 @@ pub type BoxedStep = Box<dyn Step>;
 
 #[automock]
 pub trait StepData: Send {
-    fn insert(&mut self, k: String, v: SharedData) -> Option<SharedData>;
-    fn contains_key(&self, k: &str) -> bool;
-    fn remove(&mut self, k: &str) -> Option<SharedData>;
-
-    fn remove_vec(&mut self, k: &str) -> Option<Vec<SharedData>>;
     fn init_vec_if_unset(&mut self, k: &str);
     fn init_map_if_unset(&mut self, k: &str);
     fn clear_vec(&mut self, k: &str) -> Option<Vec<SharedData>>;
-    fn try_push_to_vec(&mut self, k: &str, v: SharedData) -> Result<(), &'static str>;
+    fn try_push_to_vec(&mut self, k: &str, v: SharedData) -> Result<(), Error>;
 }
 
 #[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
 impl StepData for HashMap<String, SharedData> {
-    fn remove_vec(&mut self, k: &str) -> Option<Vec<SharedData>> {
-        match self.remove(k) {
-            Some(v) => v.into_vec(),
-            None => None,
-        }
-    }
-
-    fn insert(&mut self, k: String, v: SharedData) -> Option<SharedData> {
-        self.insert(k, v)
-    }
-
-    fn contains_key(&self, k: &str) -> bool {
-        self.contains_key(k)
-    }
-
-    fn remove(&mut self, k: &str) -> Option<SharedData> {
-        self.remove(k)
-    }
-
     fn init_vec_if_unset(&mut self, k: &str) {
         if !self.contains_key(k) {
             self.insert(k.to_string(), SharedData::Vec(vec![]));
@@ -65,16 +42,16 @@ impl StepData for HashMap<String, SharedData> {
         }
     }
 
-    fn try_push_to_vec(&mut self, k: &str, v: SharedData) -> Result<(), &'static str> {
+    fn try_push_to_vec(&mut self, k: &str, v: SharedData) -> Result<(), Error> {
         match self.entry(k.to_string()) {
             Entry::Occupied(mut entry) => match entry.get_mut() {
                 SharedData::Vec(vec) => {
                     vec.push(v);
                     Ok(())
                 }
-                _ => Err("Not a Vec"),
+                _ => Err(Error::data_type(format!("'{k}' is not a Vec"))),
             },
-            Entry::Vacant(_) => Err("Key not present"),
+            Entry::Vacant(_) => Err(Error::data_type(format!("'{k}' is not present"))),
         }
     }
 }
@@ -154,21 +131,17 @@ impl SharedData {
         }
     }
 
-    pub fn max(&self, rhs: &SharedData) -> SharedData {
-        match self {
-            SharedData::U64(s) => match rhs {
-                SharedData::U64(r) => SharedData::U64(u64::max(*s, *r)),
-                _ => panic!("Max: Cannot compare {self:?} to {rhs:?}"),
-            },
-            SharedData::F64(s) => match rhs {
-                SharedData::F64(r) => SharedData::F64(f64::max(*s, *r)),
-                _ => panic!("Max: Cannot compare {self:?} to {rhs:?}"),
-            },
-            SharedData::USize(s) => match rhs {
-                SharedData::USize(r) => SharedData::USize(usize::max(*s, *r)),
-                _ => panic!("Max: Cannot compare {self:?} to {rhs:?}"),
-            },
-            _ => panic!("Max is not valid for {self:?}"),
+    pub fn max(&self, rhs: &SharedData) -> Result<SharedData, Error> {
+        match (self, rhs) {
+            (SharedData::U64(s), SharedData::U64(r)) => Ok(SharedData::U64(u64::max(*s, *r))),
+            (SharedData::F64(s), SharedData::F64(r)) => Ok(SharedData::F64(f64::max(*s, *r))),
+            (SharedData::USize(s), SharedData::USize(r)) => {
+                Ok(SharedData::USize(usize::max(*s, *r)))
+            }
+            (SharedData::U64(_) | SharedData::F64(_) | SharedData::USize(_), _) => Err(
+                Error::data_type(format!("Max: Cannot compare {self:?} to {rhs:?}")),
+            ),
+            _ => Err(Error::data_type(format!("Max is not valid for {self:?}"))),
         }
     }
 }
@@ -198,6 +171,11 @@ impl std::fmt::Display for SharedData {
     }
 }
 
+// Flag set once every producer of `data_name` has completed
+pub fn completion_flag(data_name: &str) -> String {
+    format!("{data_name}_complete")
+}
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct StepDescription {
     pub step_type: String,
@@ -206,7 +184,7 @@ pub struct StepDescription {
 
 #[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
 impl StepDescription {
-    pub fn to_step(&self) -> Result<BoxedStep, String> {
+    pub fn to_step(&self) -> Result<BoxedStep, Error> {
         get_step_by_name_and_params(&self.step_type, self.parameters.clone())
     }
 }
@@ -218,8 +196,33 @@ pub enum ProcessStatus {
 }
 
 #[automock]
-pub trait Step: fmt::Debug {
-    fn process(&mut self, data: &mut HashMap<String, SharedData>) -> Result<ProcessStatus, String>;
+pub trait Step: fmt::Debug + Send {
+    fn process(&mut self, data: &mut HashMap<String, SharedData>) -> Result<ProcessStatus, Error>;
+
+    // Names of the data entries this step reads from
+    fn inputs(&self) -> Vec<String> {
+        vec![]
+    }
+
+    // Names of the data entries this step writes to
+    fn outputs(&self) -> Vec<String> {
+        vec![]
+    }
+
+    // Names of the flags this step waits on
+    fn input_flags(&self) -> Vec<String> {
+        vec![]
+    }
+
+    // Names of the flags this step raises
+    fn output_flags(&self) -> Vec<String> {
+        vec![]
+    }
+
+    // Names of the step descriptions this step runs
+    fn child_steps(&self) -> Vec<String> {
+        vec![]
+    }
 }
 
 #[cfg(test)]
@@ -239,4 +242,9 @@ mod tests {
             "\t\"key_string\": 42\n".to_string()
         );
     }
+
+    #[test]
+    fn completion_flag_is_derived_from_data_name() {
+        assert_eq!(completion_flag("parsed_games"), "parsed_games_complete");
+    }
 }
@@ -0,0 +1,115 @@
+use crate::basic_types::{Cell, File, Piece, Player};
+
+// Pseudo-random keys XORed together to hash a position, generated at compile time so that hashes
+// are stable across runs and machines
+struct Keys {
+    pieces: [[[u64; 64]; 6]; 2],
+    black_to_move: u64,
+    castling: [[u64; 2]; 2],
+    en_passant: [u64; 8],
+}
+
+// SplitMix64, returning the next state and its output
+const fn next_key(state: u64) -> (u64, u64) {
+    let state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    let mut z = state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    (state, z ^ (z >> 31))
+}
+
+const fn generate_keys() -> Keys {
+    let mut keys = Keys {
+        pieces: [[[0; 64]; 6]; 2],
+        black_to_move: 0,
+        castling: [[0; 2]; 2],
+        en_passant: [0; 8],
+    };
+    let mut state = 0;
+    let mut key;
+
+    let mut player = 0;
+    while player < 2 {
+        let mut piece = 0;
+        while piece < 6 {
+            let mut cell = 0;
+            while cell < 64 {
+                (state, key) = next_key(state);
+                keys.pieces[player][piece][cell] = key;
+                cell += 1;
+            }
+            piece += 1;
+        }
+        player += 1;
+    }
+
+    (state, key) = next_key(state);
+    keys.black_to_move = key;
+
+    let mut right = 0;
+    while right < 4 {
+        (state, key) = next_key(state);
+        keys.castling[right / 2][right % 2] = key;
+        right += 1;
+    }
+
+    let mut file = 0;
+    while file < 8 {
+        (state, key) = next_key(state);
+        keys.en_passant[file] = key;
+        file += 1;
+    }
+
+    keys
+}
+
+const KEYS: Keys = generate_keys();
+
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+fn player_index(player: Player) -> usize {
+    match player {
+        Player::Black => 1,
+        _ => 0,
+    }
+}
+
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+pub fn piece_key(player: Player, piece: Piece, cell: Cell) -> u64 {
+    let cell_index = (cell.rank as usize - 1) * 8 + (cell.file as usize - 1);
+    KEYS.pieces[player_index(player)][piece as usize - 1][cell_index]
+}
+
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+pub fn black_to_move_key() -> u64 {
+    KEYS.black_to_move
+}
+
+// Kingside when `rook_file` is the h-file, queenside otherwise
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+pub fn castling_key(player: Player, rook_file: File) -> u64 {
+    let side = if rook_file == File::_H { 0 } else { 1 };
+    KEYS.castling[player_index(player)][side]
+}
+
+#[cfg_attr(feature = "with_mutagen", ::mutagen::mutate)]
+pub fn en_passant_key(file: File) -> u64 {
+    KEYS.en_passant[file as usize - 1]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn keys_are_distinct() {
+        let mut keys: Vec<u64> = KEYS.pieces.iter().flatten().flatten().copied().collect();
+        keys.push(KEYS.black_to_move);
+        keys.extend(KEYS.castling.iter().flatten());
+        keys.extend(KEYS.en_passant);
+
+        assert_eq!(keys.len(), 781);
+        assert_eq!(keys.iter().collect::<HashSet<_>>().len(), 781);
+        assert!(!keys.contains(&0));
+    }
+}
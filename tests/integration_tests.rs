@@ -1,6 +1,7 @@
 extern crate chess_analytics;
 use chess_analytics::run;
 use std::fs;
+use std::io::Read;
 
 #[test]
 fn simple_count_10_games() -> Result<(), Box<dyn std::error::Error>> {
@@ -119,7 +120,7 @@ fn parse_pgn() -> Result<(), Box<dyn std::error::Error>> {
     let contents = fs::read("tests/output/int_6/int_6_test_set_1.bin.bz2")
         .expect("Something went wrong reading the file");
     let expected =
-        fs::read("test_data/test_set_1.bin.bz2").expect("Something went wrong reading the file");
+        fs::read("test_data/test_set_1_v2.bin.bz2").expect("Something went wrong reading the file");
 
     assert_eq!(contents, expected);
 
@@ -129,6 +130,293 @@ fn parse_pgn() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+#[test]
+fn parse_pgn_quarantines_corrupt_game() -> Result<(), Box<dyn std::error::Error>> {
+    let _ = std::fs::create_dir("tests/output/int_12");
+    let _ = std::fs::remove_file("tests/output/int_12/quarantine.pgn");
+    run(
+        vec!["chess_analytics", "tests/workflows/12_quarantine_pgn.yaml"]
+            .iter()
+            .map(|x| (*x).to_string()),
+    )?;
+
+    let quarantined = fs::read_to_string("tests/output/int_12/quarantine.pgn")
+        .expect("Something went wrong reading the file");
+
+    assert!(quarantined.starts_with(
+        "% test_data/corrupt_games.pgn: game 1: Unknown result: 2-0\n[Event \"Rated Classical game\"]\n"
+    ));
+    assert!(quarantined.contains("[Site \"https://lichess.org/a9tcp02g\"]"));
+    assert_eq!(quarantined.matches("\n% ").count(), 0);
+    assert!(fs::read("tests/output/int_12/int_12_corrupt_games.bin.bz2").is_ok());
+
+    let _ = std::fs::remove_file("tests/output/int_12/quarantine.pgn");
+    let _ = std::fs::remove_file("tests/output/int_12/int_12_corrupt_games.bin.bz2");
+    let _ = std::fs::remove_dir("tests/output/int_12");
+
+    Ok(())
+}
+
+#[test]
+fn export_pgn_round_trips() -> Result<(), Box<dyn std::error::Error>> {
+    let _ = std::fs::create_dir("tests/output/int_13");
+    run(
+        vec!["chess_analytics", "tests/workflows/13_export_pgn.yaml"]
+            .iter()
+            .map(|x| (*x).to_string()),
+    )?;
+    run(vec![
+        "chess_analytics",
+        "tests/workflows/14_parse_exported_pgn.yaml",
+    ]
+    .iter()
+    .map(|x| (*x).to_string()))?;
+
+    let exported = fs::read_to_string("tests/output/int_13/test_set_1.pgn")
+        .expect("Something went wrong reading the file");
+    assert_eq!(exported.matches("[Event ").count(), 10);
+
+    // Parsing the exported games gives back the same games. They are compared through their
+    // export rather than field by field: the source writes one move as "Rc8c6", which the
+    // export gives the least disambiguation it needs ("R8c6").
+    let exported_again = fs::read_to_string("tests/output/int_13/test_set_1_again.pgn")
+        .expect("Something went wrong reading the file");
+    assert_eq!(exported_again, exported);
+
+    let _ = std::fs::remove_file("tests/output/int_13/test_set_1.pgn");
+    let _ = std::fs::remove_file("tests/output/int_13/test_set_1_again.pgn");
+    let _ = std::fs::remove_dir("tests/output/int_13");
+
+    Ok(())
+}
+
+#[test]
+fn opening_tree_counts_every_game() -> Result<(), Box<dyn std::error::Error>> {
+    let _ = std::fs::create_dir("tests/output/int_15");
+    run(
+        vec!["chess_analytics", "tests/workflows/15_opening_tree.yaml"]
+            .iter()
+            .map(|x| (*x).to_string()),
+    )?;
+
+    let contents = fs::read_to_string("tests/output/int_15/opening_tree.json")
+        .expect("Something went wrong reading the file");
+    let tree: serde_json::Value = serde_json::from_str(&contents)?;
+    assert_eq!(tree["games"], 10);
+
+    // Every move kept was played in at least `min_games` games
+    let mut nodes = vec![&tree];
+    while let Some(node) = nodes.pop() {
+        for child in node["children"].as_array().unwrap() {
+            assert!(child["games"].as_u64().unwrap() >= 2);
+            nodes.push(child);
+        }
+    }
+
+    let _ = std::fs::remove_file("tests/output/int_15/opening_tree.json");
+    let _ = std::fs::remove_dir("tests/output/int_15");
+
+    Ok(())
+}
+
+#[test]
+fn polyglot_book_holds_sorted_entries() -> Result<(), Box<dyn std::error::Error>> {
+    let _ = std::fs::create_dir("tests/output/int_16");
+    run(
+        vec!["chess_analytics", "tests/workflows/16_polyglot_book.yaml"]
+            .iter()
+            .map(|x| (*x).to_string()),
+    )?;
+
+    let contents = fs::read("tests/output/int_16/book.bin")?;
+    assert!(!contents.is_empty());
+    assert_eq!(contents.len() % 16, 0);
+
+    // Entries are sorted by position key, so books can be searched
+    let keys: Vec<u64> = contents
+        .chunks_exact(16)
+        .map(|entry| u64::from_be_bytes(entry[..8].try_into().unwrap()))
+        .collect();
+    assert!(keys.windows(2).all(|pair| pair[0] <= pair[1]));
+
+    // The first move of each of the 10 games is weighted from the starting position
+    let start_key = 0x463b_9618_1691_fc9c;
+    assert!(keys.contains(&start_key));
+
+    let _ = std::fs::remove_file("tests/output/int_16/book.bin");
+    let _ = std::fs::remove_dir("tests/output/int_16");
+
+    Ok(())
+}
+
+#[test]
+fn migrate_rewrites_headerless_archive() -> Result<(), Box<dyn std::error::Error>> {
+    let _ = std::fs::create_dir("tests/output/int_17");
+    let archive = "tests/output/int_17/one_eval_one_no_eval.bin.bz2";
+    fs::copy("test_data/one_eval_one_no_eval.bin.bz2", archive)?;
+
+    let migrate = || {
+        run(vec!["chess_analytics", "migrate", archive]
+            .iter()
+            .map(|x| (*x).to_string()))
+    };
+    migrate()?;
+
+    let mut file_data = vec![];
+    bzip2::read::BzDecoder::new(fs::File::open(archive)?).read_to_end(&mut file_data)?;
+    assert_eq!(&file_data[..6], b"CABG\x02\x00");
+    assert_eq!(u64::from_le_bytes(file_data[6..14].try_into()?), 2);
+
+    // Migrating again leaves the file as it is
+    migrate()?;
+    let mut migrated_again = vec![];
+    bzip2::read::BzDecoder::new(fs::File::open(archive)?).read_to_end(&mut migrated_again)?;
+    assert_eq!(migrated_again, file_data);
+
+    run(vec![
+        "chess_analytics",
+        "tests/workflows/17_count_migrated_games.yaml",
+    ]
+    .iter()
+    .map(|x| (*x).to_string()))?;
+
+    let contents = fs::read_to_string("tests/output/int_17/tmp.txt")?;
+    assert_eq!(contents, "game_count: \n\t\"\": 2\n\n");
+
+    let _ = std::fs::remove_file("tests/output/int_17/tmp.txt");
+    let _ = std::fs::remove_file(archive);
+    let _ = std::fs::remove_dir("tests/output/int_17");
+
+    Ok(())
+}
+
+#[test]
+fn migrate_requires_a_file() {
+    assert!(run(vec!["chess_analytics", "migrate"]
+        .iter()
+        .map(|x| (*x).to_string()))
+    .is_err());
+}
+
+#[test]
+fn columnar_games_filter_like_player_elo_filter() -> Result<(), Box<dyn std::error::Error>> {
+    let _ = std::fs::create_dir("tests/output/int_18");
+    run(
+        vec!["chess_analytics", "tests/workflows/18_export_columnar.yaml"]
+            .iter()
+            .map(|x| (*x).to_string()),
+    )?;
+    run(vec![
+        "chess_analytics",
+        "tests/workflows/19_columnar_elo_tc_bin.yaml",
+    ]
+    .iter()
+    .map(|x| (*x).to_string()))?;
+
+    // Same counts as reading every column of the binary file and filtering afterwards
+    let contents = fs::read_to_string("tests/output/int_18/tmp.txt")?;
+    let expected = "game_count: \n\t\"Blitz\": 1\n\t\"Rapid\": 1\n\n";
+    assert_eq!(contents, expected);
+
+    let _ = std::fs::remove_file("tests/output/int_18/tmp.txt");
+    let _ = std::fs::remove_file("tests/output/int_18/test_set_1.cols");
+    let _ = std::fs::remove_dir("tests/output/int_18");
+
+    Ok(())
+}
+
+#[test]
+fn lookup_reads_indexed_games() -> Result<(), Box<dyn std::error::Error>> {
+    let _ = std::fs::create_dir("tests/output/int_20");
+    let index = "tests/output/int_20/test_set_1.idx";
+
+    run(vec![
+        "chess_analytics",
+        "index",
+        index,
+        "test_data/test_set_1.bin.bz2",
+    ]
+    .iter()
+    .map(|x| (*x).to_string()))?;
+
+    run(vec!["chess_analytics", "lookup", index, "id", "a9tcp02g"]
+        .iter()
+        .map(|x| (*x).to_string()))?;
+    assert!(run(
+        vec!["chess_analytics", "lookup", index, "date", "2012-12-31"]
+            .iter()
+            .map(|x| (*x).to_string())
+    )
+    .is_err());
+
+    run(vec![
+        "chess_analytics",
+        "tests/workflows/20_lookup_indexed_games.yaml",
+    ]
+    .iter()
+    .map(|x| (*x).to_string()))?;
+
+    let contents = fs::read_to_string("tests/output/int_20/tmp.txt")?;
+    assert_eq!(contents, "game_count: \n\t\"\": 2\n\n");
+
+    let _ = std::fs::remove_file("tests/output/int_20/tmp.txt");
+    let _ = std::fs::remove_file(index);
+    let _ = std::fs::remove_dir("tests/output/int_20");
+
+    Ok(())
+}
+
+#[test]
+fn convert_writes_sharded_archives() -> Result<(), Box<dyn std::error::Error>> {
+    let _ = fs::remove_dir_all("tests/output/int_21");
+
+    run(vec![
+        "chess_analytics",
+        "convert",
+        "test_data/test_set_1.pgn",
+        "tests/output/int_21",
+        "--shard-size",
+        "4",
+        "--threads",
+        "2",
+    ]
+    .iter()
+    .map(|x| (*x).to_string()))?;
+
+    for shard in ["000000", "000001", "000002"] {
+        assert!(fs::metadata(format!("tests/output/int_21/test_set_1_{shard}.bin.bz2")).is_ok());
+    }
+
+    run(vec![
+        "chess_analytics",
+        "tests/workflows/21_count_converted_games.yaml",
+    ]
+    .iter()
+    .map(|x| (*x).to_string()))?;
+
+    let contents = fs::read_to_string("tests/output/int_21/tmp.txt")?;
+    assert_eq!(contents, "game_count: \n\t\"\": 10\n\n");
+
+    let _ = fs::remove_dir_all("tests/output/int_21");
+
+    Ok(())
+}
+
+#[test]
+fn convert_rejects_invalid_compression() {
+    assert!(run(vec![
+        "chess_analytics",
+        "convert",
+        "test_data/test_set_1.pgn",
+        "tests/output/int_21",
+        "--compression",
+        "0",
+    ]
+    .iter()
+    .map(|x| (*x).to_string()))
+    .is_err());
+}
+
 #[test]
 fn avg_perfect_checkmate_unbinned() -> Result<(), Box<dyn std::error::Error>> {
     let _ = std::fs::create_dir("tests/output/int_7");
@@ -153,6 +441,50 @@ fn avg_perfect_checkmate_unbinned() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+#[test]
+fn dag_count_mates_by_time_10_games() -> Result<(), Box<dyn std::error::Error>> {
+    let _ = std::fs::create_dir("tests/output/int_9");
+    run(vec![
+        "chess_analytics",
+        "tests/workflows/9_dag_count_mates_by_time.yaml",
+    ]
+    .iter()
+    .map(|x| (*x).to_string()))?;
+
+    let contents = fs::read_to_string("tests/output/int_9/tmp.txt")
+        .expect("Something went wrong reading the file");
+
+    let expected = "game_count: \n\t\"Blitz\": 1\n\t\"Rapid\": 3\n\n";
+    assert_eq!(contents, expected);
+
+    let _ = std::fs::remove_file("tests/output/int_9/tmp.txt");
+    let _ = std::fs::remove_dir("tests/output/int_9");
+
+    Ok(())
+}
+
+#[test]
+fn pipeline_count_mates_by_time_10_games() -> Result<(), Box<dyn std::error::Error>> {
+    let _ = std::fs::create_dir("tests/output/int_10");
+    run(vec![
+        "chess_analytics",
+        "tests/workflows/10_pipeline_count_mates_by_time.yaml",
+    ]
+    .iter()
+    .map(|x| (*x).to_string()))?;
+
+    let contents = fs::read_to_string("tests/output/int_10/tmp.txt")
+        .expect("Something went wrong reading the file");
+
+    let expected = "game_count: \n\t\"Blitz\": 1\n\t\"Rapid\": 3\n\n";
+    assert_eq!(contents, expected);
+
+    let _ = std::fs::remove_file("tests/output/int_10/tmp.txt");
+    let _ = std::fs::remove_dir("tests/output/int_10");
+
+    Ok(())
+}
+
 #[test]
 fn noop_workflow() -> Result<(), Box<dyn std::error::Error>> {
     let _ = std::fs::create_dir("tests/output/int_8");
@@ -169,10 +501,69 @@ fn noop_workflow() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+#[test]
+fn validate_valid_workflow() -> Result<(), Box<dyn std::error::Error>> {
+    run(vec![
+        "chess_analytics",
+        "validate",
+        "tests/workflows/4_player_elo_tc_bin_1.yaml",
+    ]
+    .iter()
+    .map(|x| (*x).to_string()))?;
+
+    Ok(())
+}
+
+#[test]
+fn validate_invalid_workflow() -> Result<(), Box<dyn std::error::Error>> {
+    assert_eq!(
+        run(vec![
+            "chess_analytics",
+            "validate",
+            "tests/workflows/11_invalid_workflow.yaml",
+        ]
+        .iter()
+        .map(|x| (*x).to_string()))
+        .map_err(|err| err.to_string()),
+        Err("Found 3 problem(s) in tests/workflows/11_invalid_workflow.yaml".to_string())
+    );
+
+    // Validation never runs the workflow
+    assert!(!std::path::Path::new("tests/output/int_11").exists());
+
+    Ok(())
+}
+
+#[test]
+fn describe_step_type() -> Result<(), Box<dyn std::error::Error>> {
+    run(vec!["chess_analytics", "describe", "CountMap"]
+        .iter()
+        .map(|x| (*x).to_string()))?;
+    run(vec!["chess_analytics", "describe"]
+        .iter()
+        .map(|x| (*x).to_string()))?;
+
+    Ok(())
+}
+
+#[test]
+fn describe_unknown_step_type() -> Result<(), Box<dyn std::error::Error>> {
+    assert_eq!(
+        run(vec!["chess_analytics", "describe", "CountReduce"]
+            .iter()
+            .map(|x| (*x).to_string()))
+        .map_err(|err| err.to_string()),
+        Err("Step with name 'CountReduce' not found".to_string())
+    );
+
+    Ok(())
+}
+
 #[test]
 fn workflow_param_not_provided() -> Result<(), Box<dyn std::error::Error>> {
     assert_eq!(
-        run(vec!["chess_analytics",].iter().map(|x| (*x).to_string())),
+        run(vec!["chess_analytics",].iter().map(|x| (*x).to_string()))
+            .map_err(|err| err.to_string()),
         Err("First argument (configuration path) is required".to_string())
     );
 
@@ -186,7 +577,7 @@ fn non_existent_workflow() -> Result<(), Box<dyn std::error::Error>> {
         "tests/workflows/does_not_exist.yaml",
     ]
     .iter()
-    .map(|x| (*x).to_string())), Err("Could not open configuration file: Os { code: 2, kind: NotFound, message: \"No such file or directory\" }".to_string()));
+    .map(|x| (*x).to_string())).map_err(|err| err.to_string()), Err("Could not open configuration file: Os { code: 2, kind: NotFound, message: \"No such file or directory\" }".to_string()));
 
     Ok(())
 }